@@ -1,15 +1,21 @@
+use super::cleanup::recover_on_startup;
+use crate::signals::wait_for_shutdown;
 use anyhow::Result;
-use chaos_core::{Executor, Target};
+use chaos_core::{Executor, InjectorParams, Journal, Target};
 use colored::Colorize;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// Bound on removing the injection after SIGINT/SIGTERM
+const ABORT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
 
 pub async fn execute(
     pid: Option<u32>,
     address: Option<String>,
     injection: String,
     duration: Option<String>,
-    _config: Option<PathBuf>,
-) -> Result<()> {
+    config: Option<PathBuf>,
+) -> Result<ExitCode> {
     println!("{}", "=== Attach Mode ===".bold().cyan());
 
     // Determine target
@@ -32,17 +38,28 @@ pub async fn execute(
 
     println!("Injection: {}", injection.green());
 
+    let params = match &config {
+        Some(path) => {
+            println!("Parameters: {}", path.display());
+            load_params(path).await?
+        }
+        None => InjectorParams::new(),
+    };
+
     if let Some(dur) = &duration {
         println!("Duration: {}", dur);
     }
 
     // Create executor
-    let executor = Executor::with_defaults();
+    let executor = Executor::with_defaults().with_journal(Journal::open_default());
+    recover_on_startup(&executor).await?;
 
     println!("\n{}", "Applying injection...".yellow());
 
     // Apply injection
-    let handle = executor.inject(&injection, &target).await?;
+    let handle = executor
+        .inject_with_params(&injection, &target, &params)
+        .await?;
 
     println!("{}", "✓ Injection applied successfully!".green().bold());
     println!("Injection ID: {}", handle.id);
@@ -50,19 +67,51 @@ pub async fn execute(
     // Wait for duration if specified
     if let Some(dur_str) = duration {
         let duration = humantime::parse_duration(&dur_str)?;
-        println!("\nWaiting {:?}...", duration);
-        tokio::time::sleep(duration).await;
+        println!("\nWaiting {:?}... (Ctrl-C to stop early)", duration);
+        let signal = tokio::select! {
+            _ = tokio::time::sleep(duration) => None,
+            signal = wait_for_shutdown() => Some(signal),
+        };
+
+        if let Some(signal) = signal {
+            println!(
+                "\n{}",
+                format!("⚠ Received {}, removing injection...", signal.name()).yellow()
+            );
+            match tokio::time::timeout(ABORT_TIMEOUT, executor.remove(handle)).await {
+                Ok(Ok(())) => println!("{}", "✓ Injection removed".green()),
+                Ok(Err(e)) => println!("{} {}", "✗ Failed to remove injection:".red(), e),
+                Err(_) => println!(
+                    "{}",
+                    "✗ Timed out removing injection; run 'chaos cleanup'".red()
+                ),
+            }
+            return Ok(ExitCode::from(signal.exit_code()));
+        }
 
         println!("\n{}", "Removing injection...".yellow());
         executor.remove(handle).await?;
         println!("{}", "✓ Injection removed".green());
     } else {
+        executor.detach(&handle).await?;
         println!(
             "\n{}",
             "Injection will remain active (no duration specified)".yellow()
         );
-        println!("Injection ID: {} (save this to remove later)", handle.id);
+        println!("Injection ID: {}", handle.id);
+        println!("Run 'chaos cleanup' to remove it");
     }
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Load injection parameters from a YAML or JSON file
+async fn load_params(path: &Path) -> Result<InjectorParams> {
+    let contents = tokio::fs::read_to_string(path).await?;
+
+    match path.extension().and_then(|s| s.to_str()) {
+        Some("yaml") | Some("yml") => Ok(serde_yaml::from_str(&contents)?),
+        Some("json") => Ok(serde_json::from_str(&contents)?),
+        _ => anyhow::bail!("Unsupported config format. Use .yaml, .yml, or .json"),
+    }
 }
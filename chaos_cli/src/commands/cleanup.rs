@@ -0,0 +1,93 @@
+use anyhow::Result;
+use chaos_core::{Executor, Journal, RecoveryReport};
+use colored::Colorize;
+
+pub async fn execute(dry_run: bool) -> Result<()> {
+    println!("{}", "=== Cleanup ===".bold().cyan());
+
+    let journal = Journal::open_default();
+    println!("Journal: {}", journal.dir().display());
+
+    if dry_run {
+        let entries = journal.entries().await?;
+        println!("\nJournaled injections: {}\n", entries.len());
+        for entry in entries {
+            let state = if entry.handle.is_none() {
+                "incomplete".red()
+            } else if entry.is_orphaned().await {
+                "orphaned".yellow()
+            } else {
+                "active".green()
+            };
+            println!(
+                "  {} {} on {} (pid {}, {}){}",
+                "•".green(),
+                entry.injector_name,
+                entry.target.description(),
+                entry.owner_pid,
+                state,
+                if entry.detached { " [detached]" } else { "" }
+            );
+        }
+        return Ok(());
+    }
+
+    let executor = Executor::with_defaults().with_journal(journal);
+    let report = executor.recover(true).await?;
+    print_report(&report);
+
+    if !report.failed.is_empty() {
+        anyhow::bail!("{} injection(s) could not be removed", report.failed.len());
+    }
+
+    Ok(())
+}
+
+/// Roll back injections left behind by a previous run that died, before
+/// applying anything new
+pub async fn recover_on_startup(executor: &Executor) -> Result<()> {
+    let report = executor.recover(false).await?;
+    if !report.is_empty() {
+        println!("\n{}", "Recovered orphaned injections:".bold().yellow());
+        print_report(&report);
+    }
+    Ok(())
+}
+
+fn print_report(report: &RecoveryReport) {
+    for entry in &report.removed {
+        println!(
+            "  {} Removed {} on {}",
+            "✓".green(),
+            entry.injector_name,
+            entry.target.description()
+        );
+    }
+    for (entry, error) in &report.failed {
+        println!(
+            "  {} Failed to remove {} on {}: {}",
+            "✗".red(),
+            entry.injector_name,
+            entry.target.description(),
+            error
+        );
+    }
+    for entry in &report.incomplete {
+        println!(
+            "  {} {} on {} was interrupted mid-inject; check it manually",
+            "⚠".yellow(),
+            entry.injector_name,
+            entry.target.description()
+        );
+    }
+    if !report.skipped.is_empty() {
+        println!(
+            "  {} {} injection(s) still owned by a running process",
+            "ℹ".blue(),
+            report.skipped.len()
+        );
+    }
+    if report.is_empty() {
+        println!("  {} Nothing to clean up", "✓".green());
+    }
+}
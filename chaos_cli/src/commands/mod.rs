@@ -1,4 +1,5 @@
 pub mod attach;
+pub mod cleanup;
 pub mod list;
 pub mod report;
 pub mod run;
@@ -1,3 +1,4 @@
+use crate::ui;
 use anyhow::Result;
 use colored::Colorize;
 use std::path::PathBuf;
@@ -67,6 +68,18 @@ fn print_cli_report(result: &chaos_scenarios::runner::ScenarioResult) {
     println!("Total Duration: {:?}", result.total_duration);
     println!("Total Injections: {}", result.total_injections);
     println!("Success Rate: {:.2}%", result.success_rate() * 100.0);
+    if result.slo_violation_count() > 0 {
+        println!("SLO Violations: {}", result.slo_violation_count());
+    }
+    if let Some(reason) = &result.halt_reason {
+        println!("Halted: {}", reason.yellow());
+    }
+    if let Some(before) = &result.steady_state_before {
+        ui::print_hypothesis("Steady State (before)", before);
+    }
+    if let Some(after) = &result.steady_state_after {
+        ui::print_hypothesis("Steady State (after)", after);
+    }
 
     println!("\n{}", "Phase Results:".bold());
     for phase in &result.phase_results {
@@ -76,6 +89,7 @@ fn print_cli_report(result: &chaos_scenarios::runner::ScenarioResult) {
             phase.duration,
             phase.injection_count
         );
+        ui::print_phase_metrics(phase);
     }
 }
 
@@ -104,10 +118,22 @@ Test completed successfully.
         result
             .phase_results
             .iter()
-            .map(|p| format!(
-                "- **{}**: {:?} ({} injections)",
-                p.name, p.duration, p.injection_count
-            ))
+            .map(|p| match &p.metrics {
+                Some(m) => format!(
+                    "- **{}**: {:?} ({} injections, {} requests, {:.1}% errors, p99 {:?}, {} SLO violations)",
+                    p.name,
+                    p.duration,
+                    p.injection_count,
+                    m.total_requests,
+                    m.error_rate * 100.0,
+                    m.latency_p99,
+                    p.slo_violations.len()
+                ),
+                None => format!(
+                    "- **{}**: {:?} ({} injections)",
+                    p.name, p.duration, p.injection_count
+                ),
+            })
             .collect::<Vec<_>>()
             .join("\n")
     )
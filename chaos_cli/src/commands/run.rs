@@ -1,8 +1,13 @@
+use super::cleanup::recover_on_startup;
+use crate::signals::wait_for_shutdown;
+use crate::ui;
 use anyhow::Result;
-use chaos_scenarios::{parse_scenario_from_file, ScenarioRunner};
+use chaos_core::{Executor, Journal};
+use chaos_scenarios::{parse_scenario_from_file, RunStatus, ScenarioRunner};
 use colored::Colorize;
 use indicatif::{ProgressBar, ProgressStyle};
 use std::path::PathBuf;
+use std::process::ExitCode;
 use tracing::info;
 
 pub async fn execute(
@@ -12,7 +17,8 @@ pub async fn execute(
     output_markdown: Option<PathBuf>,
     prometheus_port: Option<u16>,
     seed: Option<u64>,
-) -> Result<()> {
+    artifacts_dir: Option<PathBuf>,
+) -> Result<ExitCode> {
     println!("{}", "=== Chaos Framework ===".bold().cyan());
     println!("Loading scenario: {}", scenario_file.display());
 
@@ -49,8 +55,21 @@ pub async fn execute(
 
     println!("\n{}", "Starting chaos test...".bold().yellow());
 
-    // Run scenario
-    let runner = ScenarioRunner::with_defaults();
+    // Run scenario, journaling injections so a crash can be rolled back
+    let executor = Executor::with_defaults().with_journal(Journal::open_default());
+    recover_on_startup(&executor).await?;
+    let mut runner = ScenarioRunner::new(executor);
+    if let Some(dir) = artifacts_dir {
+        runner = runner.with_artifacts_dir(dir);
+    }
+
+    // SIGINT/SIGTERM stop the run and remove active injections
+    let token = runner.cancellation_token();
+    let shutdown = tokio::spawn(async move {
+        let signal = wait_for_shutdown().await;
+        token.cancel();
+        signal
+    });
 
     // Spawn progress updater
     let pb_clone = pb.clone();
@@ -72,24 +91,76 @@ pub async fn execute(
 
     pb.finish_and_clear();
 
+    let signal = if result.is_aborted() {
+        shutdown.await.ok()
+    } else {
+        shutdown.abort();
+        None
+    };
+    if let Some(signal) = signal {
+        println!(
+            "\n{}",
+            format!(
+                "⚠ Aborted by {}: remaining phases skipped, injections removed",
+                signal.name()
+            )
+            .bold()
+            .yellow()
+        );
+    }
+
+    if let Some(reason) = &result.halt_reason {
+        println!(
+            "\n{}",
+            format!("⚠ Halted by abort rule: {}", reason)
+                .bold()
+                .yellow()
+        );
+        println!("  Remaining phases skipped, injections removed");
+    }
+
     // Display results
     println!("\n{}", "=== Test Results ===".bold().green());
     println!("Scenario: {}", result.scenario_name.cyan());
     println!("Total Duration: {:?}", result.total_duration);
     println!("Total Injections: {}", result.total_injections);
     println!("Success Rate: {:.2}%", result.success_rate() * 100.0);
+    if result.slo_violation_count() > 0 {
+        println!("SLO Violations: {}", result.slo_violation_count());
+    }
+    if let Some(before) = &result.steady_state_before {
+        ui::print_hypothesis("Steady State (before)", before);
+    }
+    if let Some(after) = &result.steady_state_after {
+        ui::print_hypothesis("Steady State (after)", after);
+    }
 
     println!("\n{}", "Phase Results:".bold());
     for phase in &result.phase_results {
         println!(
-            "  {} - Duration: {:?}, Injections: {}",
+            "  {} - Duration: {:?}, Injections: {}{}",
             phase.name.yellow(),
             phase.duration,
-            phase.injection_count
+            phase.injection_count,
+            if phase.interrupted {
+                " (interrupted)"
+            } else {
+                ""
+            }
         );
+        ui::print_phase_metrics(phase);
     }
 
-    // Save outputs
+    // Save outputs; an aborted or halted run always keeps its partial result
+    let output_json = output_json.or_else(|| {
+        (result.is_aborted() || result.is_halted()).then(|| {
+            PathBuf::from(format!(
+                "chaos_{}_{}.json",
+                result.status,
+                result.started_at.format("%Y%m%d_%H%M%S")
+            ))
+        })
+    });
     if let Some(json_path) = output_json {
         println!("\nSaving JSON report to: {}", json_path.display());
         let json = serde_json::to_string_pretty(&result)?;
@@ -110,10 +181,40 @@ pub async fn execute(
         println!("Prometheus metrics would be available on port: {}", port);
     }
 
+    if let Some(signal) = signal {
+        println!("\n{}", "✗ Chaos test aborted".bold().red());
+        return Ok(ExitCode::from(signal.exit_code()));
+    }
+
+    if result.is_halted() {
+        println!("\n{}", "✗ Chaos test halted".bold().red());
+        return Ok(ExitCode::FAILURE);
+    }
+
+    if result.status == RunStatus::Skipped {
+        println!(
+            "\n{}",
+            "✗ Steady state did not hold before the run; nothing was injected"
+                .bold()
+                .red()
+        );
+        return Ok(ExitCode::FAILURE);
+    }
+
+    if result.hypothesis_held() == Some(false) {
+        println!(
+            "\n{}",
+            "✗ Steady-state hypothesis deviated during the run"
+                .bold()
+                .red()
+        );
+        return Ok(ExitCode::FAILURE);
+    }
+
     println!(
         "\n{}",
         "✓ Chaos test completed successfully!".bold().green()
     );
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use chaos_scenarios::parse_scenario_from_file;
+use chaos_scenarios::{parse_scenario_from_file, ScenarioRunner};
 use colored::Colorize;
 use std::path::PathBuf;
 
@@ -9,6 +9,12 @@ pub async fn execute(scenario_file: PathBuf) -> Result<()> {
 
     match parse_scenario_from_file(&scenario_file).await {
         Ok(scenario) => {
+            if let Err(e) = ScenarioRunner::with_defaults().validate_injections(&scenario) {
+                println!("\n{}", "✗ Scenario is invalid!".red().bold());
+                println!("\nError: {}", e);
+                return Err(e);
+            }
+
             println!("\n{}", "✓ Scenario is valid!".green().bold());
             println!("\nScenario Details:");
             println!("  Name: {}", scenario.name);
@@ -1,10 +1,11 @@
 mod commands;
+mod signals;
 mod ui;
 
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
+use std::process::ExitCode;
 use tracing::Level;
-use tracing_subscriber;
 
 #[derive(Parser)]
 #[command(name = "chaos")]
@@ -49,6 +50,10 @@ enum Commands {
         /// Override scenario seed
         #[arg(long)]
         seed: Option<u64>,
+
+        /// Store files produced by injections (e.g. restart logs) here
+        #[arg(long)]
+        artifacts_dir: Option<PathBuf>,
     },
 
     /// Start the web dashboard
@@ -119,10 +124,17 @@ enum Commands {
 
     /// List available injectors
     List,
+
+    /// Remove injections left behind by crashed or detached runs
+    Cleanup {
+        /// Only list journaled injections, don't remove anything
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
+async fn main() -> anyhow::Result<ExitCode> {
     let cli = Cli::parse();
 
     // Setup logging
@@ -147,6 +159,7 @@ async fn main() -> anyhow::Result<()> {
             output_markdown,
             prometheus_port,
             seed,
+            artifacts_dir,
         } => {
             commands::run::execute(
                 scenario_file,
@@ -155,8 +168,9 @@ async fn main() -> anyhow::Result<()> {
                 output_markdown,
                 prometheus_port,
                 seed,
+                artifacts_dir,
             )
-            .await?;
+            .await
         }
 
         Commands::Serve {
@@ -166,6 +180,7 @@ async fn main() -> anyhow::Result<()> {
             results_dir,
         } => {
             commands::serve::execute(port, host, scenarios_dir, results_dir).await?;
+            Ok(ExitCode::SUCCESS)
         }
 
         Commands::Attach {
@@ -174,9 +189,7 @@ async fn main() -> anyhow::Result<()> {
             injection,
             duration,
             config,
-        } => {
-            commands::attach::execute(pid, address, injection, duration, config).await?;
-        }
+        } => commands::attach::execute(pid, address, injection, duration, config).await,
 
         Commands::Report {
             metrics_file,
@@ -185,16 +198,22 @@ async fn main() -> anyhow::Result<()> {
             compare,
         } => {
             commands::report::execute(metrics_file, format, output, compare).await?;
+            Ok(ExitCode::SUCCESS)
         }
 
         Commands::Validate { scenario_file } => {
             commands::validate::execute(scenario_file).await?;
+            Ok(ExitCode::SUCCESS)
         }
 
         Commands::List => {
             commands::list::execute().await?;
+            Ok(ExitCode::SUCCESS)
         }
-    }
 
-    Ok(())
+        Commands::Cleanup { dry_run } => {
+            commands::cleanup::execute(dry_run).await?;
+            Ok(ExitCode::SUCCESS)
+        }
+    }
 }
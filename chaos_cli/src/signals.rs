@@ -0,0 +1,63 @@
+// Shutdown signal handling for commands that leave faults applied while they wait
+use tracing::warn;
+
+/// Signal that asked the process to stop
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shutdown {
+    Interrupt,
+    Terminate,
+}
+
+impl Shutdown {
+    pub fn name(self) -> &'static str {
+        match self {
+            Shutdown::Interrupt => "SIGINT",
+            Shutdown::Terminate => "SIGTERM",
+        }
+    }
+
+    /// Shell convention for a process stopped by a signal: 128 + signal number
+    pub fn exit_code(self) -> u8 {
+        match self {
+            Shutdown::Interrupt => 130,
+            Shutdown::Terminate => 143,
+        }
+    }
+}
+
+/// Resolve on the first SIGINT or SIGTERM. If a handler cannot be installed
+/// this never resolves for that signal, leaving the default behaviour.
+pub async fn wait_for_shutdown() -> Shutdown {
+    let interrupt = async {
+        match tokio::signal::ctrl_c().await {
+            Ok(()) => Shutdown::Interrupt,
+            Err(e) => {
+                warn!("Failed to install SIGINT handler: {}", e);
+                std::future::pending().await
+            }
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut stream) => {
+                stream.recv().await;
+                Shutdown::Terminate
+            }
+            Err(e) => {
+                warn!("Failed to install SIGTERM handler: {}", e);
+                std::future::pending().await
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<Shutdown>();
+
+    tokio::select! {
+        signal = interrupt => signal,
+        signal = terminate => signal,
+    }
+}
@@ -26,3 +26,50 @@ pub fn print_warning(text: &str) {
 pub fn print_info(text: &str) {
     println!("{} {}", "ℹ".blue().bold(), text);
 }
+
+/// Indented metrics and SLO lines printed under a phase result
+pub fn print_phase_metrics(phase: &chaos_scenarios::runner::PhaseResult) {
+    if let Some(m) = &phase.metrics {
+        println!(
+            "    requests: {} ({:.1}% errors), p50 {:?}, p99 {:?}, max {:?}",
+            m.total_requests,
+            m.error_rate * 100.0,
+            m.latency_p50,
+            m.latency_p99,
+            m.max_latency
+        );
+    }
+    if !phase.slo_violations.is_empty() {
+        println!(
+            "    {} {} SLO violation(s)",
+            "⚠".yellow().bold(),
+            phase.slo_violations.len()
+        );
+    }
+    if let Some(hypothesis) = &phase.hypothesis {
+        print_hypothesis("    steady state", hypothesis);
+    }
+}
+
+/// One line for the hypothesis, plus one per probe that failed
+pub fn print_hypothesis(label: &str, hypothesis: &chaos_scenarios::HypothesisResult) {
+    if hypothesis.passed {
+        println!("{}: {} held", label, "✓".green().bold());
+        return;
+    }
+
+    println!("{}: {} deviated", label, "✗".red().bold());
+    for probe in hypothesis.probes.iter().filter(|p| p.failures > 0) {
+        println!(
+            "      {} failed {}/{}{}",
+            probe.name,
+            probe.failures,
+            probe.checks,
+            probe
+                .last_failure
+                .as_ref()
+                .map(|f| format!(" (last: {})", f))
+                .unwrap_or_default()
+        );
+    }
+}
@@ -0,0 +1,534 @@
+use crate::{error::*, state};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+/// Parent of every chaos-owned group, directly below the hierarchy root
+const CHAOS_PARENT: &str = "chaos";
+
+/// How often `restore` retries emptying and removing a group
+const RESTORE_PASSES: usize = 10;
+
+/// Serializes creating chaos groups with removing their parent, and guards
+/// the record of root controllers, across chaos processes
+static CGROUP_LOCK: Mutex<()> = Mutex::const_new(());
+const CGROUP_LOCK_FILE: &str = "cgroup.lock";
+/// Controllers chaos enabled in the root's `cgroup.subtree_control`, one
+/// per line, so the last group to go can disable them again
+const ROOT_CONTROLLERS_FILE: &str = "cgroup_root_controllers";
+
+/// Where the cgroup v2 hierarchy is mounted: `/sys/fs/cgroup` on unified
+/// hosts, usually `/sys/fs/cgroup/unified` on hybrid ones
+pub fn unified_root() -> Result<PathBuf> {
+    let mountinfo = std::fs::read_to_string("/proc/self/mountinfo")?;
+    cgroup2_mount(&mountinfo)
+        .ok_or_else(|| ChaosError::SystemError("No cgroup v2 hierarchy is mounted".to_string()))
+}
+
+/// Mount point of the first cgroup2 filesystem in a mountinfo listing
+fn cgroup2_mount(mountinfo: &str) -> Option<PathBuf> {
+    mountinfo.lines().find_map(|line| {
+        let (mount, filesystem) = line.split_once(" - ")?;
+        let mount_point = mount.split_whitespace().nth(4)?;
+        (filesystem.split_whitespace().next()? == "cgroup2").then(|| PathBuf::from(mount_point))
+    })
+}
+
+/// Which hierarchy a controller is attached to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CgroupVersion {
+    /// The unified hierarchy
+    V2,
+    /// A legacy hierarchy, mounted at the given path
+    V1(PathBuf),
+}
+
+/// Where `controller` (e.g. `cpu`) can be used: the v2 hierarchy if its
+/// root offers it, otherwise the v1 hierarchy it is mounted with
+pub fn controller_version(controller: &str) -> Result<CgroupVersion> {
+    let mountinfo = std::fs::read_to_string("/proc/self/mountinfo")?;
+    if let Some(root) = cgroup2_mount(&mountinfo) {
+        let available =
+            std::fs::read_to_string(root.join("cgroup.controllers")).unwrap_or_default();
+        if available.split_whitespace().any(|c| c == controller) {
+            return Ok(CgroupVersion::V2);
+        }
+    }
+    cgroup1_mount(&mountinfo, controller)
+        .map(CgroupVersion::V1)
+        .ok_or_else(|| {
+            ChaosError::SystemError(format!(
+                "The {} controller is not available in any cgroup hierarchy",
+                controller
+            ))
+        })
+}
+
+/// Mount point of the v1 hierarchy `controller` is bound to
+fn cgroup1_mount(mountinfo: &str, controller: &str) -> Option<PathBuf> {
+    mountinfo.lines().find_map(|line| {
+        let (mount, filesystem) = line.split_once(" - ")?;
+        let mount_point = mount.split_whitespace().nth(4)?;
+        let mut filesystem = filesystem.split_whitespace();
+        let (kind, options) = (filesystem.next()?, filesystem.nth(1)?);
+        (kind == "cgroup" && options.split(',').any(|option| option == controller))
+            .then(|| PathBuf::from(mount_point))
+    })
+}
+
+/// The v1 `controller` group in a `/proc/<pid>/cgroup` listing, relative
+/// to the hierarchy's mount point
+fn v1_membership<'a>(proc_cgroup: &'a str, controller: &str) -> Option<&'a str> {
+    proc_cgroup.lines().find_map(|line| {
+        let mut fields = line.splitn(3, ':');
+        let controllers = fields.nth(1)?;
+        let path = fields.next()?;
+        controllers
+            .split(',')
+            .any(|c| c == controller)
+            .then(|| path.trim_start_matches('/'))
+    })
+}
+
+/// Absolute path of the v1 `controller` group thread `tid` of process
+/// `pid` belongs to, in the hierarchy mounted at `mount`
+pub fn thread_v1_cgroup(mount: &Path, pid: u32, tid: u32, controller: &str) -> Result<PathBuf> {
+    let proc_cgroup = std::fs::read_to_string(format!("/proc/{}/task/{}/cgroup", pid, tid))
+        .map_err(|_| ChaosError::TargetNotFound(format!("Thread {} not found", tid)))?;
+    let relative = v1_membership(&proc_cgroup, controller).ok_or_else(|| {
+        ChaosError::SystemError(format!(
+            "Thread {} has no {} cgroup membership",
+            tid, controller
+        ))
+    })?;
+    Ok(mount.join(relative))
+}
+
+/// The v2 group of a process, relative to the hierarchy root
+fn v2_membership(proc_cgroup: &str) -> Option<&str> {
+    proc_cgroup
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .map(|path| path.trim_start_matches('/'))
+}
+
+/// Absolute path of the cgroup v2 group `pid` belongs to
+pub fn process_cgroup(pid: u32) -> Result<PathBuf> {
+    let proc_cgroup = std::fs::read_to_string(format!("/proc/{}/cgroup", pid))
+        .map_err(|_| ChaosError::TargetNotFound(format!("Process {} not found", pid)))?;
+    let relative = v2_membership(&proc_cgroup).ok_or_else(|| {
+        ChaosError::SystemError(format!("Process {} has no cgroup v2 membership", pid))
+    })?;
+    Ok(unified_root()?.join(relative))
+}
+
+/// A process moved into a chaos group and where it came from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CgroupMember {
+    pub pid: u32,
+    pub original: PathBuf,
+}
+
+/// A cgroup v2 group created for one injection. Processes are moved in with
+/// `adopt` and moved back to their original groups by `restore`, which also
+/// deletes the group.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChaosCgroup {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub members: Vec<CgroupMember>,
+}
+
+impl ChaosCgroup {
+    /// Create `<root>/chaos/<name>` with `controllers` (such as `io` or
+    /// `cpu`) enabled for it
+    pub async fn create(name: &str, controllers: &[&str]) -> Result<Self> {
+        let root = unified_root()?;
+        let parent = root.join(CHAOS_PARENT);
+        let _lock = state::lock(&CGROUP_LOCK, CGROUP_LOCK_FILE).await?;
+        let created = Self::create_locked(&root, &parent, name, controllers).await;
+        if created.is_err() && tokio::fs::remove_dir(&parent).await.is_ok() {
+            disable_root_controllers(&root).await?;
+        }
+        created
+    }
+
+    async fn create_locked(
+        root: &Path,
+        parent: &Path,
+        name: &str,
+        controllers: &[&str],
+    ) -> Result<Self> {
+        tokio::fs::create_dir_all(parent).await.map_err(|e| {
+            ChaosError::InjectionFailed(format!("Failed to create {}: {}", parent.display(), e))
+        })?;
+        for controller in controllers {
+            let available = tokio::fs::read_to_string(root.join("cgroup.controllers"))
+                .await
+                .unwrap_or_default();
+            if !available.split_whitespace().any(|c| c == *controller) {
+                return Err(ChaosError::SystemError(format!(
+                    "The {} controller is not available in the cgroup v2 hierarchy at {} \
+                     (still bound to cgroup v1?)",
+                    controller,
+                    root.display()
+                )));
+            }
+            // Remember what we change on the host's root so it can be undone
+            let enabled = tokio::fs::read_to_string(root.join("cgroup.subtree_control"))
+                .await
+                .unwrap_or_default();
+            if !enabled.split_whitespace().any(|c| c == *controller) {
+                let mut recorded = root_controllers().await;
+                if !recorded.iter().any(|c| c == controller) {
+                    recorded.push(controller.to_string());
+                    set_root_controllers(&recorded).await?;
+                }
+                write_file(
+                    &root.join("cgroup.subtree_control"),
+                    &format!("+{}", controller),
+                )
+                .await?;
+                info!(
+                    "Enabled the {} controller below {}",
+                    controller,
+                    root.display()
+                );
+            }
+            write_file(
+                &parent.join("cgroup.subtree_control"),
+                &format!("+{}", controller),
+            )
+            .await?;
+        }
+
+        let path = parent.join(name);
+        tokio::fs::create_dir(&path).await.map_err(|e| {
+            ChaosError::InjectionFailed(format!(
+                "Failed to create cgroup {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        info!("Created cgroup {}", path.display());
+        Ok(Self {
+            path,
+            members: Vec::new(),
+        })
+    }
+
+    /// Move `pid` and its threads into this group
+    pub async fn adopt(&mut self, pid: u32) -> Result<()> {
+        let original = process_cgroup(pid)?;
+        write_file(&self.path.join("cgroup.procs"), &pid.to_string()).await?;
+        self.members.push(CgroupMember { pid, original });
+        Ok(())
+    }
+
+    /// Create `name` below this group as a threaded child, so single
+    /// threads of its members can be moved there through `cgroup.threads`.
+    /// `controllers` must be threaded ones, such as `cpu` or `pids`.
+    pub async fn threaded_child(&self, name: &str, controllers: &[&str]) -> Result<PathBuf> {
+        let path = self.path.join(name);
+        tokio::fs::create_dir(&path).await.map_err(|e| {
+            ChaosError::InjectionFailed(format!(
+                "Failed to create cgroup {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        write_file(&path.join("cgroup.type"), "threaded").await?;
+        for controller in controllers {
+            self.write("cgroup.subtree_control", &format!("+{}", controller))
+                .await?;
+        }
+        Ok(path)
+    }
+
+    /// Write a control file of this group, e.g. `io.max`
+    pub async fn write(&self, file: &str, value: &str) -> Result<()> {
+        write_file(&self.path.join(file), value).await
+    }
+
+    pub async fn read(&self, file: &str) -> Result<String> {
+        Ok(tokio::fs::read_to_string(self.path.join(file)).await?)
+    }
+
+    /// Read the group back from a handle's metadata
+    pub fn from_metadata(metadata: &serde_json::Value) -> Result<Self> {
+        metadata
+            .get("cgroup")
+            .cloned()
+            .and_then(|cgroup| serde_json::from_value(cgroup).ok())
+            .ok_or_else(|| ChaosError::CleanupFailed("Missing cgroup metadata".to_string()))
+    }
+
+    /// Handle metadata recording this group under `cgroup` alongside `extra`
+    pub fn metadata(&self, extra: serde_json::Value) -> serde_json::Value {
+        let mut metadata = match extra {
+            serde_json::Value::Object(extra) => extra,
+            _ => serde_json::Map::new(),
+        };
+        metadata.insert(
+            "cgroup".to_string(),
+            serde_json::to_value(self).unwrap_or_default(),
+        );
+        serde_json::Value::Object(metadata)
+    }
+
+    /// Move every process back to where its member came from (or the
+    /// closest surviving ancestor) and delete the group
+    pub async fn restore(&self) -> Result<()> {
+        if !self.path.exists() {
+            info!("cgroup {} is already gone", self.path.display());
+            return Ok(());
+        }
+        // Children forked inside the group follow the first member home
+        let fallback = self
+            .members
+            .first()
+            .map(|member| member.original.clone())
+            .or_else(|| self.path.ancestors().nth(2).map(Path::to_path_buf))
+            .unwrap_or_default();
+        // A member may fork while we migrate, so repeat until the group is empty
+        for _ in 0..RESTORE_PASSES {
+            let procs = tokio::fs::read_to_string(self.path.join("cgroup.procs"))
+                .await
+                .unwrap_or_default();
+            let pids: Vec<u32> = procs
+                .lines()
+                .filter_map(|pid| pid.trim().parse().ok())
+                .collect();
+            if pids.is_empty() {
+                break;
+            }
+            for pid in pids {
+                let original = self
+                    .members
+                    .iter()
+                    .find(|member| member.pid == pid)
+                    .map_or(fallback.as_path(), |member| member.original.as_path());
+                let home = surviving_ancestor(original);
+                if let Err(e) = write_file(&home.join("cgroup.procs"), &pid.to_string()).await {
+                    // The process may have exited meanwhile
+                    warn!("Could not move {} back to {}: {}", pid, home.display(), e);
+                }
+            }
+        }
+
+        // Nested groups, such as threaded children, go first
+        if let Ok(mut entries) = tokio::fs::read_dir(&self.path).await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                if entry.file_type().await.is_ok_and(|kind| kind.is_dir()) {
+                    let _ = tokio::fs::remove_dir(entry.path()).await;
+                }
+            }
+        }
+
+        // Exiting tasks linger in the group for a moment
+        let mut attempt = 0;
+        while let Err(e) = tokio::fs::remove_dir(&self.path).await {
+            attempt += 1;
+            if e.raw_os_error() != Some(libc::EBUSY) || attempt >= RESTORE_PASSES {
+                return Err(ChaosError::CleanupFailed(format!(
+                    "Failed to remove cgroup {}: {}",
+                    self.path.display(),
+                    e
+                )));
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        info!("Removed cgroup {}", self.path.display());
+
+        // Only succeeds once no other injection uses it; the last one out
+        // disables the root controllers chaos enabled
+        if let Some(parent) = self.path.parent() {
+            let _lock = state::lock(&CGROUP_LOCK, CGROUP_LOCK_FILE).await?;
+            if tokio::fs::remove_dir(parent).await.is_ok() {
+                if let Some(root) = parent.parent() {
+                    disable_root_controllers(root).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Controllers chaos enabled on the root and has not disabled yet
+async fn root_controllers() -> Vec<String> {
+    tokio::fs::read_to_string(state::runtime_dir().join(ROOT_CONTROLLERS_FILE))
+        .await
+        .unwrap_or_default()
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+async fn set_root_controllers(controllers: &[String]) -> Result<()> {
+    let path = state::runtime_dir().join(ROOT_CONTROLLERS_FILE);
+    if controllers.is_empty() {
+        return match tokio::fs::remove_file(&path).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        };
+    }
+    let mut contents = controllers.join("\n");
+    contents.push('\n');
+    tokio::fs::write(&path, contents).await?;
+    Ok(())
+}
+
+/// Disable the recorded controllers below `root`. Any still needed by a
+/// group created since stay enabled and recorded for a later attempt.
+async fn disable_root_controllers(root: &Path) -> Result<()> {
+    let mut kept = Vec::new();
+    for controller in root_controllers().await {
+        let control = root.join("cgroup.subtree_control");
+        match write_file(&control, &format!("-{}", controller)).await {
+            Ok(()) => info!(
+                "Disabled the {} controller below {}",
+                controller,
+                root.display()
+            ),
+            Err(e) => {
+                warn!("{}", e);
+                kept.push(controller);
+            }
+        }
+    }
+    set_root_controllers(&kept).await
+}
+
+/// `path`, or its closest ancestor that still exists
+pub(crate) fn surviving_ancestor(path: &Path) -> &Path {
+    path.ancestors()
+        .find(|ancestor| ancestor.join("cgroup.procs").exists())
+        .unwrap_or(path)
+}
+
+pub(crate) async fn write_file(path: &Path, value: &str) -> Result<()> {
+    tokio::fs::write(path, value).await.map_err(|e| {
+        ChaosError::InjectionFailed(format!(
+            "Failed to write '{}' to {}: {}",
+            value,
+            path.display(),
+            e
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cgroup_files() {
+        let mountinfo = "\
+            25 30 0:23 / /sys rw,nosuid shared:7 - sysfs sysfs rw\n\
+            35 25 0:30 / /sys/fs/cgroup/unified rw,nosuid shared:10 - cgroup2 cgroup2 rw,nsdelegate\n\
+            36 25 0:31 / /sys/fs/cgroup/cpu rw shared:11 - cgroup cgroup rw,cpu\n";
+        assert_eq!(
+            cgroup2_mount(mountinfo),
+            Some(PathBuf::from("/sys/fs/cgroup/unified"))
+        );
+        assert_eq!(
+            cgroup2_mount("25 30 0:23 / /sys rw - sysfs sysfs rw\n"),
+            None
+        );
+
+        let proc_cgroup = "4:memory:/docker/abc\n0::/system.slice/nginx.service\n";
+        assert_eq!(
+            v2_membership(proc_cgroup),
+            Some("system.slice/nginx.service")
+        );
+        assert_eq!(v2_membership("0::/\n"), Some(""));
+        assert_eq!(v2_membership("4:memory:/\n"), None);
+
+        assert_eq!(
+            cgroup1_mount(mountinfo, "cpu"),
+            Some(PathBuf::from("/sys/fs/cgroup/cpu"))
+        );
+        assert_eq!(cgroup1_mount(mountinfo, "cpuset"), None);
+        let combined =
+            "37 25 0:32 / /sys/fs/cgroup/cpu,cpuacct rw - cgroup cgroup rw,cpu,cpuacct\n";
+        assert_eq!(
+            cgroup1_mount(combined, "cpuacct"),
+            Some(PathBuf::from("/sys/fs/cgroup/cpu,cpuacct"))
+        );
+        assert_eq!(
+            v1_membership("5:cpu,cpuacct:/user.slice\n0::/\n", "cpu"),
+            Some("user.slice")
+        );
+        assert_eq!(v1_membership("0::/user.slice\n", "cpu"), None);
+    }
+
+    #[tokio::test]
+    async fn test_adopt_and_restore() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let name = format!("test_{}", uuid::Uuid::new_v4());
+        let mut cgroup = match ChaosCgroup::create(&name, &[]).await {
+            Ok(cgroup) => cgroup,
+            Err(e) => {
+                // Needs a writable cgroup v2 hierarchy
+                eprintln!("skipping cgroup test: {}", e);
+                child.kill().unwrap();
+                child.wait().unwrap();
+                return;
+            }
+        };
+        let original = process_cgroup(child.id()).unwrap();
+        cgroup.adopt(child.id()).await.unwrap();
+        assert_eq!(process_cgroup(child.id()).unwrap(), cgroup.path);
+
+        let metadata = cgroup.metadata(serde_json::json!({"pid": child.id()}));
+        assert_eq!(metadata["pid"], child.id());
+        let restored = ChaosCgroup::from_metadata(&metadata).unwrap();
+        restored.restore().await.unwrap();
+        assert_eq!(process_cgroup(child.id()).unwrap(), original);
+        assert!(!cgroup.path.exists());
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_root_controllers_restored() {
+        let Ok(root) = unified_root() else {
+            eprintln!("skipping cgroup controller test: no cgroup v2 hierarchy");
+            return;
+        };
+        let read = |file: &str| std::fs::read_to_string(root.join(file)).unwrap_or_default();
+        let enabled = |controller: &str| {
+            read("cgroup.subtree_control")
+                .split_whitespace()
+                .any(|c| c == controller)
+        };
+        let available = read("cgroup.controllers");
+        let Some(controller) = available.split_whitespace().find(|c| !enabled(c)) else {
+            eprintln!("skipping cgroup controller test: every controller is enabled");
+            return;
+        };
+
+        let name = format!("test_{}", uuid::Uuid::new_v4());
+        let cgroup = match ChaosCgroup::create(&name, &[controller]).await {
+            Ok(cgroup) => cgroup,
+            Err(e) => {
+                eprintln!("skipping cgroup controller test: {}", e);
+                return;
+            }
+        };
+        assert!(enabled(controller));
+        cgroup.restore().await.unwrap();
+        if root.join(CHAOS_PARENT).exists() {
+            // Another injection still uses the parent; the last one out
+            // disables the controller
+            eprintln!("skipping cgroup controller check: chaos groups still in use");
+            return;
+        }
+        assert!(!enabled(controller));
+    }
+}
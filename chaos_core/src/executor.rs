@@ -1,17 +1,48 @@
 use crate::{
-    error::Result,
-    handle::{InjectionHandle, InjectionState},
-    injectors::InjectorRegistry,
+    error::{ChaosError, Result},
+    handle::{InjectionHandle, InjectionReport, InjectionState},
+    injectors::{DynInjector, InjectorParams, InjectorRegistry},
+    journal::{Journal, JournalEntry},
     target::Target,
 };
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::info;
+use tracing::{info, warn};
 
 pub struct Executor {
     registry: Arc<InjectorRegistry>,
     active_injections: Arc<RwLock<HashMap<String, InjectionState>>>,
+    /// Configured injector instance behind each active handle, so removal
+    /// reaches the same instance that applied the fault
+    instances: Arc<RwLock<HashMap<String, ActiveInstance>>>,
+    journal: Option<Journal>,
+}
+
+struct ActiveInstance {
+    injector: DynInjector,
+    journal_id: Option<String>,
+}
+
+/// Outcome of replaying the journal after a crash
+#[derive(Debug, Default)]
+pub struct RecoveryReport {
+    /// Orphaned injections that were removed
+    pub removed: Vec<JournalEntry>,
+    /// Orphaned injections whose removal failed; they stay in the journal
+    pub failed: Vec<(JournalEntry, String)>,
+    /// Entries without a handle: the owner stopped mid-inject, so what was
+    /// applied is unknown and must be checked by hand
+    pub incomplete: Vec<JournalEntry>,
+    /// Entries still owned by a live process, or detached ones when those
+    /// were not requested
+    pub skipped: Vec<JournalEntry>,
+}
+
+impl RecoveryReport {
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.failed.is_empty() && self.incomplete.is_empty()
+    }
 }
 
 impl Executor {
@@ -19,20 +50,39 @@ impl Executor {
         Self {
             registry: Arc::new(registry),
             active_injections: Arc::new(RwLock::new(HashMap::new())),
+            instances: Arc::new(RwLock::new(HashMap::new())),
+            journal: None,
         }
     }
 
+    /// Record every injection in `journal` so it can be rolled back if this
+    /// process dies before removing it
+    pub fn with_journal(mut self, journal: Journal) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    pub fn journal(&self) -> Option<&Journal> {
+        self.journal.as_ref()
+    }
+
     pub fn with_defaults() -> Self {
         Self::new(InjectorRegistry::with_defaults())
     }
 
     pub async fn inject(&self, injector_name: &str, target: &Target) -> Result<InjectionHandle> {
-        let injector = self.registry.get(injector_name).ok_or_else(|| {
-            crate::error::ChaosError::InvalidConfig(format!(
-                "Injector '{}' not found",
-                injector_name
-            ))
-        })?;
+        self.inject_with_params(injector_name, target, &InjectorParams::new())
+            .await
+    }
+
+    /// Apply an injection using an injector configured from `params`
+    pub async fn inject_with_params(
+        &self,
+        injector_name: &str,
+        target: &Target,
+        params: &InjectorParams,
+    ) -> Result<InjectionHandle> {
+        let injector = self.registry.create(injector_name, params)?;
 
         info!(
             "Applying injection '{}' to target: {}",
@@ -40,29 +90,81 @@ impl Executor {
             target.description()
         );
 
-        let handle = injector.inject(target).await?;
+        let journal_id = match &self.journal {
+            Some(journal) => Some(
+                journal
+                    .record_intent(injector_name, target, params)
+                    .await
+                    .map_err(|e| {
+                        ChaosError::InjectionFailed(format!(
+                            "Refusing to inject without a journal entry: {}",
+                            e
+                        ))
+                    })?,
+            ),
+            None => None,
+        };
+
+        let handle = match injector.inject(target).await {
+            Ok(handle) => handle,
+            Err(e) => {
+                if let (Some(journal), Some(id)) = (&self.journal, &journal_id) {
+                    journal.clear(id).await.ok();
+                }
+                return Err(e);
+            }
+        };
+
+        if let (Some(journal), Some(id)) = (&self.journal, &journal_id) {
+            if let Err(e) = journal.record_applied(id, &handle).await {
+                warn!("Failed to journal injection '{}': {}", handle.id, e);
+            }
+        }
+
         let state = InjectionState::new(handle.clone());
 
         self.active_injections
             .write()
             .await
             .insert(handle.id.clone(), state);
+        self.instances.write().await.insert(
+            handle.id.clone(),
+            ActiveInstance {
+                injector,
+                journal_id,
+            },
+        );
 
         Ok(handle)
     }
 
+    /// Check that `params` are valid for the named injector without applying it
+    pub fn validate_params(&self, injector_name: &str, params: &InjectorParams) -> Result<()> {
+        self.registry.create(injector_name, params).map(|_| ())
+    }
+
     pub async fn remove(&self, handle: InjectionHandle) -> Result<()> {
-        let injector = self.registry.get(&handle.injector_name).ok_or_else(|| {
-            crate::error::ChaosError::InvalidConfig(format!(
-                "Injector '{}' not found",
-                handle.injector_name
-            ))
-        })?;
+        let instance = self
+            .instances
+            .read()
+            .await
+            .get(&handle.id)
+            .map(|i| (i.injector.clone(), i.journal_id.clone()));
+        let (injector, journal_id) = match instance {
+            Some(instance) => instance,
+            None => self.rebuild(&handle).await?,
+        };
 
         info!("Removing injection '{}'", handle.id);
 
         injector.remove(handle.clone()).await?;
 
+        self.instances.write().await.remove(&handle.id);
+        if let (Some(journal), Some(id)) = (&self.journal, journal_id) {
+            if let Err(e) = journal.clear(&id).await {
+                warn!("Failed to clear journal entry for '{}': {}", handle.id, e);
+            }
+        }
         if let Some(state) = self.active_injections.write().await.remove(&handle.id) {
             state.deactivate().await;
         }
@@ -70,6 +172,36 @@ impl Executor {
         Ok(())
     }
 
+    /// Injector for a handle this executor did not apply, configured from
+    /// the parameters journaled with it when there is an entry
+    async fn rebuild(&self, handle: &InjectionHandle) -> Result<(DynInjector, Option<String>)> {
+        if let Some(journal) = &self.journal {
+            let entry = journal
+                .entries()
+                .await?
+                .into_iter()
+                .find(|e| e.handle.as_ref().is_some_and(|h| h.id == handle.id));
+            if let Some(entry) = entry {
+                let injector = self.registry.create(&entry.injector_name, &entry.params)?;
+                return Ok((injector, Some(entry.id)));
+            }
+        }
+
+        let injector = match self.registry.get(&handle.injector_name) {
+            Some(injector) => injector.clone(),
+            None => self
+                .registry
+                .create(&handle.injector_name, &InjectorParams::new())
+                .map_err(|e| {
+                    ChaosError::InvalidConfig(format!(
+                        "Cannot remove '{}' without the parameters it was applied with: {}",
+                        handle.id, e
+                    ))
+                })?,
+        };
+        Ok((injector, None))
+    }
+
     pub async fn remove_all(&self) -> Result<()> {
         info!("Removing all active injections");
 
@@ -90,6 +222,87 @@ impl Executor {
         Ok(())
     }
 
+    /// Stop tracking an injection that should outlive this process. Its
+    /// journal entry is kept but marked detached, so only an explicit
+    /// cleanup removes it.
+    pub async fn detach(&self, handle: &InjectionHandle) -> Result<()> {
+        let instance = self.instances.write().await.remove(&handle.id);
+        self.active_injections.write().await.remove(&handle.id);
+
+        if let (Some(journal), Some(id)) = (&self.journal, instance.and_then(|i| i.journal_id)) {
+            journal.mark_detached(&id).await?;
+        }
+        Ok(())
+    }
+
+    /// Replay `remove` for every journaled injection whose owning process is
+    /// gone. Detached injections are only removed when `include_detached` is
+    /// set.
+    pub async fn recover(&self, include_detached: bool) -> Result<RecoveryReport> {
+        let mut report = RecoveryReport::default();
+        let Some(journal) = &self.journal else {
+            return Ok(report);
+        };
+
+        for entry in journal.entries().await? {
+            if !entry.is_orphaned().await || (entry.detached && !include_detached) {
+                report.skipped.push(entry);
+                continue;
+            }
+
+            let Some(handle) = entry.handle.clone() else {
+                warn!(
+                    "Injection '{}' on {} was interrupted while being applied; check it manually",
+                    entry.injector_name,
+                    entry.target.description()
+                );
+                journal.clear(&entry.id).await?;
+                report.incomplete.push(entry);
+                continue;
+            };
+
+            info!(
+                "Rolling back orphaned injection '{}' ({}) on {}",
+                handle.id,
+                entry.injector_name,
+                entry.target.description()
+            );
+
+            let removed = match self.registry.create(&entry.injector_name, &entry.params) {
+                Ok(injector) => injector.remove(handle).await,
+                Err(e) => Err(e),
+            };
+
+            match removed {
+                Ok(()) => {
+                    journal.clear(&entry.id).await?;
+                    report.removed.push(entry);
+                }
+                Err(e) => {
+                    warn!("Failed to roll back '{}': {}", entry.id, e);
+                    report.failed.push((entry, e.to_string()));
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// What an active injection has observed so far; empty once it is
+    /// removed
+    pub async fn report(&self, handle: &InjectionHandle) -> InjectionReport {
+        let injector = self
+            .instances
+            .read()
+            .await
+            .get(&handle.id)
+            .map(|i| i.injector.clone());
+        match injector {
+            Some(injector) => injector.report(handle).await,
+            None => InjectionReport::default(),
+        }
+    }
+
     pub async fn list_active(&self) -> Vec<InjectionHandle> {
         self.active_injections
             .read()
@@ -127,4 +340,163 @@ mod tests {
         let executor = Executor::with_defaults();
         assert_eq!(executor.list_active().await.len(), 0);
     }
+
+    struct CountingInjector {
+        removed: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl crate::injectors::Injector for CountingInjector {
+        async fn inject(&self, target: &Target) -> Result<InjectionHandle> {
+            Ok(InjectionHandle::new(
+                "counting",
+                target.clone(),
+                serde_json::json!({}),
+            ))
+        }
+
+        async fn remove(&self, _handle: InjectionHandle) -> Result<()> {
+            self.removed
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            "counting"
+        }
+    }
+
+    fn counting_executor(dir: &std::path::Path) -> (Executor, Arc<std::sync::atomic::AtomicUsize>) {
+        let removed = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut registry = InjectorRegistry::new();
+        registry.register(
+            "counting",
+            Arc::new(CountingInjector {
+                removed: removed.clone(),
+            }),
+        );
+        (
+            Executor::new(registry).with_journal(Journal::new(dir)),
+            removed,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_journal_cleared_after_remove() {
+        let dir = std::env::temp_dir().join(format!("chaos_exec_test_{}", uuid::Uuid::new_v4()));
+        let (executor, removed) = counting_executor(&dir);
+        let journal = executor.journal().unwrap().clone();
+
+        let handle = executor
+            .inject("counting", &Target::process(1))
+            .await
+            .unwrap();
+        let entries = journal.entries().await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].handle.as_ref().unwrap().id, handle.id);
+
+        executor.remove(handle).await.unwrap();
+        assert_eq!(removed.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert!(journal.entries().await.unwrap().is_empty());
+
+        tokio::fs::remove_dir_all(&dir).await.ok();
+    }
+
+    #[tokio::test]
+    async fn test_recover_orphaned_injections() {
+        let dir = std::env::temp_dir().join(format!("chaos_exec_test_{}", uuid::Uuid::new_v4()));
+        let (executor, removed) = counting_executor(&dir);
+        let journal = executor.journal().unwrap().clone();
+
+        // An injection left behind by a process that no longer exists
+        let target = Target::process(1);
+        let id = journal
+            .record_intent("counting", &target, &InjectorParams::new())
+            .await
+            .unwrap();
+        let handle = InjectionHandle::new("counting", target.clone(), serde_json::json!({}));
+        journal.record_applied(&id, &handle).await.unwrap();
+        rewrite_owner(&dir, &id, 999_999).await;
+
+        // One still owned by this (live) process
+        executor.inject("counting", &target).await.unwrap();
+
+        let report = executor.recover(false).await.unwrap();
+        assert_eq!(report.removed.len(), 1);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(removed.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert_eq!(journal.entries().await.unwrap().len(), 1);
+
+        tokio::fs::remove_dir_all(&dir).await.ok();
+    }
+
+    #[tokio::test]
+    async fn test_remove_rebuilds_from_journal() {
+        let dir = std::env::temp_dir().join(format!("chaos_exec_test_{}", uuid::Uuid::new_v4()));
+        let removed = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let registry = || {
+            let removed = removed.clone();
+            let mut registry = InjectorRegistry::new();
+            // No default instance: the factory needs a parameter
+            registry.register_factory("counting", move |p: &InjectorParams| {
+                if !p.contains_key("required") {
+                    return Err(ChaosError::InvalidConfig("required is missing".to_string()));
+                }
+                Ok(Arc::new(CountingInjector {
+                    removed: removed.clone(),
+                }) as DynInjector)
+            });
+            registry
+        };
+        let params: InjectorParams =
+            serde_json::from_value(serde_json::json!({"required": true})).unwrap();
+
+        let applier = Executor::new(registry()).with_journal(Journal::new(&dir));
+        let handle = applier
+            .inject_with_params("counting", &Target::process(1), &params)
+            .await
+            .unwrap();
+
+        // A fresh executor has no instance behind the handle
+        let remover = Executor::new(registry()).with_journal(Journal::new(&dir));
+        remover.remove(handle.clone()).await.unwrap();
+        assert_eq!(removed.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert!(remover
+            .journal()
+            .unwrap()
+            .entries()
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Without a journal the parameters are unknown
+        assert!(Executor::new(registry()).remove(handle).await.is_err());
+
+        tokio::fs::remove_dir_all(&dir).await.ok();
+    }
+
+    async fn rewrite_owner(dir: &std::path::Path, id: &str, pid: u32) {
+        let path = dir.join(format!("{}.json", id));
+        let mut entry: JournalEntry =
+            serde_json::from_slice(&tokio::fs::read(&path).await.unwrap()).unwrap();
+        entry.owner_pid = pid;
+        tokio::fs::write(&path, serde_json::to_vec(&entry).unwrap())
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_validate_params() {
+        let executor = Executor::with_defaults();
+        let params: InjectorParams =
+            serde_json::from_value(serde_json::json!({"intensity": 0.5})).unwrap();
+
+        assert!(executor.validate_params("cpu_starvation", &params).is_ok());
+        assert!(executor
+            .validate_params("memory_pressure", &params)
+            .is_err());
+        assert!(executor
+            .validate_params("no_such_injector", &params)
+            .is_err());
+    }
 }
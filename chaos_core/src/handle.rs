@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,6 +33,21 @@ impl InjectionHandle {
     }
 }
 
+/// What an active injection has observed since it was applied
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InjectionReport {
+    /// Time from each fault until the target was healthy again
+    pub recoveries: Vec<Duration>,
+    /// Where the target lives now, if the injection replaced it (e.g. a
+    /// killed process restarted under a new PID)
+    pub retarget: Option<crate::target::Target>,
+    /// Files written while the injection was active, such as captured logs
+    pub artifacts: Vec<PathBuf>,
+    /// Named values measured while the injection was active, such as the
+    /// CPU utilization it achieved
+    pub measurements: Vec<(String, f64)>,
+}
+
 #[derive(Debug, Clone)]
 pub struct InjectionState {
     handle: InjectionHandle,
@@ -0,0 +1,85 @@
+use crate::error::{ChaosError, Result};
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::info;
+
+/// Status code and latency of a single HTTP `GET`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HttpCheck {
+    pub status: u16,
+    pub latency: Duration,
+}
+
+impl HttpCheck {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// `GET` the URL once. Any response, whatever its status, is `Ok`; only
+/// connection failures and timeouts are errors.
+pub async fn http_check(
+    client: &reqwest::Client,
+    url: &str,
+    timeout: Duration,
+) -> Result<HttpCheck> {
+    let start = Instant::now();
+    let response = client.get(url).timeout(timeout).send().await.map_err(|e| {
+        if e.is_timeout() {
+            ChaosError::NetworkError(format!("{} timed out after {:?}", url, timeout))
+        } else {
+            ChaosError::NetworkError(format!("{} unreachable: {}", url, e))
+        }
+    })?;
+
+    Ok(HttpCheck {
+        status: response.status().as_u16(),
+        latency: start.elapsed(),
+    })
+}
+
+/// Poll the URL once per `interval` until it answers 2xx, up to `attempts`
+/// times. Returns whether it became healthy.
+pub async fn wait_for_http_health(url: &str, attempts: u32, interval: Duration) -> bool {
+    info!("Waiting for health check: {}", url);
+    let client = reqwest::Client::new();
+
+    for attempt in 1..=attempts {
+        match http_check(&client, url, interval.max(Duration::from_secs(1))).await {
+            Ok(check) if check.is_success() => {
+                info!("Health check passed");
+                return true;
+            }
+            _ => {
+                if attempt % 5 == 0 {
+                    info!("Health check attempt {}/{}...", attempt, attempts);
+                }
+                tokio::time::sleep(interval).await;
+            }
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_unreachable_url() {
+        let port = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let url = format!("http://127.0.0.1:{}/health", port);
+
+        let client = reqwest::Client::new();
+        assert!(http_check(&client, &url, Duration::from_secs(1))
+            .await
+            .is_err());
+        assert!(!wait_for_http_health(&url, 1, Duration::from_millis(10)).await);
+    }
+}
@@ -0,0 +1,494 @@
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+mod vdso;
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+use crate::tracer::{self, Registers, StoppedThread, SyscallHook, Tracer};
+use crate::{
+    error::*,
+    handle::InjectionHandle,
+    injectors::{params, Injector, InjectorParams},
+    target::Target,
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+use std::time::Instant;
+use tracing::info;
+
+const NANOS_PER_SEC: i128 = 1_000_000_000;
+
+/// Clocks that follow the wall clock; monotonic and CPU clocks are left alone
+#[cfg_attr(
+    not(all(target_os = "linux", target_arch = "x86_64")),
+    allow(dead_code)
+)]
+const WALL_CLOCKS: &[libc::clockid_t] = &[
+    libc::CLOCK_REALTIME,
+    libc::CLOCK_REALTIME_COARSE,
+    libc::CLOCK_REALTIME_ALARM,
+    libc::CLOCK_TAI,
+];
+
+/// How far a process's wall clock is moved from the host's
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ClockSkewConfig {
+    /// Initial shift
+    pub offset: Duration,
+    /// Shift into the past rather than the future
+    #[serde(default)]
+    pub behind: bool,
+    /// Seconds gained per real second, so `0.5` makes the clock run 50% fast
+    /// and `-0.1` 10% slow
+    #[serde(default)]
+    pub drift: f64,
+}
+
+impl ClockSkewConfig {
+    /// Total shift in nanoseconds `elapsed` after the injection started
+    fn skew_nanos(&self, elapsed: Duration) -> i128 {
+        let offset = self.offset.as_nanos() as i128;
+        let offset = if self.behind { -offset } else { offset };
+        offset + (elapsed.as_nanos() as f64 * self.drift) as i128
+    }
+}
+
+/// `seconds` plus a fraction in units of `unit` nanoseconds, moved by
+/// `skew` nanoseconds
+#[cfg_attr(
+    not(all(target_os = "linux", target_arch = "x86_64")),
+    allow(dead_code)
+)]
+fn shift_time(seconds: i64, fraction: i64, unit: i64, skew: i128) -> (i64, i64) {
+    let total = seconds as i128 * NANOS_PER_SEC + (fraction * unit) as i128 + skew;
+    (
+        total.div_euclid(NANOS_PER_SEC) as i64,
+        (total.rem_euclid(NANOS_PER_SEC) / unit as i128) as i64,
+    )
+}
+
+/// Makes a running process see a shifted or drifting wall clock while the
+/// host clock stays untouched. Time namespaces only offset the monotonic and
+/// boot clocks, so the process is traced instead: its vDSO time functions
+/// are turned into real syscalls and their results rewritten.
+#[derive(Default)]
+pub struct ClockSkewInjector {
+    config: ClockSkewConfig,
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    tracers: Mutex<HashMap<String, Tracer>>,
+    #[cfg(not(all(target_os = "linux", target_arch = "x86_64")))]
+    #[allow(dead_code)]
+    tracers: Mutex<HashMap<String, ()>>,
+}
+
+impl ClockSkewInjector {
+    pub fn new(config: ClockSkewConfig) -> Self {
+        Self {
+            config,
+            tracers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn builder() -> ClockSkewBuilder {
+        ClockSkewBuilder::default()
+    }
+
+    pub fn from_params(params: &InjectorParams) -> Result<Self> {
+        let params: ClockSkewParams = params::parse_params("clock_skew", params)?;
+        let (offset, behind) = params.offset.unwrap_or_default();
+        let injector = Self::new(ClockSkewConfig {
+            offset,
+            behind,
+            drift: params.drift.unwrap_or(0.0),
+        });
+        injector.validate_config()?;
+        Ok(injector)
+    }
+
+    fn validate_config(&self) -> Result<()> {
+        let drift = self.config.drift;
+        if !drift.is_finite() || drift <= -1.0 {
+            return Err(ChaosError::InvalidConfig(format!(
+                "clock_skew drift must be above -1.0 so the clock keeps moving forward, got {}",
+                drift
+            )));
+        }
+        Ok(())
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    async fn inject_linux(&self, target: &Target) -> Result<InjectionHandle> {
+        self.validate_config()?;
+        if self.config.offset.is_zero() && self.config.drift == 0.0 {
+            return Err(ChaosError::InvalidConfig(
+                "clock_skew requires an offset or a drift".to_string(),
+            ));
+        }
+        let pid = target.pid().await?;
+        info!(
+            "Skewing the clock of {} by {}{:?} (drift {})",
+            target.description(),
+            if self.config.behind { "-" } else { "+" },
+            self.config.offset,
+            self.config.drift
+        );
+
+        let hook = ClockHook {
+            config: self.config.clone(),
+            started: Instant::now(),
+            vdso: None,
+        };
+        let tracer = tokio::task::spawn_blocking(move || Tracer::attach(pid, hook))
+            .await
+            .map_err(|e| ChaosError::InjectionFailed(e.to_string()))??;
+
+        let metadata = serde_json::json!({
+            "pid": pid,
+            "offset_ms": self.config.skew_nanos(Duration::ZERO) / 1_000_000,
+            "drift": self.config.drift,
+        });
+        let handle = InjectionHandle::new("clock_skew", target.clone(), metadata);
+        self.tracers
+            .lock()
+            .unwrap()
+            .insert(handle.id.clone(), tracer);
+        Ok(handle)
+    }
+
+    #[cfg(not(all(target_os = "linux", target_arch = "x86_64")))]
+    async fn inject_linux(&self, _target: &Target) -> Result<InjectionHandle> {
+        Err(ChaosError::SystemError(
+            "Clock skew injection is only supported on x86_64 Linux".to_string(),
+        ))
+    }
+}
+
+/// Shifts the results of wall clock syscalls
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+struct ClockHook {
+    config: ClockSkewConfig,
+    started: Instant,
+    vdso: Option<vdso::VdsoPatch>,
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+impl ClockHook {
+    /// Rewrite a `timespec` (`unit` 1) or `timeval` (`unit` 1000) at `address`
+    fn shift_struct(&self, pid: u32, address: u64, unit: i64) {
+        let Ok(bytes) = tracer::read_memory(pid, address, 16) else {
+            return;
+        };
+        let field = |i: usize| i64::from_le_bytes(bytes[i * 8..i * 8 + 8].try_into().unwrap());
+        let skew = self.config.skew_nanos(self.started.elapsed());
+        let (seconds, fraction) = shift_time(field(0), field(1), unit, skew);
+        let mut shifted = seconds.to_le_bytes().to_vec();
+        shifted.extend_from_slice(&fraction.to_le_bytes());
+        let _ = tracer::write_memory(pid, address, &shifted);
+    }
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+impl SyscallHook for ClockHook {
+    fn attached(&mut self, pid: u32) -> Result<()> {
+        self.vdso = vdso::VdsoPatch::apply(pid)?;
+        Ok(())
+    }
+
+    fn exit(&mut self, pid: u32, regs: &mut Registers) -> bool {
+        let succeeded = regs.rax as i64 >= 0;
+        match regs.orig_rax as i64 {
+            libc::SYS_clock_gettime
+                if succeeded
+                    && regs.rsi != 0
+                    && WALL_CLOCKS.contains(&(regs.rdi as libc::clockid_t)) =>
+            {
+                self.shift_struct(pid, regs.rsi, 1);
+                false
+            }
+            libc::SYS_gettimeofday if succeeded && regs.rdi != 0 => {
+                self.shift_struct(pid, regs.rdi, 1000);
+                false
+            }
+            libc::SYS_time if succeeded => {
+                let skew = self.config.skew_nanos(self.started.elapsed());
+                let (seconds, _) = shift_time(regs.rax as i64, 0, 1, skew);
+                regs.rax = seconds as u64;
+                if regs.rdi != 0 {
+                    let _ = tracer::write_memory(pid, regs.rdi, &seconds.to_le_bytes());
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn release(&mut self, _pid: u32, threads: &mut [StoppedThread]) {
+        if let Some(vdso) = self.vdso.take() {
+            vdso.revert(threads);
+        }
+    }
+}
+
+#[async_trait]
+impl Injector for ClockSkewInjector {
+    async fn inject(&self, target: &Target) -> Result<InjectionHandle> {
+        self.inject_linux(target).await
+    }
+
+    async fn remove(&self, handle: InjectionHandle) -> Result<()> {
+        let tracer = self.tracers.lock().unwrap().remove(&handle.id);
+        match tracer {
+            #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+            Some(tracer) => tokio::task::spawn_blocking(move || tracer.detach())
+                .await
+                .map_err(|e| ChaosError::CleanupFailed(e.to_string()))??,
+            #[cfg(not(all(target_os = "linux", target_arch = "x86_64")))]
+            Some(()) => {}
+            // The kernel detaches a tracer's tracees when it exits; the
+            // patched vDSO then keeps making plain, unshifted syscalls
+            None => info!("No tracer for {}, already detached", handle.id),
+        }
+        info!("Removed clock skew");
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "clock_skew"
+    }
+
+    async fn validate(&self) -> Result<()> {
+        self.validate_config()
+    }
+
+    fn required_capabilities(&self) -> Vec<String> {
+        vec!["CAP_SYS_PTRACE".to_string()]
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ClockSkewParams {
+    #[serde(default, deserialize_with = "signed_duration")]
+    offset: Option<(Duration, bool)>,
+    #[serde(default)]
+    drift: Option<f64>,
+}
+
+/// A duration with an optional sign such as `"-2h"` or `"+90s"`, as the
+/// magnitude and whether it is negative
+fn signed_duration<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Option<(Duration, bool)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let opt = Option::<String>::deserialize(deserializer)?;
+    opt.map(|s| {
+        let s = s.trim();
+        let (magnitude, negative) = match s.strip_prefix('-') {
+            Some(rest) => (rest, true),
+            None => (s.strip_prefix('+').unwrap_or(s), false),
+        };
+        humantime::parse_duration(magnitude.trim())
+            .map(|duration| (duration, negative))
+            .map_err(serde::de::Error::custom)
+    })
+    .transpose()
+}
+
+#[derive(Default)]
+pub struct ClockSkewBuilder {
+    config: ClockSkewConfig,
+}
+
+impl ClockSkewBuilder {
+    pub fn ahead(mut self, offset: Duration) -> Self {
+        self.config.offset = offset;
+        self.config.behind = false;
+        self
+    }
+
+    pub fn behind(mut self, offset: Duration) -> Self {
+        self.config.offset = offset;
+        self.config.behind = true;
+        self
+    }
+
+    pub fn drift(mut self, drift: f64) -> Self {
+        self.config.drift = drift;
+        self
+    }
+
+    pub fn build(self) -> ClockSkewInjector {
+        ClockSkewInjector::new(self.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_skew_config() {
+        let config = ClockSkewInjector::builder()
+            .behind(Duration::from_secs(10))
+            .drift(0.5)
+            .build()
+            .config;
+        assert_eq!(config.skew_nanos(Duration::ZERO), -10 * NANOS_PER_SEC);
+        assert_eq!(
+            config.skew_nanos(Duration::from_secs(4)),
+            -8 * NANOS_PER_SEC
+        );
+
+        assert_eq!(
+            shift_time(100, 900_000_000, 1, 200_000_000),
+            (101, 100_000_000)
+        );
+        assert_eq!(shift_time(100, 500, 1000, -1_000_000), (99, 999_500));
+        assert_eq!(shift_time(100, 0, 1, 3_600 * NANOS_PER_SEC), (3_700, 0));
+
+        let params: InjectorParams = serde_json::from_value(serde_json::json!({
+            "offset": "-2h",
+            "drift": 0.1,
+        }))
+        .unwrap();
+        let injector = ClockSkewInjector::from_params(&params).unwrap();
+        assert_eq!(injector.config.offset, Duration::from_secs(7200));
+        assert!(injector.config.behind);
+        let params: InjectorParams =
+            serde_json::from_value(serde_json::json!({"offset": "+30m"})).unwrap();
+        assert!(
+            !ClockSkewInjector::from_params(&params)
+                .unwrap()
+                .config
+                .behind
+        );
+
+        for invalid in [
+            serde_json::json!({"offset": "yesterday"}),
+            serde_json::json!({"drift": -1.0}),
+            serde_json::json!({"offset": "1h", "speed": 2}),
+        ] {
+            let params: InjectorParams = serde_json::from_value(invalid).unwrap();
+            assert!(ClockSkewInjector::from_params(&params).is_err());
+        }
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[tokio::test]
+    async fn test_clock_skew_live() {
+        let log = std::env::temp_dir().join(format!("chaos_clock_{}", uuid::Uuid::new_v4()));
+        let script = format!(
+            "while :; do echo $EPOCHSECONDS; sleep 0.05; done > {}",
+            log.display()
+        );
+        let Ok(mut child) = std::process::Command::new("bash")
+            .args(["-c", &script])
+            .spawn()
+        else {
+            eprintln!("skipping clock_skew test: bash not found");
+            return;
+        };
+        let observed_skew = || async {
+            tokio::time::sleep(Duration::from_millis(400)).await;
+            let log = std::fs::read_to_string(&log).unwrap_or_default();
+            let seen: i64 = log.lines().last().unwrap_or_default().parse().unwrap();
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs() as i64;
+            seen - now
+        };
+        assert!(observed_skew().await.abs() <= 1);
+
+        let injector = ClockSkewInjector::builder()
+            .ahead(Duration::from_secs(3600))
+            .build();
+        let target = Target::Process { pid: child.id() };
+        let handle = match injector.inject(&target).await {
+            Ok(handle) => handle,
+            Err(e) => {
+                // Needs ptrace permission over the child
+                eprintln!("skipping clock_skew test: {}", e);
+                child.kill().unwrap();
+                child.wait().unwrap();
+                let _ = std::fs::remove_file(&log);
+                return;
+            }
+        };
+        assert!((observed_skew().await - 3600).abs() <= 1);
+
+        injector.remove(handle).await.unwrap();
+        assert!(observed_skew().await.abs() <= 1);
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+        let _ = std::fs::remove_file(&log);
+    }
+
+    /// Syscalls the hook ignores must not wait on the tracer: a round of
+    /// getppid calls may only get slightly slower while traced
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[tokio::test]
+    async fn test_clock_skew_untraced_syscalls_not_slowed() {
+        use std::io::{BufRead, BufReader, Write};
+        use std::process::{Command, Stdio};
+
+        let script = "import os, sys\n\
+                      for _ in sys.stdin:\n    \
+                      [os.getppid() for _ in range(2000)]\n    \
+                      print('done', flush=True)\n";
+        let Ok(mut child) = Command::new("python3")
+            .args(["-c", script])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+        else {
+            eprintln!("skipping clock_skew overhead test: python3 not found");
+            return;
+        };
+        let mut stdin = child.stdin.take().unwrap();
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut round = move || {
+            let start = Instant::now();
+            stdin.write_all(b"go\n").unwrap();
+            let mut line = String::new();
+            stdout.read_line(&mut line).unwrap();
+            assert_eq!(line.trim(), "done");
+            start.elapsed()
+        };
+        round();
+        let baseline = round();
+
+        let injector = ClockSkewInjector::builder()
+            .ahead(Duration::from_secs(3600))
+            .build();
+        let target = Target::Process { pid: child.id() };
+        let handle = match injector.inject(&target).await {
+            Ok(handle) => handle,
+            Err(e) => {
+                eprintln!("skipping clock_skew overhead test: {}", e);
+                child.kill().unwrap();
+                child.wait().unwrap();
+                return;
+            }
+        };
+        round();
+        let traced = round();
+        injector.remove(handle).await.unwrap();
+
+        // Waiting out a polling interval per stop would add seconds
+        assert!(
+            traced < baseline + Duration::from_millis(500),
+            "traced round took {:?}, untraced {:?}",
+            traced,
+            baseline
+        );
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
@@ -0,0 +1,249 @@
+use crate::error::*;
+use crate::tracer::{read_memory, write_memory, StopKind, StoppedThread};
+use tracing::{debug, info};
+
+/// vDSO functions answered without entering the kernel, and the syscall
+/// each one stands in for
+const TIME_FUNCTIONS: &[(&str, i64)] = &[
+    ("__vdso_clock_gettime", libc::SYS_clock_gettime),
+    ("__vdso_gettimeofday", libc::SYS_gettimeofday),
+    ("__vdso_time", libc::SYS_time),
+];
+
+const SHT_DYNSYM: u32 = 11;
+const PT_LOAD: u32 = 1;
+
+/// `mov eax, <number>; syscall; ret`
+fn syscall_stub(number: i64) -> [u8; 8] {
+    let number = (number as u32).to_le_bytes();
+    [
+        0xb8, number[0], number[1], number[2], number[3], 0x0f, 0x05, 0xc3,
+    ]
+}
+
+/// Offset of the `ret` in a stub; threads returning from its syscall stop here
+const STUB_RET: u64 = 7;
+
+/// One overwritten function
+#[derive(Debug)]
+struct Patch {
+    address: u64,
+    original: Vec<u8>,
+}
+
+/// The time functions of a process's vDSO replaced by real syscalls, so a
+/// tracer gets to see and rewrite every clock read
+#[derive(Debug)]
+pub(crate) struct VdsoPatch {
+    pid: u32,
+    patches: Vec<Patch>,
+}
+
+impl VdsoPatch {
+    /// Patch `pid`, which must be stopped; `None` if it has no vDSO
+    pub fn apply(pid: u32) -> Result<Option<Self>> {
+        let maps = std::fs::read_to_string(format!("/proc/{}/maps", pid))
+            .map_err(|_| ChaosError::TargetNotFound(format!("Process {} not found", pid)))?;
+        let Some((start, end)) = vdso_range(&maps) else {
+            return Ok(None);
+        };
+        let image = read_memory(pid, start, (end - start) as usize)?;
+        let symbols = symbols(&image).ok_or_else(|| {
+            ChaosError::InjectionFailed(format!("Cannot parse the vDSO of process {}", pid))
+        })?;
+
+        let mut patch = Self {
+            pid,
+            patches: Vec::new(),
+        };
+        for (function, number) in TIME_FUNCTIONS {
+            let Some(&(_, offset, size)) = symbols.iter().find(|(name, ..)| name == function)
+            else {
+                continue;
+            };
+            let stub = syscall_stub(*number);
+            // Entry points may be short jumps into the implementation; the
+            // alignment padding up to the next function is room as well
+            let room = symbols
+                .iter()
+                .map(|(_, next, _)| *next)
+                .filter(|next| *next > offset)
+                .min()
+                .map_or(size, |next| size.max(next - offset));
+            let original = image.get(offset as usize..offset as usize + stub.len());
+            let Some(original) = original.filter(|_| room >= stub.len() as u64) else {
+                continue;
+            };
+            let original = original.to_vec();
+            let address = start + offset;
+            if let Err(e) = write_memory(pid, address, &stub) {
+                patch.restore();
+                return Err(ChaosError::InjectionFailed(format!(
+                    "Failed to patch {} in process {}: {}",
+                    function, pid, e
+                )));
+            }
+            debug!("Patched {} at {:#x} in {}", function, address, pid);
+            patch.patches.push(Patch { address, original });
+        }
+        info!(
+            "Routed {} vDSO time function(s) of process {} through syscalls",
+            patch.patches.len(),
+            pid
+        );
+        Ok(Some(patch))
+    }
+
+    /// Put the original code back. Threads caught inside a stub are moved
+    /// out of it: before its syscall ran they restart the original
+    /// function, after it they return to the caller.
+    pub fn revert(self, threads: &mut [StoppedThread]) {
+        for thread in threads.iter_mut() {
+            let regs = &mut thread.regs;
+            let Some(patch) = self
+                .patches
+                .iter()
+                .find(|patch| (patch.address..=patch.address + STUB_RET).contains(&regs.rip))
+            else {
+                continue;
+            };
+            if thread.stop == StopKind::SyscallEntry {
+                regs.orig_rax = u64::MAX;
+                regs.rip = patch.address;
+            } else if regs.rip == patch.address + STUB_RET {
+                match read_memory(self.pid, regs.rsp, 8) {
+                    Ok(bytes) => {
+                        regs.rip = u64::from_le_bytes(bytes.try_into().unwrap_or_default());
+                        regs.rsp += 8;
+                    }
+                    Err(e) => debug!("Cannot read the stack of {}: {}", thread.tid, e),
+                }
+            } else {
+                regs.rip = patch.address;
+            }
+        }
+        self.restore();
+    }
+
+    fn restore(&self) {
+        for patch in &self.patches {
+            if let Err(e) = write_memory(self.pid, patch.address, &patch.original) {
+                debug!("Could not restore vDSO code in {}: {}", self.pid, e);
+            }
+        }
+    }
+}
+
+/// Address range of the `[vdso]` mapping in a maps listing
+fn vdso_range(maps: &str) -> Option<(u64, u64)> {
+    let line = maps.lines().find(|line| line.ends_with("[vdso]"))?;
+    let (start, end) = line.split_whitespace().next()?.split_once('-')?;
+    Some((
+        u64::from_str_radix(start, 16).ok()?,
+        u64::from_str_radix(end, 16).ok()?,
+    ))
+}
+
+fn read<const N: usize>(image: &[u8], offset: u64) -> Option<[u8; N]> {
+    let offset = usize::try_from(offset).ok()?;
+    image.get(offset..offset.checked_add(N)?)?.try_into().ok()
+}
+
+fn u16_at(image: &[u8], offset: u64) -> Option<u64> {
+    read::<2>(image, offset).map(|b| u16::from_le_bytes(b) as u64)
+}
+
+fn u32_at(image: &[u8], offset: u64) -> Option<u32> {
+    read::<4>(image, offset).map(u32::from_le_bytes)
+}
+
+fn u64_at(image: &[u8], offset: u64) -> Option<u64> {
+    read::<8>(image, offset).map(u64::from_le_bytes)
+}
+
+/// Dynamic symbols of an in-memory ELF64 image as (name, offset into the
+/// image, size)
+fn symbols(image: &[u8]) -> Option<Vec<(String, u64, u64)>> {
+    if image.get(..4)? != b"\x7fELF" {
+        return None;
+    }
+    // Symbol values are link-time addresses; the first segment is mapped
+    // at the start of the image
+    let (phoff, phentsize, phnum) = (
+        u64_at(image, 0x20)?,
+        u16_at(image, 0x36)?,
+        u16_at(image, 0x38)?,
+    );
+    let base = (0..phnum)
+        .map(|i| phoff + i * phentsize)
+        .find(|header| u32_at(image, *header) == Some(PT_LOAD))
+        .and_then(|header| u64_at(image, header + 16))?;
+
+    let (shoff, shentsize, shnum) = (
+        u64_at(image, 0x28)?,
+        u16_at(image, 0x3a)?,
+        u16_at(image, 0x3c)?,
+    );
+    let section = |i: u64| shoff + i * shentsize;
+    let dynsym = (0..shnum)
+        .map(section)
+        .find(|header| u32_at(image, header + 4) == Some(SHT_DYNSYM))?;
+    let (symoff, symsize, symentsize) = (
+        u64_at(image, dynsym + 24)?,
+        u64_at(image, dynsym + 32)?,
+        u64_at(image, dynsym + 56)?,
+    );
+    let strtab = section(u32_at(image, dynsym + 40)? as u64);
+    let stroff = u64_at(image, strtab + 24)?;
+    if symentsize == 0 {
+        return None;
+    }
+
+    let mut symbols = Vec::new();
+    for symbol in (0..symsize / symentsize).map(|i| symoff + i * symentsize) {
+        let name_start = (stroff + u32_at(image, symbol)? as u64) as usize;
+        let name = image.get(name_start..)?;
+        let name = &name[..name.iter().position(|b| *b == 0)?];
+        let value = u64_at(image, symbol + 8)?;
+        if name.is_empty() || value < base {
+            continue;
+        }
+        symbols.push((
+            String::from_utf8_lossy(name).into_owned(),
+            value - base,
+            u64_at(image, symbol + 16)?,
+        ));
+    }
+    Some(symbols)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_own_vdso_symbols() {
+        assert_eq!(
+            syscall_stub(libc::SYS_clock_gettime),
+            [0xb8, 228, 0, 0, 0, 0x0f, 0x05, 0xc3]
+        );
+        assert_eq!(
+            vdso_range("7ffd1e5f2000-7ffd1e5f4000 r-xp 00000000 00:00 0 [vdso]\n"),
+            Some((0x7ffd1e5f2000, 0x7ffd1e5f4000))
+        );
+
+        let maps = std::fs::read_to_string("/proc/self/maps").unwrap();
+        let Some((start, end)) = vdso_range(&maps) else {
+            // Booted with vdso=0
+            return;
+        };
+        let image = read_memory(std::process::id(), start, (end - start) as usize).unwrap();
+        let symbols = symbols(&image).unwrap();
+        let (_, offset, size) = symbols
+            .iter()
+            .find(|(name, ..)| name == "__vdso_clock_gettime")
+            .unwrap();
+        assert!(*size > 0);
+        assert!((*offset as usize) < image.len());
+    }
+}
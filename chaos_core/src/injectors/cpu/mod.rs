@@ -0,0 +1,646 @@
+mod quota;
+mod stat;
+
+pub use quota::*;
+
+use crate::{
+    error::*,
+    handle::{InjectionHandle, InjectionReport},
+    injectors::{params, Injector, InjectorParams},
+    target::Target,
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use stat::CpuTimes;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Burners alternate busy and idle within slices of this length
+const SLICE: Duration = Duration::from_millis(10);
+
+/// Share of each period a spike lasts
+const SPIKE_DUTY: f64 = 0.1;
+
+/// How the load of each burner changes over a wave period
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WaveShape {
+    /// Steady load at `intensity`
+    #[default]
+    Constant,
+    /// `intensity` for the first half of each period, idle for the second
+    Square,
+    /// Rises smoothly from idle to `intensity` and back
+    Sine,
+    /// Short bursts at `intensity` at the start of each period
+    Spikes,
+}
+
+impl WaveShape {
+    /// Load at `phase` (0..1) of a period, relative to the peak
+    pub fn level(&self, phase: f64) -> f64 {
+        match self {
+            WaveShape::Constant => 1.0,
+            WaveShape::Square => (phase < 0.5) as u8 as f64,
+            WaveShape::Sine => (1.0 - (2.0 * std::f64::consts::PI * phase).cos()) / 2.0,
+            WaveShape::Spikes => (phase < SPIKE_DUTY) as u8 as f64,
+        }
+    }
+
+    /// Average level over a period
+    pub fn mean(&self) -> f64 {
+        match self {
+            WaveShape::Constant => 1.0,
+            WaveShape::Square | WaveShape::Sine => 0.5,
+            WaveShape::Spikes => SPIKE_DUTY,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RealtimePolicy {
+    /// `SCHED_FIFO`
+    Fifo,
+    /// `SCHED_RR`
+    Rr,
+}
+
+/// Real-time scheduling for the burners, so they preempt the target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Realtime {
+    pub policy: RealtimePolicy,
+    /// 1 (lowest) to 99
+    pub priority: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CpuStarvationConfig {
+    pub intensity: f64,    // 0.0 - 1.0, percentage of CPU to consume
+    pub threads: Vec<u32>, // Specific CPU cores to target (empty = the target's CPUs)
+    pub duration: Option<Duration>,
+    #[serde(default)]
+    pub wave: WaveShape,
+    #[serde(default = "default_wave_period")]
+    pub wave_period: Duration,
+    /// Nice value of the burners; negative values outrank the target
+    #[serde(default)]
+    pub nice: Option<i32>,
+    #[serde(default)]
+    pub realtime: Option<Realtime>,
+}
+
+fn default_wave_period() -> Duration {
+    Duration::from_secs(10)
+}
+
+impl Default for CpuStarvationConfig {
+    fn default() -> Self {
+        Self {
+            intensity: 0.8,
+            threads: vec![],
+            duration: None,
+            wave: WaveShape::Constant,
+            wave_period: default_wave_period(),
+            nice: None,
+            realtime: None,
+        }
+    }
+}
+
+impl CpuStarvationConfig {
+    /// Share of a slice to burn `elapsed` into the injection
+    fn level(&self, elapsed: Duration) -> f64 {
+        let phase = if self.wave_period.is_zero() {
+            0.0
+        } else {
+            (elapsed.as_secs_f64() / self.wave_period.as_secs_f64()).fract()
+        };
+        self.intensity * self.wave.level(phase)
+    }
+
+    /// Utilization each burner aims for on average
+    pub fn requested_utilization(&self) -> f64 {
+        self.intensity * self.wave.mean()
+    }
+}
+
+/// Burns CPU on the CPUs a target may run on (or chosen cores), with the
+/// load shaped by a wave and optionally at a higher priority than the target
+#[derive(Default)]
+pub struct CpuStarvationInjector {
+    config: CpuStarvationConfig,
+    burns: Mutex<HashMap<String, Burn>>,
+}
+
+/// Burner threads of one handle
+struct Burn {
+    stop: Arc<AtomicBool>,
+    burners: Vec<std::thread::JoinHandle<()>>,
+    cpus: Vec<u32>,
+    /// CPU times when the burners started
+    baseline: Option<CpuTimes>,
+}
+
+impl Burn {
+    /// Utilization of the burned CPUs since the start, from /proc/stat
+    fn achieved(&self) -> Option<f64> {
+        let now = CpuTimes::read(&self.cpus).ok()?;
+        self.baseline?.utilization(&now)
+    }
+
+    async fn stop(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        let burners = self.burners;
+        let _ = tokio::task::spawn_blocking(move || {
+            for burner in burners {
+                let _ = burner.join();
+            }
+        })
+        .await;
+    }
+}
+
+impl CpuStarvationInjector {
+    pub fn new(config: CpuStarvationConfig) -> Self {
+        Self {
+            config,
+            burns: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn builder() -> CpuStarvationBuilder {
+        CpuStarvationBuilder::default()
+    }
+
+    pub fn from_params(params: &InjectorParams) -> Result<Self> {
+        let params: CpuStarvationParams = params::parse_params("cpu_starvation", params)?;
+        let mut builder = Self::builder();
+        if let Some(intensity) = params.intensity {
+            builder = builder.intensity(intensity);
+        }
+        if let Some(threads) = params.threads {
+            builder = builder.threads(threads);
+        }
+        if let Some(duration) = params.duration {
+            builder = builder.duration(duration);
+        }
+        if let Some(wave) = params.wave {
+            builder = builder.wave(wave, params.wave_period.unwrap_or(default_wave_period()));
+        }
+        if let Some(nice) = params.nice {
+            builder = builder.nice(nice);
+        }
+        match (params.realtime, params.realtime_priority) {
+            (Some(policy), priority) => builder = builder.realtime(policy, priority.unwrap_or(1)),
+            (None, Some(_)) => {
+                return Err(ChaosError::InvalidConfig(
+                    "cpu_starvation realtime_priority requires realtime".to_string(),
+                ))
+            }
+            (None, None) => {}
+        }
+        let injector = builder.build();
+        injector.validate_config()?;
+        Ok(injector)
+    }
+
+    fn validate_config(&self) -> Result<()> {
+        let config = &self.config;
+        if !config.intensity.is_finite() {
+            return Err(ChaosError::InvalidConfig(
+                "cpu_starvation intensity must be between 0 and 1".to_string(),
+            ));
+        }
+        if config.wave != WaveShape::Constant && config.wave_period < SLICE {
+            return Err(ChaosError::InvalidConfig(format!(
+                "cpu_starvation wave_period must be at least {:?}",
+                SLICE
+            )));
+        }
+        if config.nice.is_some_and(|nice| !(-20..=19).contains(&nice)) {
+            return Err(ChaosError::InvalidConfig(
+                "cpu_starvation nice must be between -20 and 19".to_string(),
+            ));
+        }
+        if config
+            .realtime
+            .is_some_and(|realtime| !(1..=99).contains(&realtime.priority))
+        {
+            return Err(ChaosError::InvalidConfig(
+                "cpu_starvation realtime_priority must be between 1 and 99".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Explicit cores, else the CPUs a process target may run on, else all
+    async fn cpus(&self, target: &Target) -> Result<Vec<u32>> {
+        if !self.config.threads.is_empty() {
+            return Ok(self.config.threads.clone());
+        }
+        match target {
+            Target::Process { .. } | Target::Thread { .. } | Target::Container { .. } => {
+                affinity(target.pid().await?)
+            }
+            _ => Ok((0..num_cpus::get() as u32).collect()),
+        }
+    }
+}
+
+/// CPUs `pid` is allowed to run on
+#[cfg(target_os = "linux")]
+fn affinity(pid: u32) -> Result<Vec<u32>> {
+    use nix::sched::{sched_getaffinity, CpuSet};
+    use nix::unistd::Pid;
+
+    let set = sched_getaffinity(Pid::from_raw(pid as i32))
+        .map_err(|_| ChaosError::TargetNotFound(format!("Process {} not found", pid)))?;
+    Ok((0..CpuSet::count())
+        .filter(|cpu| set.is_set(*cpu).unwrap_or(false))
+        .map(|cpu| cpu as u32)
+        .collect())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn affinity(_pid: u32) -> Result<Vec<u32>> {
+    Ok((0..num_cpus::get() as u32).collect())
+}
+
+/// Pin the calling thread to `cpu` and give it the configured priority
+#[cfg(target_os = "linux")]
+fn prepare_burner(cpu: u32, config: &CpuStarvationConfig) -> Result<()> {
+    use nix::sched::{sched_setaffinity, CpuSet};
+    use nix::unistd::{gettid, Pid};
+
+    let mut set = CpuSet::new();
+    set.set(cpu as usize)
+        .and_then(|_| sched_setaffinity(Pid::from_raw(0), &set))
+        .map_err(|e| ChaosError::SystemError(format!("Cannot pin burner to CPU {}: {}", cpu, e)))?;
+
+    if let Some(nice) = config.nice {
+        // Per thread on Linux
+        if unsafe { libc::setpriority(libc::PRIO_PROCESS as _, gettid().as_raw() as _, nice) } != 0
+        {
+            return Err(ChaosError::SystemError(format!(
+                "Cannot set burner nice value {}: {}",
+                nice,
+                std::io::Error::last_os_error()
+            )));
+        }
+    }
+    if let Some(realtime) = config.realtime {
+        let policy = match realtime.policy {
+            RealtimePolicy::Fifo => libc::SCHED_FIFO,
+            RealtimePolicy::Rr => libc::SCHED_RR,
+        };
+        let param = libc::sched_param {
+            sched_priority: realtime.priority as i32,
+        };
+        if unsafe { libc::sched_setscheduler(0, policy, &param) } != 0 {
+            return Err(ChaosError::SystemError(format!(
+                "Cannot give burner {:?} priority {}: {}",
+                realtime.policy,
+                realtime.priority,
+                std::io::Error::last_os_error()
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn prepare_burner(_cpu: u32, config: &CpuStarvationConfig) -> Result<()> {
+    if config.nice.is_some() || config.realtime.is_some() {
+        return Err(ChaosError::SystemError(
+            "Burner priorities are only supported on Linux".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Alternate busy and idle within each slice until stopped
+fn burn(config: &CpuStarvationConfig, stop: &AtomicBool) {
+    let started = Instant::now();
+    while !stop.load(Ordering::Relaxed) {
+        let elapsed = started.elapsed();
+        if config.duration.is_some_and(|duration| elapsed >= duration) {
+            break;
+        }
+        let slice = Instant::now();
+        let busy = SLICE.mul_f64(config.level(elapsed));
+        while slice.elapsed() < busy {
+            std::hint::spin_loop();
+        }
+        let idle = SLICE.saturating_sub(slice.elapsed());
+        if !idle.is_zero() {
+            std::thread::sleep(idle);
+        }
+    }
+}
+
+#[async_trait]
+impl Injector for CpuStarvationInjector {
+    async fn inject(&self, target: &Target) -> Result<InjectionHandle> {
+        self.validate_config()?;
+        let cpus = self.cpus(target).await?;
+        info!(
+            "Injecting CPU starvation: intensity={}, wave={:?}, cpus={:?}",
+            self.config.intensity, self.config.wave, cpus
+        );
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let (ready_tx, mut ready_rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut burners = Vec::new();
+        // Burners started before a failure are stopped and joined below
+        let mut failure = None;
+        for cpu in cpus.iter().copied() {
+            let (config, stop, ready) = (self.config.clone(), stop.clone(), ready_tx.clone());
+            let spawned = std::thread::Builder::new()
+                .name(format!("chaos-burner-{}", cpu))
+                .spawn(move || {
+                    let prepared = prepare_burner(cpu, &config);
+                    let ok = prepared.is_ok();
+                    let _ = ready.send(prepared);
+                    drop(ready);
+                    if ok {
+                        burn(&config, &stop);
+                    }
+                });
+            match spawned {
+                Ok(burner) => burners.push(burner),
+                Err(e) => {
+                    failure = Some(ChaosError::from(e));
+                    break;
+                }
+            }
+        }
+        drop(ready_tx);
+
+        while let Some(prepared) = ready_rx.recv().await {
+            if let Err(e) = prepared {
+                failure.get_or_insert(e);
+            }
+        }
+        let burn = Burn {
+            stop,
+            burners,
+            baseline: CpuTimes::read(&cpus).ok(),
+            cpus: cpus.clone(),
+        };
+        if let Some(e) = failure {
+            burn.stop().await;
+            return Err(e);
+        }
+
+        let metadata = serde_json::json!({
+            "intensity": self.config.intensity,
+            "cores": cpus,
+            "num_threads": burn.burners.len(),
+            "wave": self.config.wave,
+            "requested_utilization": self.config.requested_utilization(),
+        });
+        let handle = InjectionHandle::new("cpu_starvation", target.clone(), metadata);
+        self.burns.lock().unwrap().insert(handle.id.clone(), burn);
+        Ok(handle)
+    }
+
+    async fn remove(&self, handle: InjectionHandle) -> Result<()> {
+        info!("Removing CPU starvation");
+
+        let burn = self.burns.lock().unwrap().remove(&handle.id);
+        let Some(burn) = burn else {
+            // The burners ended with the process that started them
+            return Ok(());
+        };
+        match burn.achieved() {
+            Some(achieved) => info!(
+                "CPU utilization on {:?}: {:.0}% (requested {:.0}%)",
+                burn.cpus,
+                achieved * 100.0,
+                self.config.requested_utilization() * 100.0
+            ),
+            None => warn!("Could not measure CPU utilization on {:?}", burn.cpus),
+        }
+        burn.stop().await;
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "cpu_starvation"
+    }
+
+    async fn validate(&self) -> Result<()> {
+        self.validate_config()
+    }
+
+    fn required_capabilities(&self) -> Vec<String> {
+        vec!["CAP_SYS_NICE".to_string()]
+    }
+
+    async fn report(&self, handle: &InjectionHandle) -> InjectionReport {
+        let burns = self.burns.lock().unwrap();
+        let Some(burn) = burns.get(&handle.id) else {
+            return InjectionReport::default();
+        };
+        let mut measurements = vec![(
+            "cpu_utilization_requested".to_string(),
+            self.config.requested_utilization(),
+        )];
+        if let Some(achieved) = burn.achieved() {
+            measurements.push(("cpu_utilization_achieved".to_string(), achieved));
+        }
+        InjectionReport {
+            measurements,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CpuStarvationParams {
+    #[serde(default)]
+    intensity: Option<f64>,
+    #[serde(default)]
+    threads: Option<Vec<u32>>,
+    #[serde(default, deserialize_with = "params::opt_duration")]
+    duration: Option<Duration>,
+    #[serde(default)]
+    wave: Option<WaveShape>,
+    #[serde(default, deserialize_with = "params::opt_duration")]
+    wave_period: Option<Duration>,
+    #[serde(default)]
+    nice: Option<i32>,
+    #[serde(default)]
+    realtime: Option<RealtimePolicy>,
+    #[serde(default)]
+    realtime_priority: Option<u8>,
+}
+
+#[derive(Default)]
+pub struct CpuStarvationBuilder {
+    config: CpuStarvationConfig,
+}
+
+impl CpuStarvationBuilder {
+    pub fn intensity(mut self, intensity: f64) -> Self {
+        self.config.intensity = intensity.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn threads(mut self, threads: Vec<u32>) -> Self {
+        self.config.threads = threads;
+        self
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.config.duration = Some(duration);
+        self
+    }
+
+    /// Shape the load as `shape`, repeating every `period`
+    pub fn wave(mut self, shape: WaveShape, period: Duration) -> Self {
+        self.config.wave = shape;
+        self.config.wave_period = period;
+        self
+    }
+
+    pub fn nice(mut self, nice: i32) -> Self {
+        self.config.nice = Some(nice);
+        self
+    }
+
+    pub fn realtime(mut self, policy: RealtimePolicy, priority: u8) -> Self {
+        self.config.realtime = Some(Realtime { policy, priority });
+        self
+    }
+
+    pub fn build(self) -> CpuStarvationInjector {
+        CpuStarvationInjector::new(self.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_starvation_builder() {
+        let injector = CpuStarvationInjector::builder()
+            .intensity(0.5)
+            .threads(vec![0, 1])
+            .build();
+
+        assert_eq!(injector.config.intensity, 0.5);
+        assert_eq!(injector.config.threads, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_cpu_starvation_from_params() {
+        let params: InjectorParams =
+            serde_json::from_value(serde_json::json!({"intensity": 0.5, "threads": [0, 1]}))
+                .unwrap();
+        let injector = CpuStarvationInjector::from_params(&params).unwrap();
+
+        assert_eq!(injector.config.intensity, 0.5);
+        assert_eq!(injector.config.threads, vec![0, 1]);
+
+        let params: InjectorParams =
+            serde_json::from_value(serde_json::json!({"threads": "all"})).unwrap();
+        assert!(CpuStarvationInjector::from_params(&params).is_err());
+
+        let params: InjectorParams = serde_json::from_value(serde_json::json!({
+            "intensity": 0.6,
+            "wave": "spikes",
+            "wave_period": "2s",
+            "nice": -5,
+            "realtime": "rr",
+            "realtime_priority": 10,
+        }))
+        .unwrap();
+        let injector = CpuStarvationInjector::from_params(&params).unwrap();
+        assert_eq!(
+            injector.config,
+            CpuStarvationInjector::builder()
+                .intensity(0.6)
+                .wave(WaveShape::Spikes, Duration::from_secs(2))
+                .nice(-5)
+                .realtime(RealtimePolicy::Rr, 10)
+                .build()
+                .config
+        );
+
+        for invalid in [
+            serde_json::json!({"wave": "triangle"}),
+            serde_json::json!({"wave": "sine", "wave_period": "1ms"}),
+            serde_json::json!({"nice": -21}),
+            serde_json::json!({"realtime": "fifo", "realtime_priority": 0}),
+            serde_json::json!({"realtime_priority": 5}),
+        ] {
+            let params: InjectorParams = serde_json::from_value(invalid).unwrap();
+            assert!(CpuStarvationInjector::from_params(&params).is_err());
+        }
+    }
+
+    #[test]
+    fn test_wave_shapes() {
+        assert_eq!(WaveShape::Square.level(0.25), 1.0);
+        assert_eq!(WaveShape::Square.level(0.75), 0.0);
+        assert_eq!(WaveShape::Spikes.level(0.05), 1.0);
+        assert_eq!(WaveShape::Spikes.level(0.5), 0.0);
+        assert_eq!(WaveShape::Sine.level(0.0), 0.0);
+        assert!((WaveShape::Sine.level(0.5) - 1.0).abs() < 1e-9);
+
+        let config = CpuStarvationInjector::builder()
+            .intensity(0.8)
+            .wave(WaveShape::Square, Duration::from_secs(2))
+            .build()
+            .config;
+        assert_eq!(config.level(Duration::from_millis(500)), 0.8);
+        assert_eq!(config.level(Duration::from_millis(1500)), 0.0);
+        assert_eq!(config.level(Duration::from_millis(2500)), 0.8);
+        assert!((config.requested_utilization() - 0.4).abs() < 1e-9);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_contention_follows_target_affinity() {
+        use nix::sched::{sched_setaffinity, CpuSet};
+        use nix::unistd::Pid;
+
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let mut set = CpuSet::new();
+        set.set(0).unwrap();
+        sched_setaffinity(Pid::from_raw(child.id() as i32), &set).unwrap();
+
+        let injector = CpuStarvationInjector::builder()
+            .intensity(0.5)
+            .wave(WaveShape::Square, Duration::from_millis(100))
+            .build();
+        let handle = injector.inject(&Target::process(child.id())).await.unwrap();
+        assert_eq!(handle.metadata["cores"], serde_json::json!([0]));
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let report = injector.report(&handle).await;
+        assert_eq!(
+            report.measurements[0],
+            ("cpu_utilization_requested".to_string(), 0.25)
+        );
+        let (name, achieved) = &report.measurements[1];
+        assert_eq!(name, "cpu_utilization_achieved");
+        assert!((0.0..=1.0).contains(achieved));
+
+        injector.remove(handle.clone()).await.unwrap();
+        assert!(injector.report(&handle).await.measurements.is_empty());
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
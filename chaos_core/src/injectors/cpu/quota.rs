@@ -0,0 +1,468 @@
+#[cfg(target_os = "linux")]
+use crate::cgroup::{self, CgroupMember, CgroupVersion, ChaosCgroup};
+use crate::{
+    error::*,
+    handle::InjectionHandle,
+    injectors::{params, Injector, InjectorParams},
+    target::Target,
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+#[cfg(target_os = "linux")]
+use std::path::PathBuf;
+use std::time::Duration;
+#[cfg(target_os = "linux")]
+use tracing::{info, warn};
+
+/// Threaded child of a v2 group that holds a single throttled thread
+#[cfg(target_os = "linux")]
+const THREADED: &str = "throttled";
+
+/// CPU bandwidth limit applied through the cgroup cpu controller
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CpuQuotaConfig {
+    /// Share of one CPU the target may use, in percent (1-100)
+    pub quota: u32,
+    /// Period the quota is enforced over; shorter periods throttle in
+    /// smaller, more frequent stalls
+    pub period: Duration,
+    /// Throttle every thread of the process instead of only the targeted
+    /// one (the main thread, or the thread of a thread target)
+    pub all_threads: bool,
+}
+
+impl Default for CpuQuotaConfig {
+    fn default() -> Self {
+        Self {
+            quota: 50,
+            period: Duration::from_millis(100),
+            all_threads: true,
+        }
+    }
+}
+
+impl CpuQuotaConfig {
+    fn period_us(&self) -> u64 {
+        self.period.as_micros() as u64
+    }
+
+    /// Runtime per period; the kernel accepts no less than 1ms
+    fn quota_us(&self) -> u64 {
+        (self.period_us() * self.quota as u64 / 100).max(1000)
+    }
+
+    /// `cpu.max` line of the v2 controller
+    fn cpu_max(&self) -> String {
+        format!("{} {}", self.quota_us(), self.period_us())
+    }
+}
+
+/// Where the throttled tasks live, per cgroup version
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "version", rename_all = "lowercase")]
+enum QuotaGroup {
+    V2 {
+        cgroup: ChaosCgroup,
+    },
+    V1 {
+        path: PathBuf,
+        /// Threads moved in, keyed by thread ID
+        members: Vec<CgroupMember>,
+    },
+}
+
+#[cfg(target_os = "linux")]
+impl QuotaGroup {
+    /// Move everything back where it came from and delete the group
+    async fn restore(&self) -> Result<()> {
+        match self {
+            QuotaGroup::V2 { cgroup } => cgroup.restore().await,
+            QuotaGroup::V1 { path, members } => restore_v1(path, members).await,
+        }
+    }
+}
+
+/// Limits how much CPU time a process (or one of its threads) gets by
+/// moving it into a chaos-owned cgroup with a CFS bandwidth quota, and moves
+/// it back on removal
+#[derive(Debug, Clone, Default)]
+pub struct CpuQuotaInjector {
+    config: CpuQuotaConfig,
+}
+
+impl CpuQuotaInjector {
+    pub fn new(quota: u32) -> Self {
+        Self::builder().quota(quota).build()
+    }
+
+    pub fn builder() -> CpuQuotaBuilder {
+        CpuQuotaBuilder::default()
+    }
+
+    pub fn from_params(params: &InjectorParams) -> Result<Self> {
+        let params: CpuQuotaParams = params::parse_params("cpu_quota", params)?;
+        let mut builder = Self::builder();
+        if let Some(quota) = params.quota {
+            builder = builder.quota(quota);
+        }
+        if let Some(period) = params.period {
+            builder = builder.period(period);
+        }
+        if let Some(all_threads) = params.all_threads {
+            builder = builder.all_threads(all_threads);
+        }
+        let injector = builder.build();
+        injector.validate_config()?;
+        Ok(injector)
+    }
+
+    fn validate_config(&self) -> Result<()> {
+        if self.config.quota == 0 {
+            return Err(ChaosError::InvalidConfig(
+                "cpu_quota quota must be at least 1%".to_string(),
+            ));
+        }
+        if !(Duration::from_millis(1)..=Duration::from_secs(1)).contains(&self.config.period) {
+            return Err(ChaosError::InvalidConfig(
+                "cpu_quota period must be between 1ms and 1s".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn inject_linux(&self, target: &Target) -> Result<InjectionHandle> {
+        self.validate_config()?;
+        let (pid, thread) = match target {
+            Target::Thread { tid } => (thread_group(*tid)?, Some(*tid)),
+            _ => {
+                let pid = target.pid().await?;
+                (pid, (!self.config.all_threads).then_some(pid))
+            }
+        };
+        info!(
+            "Setting CPU quota to {}% per {:?} for {}{}",
+            self.config.quota,
+            self.config.period,
+            target.description(),
+            thread.map_or(String::new(), |tid| format!(" (thread {})", tid))
+        );
+
+        let name = format!("cpu_quota_{}", uuid::Uuid::new_v4());
+        let group = match cgroup::controller_version("cpu")? {
+            CgroupVersion::V2 => self.limit_v2(&name, pid, thread).await?,
+            CgroupVersion::V1(mount) => self.limit_v1(&mount, &name, pid, thread).await?,
+        };
+
+        let mut metadata = serde_json::json!({
+            "pid": pid,
+            "thread": thread,
+            "quota": self.config.quota,
+            "period_us": self.config.period_us(),
+        });
+        if let (Some(metadata), Ok(serde_json::Value::Object(group))) =
+            (metadata.as_object_mut(), serde_json::to_value(&group))
+        {
+            metadata.extend(group);
+        }
+        Ok(InjectionHandle::new("cpu_quota", target.clone(), metadata))
+    }
+
+    /// `cpu.max` on a v2 group holding the process, or on a threaded child
+    /// holding only `thread`
+    #[cfg(target_os = "linux")]
+    async fn limit_v2(&self, name: &str, pid: u32, thread: Option<u32>) -> Result<QuotaGroup> {
+        let mut cgroup = ChaosCgroup::create(name, &["cpu"]).await?;
+        let configured = async {
+            let cpu_max = match thread {
+                Some(_) => {
+                    cgroup.threaded_child(THREADED, &["cpu"]).await?;
+                    format!("{}/cpu.max", THREADED)
+                }
+                None => "cpu.max".to_string(),
+            };
+            cgroup.write(&cpu_max, &self.config.cpu_max()).await?;
+            cgroup.adopt(pid).await?;
+            if let Some(tid) = thread {
+                cgroup
+                    .write(&format!("{}/cgroup.threads", THREADED), &tid.to_string())
+                    .await?;
+            }
+            Ok(())
+        }
+        .await;
+        if let Err(e) = configured {
+            cgroup.restore().await?;
+            return Err(e);
+        }
+        Ok(QuotaGroup::V2 { cgroup })
+    }
+
+    /// `cpu.cfs_quota_us` on a v1 group holding the process, or only
+    /// `thread`
+    #[cfg(target_os = "linux")]
+    async fn limit_v1(
+        &self,
+        mount: &std::path::Path,
+        name: &str,
+        pid: u32,
+        thread: Option<u32>,
+    ) -> Result<QuotaGroup> {
+        let path = mount.join("chaos").join(name);
+        tokio::fs::create_dir_all(&path).await.map_err(|e| {
+            ChaosError::InjectionFailed(format!(
+                "Failed to create cgroup {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        info!("Created cgroup {}", path.display());
+
+        let mut members = Vec::new();
+        let configured = async {
+            let period = self.config.period_us().to_string();
+            cgroup::write_file(&path.join("cpu.cfs_period_us"), &period).await?;
+            let quota = self.config.quota_us().to_string();
+            cgroup::write_file(&path.join("cpu.cfs_quota_us"), &quota).await?;
+
+            // cgroup.procs takes every thread along, tasks only the one
+            let (tid, file) = match thread {
+                Some(tid) => (tid, "tasks"),
+                None => (pid, "cgroup.procs"),
+            };
+            let original = cgroup::thread_v1_cgroup(mount, pid, tid, "cpu")?;
+            cgroup::write_file(&path.join(file), &tid.to_string()).await?;
+            members.push(CgroupMember { pid: tid, original });
+            Ok(())
+        }
+        .await;
+        if let Err(e) = configured {
+            restore_v1(&path, &members).await?;
+            return Err(e);
+        }
+        Ok(QuotaGroup::V1 { path, members })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    async fn inject_linux(&self, _target: &Target) -> Result<InjectionHandle> {
+        Err(ChaosError::SystemError(
+            "CPU quota injection only supported on Linux with cgroups".to_string(),
+        ))
+    }
+}
+
+/// Process a thread belongs to
+#[cfg(target_os = "linux")]
+fn thread_group(tid: u32) -> Result<u32> {
+    let status = std::fs::read_to_string(format!("/proc/{}/status", tid))
+        .map_err(|_| ChaosError::TargetNotFound(format!("Thread {} not found", tid)))?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Tgid:"))
+        .and_then(|tgid| tgid.trim().parse().ok())
+        .ok_or_else(|| ChaosError::SystemError(format!("Cannot read the process of {}", tid)))
+}
+
+/// Move every thread of a v1 group back where it came from (threads
+/// started inside follow the first member) and delete the group
+#[cfg(target_os = "linux")]
+async fn restore_v1(path: &std::path::Path, members: &[CgroupMember]) -> Result<()> {
+    if !path.exists() {
+        info!("cgroup {} is already gone", path.display());
+        return Ok(());
+    }
+    let tasks = tokio::fs::read_to_string(path.join("tasks"))
+        .await
+        .unwrap_or_default();
+    let fallback = members
+        .first()
+        .map(|member| member.original.clone())
+        .or_else(|| path.ancestors().nth(2).map(std::path::Path::to_path_buf))
+        .unwrap_or_default();
+    for tid in tasks
+        .lines()
+        .filter_map(|tid| tid.trim().parse::<u32>().ok())
+    {
+        let original = members
+            .iter()
+            .find(|member| member.pid == tid)
+            .map_or(fallback.as_path(), |member| member.original.as_path());
+        let home = cgroup::surviving_ancestor(original);
+        if let Err(e) = cgroup::write_file(&home.join("tasks"), &tid.to_string()).await {
+            // The thread may have exited meanwhile
+            warn!("Could not move {} back to {}: {}", tid, home.display(), e);
+        }
+    }
+
+    tokio::fs::remove_dir(path).await.map_err(|e| {
+        ChaosError::CleanupFailed(format!("Failed to remove cgroup {}: {}", path.display(), e))
+    })?;
+    // Only succeeds once no other injection uses it
+    if let Some(parent) = path.parent() {
+        let _ = tokio::fs::remove_dir(parent).await;
+    }
+    info!("Removed cgroup {}", path.display());
+    Ok(())
+}
+
+#[async_trait]
+impl Injector for CpuQuotaInjector {
+    async fn inject(&self, target: &Target) -> Result<InjectionHandle> {
+        self.inject_linux(target).await
+    }
+
+    async fn remove(&self, handle: InjectionHandle) -> Result<()> {
+        #[cfg(target_os = "linux")]
+        {
+            let group: QuotaGroup = serde_json::from_value(handle.metadata.clone())
+                .map_err(|_| ChaosError::CleanupFailed("Missing cpu_quota metadata".to_string()))?;
+            group.restore().await?;
+            info!("Removed CPU quota");
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = handle;
+        }
+
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "cpu_quota"
+    }
+
+    async fn validate(&self) -> Result<()> {
+        self.validate_config()
+    }
+
+    fn required_capabilities(&self) -> Vec<String> {
+        vec!["CAP_SYS_ADMIN".to_string()]
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CpuQuotaParams {
+    #[serde(default)]
+    quota: Option<u32>,
+    #[serde(default, deserialize_with = "params::opt_duration")]
+    period: Option<Duration>,
+    #[serde(default)]
+    all_threads: Option<bool>,
+}
+
+#[derive(Default)]
+pub struct CpuQuotaBuilder {
+    config: CpuQuotaConfig,
+}
+
+impl CpuQuotaBuilder {
+    /// Percent of one CPU, capped at 100
+    pub fn quota(mut self, quota: u32) -> Self {
+        self.config.quota = quota.min(100);
+        self
+    }
+
+    pub fn period(mut self, period: Duration) -> Self {
+        self.config.period = period;
+        self
+    }
+
+    pub fn all_threads(mut self, all_threads: bool) -> Self {
+        self.config.all_threads = all_threads;
+        self
+    }
+
+    pub fn build(self) -> CpuQuotaInjector {
+        CpuQuotaInjector {
+            config: self.config,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_quota_config() {
+        let injector = CpuQuotaInjector::new(150);
+        assert_eq!(injector.config.quota, 100);
+
+        let params: InjectorParams = serde_json::from_value(serde_json::json!({
+            "quota": 20,
+            "period": "50ms",
+            "all_threads": false,
+        }))
+        .unwrap();
+        let injector = CpuQuotaInjector::from_params(&params).unwrap();
+        assert_eq!(injector.config.cpu_max(), "10000 50000");
+        assert!(!injector.config.all_threads);
+
+        // The kernel's minimum runtime wins over tiny quotas
+        let injector = CpuQuotaInjector::builder()
+            .quota(1)
+            .period(Duration::from_millis(10))
+            .build();
+        assert_eq!(injector.config.cpu_max(), "1000 10000");
+
+        for invalid in [
+            serde_json::json!({"quota": 0}),
+            serde_json::json!({"period": "2s"}),
+            serde_json::json!({"period": "500us"}),
+            serde_json::json!({"quota": "half"}),
+        ] {
+            let params: InjectorParams = serde_json::from_value(invalid).unwrap();
+            assert!(CpuQuotaInjector::from_params(&params).is_err());
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_cpu_quota_live() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let pid = child.id();
+        let version = cgroup::controller_version("cpu");
+        let injector = CpuQuotaInjector::builder().quota(10).build();
+        let handle = match injector.inject(&Target::process(pid)).await {
+            Ok(handle) => handle,
+            Err(e) => {
+                // Needs a writable cpu controller
+                eprintln!("skipping cpu_quota test: {}", e);
+                child.kill().unwrap();
+                child.wait().unwrap();
+                return;
+            }
+        };
+
+        let group: QuotaGroup = serde_json::from_value(handle.metadata.clone()).unwrap();
+        let path = match (&group, version.unwrap()) {
+            (QuotaGroup::V2 { cgroup }, CgroupVersion::V2) => {
+                assert_eq!(cgroup::process_cgroup(pid).unwrap(), cgroup.path);
+                assert_eq!(cgroup.read("cpu.max").await.unwrap().trim(), "10000 100000");
+                cgroup.path.clone()
+            }
+            (QuotaGroup::V1 { path, .. }, CgroupVersion::V1(mount)) => {
+                assert_eq!(
+                    cgroup::thread_v1_cgroup(&mount, pid, pid, "cpu").unwrap(),
+                    *path
+                );
+                let quota = std::fs::read_to_string(path.join("cpu.cfs_quota_us")).unwrap();
+                assert_eq!(quota.trim(), "10000");
+                path.clone()
+            }
+            (group, version) => panic!("{:?} does not match {:?}", group, version),
+        };
+
+        injector.remove(handle).await.unwrap();
+        assert!(!path.exists());
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
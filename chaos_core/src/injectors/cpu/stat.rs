@@ -0,0 +1,94 @@
+use crate::error::*;
+
+/// Busy and total time of a set of CPUs, in clock ticks from /proc/stat
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(super) struct CpuTimes {
+    busy: u64,
+    total: u64,
+}
+
+impl CpuTimes {
+    pub fn read(cpus: &[u32]) -> Result<Self> {
+        let stat = std::fs::read_to_string("/proc/stat")?;
+        Self::parse(&stat, cpus)
+            .ok_or_else(|| ChaosError::SystemError("Cannot parse /proc/stat".to_string()))
+    }
+
+    /// Sum the `cpuN` lines of `cpus`
+    fn parse(stat: &str, cpus: &[u32]) -> Option<Self> {
+        let mut times = Self::default();
+        for line in stat.lines() {
+            let mut fields = line.split_whitespace();
+            let Some(cpu) = fields
+                .next()
+                .and_then(|name| name.strip_prefix("cpu"))
+                .and_then(|id| id.parse::<u32>().ok())
+            else {
+                continue;
+            };
+            if !cpus.contains(&cpu) {
+                continue;
+            }
+            // user nice system idle iowait irq softirq steal; guest time is
+            // already part of user
+            let ticks: Vec<u64> = fields
+                .take(8)
+                .map(|t| t.parse().ok())
+                .collect::<Option<_>>()?;
+            let total: u64 = ticks.iter().sum();
+            let idle = ticks.get(3)? + ticks.get(4).copied().unwrap_or(0);
+            times.busy += total - idle;
+            times.total += total;
+        }
+        Some(times)
+    }
+
+    /// Share of time the CPUs were busy between `self` and `later`; `None`
+    /// before a tick has passed
+    pub fn utilization(&self, later: &Self) -> Option<f64> {
+        let total = later.total.checked_sub(self.total)?;
+        let busy = later.busy.checked_sub(self.busy)?;
+        (total > 0).then(|| busy as f64 / total as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_times() {
+        let before = "\
+cpu  300 0 100 1500 100 0 0 0 0 0
+cpu0 100 0 50 800 50 0 0 0 0 0
+cpu1 200 0 50 700 50 0 0 0 0 0
+intr 1868633 0 0
+";
+        let after = "\
+cpu  600 0 200 1700 100 0 0 0 0 0
+cpu0 350 0 50 850 50 0 0 0 0 0
+cpu1 250 0 150 850 50 0 0 0 0 0
+";
+        let first = CpuTimes::parse(before, &[0]).unwrap();
+        assert_eq!(
+            first,
+            CpuTimes {
+                busy: 150,
+                total: 1000
+            }
+        );
+        let second = CpuTimes::parse(after, &[0]).unwrap();
+        assert_eq!(first.utilization(&second), Some(250.0 / 300.0));
+
+        let both = CpuTimes::parse(before, &[0, 1]).unwrap();
+        assert_eq!(
+            both,
+            CpuTimes {
+                busy: 400,
+                total: 2000
+            }
+        );
+        assert_eq!(both.utilization(&both), None);
+        assert_eq!(CpuTimes::parse("cpu0 1 2\n", &[0]), None);
+    }
+}
@@ -0,0 +1,754 @@
+use super::{DiskFault, DiskFaultRule, DiskOperation};
+use crate::error::*;
+use fuser::{
+    BackgroundSession, FileAttr, FileType, Filesystem, MountOption, ReplyAttr, ReplyCreate,
+    ReplyData, ReplyDirectory, ReplyEmpty, ReplyEntry, ReplyOpen, ReplyStatfs, ReplyWrite, Request,
+    TimeOrNow,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fs::{self, File, FileTimes, OpenOptions};
+use std::io;
+use std::os::fd::AsRawFd;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{DirBuilderExt, FileExt, FileTypeExt, MetadataExt, OpenOptionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::runtime::Handle;
+use tracing::{info, warn};
+
+/// How long the kernel may cache attributes and lookups; short because the
+/// underlying directory can still change through already open descriptors
+const TTL: Duration = Duration::from_secs(1);
+const ROOT_INO: u64 = 1;
+
+/// A fault filesystem mounted over a directory
+pub(crate) struct FaultMount {
+    path: PathBuf,
+    active: Arc<AtomicBool>,
+    session: BackgroundSession,
+}
+
+impl FaultMount {
+    /// Mount over `path`, passing every operation through to the directory
+    /// underneath and faulting those the rules select
+    pub fn mount(path: &Path, rules: Vec<DiskFaultRule>, seed: Option<u64>) -> Result<Self> {
+        // Opened before mounting, so the original contents stay reachable
+        // through /proc/self/fd once they are hidden
+        let root = File::open(path)?;
+        if !root.metadata()?.is_dir() {
+            return Err(ChaosError::InvalidConfig(format!(
+                "disk_slow path {} is not a directory",
+                path.display()
+            )));
+        }
+        let active = Arc::new(AtomicBool::new(true));
+        let filesystem = FaultFs::new(root, rules, seed, active.clone());
+
+        let mut options = vec![
+            MountOption::FSName("chaos".to_string()),
+            MountOption::Subtype("chaos_disk".to_string()),
+            MountOption::DefaultPermissions,
+        ];
+        if nix::unistd::geteuid().is_root() {
+            options.push(MountOption::AllowOther);
+        }
+        let session = fuser::spawn_mount2(filesystem, path, &options).map_err(|e| {
+            ChaosError::InjectionFailed(format!(
+                "Mounting fault filesystem on {} failed (is /dev/fuse available?): {}",
+                path.display(),
+                e
+            ))
+        })?;
+
+        info!("Mounted fault filesystem on {}", path.display());
+        Ok(Self {
+            path: path.to_path_buf(),
+            active,
+            session,
+        })
+    }
+
+    /// Detach the mount; descriptors still open through it keep working but
+    /// see no more faults
+    pub fn unmount(self) -> Result<()> {
+        self.active.store(false, Ordering::SeqCst);
+        let result = nix::mount::umount2(&self.path, nix::mount::MntFlags::MNT_DETACH);
+        drop(self.session);
+        result.map_err(|e| {
+            ChaosError::CleanupFailed(format!("Unmounting {} failed: {}", self.path.display(), e))
+        })?;
+        info!("Unmounted fault filesystem from {}", self.path.display());
+        Ok(())
+    }
+}
+
+/// Passthrough filesystem over a directory opened before the mount hid it
+struct FaultFs {
+    root: File,
+    /// `/proc/self/fd/<root>`, the hidden directory
+    base: PathBuf,
+    rules: Vec<DiskFaultRule>,
+    rng: StdRng,
+    active: Arc<AtomicBool>,
+    /// Paths relative to the root, which is the empty path
+    inodes: HashMap<u64, PathBuf>,
+    paths: HashMap<PathBuf, u64>,
+    next_ino: u64,
+    files: Arc<Mutex<HashMap<u64, Arc<File>>>>,
+    next_fh: u64,
+    /// Delayed operations sleep on the runtime the injection was made from
+    runtime: Handle,
+    /// New files are handed to the caller when we can change ownership
+    chown: bool,
+}
+
+impl FaultFs {
+    fn new(
+        root: File,
+        rules: Vec<DiskFaultRule>,
+        seed: Option<u64>,
+        active: Arc<AtomicBool>,
+    ) -> Self {
+        let base = PathBuf::from(format!("/proc/self/fd/{}", root.as_raw_fd()));
+        Self {
+            root,
+            base,
+            rules,
+            rng: seed.map_or_else(StdRng::from_entropy, StdRng::seed_from_u64),
+            active,
+            inodes: HashMap::from([(ROOT_INO, PathBuf::new())]),
+            paths: HashMap::from([(PathBuf::new(), ROOT_INO)]),
+            next_ino: ROOT_INO + 1,
+            files: Arc::new(Mutex::new(HashMap::new())),
+            next_fh: 1,
+            runtime: Handle::current(),
+            chown: nix::unistd::geteuid().is_root(),
+        }
+    }
+
+    fn relative(&self, ino: u64) -> std::result::Result<PathBuf, i32> {
+        self.inodes.get(&ino).cloned().ok_or(libc::ENOENT)
+    }
+
+    fn child(&self, parent: u64, name: &OsStr) -> std::result::Result<PathBuf, i32> {
+        Ok(self.relative(parent)?.join(name))
+    }
+
+    fn real(&self, relative: &Path) -> PathBuf {
+        self.base.join(relative)
+    }
+
+    fn ino_for(&mut self, relative: &Path) -> u64 {
+        if let Some(ino) = self.paths.get(relative) {
+            return *ino;
+        }
+        let ino = self.next_ino;
+        self.next_ino += 1;
+        self.inodes.insert(ino, relative.to_path_buf());
+        self.paths.insert(relative.to_path_buf(), ino);
+        ino
+    }
+
+    fn forget_path(&mut self, relative: &Path) {
+        if let Some(ino) = self.paths.remove(relative) {
+            self.inodes.remove(&ino);
+        }
+    }
+
+    fn attr(&mut self, relative: &Path) -> io::Result<FileAttr> {
+        let metadata = fs::symlink_metadata(self.real(relative))?;
+        Ok(file_attr(self.ino_for(relative), &metadata))
+    }
+
+    fn fault(&mut self, operation: DiskOperation, relative: &Path) -> DiskFault {
+        if !self.active.load(Ordering::Relaxed) {
+            return DiskFault::default();
+        }
+        let path = relative.to_string_lossy();
+        super::plan_fault(&self.rules, operation, &path, &mut self.rng)
+    }
+
+    /// Run `work` now, or after the fault's delay without blocking other
+    /// requests
+    fn dispatch(&self, delay: Duration, work: impl FnOnce() + Send + 'static) {
+        if delay.is_zero() {
+            work();
+            return;
+        }
+        self.runtime.spawn(async move {
+            tokio::time::sleep(delay).await;
+            let _ = tokio::task::spawn_blocking(work).await;
+        });
+    }
+
+    fn file(&self, fh: u64) -> Option<Arc<File>> {
+        self.files.lock().unwrap().get(&fh).cloned()
+    }
+
+    fn register(&mut self, file: File) -> u64 {
+        let fh = self.next_fh;
+        self.next_fh += 1;
+        self.files.lock().unwrap().insert(fh, Arc::new(file));
+        fh
+    }
+
+    fn hand_over(&self, real: &Path, req: &Request<'_>) {
+        if self.chown {
+            if let Err(e) = std::os::unix::fs::lchown(real, Some(req.uid()), Some(req.gid())) {
+                warn!(
+                    "Could not hand {} to uid {}: {}",
+                    real.display(),
+                    req.uid(),
+                    e
+                );
+            }
+        }
+    }
+}
+
+fn errno(e: io::Error) -> i32 {
+    e.raw_os_error().unwrap_or(libc::EIO)
+}
+
+fn file_type(file_type: fs::FileType) -> FileType {
+    if file_type.is_dir() {
+        FileType::Directory
+    } else if file_type.is_symlink() {
+        FileType::Symlink
+    } else if file_type.is_fifo() {
+        FileType::NamedPipe
+    } else if file_type.is_socket() {
+        FileType::Socket
+    } else if file_type.is_block_device() {
+        FileType::BlockDevice
+    } else if file_type.is_char_device() {
+        FileType::CharDevice
+    } else {
+        FileType::RegularFile
+    }
+}
+
+fn file_attr(ino: u64, metadata: &fs::Metadata) -> FileAttr {
+    let time = |secs: i64, nsecs: i64| {
+        UNIX_EPOCH + Duration::new(secs.max(0) as u64, nsecs.clamp(0, 999_999_999) as u32)
+    };
+    FileAttr {
+        ino,
+        size: metadata.size(),
+        blocks: metadata.blocks(),
+        atime: time(metadata.atime(), metadata.atime_nsec()),
+        mtime: time(metadata.mtime(), metadata.mtime_nsec()),
+        ctime: time(metadata.ctime(), metadata.ctime_nsec()),
+        crtime: time(metadata.ctime(), metadata.ctime_nsec()),
+        kind: file_type(metadata.file_type()),
+        perm: (metadata.mode() & 0o7777) as u16,
+        nlink: metadata.nlink() as u32,
+        uid: metadata.uid(),
+        gid: metadata.gid(),
+        rdev: metadata.rdev() as u32,
+        blksize: metadata.blksize() as u32,
+        flags: 0,
+    }
+}
+
+/// Open options for the access mode and flags of an open(2) call
+fn open_options(flags: i32) -> OpenOptions {
+    let mut options = OpenOptions::new();
+    match flags & libc::O_ACCMODE {
+        libc::O_WRONLY => options.write(true),
+        libc::O_RDWR => options.read(true).write(true),
+        _ => options.read(true),
+    };
+    options
+        .custom_flags(flags & !(libc::O_ACCMODE | libc::O_CREAT | libc::O_EXCL | libc::O_NOCTTY));
+    options
+}
+
+fn system_time(time: TimeOrNow) -> SystemTime {
+    match time {
+        TimeOrNow::SpecificTime(time) => time,
+        TimeOrNow::Now => SystemTime::now(),
+    }
+}
+
+impl Filesystem for FaultFs {
+    fn lookup(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        let attr = self
+            .child(parent, name)
+            .and_then(|path| self.attr(&path).map_err(errno));
+        match attr {
+            Ok(attr) => reply.entry(&TTL, &attr, 0),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn getattr(&mut self, _req: &Request<'_>, ino: u64, _fh: Option<u64>, reply: ReplyAttr) {
+        let attr = self
+            .relative(ino)
+            .and_then(|path| self.attr(&path).map_err(errno));
+        match attr {
+            Ok(attr) => reply.attr(&TTL, &attr),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn setattr(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        mode: Option<u32>,
+        uid: Option<u32>,
+        gid: Option<u32>,
+        size: Option<u64>,
+        atime: Option<TimeOrNow>,
+        mtime: Option<TimeOrNow>,
+        _ctime: Option<SystemTime>,
+        fh: Option<u64>,
+        _crtime: Option<SystemTime>,
+        _chgtime: Option<SystemTime>,
+        _bkuptime: Option<SystemTime>,
+        _flags: Option<u32>,
+        reply: ReplyAttr,
+    ) {
+        let path = match self.relative(ino) {
+            Ok(path) => path,
+            Err(e) => return reply.error(e),
+        };
+        let real = self.real(&path);
+        let open_file = fh.and_then(|fh| self.file(fh));
+        let result = (|| -> io::Result<()> {
+            if let Some(mode) = mode {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(&real, fs::Permissions::from_mode(mode & 0o7777))?;
+            }
+            if uid.is_some() || gid.is_some() {
+                std::os::unix::fs::lchown(&real, uid, gid)?;
+            }
+            if let Some(size) = size {
+                match &open_file {
+                    Some(file) => file.set_len(size)?,
+                    None => OpenOptions::new().write(true).open(&real)?.set_len(size)?,
+                }
+            }
+            if atime.is_some() || mtime.is_some() {
+                let mut times = FileTimes::new();
+                if let Some(atime) = atime {
+                    times = times.set_accessed(system_time(atime));
+                }
+                if let Some(mtime) = mtime {
+                    times = times.set_modified(system_time(mtime));
+                }
+                match &open_file {
+                    Some(file) => file.set_times(times)?,
+                    None => File::open(&real)?.set_times(times)?,
+                }
+            }
+            Ok(())
+        })();
+        match result.and_then(|_| self.attr(&path)) {
+            Ok(attr) => reply.attr(&TTL, &attr),
+            Err(e) => reply.error(errno(e)),
+        }
+    }
+
+    fn readlink(&mut self, _req: &Request<'_>, ino: u64, reply: ReplyData) {
+        let target = self
+            .relative(ino)
+            .and_then(|path| fs::read_link(self.real(&path)).map_err(errno));
+        match target {
+            Ok(target) => reply.data(target.as_os_str().as_bytes()),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn mkdir(
+        &mut self,
+        req: &Request<'_>,
+        parent: u64,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+        reply: ReplyEntry,
+    ) {
+        let path = match self.child(parent, name) {
+            Ok(path) => path,
+            Err(e) => return reply.error(e),
+        };
+        let real = self.real(&path);
+        let result = fs::DirBuilder::new().mode(mode & !umask).create(&real);
+        match result.and_then(|_| {
+            self.hand_over(&real, req);
+            self.attr(&path)
+        }) {
+            Ok(attr) => reply.entry(&TTL, &attr, 0),
+            Err(e) => reply.error(errno(e)),
+        }
+    }
+
+    fn unlink(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEmpty) {
+        let path = match self.child(parent, name) {
+            Ok(path) => path,
+            Err(e) => return reply.error(e),
+        };
+        match fs::remove_file(self.real(&path)) {
+            Ok(()) => {
+                self.forget_path(&path);
+                reply.ok()
+            }
+            Err(e) => reply.error(errno(e)),
+        }
+    }
+
+    fn rmdir(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEmpty) {
+        let path = match self.child(parent, name) {
+            Ok(path) => path,
+            Err(e) => return reply.error(e),
+        };
+        match fs::remove_dir(self.real(&path)) {
+            Ok(()) => {
+                self.forget_path(&path);
+                reply.ok()
+            }
+            Err(e) => reply.error(errno(e)),
+        }
+    }
+
+    fn symlink(
+        &mut self,
+        req: &Request<'_>,
+        parent: u64,
+        link_name: &OsStr,
+        target: &Path,
+        reply: ReplyEntry,
+    ) {
+        let path = match self.child(parent, link_name) {
+            Ok(path) => path,
+            Err(e) => return reply.error(e),
+        };
+        let real = self.real(&path);
+        match std::os::unix::fs::symlink(target, &real).and_then(|_| {
+            self.hand_over(&real, req);
+            self.attr(&path)
+        }) {
+            Ok(attr) => reply.entry(&TTL, &attr, 0),
+            Err(e) => reply.error(errno(e)),
+        }
+    }
+
+    fn rename(
+        &mut self,
+        _req: &Request<'_>,
+        parent: u64,
+        name: &OsStr,
+        newparent: u64,
+        newname: &OsStr,
+        flags: u32,
+        reply: ReplyEmpty,
+    ) {
+        if flags != 0 {
+            return reply.error(libc::EINVAL);
+        }
+        let (from, to) = match (self.child(parent, name), self.child(newparent, newname)) {
+            (Ok(from), Ok(to)) => (from, to),
+            (Err(e), _) | (_, Err(e)) => return reply.error(e),
+        };
+        if let Err(e) = fs::rename(self.real(&from), self.real(&to)) {
+            return reply.error(errno(e));
+        }
+        self.forget_path(&to);
+        // Move the inodes of the renamed entry and everything below it
+        let moved: Vec<_> = self
+            .paths
+            .keys()
+            .filter(|path| path.starts_with(&from))
+            .cloned()
+            .collect();
+        for old in moved {
+            let ino = self.paths.remove(&old).unwrap_or_default();
+            let new = to.join(old.strip_prefix(&from).unwrap_or(&old));
+            self.inodes.insert(ino, new.clone());
+            self.paths.insert(new, ino);
+        }
+        reply.ok()
+    }
+
+    fn link(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        newparent: u64,
+        newname: &OsStr,
+        reply: ReplyEntry,
+    ) {
+        let (from, to) = match (self.relative(ino), self.child(newparent, newname)) {
+            (Ok(from), Ok(to)) => (from, to),
+            (Err(e), _) | (_, Err(e)) => return reply.error(e),
+        };
+        match fs::hard_link(self.real(&from), self.real(&to)).and_then(|_| self.attr(&to)) {
+            Ok(attr) => reply.entry(&TTL, &attr, 0),
+            Err(e) => reply.error(errno(e)),
+        }
+    }
+
+    fn open(&mut self, _req: &Request<'_>, ino: u64, flags: i32, reply: ReplyOpen) {
+        let path = match self.relative(ino) {
+            Ok(path) => path,
+            Err(e) => return reply.error(e),
+        };
+        let fault = self.fault(DiskOperation::Open, &path);
+        if let Some(e) = fault.errno {
+            return self.dispatch(fault.delay, move || reply.error(e));
+        }
+        match open_options(flags).open(self.real(&path)) {
+            // Direct I/O so every read and write reaches us instead of the
+            // page cache
+            Ok(file) => {
+                let fh = self.register(file);
+                self.dispatch(fault.delay, move || {
+                    reply.opened(fh, fuser::consts::FOPEN_DIRECT_IO)
+                });
+            }
+            Err(e) => reply.error(errno(e)),
+        }
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        let (Ok(path), Some(file)) = (self.relative(ino), self.file(fh)) else {
+            return reply.error(libc::EBADF);
+        };
+        let fault = self.fault(DiskOperation::Read, &path);
+        self.dispatch(fault.delay, move || {
+            if let Some(e) = fault.errno {
+                return reply.error(e);
+            }
+            let mut buffer = vec![0; size as usize];
+            let mut filled = 0;
+            while filled < buffer.len() {
+                match file.read_at(&mut buffer[filled..], offset as u64 + filled as u64) {
+                    Ok(0) => break,
+                    Ok(n) => filled += n,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return reply.error(errno(e)),
+                }
+            }
+            reply.data(&buffer[..filled]);
+        });
+    }
+
+    fn write(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        fh: u64,
+        offset: i64,
+        data: &[u8],
+        _write_flags: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyWrite,
+    ) {
+        let (Ok(path), Some(file)) = (self.relative(ino), self.file(fh)) else {
+            return reply.error(libc::EBADF);
+        };
+        let fault = self.fault(DiskOperation::Write, &path);
+        let mut data = data.to_vec();
+        if fault.short_write && data.len() > 1 {
+            data.truncate(self.rng.gen_range(1..data.len()));
+        }
+        self.dispatch(fault.delay, move || {
+            if let Some(e) = fault.errno {
+                return reply.error(e);
+            }
+            match file.write_all_at(&data, offset as u64) {
+                Ok(()) => reply.written(data.len() as u32),
+                Err(e) => reply.error(errno(e)),
+            }
+        });
+    }
+
+    fn flush(&mut self, _req: &Request<'_>, _ino: u64, _fh: u64, _owner: u64, reply: ReplyEmpty) {
+        reply.ok();
+    }
+
+    fn release(
+        &mut self,
+        _req: &Request<'_>,
+        _ino: u64,
+        fh: u64,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        _flush: bool,
+        reply: ReplyEmpty,
+    ) {
+        self.files.lock().unwrap().remove(&fh);
+        reply.ok();
+    }
+
+    fn fsync(&mut self, _req: &Request<'_>, ino: u64, fh: u64, datasync: bool, reply: ReplyEmpty) {
+        let (Ok(path), Some(file)) = (self.relative(ino), self.file(fh)) else {
+            return reply.error(libc::EBADF);
+        };
+        let fault = self.fault(DiskOperation::Fsync, &path);
+        self.dispatch(fault.delay, move || {
+            if let Some(e) = fault.errno {
+                return reply.error(e);
+            }
+            let synced = if datasync {
+                file.sync_data()
+            } else {
+                file.sync_all()
+            };
+            match synced {
+                Ok(()) => reply.ok(),
+                Err(e) => reply.error(errno(e)),
+            }
+        });
+    }
+
+    fn opendir(&mut self, _req: &Request<'_>, ino: u64, _flags: i32, reply: ReplyOpen) {
+        let path = match self.relative(ino) {
+            Ok(path) => path,
+            Err(e) => return reply.error(e),
+        };
+        let fault = self.fault(DiskOperation::Open, &path);
+        self.dispatch(fault.delay, move || match fault.errno {
+            Some(e) => reply.error(e),
+            None => reply.opened(0, 0),
+        });
+    }
+
+    fn readdir(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectory,
+    ) {
+        let path = match self.relative(ino) {
+            Ok(path) => path,
+            Err(e) => return reply.error(e),
+        };
+        let entries = match fs::read_dir(self.real(&path)) {
+            Ok(entries) => entries,
+            Err(e) => return reply.error(errno(e)),
+        };
+        let parent = path
+            .parent()
+            .map_or(ROOT_INO, |parent| self.ino_for(parent));
+        let mut listing = vec![
+            (ino, FileType::Directory, OsStr::new(".").to_os_string()),
+            (parent, FileType::Directory, OsStr::new("..").to_os_string()),
+        ];
+        for entry in entries.flatten() {
+            let kind = entry.file_type().map_or(FileType::RegularFile, file_type);
+            let ino = self.ino_for(&path.join(entry.file_name()));
+            listing.push((ino, kind, entry.file_name()));
+        }
+        for (index, (ino, kind, name)) in listing.into_iter().enumerate().skip(offset as usize) {
+            if reply.add(ino, index as i64 + 1, kind, name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+
+    fn fsyncdir(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _fh: u64,
+        _datasync: bool,
+        reply: ReplyEmpty,
+    ) {
+        let path = match self.relative(ino) {
+            Ok(path) => path,
+            Err(e) => return reply.error(e),
+        };
+        let fault = self.fault(DiskOperation::Fsync, &path);
+        let real = self.real(&path);
+        self.dispatch(fault.delay, move || {
+            if let Some(e) = fault.errno {
+                return reply.error(e);
+            }
+            match File::open(real).and_then(|dir| dir.sync_all()) {
+                Ok(()) => reply.ok(),
+                Err(e) => reply.error(errno(e)),
+            }
+        });
+    }
+
+    fn statfs(&mut self, _req: &Request<'_>, _ino: u64, reply: ReplyStatfs) {
+        match nix::sys::statvfs::fstatvfs(&self.root) {
+            Ok(stats) => reply.statfs(
+                stats.blocks(),
+                stats.blocks_free(),
+                stats.blocks_available(),
+                stats.files(),
+                stats.files_free(),
+                stats.block_size() as u32,
+                stats.name_max() as u32,
+                stats.fragment_size() as u32,
+            ),
+            Err(e) => reply.error(e as i32),
+        }
+    }
+
+    fn access(&mut self, _req: &Request<'_>, _ino: u64, _mask: i32, reply: ReplyEmpty) {
+        // default_permissions has the kernel check access itself
+        reply.ok();
+    }
+
+    fn create(
+        &mut self,
+        req: &Request<'_>,
+        parent: u64,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+        flags: i32,
+        reply: ReplyCreate,
+    ) {
+        let path = match self.child(parent, name) {
+            Ok(path) => path,
+            Err(e) => return reply.error(e),
+        };
+        let fault = self.fault(DiskOperation::Open, &path);
+        if let Some(e) = fault.errno {
+            return self.dispatch(fault.delay, move || reply.error(e));
+        }
+        let real = self.real(&path);
+        let mut options = open_options(flags);
+        options.create(true).mode(mode & !umask);
+        if flags & libc::O_EXCL != 0 {
+            options.create_new(true);
+        }
+        let created = options.open(&real).and_then(|file| {
+            self.hand_over(&real, req);
+            Ok((file, self.attr(&path)?))
+        });
+        match created {
+            Ok((file, attr)) => {
+                let fh = self.register(file);
+                self.dispatch(fault.delay, move || {
+                    reply.created(&TTL, &attr, 0, fh, fuser::consts::FOPEN_DIRECT_IO)
+                });
+            }
+            Err(e) => reply.error(errno(e)),
+        }
+    }
+}
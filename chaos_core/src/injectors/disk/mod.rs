@@ -0,0 +1,1248 @@
+#[cfg(target_os = "linux")]
+mod fault_fs;
+mod throttle;
+
+pub use throttle::*;
+
+use super::network::LatencyDistribution;
+use crate::{
+    error::*,
+    handle::InjectionHandle,
+    injectors::{params, Injector, InjectorParams},
+    target::Target,
+};
+use async_trait::async_trait;
+use rand::Rng;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::info;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskSlowConfig {
+    /// Delay of `operations` on every path when no rules are given
+    pub latency: Duration,
+    pub operations: Vec<DiskOperation>,
+    /// Directory the fault filesystem is mounted over
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    #[serde(default)]
+    pub rules: Vec<DiskFaultRule>,
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum DiskOperation {
+    #[serde(alias = "read")]
+    Read,
+    #[serde(alias = "write")]
+    Write,
+    #[serde(alias = "fsync")]
+    Fsync,
+    #[serde(alias = "open")]
+    Open,
+    #[serde(alias = "all")]
+    All,
+}
+
+impl DiskOperation {
+    pub fn covers(self, operation: DiskOperation) -> bool {
+        self == DiskOperation::All || self == operation
+    }
+}
+
+impl Default for DiskSlowConfig {
+    fn default() -> Self {
+        Self {
+            latency: Duration::from_millis(100),
+            operations: vec![DiskOperation::All],
+            path: None,
+            rules: Vec::new(),
+            seed: None,
+        }
+    }
+}
+
+impl DiskSlowConfig {
+    /// The configured rules, or `latency` on `operations` for every path
+    pub fn effective_rules(&self) -> Vec<DiskFaultRule> {
+        if !self.rules.is_empty() {
+            return self.rules.clone();
+        }
+        vec![DiskFaultRule {
+            operations: self.operations.clone(),
+            delay: Some(LatencyDistribution::Fixed {
+                value: self.latency.as_secs_f64() * 1000.0,
+            }),
+            ..DiskFaultRule::new(PathGlob::any())
+        }]
+    }
+}
+
+/// Paths a rule applies to, relative to the mounted directory: `*` matches
+/// within one component, `**` across components and `?` one character.
+/// Patterns without a `/` match the file name in any directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PathGlob {
+    pattern: String,
+    regex: Regex,
+}
+
+impl PathGlob {
+    /// Every path
+    pub fn any() -> Self {
+        "**".parse().expect("valid glob")
+    }
+
+    pub fn matches(&self, path: &str) -> bool {
+        let path = path.trim_start_matches('/');
+        if self.pattern.contains('/') {
+            self.regex.is_match(path)
+        } else {
+            self.regex.is_match(path.rsplit('/').next().unwrap_or(path))
+        }
+    }
+}
+
+impl FromStr for PathGlob {
+    type Err = ChaosError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let pattern = s.trim_start_matches('/');
+        if pattern.is_empty() {
+            return Err(ChaosError::InvalidConfig(
+                "disk_slow path glob must not be empty".to_string(),
+            ));
+        }
+        let mut regex = String::from("^");
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    // `**/` also matches no directory at all
+                    if chars.peek() == Some(&'/') {
+                        chars.next();
+                        regex.push_str("(?:.*/)?");
+                    } else {
+                        regex.push_str(".*");
+                    }
+                }
+                '*' => regex.push_str("[^/]*"),
+                '?' => regex.push_str("[^/]"),
+                c => regex.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        regex.push('$');
+        Ok(Self {
+            pattern: s.to_string(),
+            regex: Regex::new(&regex)
+                .map_err(|e| ChaosError::InvalidConfig(format!("Invalid glob '{}': {}", s, e)))?,
+        })
+    }
+}
+
+impl TryFrom<String> for PathGlob {
+    type Error = ChaosError;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<PathGlob> for String {
+    fn from(glob: PathGlob) -> Self {
+        glob.pattern
+    }
+}
+
+/// Error an operation fails with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiskError {
+    /// Input/output error
+    Eio,
+    /// No space left on device
+    Enospc,
+    /// Disk quota exceeded
+    Edquot,
+}
+
+impl DiskError {
+    pub fn errno(self) -> i32 {
+        match self {
+            DiskError::Eio => libc::EIO,
+            DiskError::Enospc => libc::ENOSPC,
+            DiskError::Edquot => libc::EDQUOT,
+        }
+    }
+}
+
+/// Faults for the selected operations on paths matching a glob
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskFaultRule {
+    pub path: PathGlob,
+    pub operations: Vec<DiskOperation>,
+    /// Chance that the rule fires for one operation
+    pub probability: f64,
+    /// In milliseconds
+    pub delay: Option<LatencyDistribution>,
+    pub error: Option<DiskError>,
+    /// Writes store and report only part of the buffer
+    pub short_write: bool,
+}
+
+impl DiskFaultRule {
+    pub fn new(path: PathGlob) -> Self {
+        Self {
+            path,
+            operations: vec![DiskOperation::All],
+            probability: 1.0,
+            delay: None,
+            error: None,
+            short_write: false,
+        }
+    }
+
+    pub fn operations(mut self, operations: Vec<DiskOperation>) -> Self {
+        self.operations = operations;
+        self
+    }
+
+    pub fn probability(mut self, probability: f64) -> Self {
+        self.probability = probability;
+        self
+    }
+
+    pub fn delay(mut self, delay: LatencyDistribution) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    pub fn error(mut self, error: DiskError) -> Self {
+        self.error = Some(error);
+        self
+    }
+
+    pub fn short_write(mut self) -> Self {
+        self.short_write = true;
+        self
+    }
+
+    fn validate(&self) -> Result<()> {
+        if !(0.0..=1.0).contains(&self.probability) {
+            return Err(ChaosError::InvalidConfig(format!(
+                "disk_slow rule probability must be between 0.0 and 1.0, got {}",
+                self.probability
+            )));
+        }
+        if self.operations.is_empty() {
+            return Err(ChaosError::InvalidConfig(format!(
+                "disk_slow rule for '{}' selects no operations",
+                String::from(self.path.clone())
+            )));
+        }
+        if self.error.is_some() && self.short_write {
+            return Err(ChaosError::InvalidConfig(
+                "disk_slow rule cannot both fail and short-write".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// What one operation suffers
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct DiskFault {
+    pub delay: Duration,
+    pub errno: Option<i32>,
+    pub short_write: bool,
+}
+
+/// Roll every rule for `operation` on `path`; delays of firing rules add up
+/// and the first error wins
+pub(crate) fn plan_fault<R: Rng>(
+    rules: &[DiskFaultRule],
+    operation: DiskOperation,
+    path: &str,
+    rng: &mut R,
+) -> DiskFault {
+    let mut fault = DiskFault::default();
+    for rule in rules {
+        let applies =
+            rule.operations.iter().any(|op| op.covers(operation)) && rule.path.matches(path);
+        if !applies || !rng.gen_bool(rule.probability) {
+            continue;
+        }
+        if let Some(delay) = &rule.delay {
+            fault.delay += Duration::from_secs_f64(delay.sample(rng).max(0.0) / 1000.0);
+        }
+        if fault.errno.is_none() {
+            fault.errno = rule.error.map(DiskError::errno);
+        }
+        fault.short_write |= rule.short_write && operation == DiskOperation::Write;
+    }
+    fault
+}
+
+/// Mounts a FUSE passthrough filesystem over a directory that delays, fails
+/// or short-writes selected operations
+#[derive(Default)]
+pub struct DiskSlowInjector {
+    config: DiskSlowConfig,
+    #[cfg(target_os = "linux")]
+    mounts: Mutex<HashMap<String, fault_fs::FaultMount>>,
+    #[cfg(not(target_os = "linux"))]
+    #[allow(dead_code)]
+    mounts: Mutex<HashMap<String, ()>>,
+}
+
+impl DiskSlowInjector {
+    pub fn new(config: DiskSlowConfig) -> Self {
+        Self {
+            config,
+            mounts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn builder() -> DiskSlowBuilder {
+        DiskSlowBuilder::default()
+    }
+
+    pub fn from_params(params: &InjectorParams) -> Result<Self> {
+        let params: DiskSlowParams = params::parse_params("disk_slow", params)?;
+        let mut builder = Self::builder();
+        if let Some(latency) = params.latency {
+            builder = builder.latency(latency);
+        }
+        if let Some(operations) = params.operations {
+            builder = builder.operations(operations);
+        }
+        if let Some(path) = params.path {
+            builder = builder.path(path);
+        }
+        for rule in params.rules {
+            builder = builder.rule(rule.into_rule()?);
+        }
+        if let Some(seed) = params.seed {
+            builder = builder.seed(seed);
+        }
+        let injector = builder.build();
+        injector.validate_config()?;
+        Ok(injector)
+    }
+
+    fn validate_config(&self) -> Result<()> {
+        self.config
+            .rules
+            .iter()
+            .try_for_each(DiskFaultRule::validate)
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn inject_linux(&self, target: &Target) -> Result<InjectionHandle> {
+        self.validate_config()?;
+        let path = self.config.path.clone().ok_or_else(|| {
+            ChaosError::InvalidConfig(
+                "disk_slow requires the directory to fault as `path`".to_string(),
+            )
+        })?;
+        let rules = self.config.effective_rules();
+        info!(
+            "Injecting disk faults under {} ({} rule(s))",
+            path.display(),
+            rules.len()
+        );
+
+        let metadata = serde_json::json!({
+            "path": path,
+            "rules": rules.len(),
+        });
+        let seed = self.config.seed;
+        let mount =
+            tokio::task::spawn_blocking(move || fault_fs::FaultMount::mount(&path, rules, seed))
+                .await
+                .map_err(|e| ChaosError::InjectionFailed(e.to_string()))??;
+
+        let handle = InjectionHandle::new("disk_slow", target.clone(), metadata);
+        self.mounts.lock().unwrap().insert(handle.id.clone(), mount);
+        Ok(handle)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    async fn inject_linux(&self, _target: &Target) -> Result<InjectionHandle> {
+        Err(ChaosError::SystemError(
+            "Disk slowdown injection only supported on Linux".to_string(),
+        ))
+    }
+}
+
+#[async_trait]
+impl Injector for DiskSlowInjector {
+    async fn inject(&self, target: &Target) -> Result<InjectionHandle> {
+        self.inject_linux(target).await
+    }
+
+    async fn remove(&self, handle: InjectionHandle) -> Result<()> {
+        #[cfg(target_os = "linux")]
+        {
+            let mount = self.mounts.lock().unwrap().remove(&handle.id);
+            match mount {
+                Some(mount) => tokio::task::spawn_blocking(move || mount.unmount())
+                    .await
+                    .map_err(|e| ChaosError::CleanupFailed(e.to_string()))??,
+                None => {
+                    // Mounted by another process, e.g. before a restart
+                    let path = handle.metadata.get("path").and_then(|v| v.as_str());
+                    if let Some(path) = path {
+                        info!("Detaching fault filesystem from {}", path);
+                        nix::mount::umount2(path, nix::mount::MntFlags::MNT_DETACH).map_err(
+                            |e| {
+                                ChaosError::CleanupFailed(format!(
+                                    "Unmounting {} failed: {}",
+                                    path, e
+                                ))
+                            },
+                        )?;
+                    }
+                }
+            }
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = handle;
+        }
+
+        info!("Removed disk faults");
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "disk_slow"
+    }
+
+    async fn validate(&self) -> Result<()> {
+        self.validate_config()
+    }
+
+    fn required_capabilities(&self) -> Vec<String> {
+        vec!["CAP_SYS_ADMIN".to_string()]
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DiskSlowParams {
+    #[serde(default, deserialize_with = "params::opt_duration")]
+    latency: Option<Duration>,
+    #[serde(default)]
+    operations: Option<Vec<DiskOperation>>,
+    #[serde(default)]
+    path: Option<PathBuf>,
+    #[serde(default)]
+    rules: Vec<DiskRuleParams>,
+    #[serde(default)]
+    seed: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DiskRuleParams {
+    #[serde(default)]
+    path: Option<PathGlob>,
+    #[serde(default)]
+    operations: Option<Vec<DiskOperation>>,
+    #[serde(default)]
+    probability: Option<f64>,
+    #[serde(default, deserialize_with = "params::opt_duration")]
+    delay: Option<Duration>,
+    #[serde(default, deserialize_with = "params::opt_duration")]
+    jitter: Option<Duration>,
+    #[serde(default)]
+    error: Option<DiskError>,
+    #[serde(default)]
+    short_write: bool,
+}
+
+impl DiskRuleParams {
+    fn into_rule(self) -> Result<DiskFaultRule> {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let delay = match (self.delay, self.jitter) {
+            (Some(delay), Some(jitter)) => Some(LatencyDistribution::Normal {
+                mean: ms(delay),
+                std_dev: ms(jitter),
+            }),
+            (Some(delay), None) => Some(LatencyDistribution::Fixed { value: ms(delay) }),
+            (None, Some(_)) => {
+                return Err(ChaosError::InvalidConfig(
+                    "disk_slow jitter requires a delay".to_string(),
+                ))
+            }
+            (None, None) => None,
+        };
+        Ok(DiskFaultRule {
+            operations: self.operations.unwrap_or(vec![DiskOperation::All]),
+            probability: self.probability.unwrap_or(1.0),
+            delay,
+            error: self.error,
+            short_write: self.short_write,
+            ..DiskFaultRule::new(self.path.unwrap_or_else(PathGlob::any))
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct DiskSlowBuilder {
+    config: DiskSlowConfig,
+}
+
+impl DiskSlowBuilder {
+    pub fn latency(mut self, latency: Duration) -> Self {
+        self.config.latency = latency;
+        self
+    }
+
+    pub fn operations(mut self, operations: Vec<DiskOperation>) -> Self {
+        self.config.operations = operations;
+        self
+    }
+
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.path = Some(path.into());
+        self
+    }
+
+    pub fn rule(mut self, rule: DiskFaultRule) -> Self {
+        self.config.rules.push(rule);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.config.seed = Some(seed);
+        self
+    }
+
+    pub fn build(self) -> DiskSlowInjector {
+        DiskSlowInjector::new(self.config)
+    }
+}
+
+// Disk Failure Injector
+#[derive(Debug, Clone)]
+pub struct DiskFailureInjector {
+    failure_rate: f64, // 0.0 - 1.0
+}
+
+impl Default for DiskFailureInjector {
+    fn default() -> Self {
+        Self { failure_rate: 0.1 }
+    }
+}
+
+impl DiskFailureInjector {
+    pub fn new(failure_rate: f64) -> Self {
+        Self {
+            failure_rate: failure_rate.clamp(0.0, 1.0),
+        }
+    }
+}
+
+#[async_trait]
+impl Injector for DiskFailureInjector {
+    async fn inject(&self, target: &Target) -> Result<InjectionHandle> {
+        info!("Injecting disk write failures: rate={}", self.failure_rate);
+
+        let metadata = serde_json::json!({
+            "failure_rate": self.failure_rate,
+        });
+
+        Ok(InjectionHandle::new(
+            "disk_failure",
+            target.clone(),
+            metadata,
+        ))
+    }
+
+    async fn remove(&self, _handle: InjectionHandle) -> Result<()> {
+        info!("Removing disk write failures");
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "disk_failure"
+    }
+}
+
+// Disk Fill Injector
+/// What `disk_fill` exhausts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiskFillMode {
+    /// Space, with one preallocated file
+    #[default]
+    Bytes,
+    /// Inodes, with empty files
+    Inodes,
+}
+
+/// How full the filesystem is left
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiskFillLevel {
+    /// Fraction of the filesystem in use, 0.0 - 1.0
+    Usage(f64),
+    /// Bytes, or inodes, still available to unprivileged users
+    LeaveFree(u64),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskFillConfig {
+    /// Directory on the filesystem to fill; the filler is created inside it
+    pub path: PathBuf,
+    pub mode: DiskFillMode,
+    pub level: DiskFillLevel,
+}
+
+/// Files per directory when exhausting inodes, keeping directories small
+const INODES_PER_DIR: u64 = 10_000;
+/// Chunk written when the filesystem cannot preallocate
+const FILL_CHUNK: usize = 1 << 20;
+/// File name prefix of every filler, checked before anything is deleted
+const FILLER_PREFIX: &str = "chaos_disk_fill_";
+
+/// Fills a filesystem up to a usage level, or until only a given amount is
+/// free, with a preallocated file or with empty files that use up inodes
+#[derive(Debug, Clone)]
+pub struct DiskFillInjector {
+    config: DiskFillConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DiskFillParams {
+    path: PathBuf,
+    #[serde(default)]
+    mode: Option<DiskFillMode>,
+    #[serde(default)]
+    target_usage: Option<f64>,
+    #[serde(default)]
+    leave_free: Option<serde_json::Value>,
+}
+
+/// Size such as `512MB`, `1.5GiB` or a plain number of bytes. SI units are
+/// powers of 1000, IEC units and bare `K`/`M`/`G`/`T` powers of 1024.
+pub(crate) fn parse_size(size: &str) -> Option<u64> {
+    let split = size
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(size.len());
+    let (number, unit) = size.split_at(split);
+    let number = number.parse::<f64>().ok().filter(|n| *n >= 0.0)?;
+    let multiplier = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1.0,
+        "kb" => 1e3,
+        "mb" => 1e6,
+        "gb" => 1e9,
+        "tb" => 1e12,
+        "k" | "kib" => 1024.0,
+        "m" | "mib" => 1024f64.powi(2),
+        "g" | "gib" => 1024f64.powi(3),
+        "t" | "tib" => 1024f64.powi(4),
+        _ => return None,
+    };
+    Some((number * multiplier) as u64)
+}
+
+/// A size parameter given as a number of bytes or a string for `parse_size`
+pub(crate) fn size_param(value: &serde_json::Value) -> Option<u64> {
+    match value {
+        serde_json::Value::Number(n) => n.as_u64(),
+        serde_json::Value::String(s) => parse_size(s),
+        _ => None,
+    }
+}
+
+impl DiskFillInjector {
+    pub fn new(config: DiskFillConfig) -> Self {
+        Self { config }
+    }
+
+    /// Fill the filesystem holding `path` until `target_usage` of it is used
+    pub fn usage(path: impl Into<PathBuf>, target_usage: f64) -> Self {
+        Self::new(DiskFillConfig {
+            path: path.into(),
+            mode: DiskFillMode::Bytes,
+            level: DiskFillLevel::Usage(target_usage),
+        })
+    }
+
+    /// Fill the filesystem holding `path` until `bytes` are left
+    pub fn leave_free(path: impl Into<PathBuf>, bytes: u64) -> Self {
+        Self::new(DiskFillConfig {
+            path: path.into(),
+            mode: DiskFillMode::Bytes,
+            level: DiskFillLevel::LeaveFree(bytes),
+        })
+    }
+
+    /// Exhaust inodes instead of space; a `leave_free` level counts inodes
+    pub fn inodes(mut self) -> Self {
+        self.config.mode = DiskFillMode::Inodes;
+        self
+    }
+
+    pub fn from_params(params: &InjectorParams) -> Result<Self> {
+        let params: DiskFillParams = params::parse_params("disk_fill", params)?;
+        let mode = params.mode.unwrap_or_default();
+        let level = match (params.target_usage, params.leave_free) {
+            (Some(usage), None) => DiskFillLevel::Usage(usage),
+            (None, Some(free)) => {
+                let parsed = match &free {
+                    serde_json::Value::String(_) if mode == DiskFillMode::Inodes => None,
+                    free => size_param(free),
+                };
+                DiskFillLevel::LeaveFree(parsed.ok_or_else(|| {
+                    ChaosError::InvalidConfig(format!(
+                        "Invalid disk_fill leave_free {}: expected e.g. '500MB', or a number of \
+                         inodes in inodes mode",
+                        free
+                    ))
+                })?)
+            }
+            _ => {
+                return Err(ChaosError::InvalidConfig(
+                    "disk_fill needs exactly one of target_usage or leave_free".to_string(),
+                ))
+            }
+        };
+        let injector = Self::new(DiskFillConfig {
+            path: params.path,
+            mode,
+            level,
+        });
+        injector.validate_config()?;
+        Ok(injector)
+    }
+
+    fn validate_config(&self) -> Result<()> {
+        if let DiskFillLevel::Usage(usage) = self.config.level {
+            if !(0.0..=1.0).contains(&usage) {
+                return Err(ChaosError::InvalidConfig(format!(
+                    "disk_fill target_usage must be between 0.0 and 1.0, got {}",
+                    usage
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Refuse to delete anything but a filler created directly in the fill
+    /// path, whatever a stale or forged handle names
+    fn check_filler(&self, filler: &Path) -> Result<()> {
+        let named_like_filler = filler
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(FILLER_PREFIX));
+        if !named_like_filler || filler.parent() != Some(self.config.path.as_path()) {
+            return Err(ChaosError::CleanupFailed(format!(
+                "Refusing to remove {}: not a disk fill filler in {}",
+                filler.display(),
+                self.config.path.display()
+            )));
+        }
+        Ok(())
+    }
+
+    /// How much to take from `available` of `total` to reach the level
+    fn amount_to_fill(&self, total: u64, available: u64) -> u64 {
+        let keep = match self.config.level {
+            DiskFillLevel::Usage(usage) => (total as f64 * (1.0 - usage)).round() as u64,
+            DiskFillLevel::LeaveFree(free) => free,
+        };
+        available.saturating_sub(keep)
+    }
+
+    #[cfg(unix)]
+    async fn inject_unix(&self, target: &Target) -> Result<InjectionHandle> {
+        use nix::sys::statvfs::statvfs;
+
+        self.validate_config()?;
+        let path = &self.config.path;
+        let stats = statvfs(path).map_err(|e| {
+            ChaosError::SystemError(format!(
+                "Failed to stat filesystem of {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let filler = path.join(format!("{}{}", FILLER_PREFIX, uuid::Uuid::new_v4()));
+
+        let metadata = match self.config.mode {
+            DiskFillMode::Bytes => {
+                let fragment = stats.fragment_size() as u64;
+                let bytes = self.amount_to_fill(
+                    stats.blocks() as u64 * fragment,
+                    stats.blocks_available() as u64 * fragment,
+                );
+                info!(
+                    "Filling {} with {} bytes at {}",
+                    path.display(),
+                    bytes,
+                    filler.display()
+                );
+                let file = filler.clone();
+                let filled = tokio::task::spawn_blocking(move || fill_bytes(&file, bytes))
+                    .await
+                    .map_err(|e| ChaosError::InjectionFailed(e.to_string()))?;
+                let filled = match filled {
+                    Ok(filled) => filled,
+                    Err(e) => {
+                        let _ = std::fs::remove_file(&filler);
+                        return Err(e);
+                    }
+                };
+                serde_json::json!({
+                    "filler": filler,
+                    "mode": DiskFillMode::Bytes,
+                    "bytes_filled": filled,
+                })
+            }
+            DiskFillMode::Inodes => {
+                if stats.files() == 0 {
+                    return Err(ChaosError::InvalidConfig(format!(
+                        "The filesystem of {} does not limit inodes",
+                        path.display()
+                    )));
+                }
+                let inodes =
+                    self.amount_to_fill(stats.files() as u64, stats.files_available() as u64);
+                info!(
+                    "Using up {} inodes of {} at {}",
+                    inodes,
+                    path.display(),
+                    filler.display()
+                );
+                let dir = filler.clone();
+                let used = tokio::task::spawn_blocking(move || fill_inodes(&dir, inodes))
+                    .await
+                    .map_err(|e| ChaosError::InjectionFailed(e.to_string()))?;
+                let used = match used {
+                    Ok(used) => used,
+                    Err(e) => {
+                        let _ = remove_filler(&filler);
+                        return Err(e);
+                    }
+                };
+                serde_json::json!({
+                    "filler": filler,
+                    "mode": DiskFillMode::Inodes,
+                    "inodes_used": used,
+                })
+            }
+        };
+
+        Ok(InjectionHandle::new("disk_fill", target.clone(), metadata))
+    }
+
+    #[cfg(not(unix))]
+    async fn inject_unix(&self, _target: &Target) -> Result<InjectionHandle> {
+        Err(ChaosError::SystemError(
+            "Disk fill injection only supported on Unix".to_string(),
+        ))
+    }
+}
+
+/// Allocate `bytes` to a new file, writing zeros where the filesystem cannot
+/// preallocate so the space is really used rather than sparse
+#[cfg(unix)]
+fn fill_bytes(path: &Path, bytes: u64) -> Result<u64> {
+    use std::io::Write;
+
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)?;
+    if bytes == 0 {
+        return Ok(0);
+    }
+    #[cfg(target_os = "linux")]
+    {
+        use nix::fcntl::{fallocate, FallocateFlags};
+        use std::os::fd::AsRawFd;
+        match fallocate(file.as_raw_fd(), FallocateFlags::empty(), 0, bytes as i64) {
+            Ok(()) => return Ok(bytes),
+            Err(nix::errno::Errno::EOPNOTSUPP) => {}
+            Err(e) => {
+                return Err(ChaosError::InjectionFailed(format!(
+                    "Preallocating {} bytes failed: {}",
+                    bytes, e
+                )))
+            }
+        }
+    }
+    let chunk = vec![0u8; FILL_CHUNK];
+    let mut written = 0;
+    while written < bytes {
+        let len = (bytes - written).min(FILL_CHUNK as u64) as usize;
+        match file.write(&chunk[..len]) {
+            Ok(n) => written += n as u64,
+            // Free space was over-reported; full is what was asked for
+            Err(e) if e.raw_os_error() == Some(libc::ENOSPC) => break,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    file.sync_all()?;
+    Ok(written)
+}
+
+/// Delete a filler: a directory of empty files, or a file. A symlink is
+/// removed itself, never followed.
+fn remove_filler(filler: &Path) -> std::io::Result<()> {
+    if std::fs::symlink_metadata(filler)?.is_dir() {
+        std::fs::remove_dir_all(filler)
+    } else {
+        std::fs::remove_file(filler)
+    }
+}
+
+/// Create a directory of empty files using up `inodes` inodes, the
+/// directories themselves included
+#[cfg(unix)]
+fn fill_inodes(dir: &Path, inodes: u64) -> Result<u64> {
+    std::fs::create_dir(dir)?;
+    let mut used = 1;
+    let mut current = dir.to_path_buf();
+    while used < inodes {
+        if used % INODES_PER_DIR == 0 {
+            current = dir.join(format!("d{}", used / INODES_PER_DIR));
+            match std::fs::create_dir(&current) {
+                Ok(()) => used += 1,
+                Err(e) if e.raw_os_error() == Some(libc::ENOSPC) => break,
+                Err(e) => return Err(e.into()),
+            }
+            continue;
+        }
+        match std::fs::File::create(current.join(used.to_string())) {
+            Ok(_) => used += 1,
+            Err(e) if e.raw_os_error() == Some(libc::ENOSPC) => break,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(used)
+}
+
+#[async_trait]
+impl Injector for DiskFillInjector {
+    async fn inject(&self, target: &Target) -> Result<InjectionHandle> {
+        self.inject_unix(target).await
+    }
+
+    async fn remove(&self, handle: InjectionHandle) -> Result<()> {
+        let filler = handle
+            .metadata
+            .get("filler")
+            .and_then(|v| v.as_str())
+            .map(PathBuf::from)
+            .ok_or_else(|| ChaosError::CleanupFailed("Missing filler metadata".to_string()))?;
+
+        self.check_filler(&filler)?;
+
+        info!("Removing disk fill {}", filler.display());
+        let removed = tokio::task::spawn_blocking(move || remove_filler(&filler))
+            .await
+            .map_err(|e| ChaosError::CleanupFailed(e.to_string()))?;
+        match removed {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(ChaosError::CleanupFailed(format!(
+                "Failed to remove disk fill: {}",
+                e
+            ))),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "disk_fill"
+    }
+
+    async fn validate(&self) -> Result<()> {
+        self.validate_config()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_disk_slow_builder() {
+        let injector = DiskSlowInjector::builder()
+            .latency(Duration::from_millis(200))
+            .operations(vec![DiskOperation::Write, DiskOperation::Fsync])
+            .build();
+
+        assert_eq!(injector.config.latency, Duration::from_millis(200));
+        assert_eq!(injector.config.operations.len(), 2);
+    }
+
+    #[test]
+    fn test_path_glob_and_fault_plan() {
+        let glob = |s: &str| s.parse::<PathGlob>().unwrap();
+        assert!(glob("*.log").matches("var/app/today.log"));
+        assert!(!glob("*.log").matches("today.log.1"));
+        assert!(glob("data/*.db").matches("data/orders.db"));
+        assert!(!glob("data/*.db").matches("data/archive/orders.db"));
+        assert!(glob("data/**").matches("data/archive/orders.db"));
+        assert!(glob("**/wal/?.seg").matches("wal/1.seg"));
+        assert!(glob("/**/wal/?.seg").matches("db/wal/2.seg"));
+
+        let rules = [
+            DiskFaultRule::new(glob("*.log"))
+                .operations(vec![DiskOperation::Write])
+                .error(DiskError::Enospc),
+            DiskFaultRule::new(glob("data/**"))
+                .delay(LatencyDistribution::Fixed { value: 20.0 })
+                .short_write(),
+            DiskFaultRule::new(PathGlob::any())
+                .operations(vec![DiskOperation::Fsync])
+                .delay(LatencyDistribution::Fixed { value: 5.0 }),
+        ];
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let mut plan = |op, path| plan_fault(&rules, op, path, &mut rng);
+
+        assert_eq!(plan(DiskOperation::Read, "app.log"), DiskFault::default());
+        assert_eq!(
+            plan(DiskOperation::Write, "app.log").errno,
+            Some(libc::ENOSPC)
+        );
+        let write = plan(DiskOperation::Write, "data/a.db");
+        assert!(write.short_write && write.errno.is_none());
+        assert_eq!(write.delay, Duration::from_millis(20));
+        assert!(!plan(DiskOperation::Read, "data/a.db").short_write);
+        assert_eq!(
+            plan(DiskOperation::Fsync, "data/a.db").delay,
+            Duration::from_millis(25)
+        );
+
+        let params: InjectorParams = serde_json::from_value(serde_json::json!({
+            "path": "/srv/data",
+            "rules": [
+                {"path": "*.log", "operations": ["write"], "error": "edquot"},
+                {"operations": ["Read"], "delay": "50ms", "jitter": "10ms", "probability": 0.5},
+            ],
+        }))
+        .unwrap();
+        let injector = DiskSlowInjector::from_params(&params).unwrap();
+        assert_eq!(injector.config.rules[0].error, Some(DiskError::Edquot));
+        assert!(injector.config.rules[1].path.matches("any/file"));
+        for invalid in [
+            serde_json::json!({"rules": [{"error": "eio", "short_write": true}]}),
+            serde_json::json!({"rules": [{"jitter": "10ms"}]}),
+            serde_json::json!({"rules": [{"probability": 2.0}]}),
+            serde_json::json!({"rules": [{"error": "eperm"}]}),
+        ] {
+            let params: InjectorParams = serde_json::from_value(invalid).unwrap();
+            assert!(DiskSlowInjector::from_params(&params).is_err());
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_fault_filesystem() {
+        use std::io::Write;
+
+        let dir = std::env::temp_dir().join(format!("chaos-disk-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("slow")).unwrap();
+        std::fs::write(dir.join("slow/existing.txt"), b"kept").unwrap();
+
+        let injector = DiskSlowInjector::builder()
+            .path(&dir)
+            .rule(
+                DiskFaultRule::new("*.log".parse().unwrap())
+                    .operations(vec![DiskOperation::Write])
+                    .error(DiskError::Enospc),
+            )
+            .rule(
+                DiskFaultRule::new("slow/**".parse().unwrap())
+                    .operations(vec![DiskOperation::Read])
+                    .delay(LatencyDistribution::Fixed { value: 200.0 }),
+            )
+            .rule(
+                DiskFaultRule::new("short.bin".parse().unwrap())
+                    .operations(vec![DiskOperation::Write])
+                    .short_write(),
+            )
+            .seed(1)
+            .build();
+        let handle = match injector.inject(&Target::process(std::process::id())).await {
+            Ok(handle) => handle,
+            Err(e) => {
+                // Needs /dev/fuse and the right to mount
+                eprintln!("skipping fault filesystem test: {}", e);
+                std::fs::remove_dir_all(&dir).unwrap();
+                return;
+            }
+        };
+
+        let faulted = dir.clone();
+        tokio::task::spawn_blocking(move || {
+            let started = std::time::Instant::now();
+            assert_eq!(
+                std::fs::read(faulted.join("slow/existing.txt")).unwrap(),
+                b"kept"
+            );
+            assert!(started.elapsed() >= Duration::from_millis(200));
+
+            let mut log = std::fs::File::create(faulted.join("app.log")).unwrap();
+            let error = log.write(b"line\n").unwrap_err();
+            assert_eq!(error.raw_os_error(), Some(libc::ENOSPC));
+
+            let mut short = std::fs::File::create(faulted.join("short.bin")).unwrap();
+            let written = short.write(&[7; 4096]).unwrap();
+            assert!(written > 0 && written < 4096);
+
+            std::fs::write(faulted.join("plain.txt"), b"passthrough").unwrap();
+        })
+        .await
+        .unwrap();
+
+        injector.remove(handle).await.unwrap();
+        // Everything written through the mount landed in the real directory
+        assert_eq!(
+            std::fs::read(dir.join("plain.txt")).unwrap(),
+            b"passthrough"
+        );
+        std::fs::write(dir.join("app.log"), b"line\n").unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_disk_fill_params() {
+        assert_eq!(parse_size("4096"), Some(4096));
+        assert_eq!(parse_size("500MB"), Some(500_000_000));
+        assert_eq!(parse_size("1.5GiB"), Some(1_610_612_736));
+        assert_eq!(parse_size("2k"), Some(2048));
+        assert!(parse_size("lots").is_none());
+
+        let injector = DiskFillInjector::usage("/data", 0.9);
+        assert_eq!(injector.amount_to_fill(1000, 500), 400);
+        assert_eq!(injector.amount_to_fill(1000, 50), 0);
+        let injector = DiskFillInjector::leave_free("/data", 100).inodes();
+        assert_eq!(injector.amount_to_fill(1000, 500), 400);
+        assert_eq!(injector.config.mode, DiskFillMode::Inodes);
+
+        let params: InjectorParams = serde_json::from_value(serde_json::json!({
+            "path": "/var/log",
+            "leave_free": "64MiB",
+        }))
+        .unwrap();
+        let injector = DiskFillInjector::from_params(&params).unwrap();
+        assert_eq!(
+            injector.config.level,
+            DiskFillLevel::LeaveFree(64 * 1024 * 1024)
+        );
+
+        for invalid in [
+            serde_json::json!({"path": "/var/log"}),
+            serde_json::json!({"path": "/var/log", "target_usage": 0.9, "leave_free": 10}),
+            serde_json::json!({"path": "/var/log", "target_usage": 1.5}),
+            serde_json::json!({"path": "/var/log", "mode": "inodes", "leave_free": "1MB"}),
+        ] {
+            let params: InjectorParams = serde_json::from_value(invalid).unwrap();
+            assert!(DiskFillInjector::from_params(&params).is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_disk_fill_refuses_forged_filler() {
+        let dir = std::env::temp_dir().join(format!("chaos-fill-{}", uuid::Uuid::new_v4()));
+        let victim = dir.join("data");
+        std::fs::create_dir_all(&victim).unwrap();
+        let injector = DiskFillInjector::usage(&dir, 0.5);
+        let target = Target::process(std::process::id());
+
+        for filler in [
+            victim.clone(),
+            std::env::temp_dir().join("chaos_disk_fill_elsewhere"),
+            victim.join("chaos_disk_fill_nested"),
+        ] {
+            let handle = InjectionHandle::new(
+                "disk_fill",
+                target.clone(),
+                serde_json::json!({ "filler": filler }),
+            );
+            assert!(injector.remove(handle).await.is_err());
+        }
+        assert!(victim.exists());
+
+        // A genuine filler that is already gone is not an error
+        let handle = InjectionHandle::new(
+            "disk_fill",
+            target,
+            serde_json::json!({ "filler": dir.join("chaos_disk_fill_gone") }),
+        );
+        injector.remove(handle).await.unwrap();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_disk_fill_tmpfs() {
+        use nix::mount::{mount, umount, MsFlags};
+        use nix::sys::statvfs::statvfs;
+
+        let dir = std::env::temp_dir().join(format!("chaos-fill-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let mounted = mount(
+            Some("tmpfs"),
+            &dir,
+            Some("tmpfs"),
+            MsFlags::empty(),
+            Some("size=8m,nr_inodes=500"),
+        );
+        if let Err(e) = mounted {
+            // Needs the right to mount
+            eprintln!("skipping disk fill test: {}", e);
+            std::fs::remove_dir(&dir).unwrap();
+            return;
+        }
+        let target = Target::process(std::process::id());
+
+        let injector = DiskFillInjector::leave_free(&dir, 1024 * 1024);
+        let handle = injector.inject(&target).await.unwrap();
+        let stats = statvfs(&dir).unwrap();
+        let available = stats.blocks_available() as u64 * stats.fragment_size() as u64;
+        assert!(available <= 1024 * 1024 + 4096, "{} bytes left", available);
+        injector.remove(handle).await.unwrap();
+        let stats = statvfs(&dir).unwrap();
+        assert!(stats.blocks_available() as u64 * stats.fragment_size() as u64 > 7 * 1024 * 1024);
+
+        let injector = DiskFillInjector::usage(&dir, 1.0).inodes();
+        let handle = injector.inject(&target).await.unwrap();
+        assert_eq!(statvfs(&dir).unwrap().files_available(), 0);
+        let error = std::fs::File::create(dir.join("one-more")).unwrap_err();
+        assert_eq!(error.raw_os_error(), Some(libc::ENOSPC));
+        injector.remove(handle).await.unwrap();
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+
+        umount(&dir).unwrap();
+        std::fs::remove_dir(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_disk_failure_rate_clamping() {
+        let injector = DiskFailureInjector::new(1.5);
+        assert_eq!(injector.failure_rate, 1.0);
+
+        let injector = DiskFailureInjector::new(-0.5);
+        assert_eq!(injector.failure_rate, 0.0);
+    }
+}
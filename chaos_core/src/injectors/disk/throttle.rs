@@ -0,0 +1,360 @@
+use super::size_param;
+#[cfg(target_os = "linux")]
+use crate::cgroup::ChaosCgroup;
+use crate::{
+    error::*,
+    handle::InjectionHandle,
+    injectors::{params, Injector, InjectorParams},
+    target::Target,
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+#[cfg(target_os = "linux")]
+use tracing::info;
+
+/// Block device limits applied through the cgroup v2 io controller
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IoThrottleConfig {
+    /// Block device (`/dev/nvme0n1`), `major:minor`, or any other path whose
+    /// filesystem's disk is meant
+    pub device: String,
+    /// Read bytes per second
+    pub rbps: Option<u64>,
+    /// Write bytes per second
+    pub wbps: Option<u64>,
+    /// Read operations per second
+    pub riops: Option<u64>,
+    /// Write operations per second
+    pub wiops: Option<u64>,
+    /// `io.latency` target; other groups are throttled while the target's
+    /// average completion latency stays above it
+    pub latency: Option<Duration>,
+}
+
+impl IoThrottleConfig {
+    /// `io.max` line for the device, if any limit is set
+    fn io_max(&self, device: &str) -> Option<String> {
+        let limits: Vec<_> = [
+            ("rbps", self.rbps),
+            ("wbps", self.wbps),
+            ("riops", self.riops),
+            ("wiops", self.wiops),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| value.map(|value| format!("{}={}", key, value)))
+        .collect();
+        (!limits.is_empty()).then(|| format!("{} {}", device, limits.join(" ")))
+    }
+
+    /// `io.latency` line for the device, if a target is set
+    fn io_latency(&self, device: &str) -> Option<String> {
+        self.latency
+            .map(|latency| format!("{} target={}", device, latency.as_micros()))
+    }
+}
+
+/// Moves a process into a chaos-owned cgroup v2 group whose `io.max` or
+/// `io.latency` slows it on one block device, then moves it back on removal
+#[derive(Debug, Clone, Default)]
+pub struct IoThrottleInjector {
+    config: IoThrottleConfig,
+}
+
+impl IoThrottleInjector {
+    pub fn new(config: IoThrottleConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn builder() -> IoThrottleBuilder {
+        IoThrottleBuilder::default()
+    }
+
+    pub fn from_params(params: &InjectorParams) -> Result<Self> {
+        let params: IoThrottleParams = params::parse_params("io_throttle", params)?;
+        let bytes = |name: &str, value: Option<serde_json::Value>| {
+            value
+                .map(|value| {
+                    size_param(&value).filter(|n| *n > 0).ok_or_else(|| {
+                        ChaosError::InvalidConfig(format!(
+                            "Invalid io_throttle {} {}: expected e.g. '10MB' (per second)",
+                            name, value
+                        ))
+                    })
+                })
+                .transpose()
+        };
+        let injector = Self::new(IoThrottleConfig {
+            device: params.device,
+            rbps: bytes("rbps", params.rbps)?,
+            wbps: bytes("wbps", params.wbps)?,
+            riops: params.riops,
+            wiops: params.wiops,
+            latency: params.latency,
+        });
+        injector.validate_config()?;
+        Ok(injector)
+    }
+
+    fn validate_config(&self) -> Result<()> {
+        let config = &self.config;
+        if config.device.is_empty() {
+            return Err(ChaosError::InvalidConfig(
+                "io_throttle requires a device".to_string(),
+            ));
+        }
+        if config.io_max("0:0").is_none() && config.latency.is_none() {
+            return Err(ChaosError::InvalidConfig(
+                "io_throttle needs at least one of rbps, wbps, riops, wiops or latency".to_string(),
+            ));
+        }
+        if [config.riops, config.wiops].contains(&Some(0))
+            || config.latency.is_some_and(|latency| latency.is_zero())
+        {
+            return Err(ChaosError::InvalidConfig(
+                "io_throttle limits must be positive".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn inject_linux(&self, target: &Target) -> Result<InjectionHandle> {
+        self.validate_config()?;
+        let pid = target.pid().await?;
+        let device = resolve_device(&self.config.device)?;
+        info!(
+            "Throttling I/O of {} on device {}",
+            target.description(),
+            device
+        );
+
+        let mut cgroup =
+            ChaosCgroup::create(&format!("io_throttle_{}", uuid::Uuid::new_v4()), &["io"]).await?;
+        let configured = async {
+            if let Some(io_max) = self.config.io_max(&device) {
+                cgroup.write("io.max", &io_max).await?;
+            }
+            if let Some(io_latency) = self.config.io_latency(&device) {
+                cgroup.write("io.latency", &io_latency).await?;
+            }
+            cgroup.adopt(pid).await
+        }
+        .await;
+        if let Err(e) = configured {
+            cgroup.restore().await?;
+            return Err(e);
+        }
+
+        let metadata = cgroup.metadata(serde_json::json!({
+            "pid": pid,
+            "device": device,
+        }));
+        Ok(InjectionHandle::new(
+            "io_throttle",
+            target.clone(),
+            metadata,
+        ))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    async fn inject_linux(&self, _target: &Target) -> Result<InjectionHandle> {
+        Err(ChaosError::SystemError(
+            "I/O throttling is only supported on Linux with cgroup v2".to_string(),
+        ))
+    }
+}
+
+/// `major:minor` of the whole disk `device` refers to; the io controller
+/// rejects partitions
+#[cfg(target_os = "linux")]
+fn resolve_device(device: &str) -> Result<String> {
+    use std::os::unix::fs::{FileTypeExt, MetadataExt};
+
+    let (major, minor) = match parse_major_minor(device) {
+        Some(numbers) => numbers,
+        None => {
+            let metadata = std::fs::metadata(device).map_err(|e| {
+                ChaosError::InvalidConfig(format!("Invalid io_throttle device {}: {}", device, e))
+            })?;
+            let dev = if metadata.file_type().is_block_device() {
+                metadata.rdev()
+            } else {
+                metadata.dev()
+            };
+            (
+                nix::sys::stat::major(dev) as u32,
+                nix::sys::stat::minor(dev) as u32,
+            )
+        }
+    };
+    let sysfs = std::path::PathBuf::from(format!("/sys/dev/block/{}:{}", major, minor));
+    if !sysfs.exists() {
+        return Err(ChaosError::InvalidConfig(format!(
+            "{} ({}:{}) is not backed by a block device",
+            device, major, minor
+        )));
+    }
+    if sysfs.join("partition").exists() {
+        let disk = sysfs.canonicalize()?.join("../dev");
+        return Ok(std::fs::read_to_string(disk)?.trim().to_string());
+    }
+    Ok(format!("{}:{}", major, minor))
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_major_minor(device: &str) -> Option<(u32, u32)> {
+    let (major, minor) = device.split_once(':')?;
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+#[async_trait]
+impl Injector for IoThrottleInjector {
+    async fn inject(&self, target: &Target) -> Result<InjectionHandle> {
+        self.inject_linux(target).await
+    }
+
+    async fn remove(&self, handle: InjectionHandle) -> Result<()> {
+        #[cfg(target_os = "linux")]
+        {
+            ChaosCgroup::from_metadata(&handle.metadata)?
+                .restore()
+                .await?;
+            info!("Removed I/O throttle");
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = handle;
+        }
+
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "io_throttle"
+    }
+
+    async fn validate(&self) -> Result<()> {
+        self.validate_config()
+    }
+
+    fn required_capabilities(&self) -> Vec<String> {
+        vec!["CAP_SYS_ADMIN".to_string()]
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct IoThrottleParams {
+    device: String,
+    #[serde(default)]
+    rbps: Option<serde_json::Value>,
+    #[serde(default)]
+    wbps: Option<serde_json::Value>,
+    #[serde(default)]
+    riops: Option<u64>,
+    #[serde(default)]
+    wiops: Option<u64>,
+    #[serde(default, deserialize_with = "params::opt_duration")]
+    latency: Option<Duration>,
+}
+
+#[derive(Default)]
+pub struct IoThrottleBuilder {
+    config: IoThrottleConfig,
+}
+
+impl IoThrottleBuilder {
+    pub fn device(mut self, device: impl Into<String>) -> Self {
+        self.config.device = device.into();
+        self
+    }
+
+    pub fn read_bps(mut self, bytes: u64) -> Self {
+        self.config.rbps = Some(bytes);
+        self
+    }
+
+    pub fn write_bps(mut self, bytes: u64) -> Self {
+        self.config.wbps = Some(bytes);
+        self
+    }
+
+    pub fn read_iops(mut self, iops: u64) -> Self {
+        self.config.riops = Some(iops);
+        self
+    }
+
+    pub fn write_iops(mut self, iops: u64) -> Self {
+        self.config.wiops = Some(iops);
+        self
+    }
+
+    pub fn latency(mut self, latency: Duration) -> Self {
+        self.config.latency = Some(latency);
+        self
+    }
+
+    pub fn build(self) -> IoThrottleInjector {
+        IoThrottleInjector::new(self.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_io_throttle_config() {
+        let injector = IoThrottleInjector::builder()
+            .device("259:0")
+            .write_bps(1_000_000)
+            .read_iops(50)
+            .latency(Duration::from_millis(5))
+            .build();
+        assert_eq!(
+            injector.config.io_max("259:0").unwrap(),
+            "259:0 wbps=1000000 riops=50"
+        );
+        assert_eq!(
+            injector.config.io_latency("259:0").unwrap(),
+            "259:0 target=5000"
+        );
+        assert_eq!(parse_major_minor("259:0"), Some((259, 0)));
+        assert_eq!(parse_major_minor("/dev/vda"), None);
+
+        let params: InjectorParams = serde_json::from_value(serde_json::json!({
+            "device": "/dev/nvme0n1",
+            "rbps": "10MB",
+            "wbps": 2048,
+            "wiops": 100,
+        }))
+        .unwrap();
+        let injector = IoThrottleInjector::from_params(&params).unwrap();
+        assert_eq!(injector.config.rbps, Some(10_000_000));
+        assert_eq!(injector.config.wbps, Some(2048));
+        assert!(injector.config.io_latency("259:0").is_none());
+
+        for invalid in [
+            serde_json::json!({"device": "/dev/vda"}),
+            serde_json::json!({"device": "/dev/vda", "rbps": "fast"}),
+            serde_json::json!({"device": "/dev/vda", "riops": 0}),
+            serde_json::json!({"rbps": "1MB"}),
+        ] {
+            let params: InjectorParams = serde_json::from_value(invalid).unwrap();
+            assert!(IoThrottleInjector::from_params(&params).is_err());
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_resolve_device() {
+        let Ok(device) = resolve_device("/") else {
+            // The root filesystem is not on a block device here
+            return;
+        };
+        assert!(parse_major_minor(&device).is_some());
+        assert!(resolve_device("/no/such/device").is_err());
+    }
+}
@@ -1,4 +1,9 @@
-use crate::{error::*, handle::InjectionHandle, injectors::Injector, target::Target};
+use crate::{
+    error::*,
+    handle::InjectionHandle,
+    injectors::{params, Injector, InjectorParams},
+    target::Target,
+};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -48,6 +53,21 @@ impl MemoryPressureInjector {
         MemoryPressureBuilder::default()
     }
 
+    pub fn from_params(params: &InjectorParams) -> Result<Self> {
+        let params: MemoryPressureParams = params::parse_params("memory_pressure", params)?;
+        let mut builder = Self::builder();
+        if let Some(target_usage) = params.target_usage {
+            builder = builder.target_usage(target_usage);
+        }
+        if let Some(failure_rate) = params.failure_rate {
+            builder = builder.failure_rate(failure_rate);
+        }
+        if let Some(leak_rate) = params.leak_rate {
+            builder = builder.leak_rate(leak_rate);
+        }
+        Ok(builder.build())
+    }
+
     async fn get_system_memory_info(&self) -> Result<(u64, u64)> {
         use sysinfo::System;
         let mut sys = System::new_all();
@@ -132,6 +152,17 @@ impl Injector for MemoryPressureInjector {
     }
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MemoryPressureParams {
+    #[serde(default)]
+    target_usage: Option<f64>,
+    #[serde(default)]
+    failure_rate: Option<f64>,
+    #[serde(default)]
+    leak_rate: Option<u64>,
+}
+
 #[derive(Default)]
 pub struct MemoryPressureBuilder {
     target_usage: Option<f64>,
@@ -1,18 +1,30 @@
+pub mod clock;
 pub mod cpu;
 pub mod disk;
 pub mod memory;
 pub mod network;
+pub mod params;
+pub mod pause;
 pub mod process;
+pub mod syscall;
 
-use crate::{error::Result, handle::InjectionHandle, target::Target};
+use crate::{
+    error::{ChaosError, Result},
+    handle::{InjectionHandle, InjectionReport},
+    target::Target,
+};
 use async_trait::async_trait;
 use std::sync::Arc;
 
+pub use clock::*;
 pub use cpu::*;
 pub use disk::*;
 pub use memory::*;
 pub use network::*;
+pub use params::{parse_params, InjectorFactory, InjectorParams};
+pub use pause::*;
 pub use process::*;
+pub use syscall::*;
 
 /// Core trait for all fault injectors
 #[async_trait]
@@ -35,6 +47,11 @@ pub trait Injector: Send + Sync {
     fn required_capabilities(&self) -> Vec<String> {
         vec![]
     }
+
+    /// What the injection behind `handle` has observed so far
+    async fn report(&self, _handle: &InjectionHandle) -> InjectionReport {
+        InjectionReport::default()
+    }
 }
 
 pub type DynInjector = Arc<dyn Injector>;
@@ -42,6 +59,7 @@ pub type DynInjector = Arc<dyn Injector>;
 #[derive(Default)]
 pub struct InjectorRegistry {
     injectors: std::collections::HashMap<String, DynInjector>,
+    factories: std::collections::HashMap<String, Arc<dyn InjectorFactory>>,
 }
 
 impl InjectorRegistry {
@@ -53,32 +71,150 @@ impl InjectorRegistry {
         self.injectors.insert(name.into(), injector);
     }
 
+    /// Register a factory that builds a fresh injector per injection from its
+    /// parameters. A default-configured instance is registered alongside it
+    /// when the injector has no required parameters.
+    pub fn register_factory(
+        &mut self,
+        name: impl Into<String>,
+        factory: impl InjectorFactory + 'static,
+    ) {
+        let name = name.into();
+        if let Ok(injector) = factory.create(&InjectorParams::new()) {
+            self.injectors.entry(name.clone()).or_insert(injector);
+        }
+        self.factories.insert(name, Arc::new(factory));
+    }
+
     pub fn get(&self, name: &str) -> Option<&DynInjector> {
         self.injectors.get(name)
     }
 
+    /// Build an injector configured from `params`. Injectors registered
+    /// without a factory only accept an empty parameter map.
+    pub fn create(&self, name: &str, params: &InjectorParams) -> Result<DynInjector> {
+        if let Some(factory) = self.factories.get(name) {
+            return factory.create(params);
+        }
+
+        let injector = self
+            .get(name)
+            .ok_or_else(|| ChaosError::InvalidConfig(format!("Injector '{}' not found", name)))?;
+
+        if !params.is_empty() {
+            let mut keys: Vec<&str> = params.keys().map(String::as_str).collect();
+            keys.sort_unstable();
+            return Err(ChaosError::InvalidConfig(format!(
+                "Injector '{}' does not accept parameters (got: {})",
+                name,
+                keys.join(", ")
+            )));
+        }
+
+        Ok(injector.clone())
+    }
+
+    /// Every injector name, whether registered as an instance or a factory
     pub fn list(&self) -> Vec<String> {
-        self.injectors.keys().cloned().collect()
+        let mut names: Vec<String> = self
+            .injectors
+            .keys()
+            .chain(self.factories.keys())
+            .cloned()
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        names
     }
 
     pub fn with_defaults() -> Self {
         let mut registry = Self::new();
 
-        // Register default injectors
-        registry.register(
-            "network_latency",
-            Arc::new(NetworkLatencyInjector::default()),
-        );
-        registry.register("packet_loss", Arc::new(PacketLossInjector::default()));
-        registry.register("tcp_reset", Arc::new(TcpResetInjector::default()));
-        registry.register("cpu_starvation", Arc::new(CpuStarvationInjector::default()));
-        registry.register("disk_slow", Arc::new(DiskSlowInjector::default()));
-        registry.register(
-            "memory_pressure",
-            Arc::new(MemoryPressureInjector::default()),
-        );
-        registry.register("process_kill", Arc::new(ProcessKillInjector::default()));
+        // Register default injectors, configured per injection from parameters
+        registry.register_factory("network_latency", |p: &InjectorParams| {
+            Ok(Arc::new(NetworkLatencyInjector::from_params(p)?) as DynInjector)
+        });
+        registry.register_factory("packet_loss", |p: &InjectorParams| {
+            Ok(Arc::new(PacketLossInjector::from_params(p)?) as DynInjector)
+        });
+        registry.register_factory("netem", |p: &InjectorParams| {
+            Ok(Arc::new(NetemInjector::from_params(p)?) as DynInjector)
+        });
+        registry.register_factory("tcp_proxy", |p: &InjectorParams| {
+            Ok(Arc::new(TcpProxyInjector::from_params(p)?) as DynInjector)
+        });
+        registry.register_factory("http_proxy", |p: &InjectorParams| {
+            Ok(Arc::new(HttpProxyInjector::from_params(p)?) as DynInjector)
+        });
+        registry.register_factory("websocket_proxy", |p: &InjectorParams| {
+            Ok(Arc::new(WebSocketProxyInjector::from_params(p)?) as DynInjector)
+        });
+        registry.register_factory("dns_fault", |p: &InjectorParams| {
+            Ok(Arc::new(DnsFaultInjector::from_params(p)?) as DynInjector)
+        });
+        registry.register_factory("partition", |p: &InjectorParams| {
+            Ok(Arc::new(PartitionInjector::from_params(p)?) as DynInjector)
+        });
+        registry.register_factory("tcp_reset", |p: &InjectorParams| {
+            Ok(Arc::new(TcpResetInjector::from_params(p)?) as DynInjector)
+        });
+        registry.register_factory("cpu_starvation", |p: &InjectorParams| {
+            Ok(Arc::new(CpuStarvationInjector::from_params(p)?) as DynInjector)
+        });
+        registry.register_factory("cpu_quota", |p: &InjectorParams| {
+            Ok(Arc::new(CpuQuotaInjector::from_params(p)?) as DynInjector)
+        });
+        registry.register_factory("disk_slow", |p: &InjectorParams| {
+            Ok(Arc::new(DiskSlowInjector::from_params(p)?) as DynInjector)
+        });
+        registry.register_factory("disk_fill", |p: &InjectorParams| {
+            Ok(Arc::new(DiskFillInjector::from_params(p)?) as DynInjector)
+        });
+        registry.register_factory("io_throttle", |p: &InjectorParams| {
+            Ok(Arc::new(IoThrottleInjector::from_params(p)?) as DynInjector)
+        });
+        registry.register_factory("memory_pressure", |p: &InjectorParams| {
+            Ok(Arc::new(MemoryPressureInjector::from_params(p)?) as DynInjector)
+        });
+        registry.register_factory("process_kill", |p: &InjectorParams| {
+            Ok(Arc::new(ProcessKillInjector::from_params(p)?) as DynInjector)
+        });
+        registry.register_factory("process_pause", |p: &InjectorParams| {
+            Ok(Arc::new(ProcessPauseInjector::from_params(p)?) as DynInjector)
+        });
+        registry.register_factory("syscall_fault", |p: &InjectorParams| {
+            Ok(Arc::new(SyscallFaultInjector::from_params(p)?) as DynInjector)
+        });
+        registry.register_factory("clock_skew", |p: &InjectorParams| {
+            Ok(Arc::new(ClockSkewInjector::from_params(p)?) as DynInjector)
+        });
 
         registry
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_includes_factories() {
+        let registry = InjectorRegistry::with_defaults();
+        let names = registry.list();
+
+        let mut registered: Vec<String> = registry.factories.keys().cloned().collect();
+        registered.sort_unstable();
+        assert_eq!(names, registered);
+        // Injectors with required parameters have no default instance
+        for name in [
+            "http_proxy",
+            "dns_fault",
+            "partition",
+            "disk_fill",
+            "io_throttle",
+        ] {
+            assert!(names.contains(&name.to_string()), "{} missing", name);
+            assert!(registry.get(name).is_none());
+        }
+    }
+}
@@ -0,0 +1,885 @@
+use super::LatencyDistribution;
+use crate::{
+    error::*,
+    handle::InjectionHandle,
+    injectors::{params, Injector, InjectorParams},
+    target::Target,
+};
+use async_trait::async_trait;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// How long a forwarded query waits for the upstream resolver
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(5);
+/// TTL of forged answers, short so clients recover soon after removal
+const FORGED_TTL: u32 = 5;
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+
+/// Names a rule applies to: `api.example.com`, `*.example.com` (any
+/// subdomain) or `*`. Matching ignores case and a trailing dot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct NamePattern(String);
+
+impl NamePattern {
+    pub fn matches(&self, name: &str) -> bool {
+        let name = name.trim_end_matches('.').to_ascii_lowercase();
+        match self.0.strip_prefix('*') {
+            Some("") => true,
+            Some(suffix) => name.ends_with(suffix),
+            None => name == self.0,
+        }
+    }
+}
+
+impl FromStr for NamePattern {
+    type Err = ChaosError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let pattern = s.trim_end_matches('.').to_ascii_lowercase();
+        let name = pattern.strip_prefix("*.").unwrap_or(&pattern);
+        let valid = pattern == "*"
+            || (!name.is_empty()
+                && name.split('.').all(|label| {
+                    !label.is_empty()
+                        && label.len() <= 63
+                        && label
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                }));
+        if !valid {
+            return Err(ChaosError::InvalidConfig(format!(
+                "Invalid dns_fault name '{}': expected e.g. 'api.example.com' or '*.example.com'",
+                s
+            )));
+        }
+        Ok(Self(pattern))
+    }
+}
+
+impl TryFrom<String> for NamePattern {
+    type Error = ChaosError;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<NamePattern> for String {
+    fn from(pattern: NamePattern) -> Self {
+        pattern.0
+    }
+}
+
+/// Error response code returned instead of an answer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DnsRcode {
+    Servfail,
+    Nxdomain,
+    Refused,
+}
+
+impl DnsRcode {
+    fn code(self) -> u16 {
+        match self {
+            DnsRcode::Servfail => 2,
+            DnsRcode::Nxdomain => 3,
+            DnsRcode::Refused => 5,
+        }
+    }
+}
+
+/// Faults applied to queries for matching names. A rule may add a delay and
+/// replace the answer in at most one way.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsFaultRule {
+    pub name: NamePattern,
+    /// Probability per matching query that the rule fires
+    pub probability: f64,
+    /// Delay before the query is answered, in milliseconds
+    pub delay: Option<LatencyDistribution>,
+    /// Answer with this error instead of asking the upstream
+    pub rcode: Option<DnsRcode>,
+    /// Answer UDP queries with an empty, truncated response so clients
+    /// retry over TCP
+    pub truncate: bool,
+    /// Forged addresses returned for A and AAAA queries
+    pub answer: Vec<IpAddr>,
+    /// Never answer, so clients time out
+    pub drop: bool,
+}
+
+impl DnsFaultRule {
+    pub fn new(name: NamePattern) -> Self {
+        Self {
+            name,
+            probability: 1.0,
+            delay: None,
+            rcode: None,
+            truncate: false,
+            answer: Vec::new(),
+            drop: false,
+        }
+    }
+
+    pub fn probability(mut self, probability: f64) -> Self {
+        self.probability = probability;
+        self
+    }
+
+    pub fn delay(mut self, delay: LatencyDistribution) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    pub fn rcode(mut self, rcode: DnsRcode) -> Self {
+        self.rcode = Some(rcode);
+        self
+    }
+
+    pub fn truncate(mut self) -> Self {
+        self.truncate = true;
+        self
+    }
+
+    pub fn answer(mut self, address: IpAddr) -> Self {
+        self.answer.push(address);
+        self
+    }
+
+    pub fn drop(mut self) -> Self {
+        self.drop = true;
+        self
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |reason: String| {
+            Err(ChaosError::InvalidConfig(format!(
+                "dns_fault rule '{}': {}",
+                self.name.0, reason
+            )))
+        };
+        if !(0.0..=1.0).contains(&self.probability) {
+            return invalid(format!(
+                "probability must be between 0.0 and 1.0, got {}",
+                self.probability
+            ));
+        }
+        if let Some(Err(e)) = self.delay.as_ref().map(LatencyDistribution::validate) {
+            return invalid(e.to_string());
+        }
+        let replacements = [
+            self.rcode.is_some(),
+            self.truncate,
+            !self.answer.is_empty(),
+            self.drop,
+        ]
+        .into_iter()
+        .filter(|set| *set)
+        .count();
+        if replacements > 1 {
+            return invalid("rcode, truncate, answer and drop are exclusive".to_string());
+        }
+        if replacements == 0 && self.delay.is_none() {
+            return invalid("no fault configured".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsFaultConfig {
+    /// Address to answer UDP and TCP queries on; port 0 picks a free one
+    pub listen: SocketAddr,
+    /// Checked in order; delays of every matching rule that fires add up and
+    /// the first one replacing the answer wins
+    pub rules: Vec<DnsFaultRule>,
+    /// Makes every random decision reproducible
+    pub seed: Option<u64>,
+}
+
+impl Default for DnsFaultConfig {
+    fn default() -> Self {
+        Self {
+            listen: SocketAddr::from(([127, 0, 0, 1], 0)),
+            rules: Vec::new(),
+            seed: None,
+        }
+    }
+}
+
+impl DnsFaultConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.rules.is_empty() {
+            return Err(ChaosError::InvalidConfig(
+                "dns_fault requires at least one rule".to_string(),
+            ));
+        }
+        self.rules.iter().try_for_each(DnsFaultRule::validate)
+    }
+}
+
+/// Stub resolver that forwards to the resolver given as network target and
+/// fails queries for selected names. Point a service at it through its
+/// config or a `resolv.conf`; needs no privileges unless bound to port 53.
+#[derive(Default)]
+pub struct DnsFaultInjector {
+    config: Arc<DnsFaultConfig>,
+    /// Stops the UDP and TCP listeners of each resolver
+    resolvers: Mutex<HashMap<String, CancellationToken>>,
+}
+
+impl DnsFaultInjector {
+    pub fn new(config: DnsFaultConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            resolvers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn builder() -> DnsFaultBuilder {
+        DnsFaultBuilder::default()
+    }
+
+    pub fn config(&self) -> &DnsFaultConfig {
+        &self.config
+    }
+
+    pub fn from_params(params: &InjectorParams) -> Result<Self> {
+        let params: DnsFaultParams = params::parse_params("dns_fault", params)?;
+        let mut builder = Self::builder();
+
+        if let Some(listen) = params.listen {
+            builder = builder.listen(listen);
+        }
+        for rule in params.rules {
+            builder = builder.rule(rule.into_rule()?);
+        }
+        if let Some(seed) = params.seed {
+            builder = builder.seed(seed);
+        }
+
+        let injector = builder.build();
+        injector.config.validate()?;
+        Ok(injector)
+    }
+}
+
+#[async_trait]
+impl Injector for DnsFaultInjector {
+    async fn inject(&self, target: &Target) -> Result<InjectionHandle> {
+        let Target::Network { address: upstream } = target else {
+            return Err(ChaosError::InvalidConfig(
+                "DNS fault requires Network target for the upstream resolver".to_string(),
+            ));
+        };
+        self.config.validate()?;
+
+        let bind_failed = |e: std::io::Error| {
+            ChaosError::InjectionFailed(format!(
+                "Failed to listen on {}: {}",
+                self.config.listen, e
+            ))
+        };
+        let udp = UdpSocket::bind(self.config.listen)
+            .await
+            .map_err(bind_failed)?;
+        let listen = udp.local_addr()?;
+        // TCP on the same port, which a picked port only fixes after UDP
+        let tcp = TcpListener::bind(listen).await.map_err(bind_failed)?;
+        info!("Resolving DNS on {} via {} with faults", listen, upstream);
+
+        let cancel = CancellationToken::new();
+        let rng = self
+            .config
+            .seed
+            .map(StdRng::seed_from_u64)
+            .unwrap_or_else(StdRng::from_entropy);
+        let resolver = Arc::new(Resolver {
+            upstream: *upstream,
+            config: self.config.clone(),
+            rng: Mutex::new(rng),
+        });
+        tokio::spawn(serve_udp(udp, resolver.clone(), cancel.clone()));
+        tokio::spawn(serve_tcp(tcp, resolver, cancel.clone()));
+
+        let handle = InjectionHandle::new(
+            "dns_fault",
+            target.clone(),
+            serde_json::json!({
+                "listen": listen.to_string(),
+                "upstream": upstream.to_string(),
+            }),
+        );
+        self.resolvers
+            .lock()
+            .unwrap()
+            .insert(handle.id.clone(), cancel);
+        Ok(handle)
+    }
+
+    async fn remove(&self, handle: InjectionHandle) -> Result<()> {
+        match self.resolvers.lock().unwrap().remove(&handle.id) {
+            Some(cancel) => {
+                info!("Stopping DNS resolver {}", handle.metadata["listen"]);
+                cancel.cancel();
+            }
+            // The resolver only lives as long as the process that started it
+            None => info!("DNS resolver {} is not running", handle.id),
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "dns_fault"
+    }
+
+    async fn validate(&self) -> Result<()> {
+        self.config.validate()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transport {
+    Udp,
+    Tcp,
+}
+
+struct Resolver {
+    upstream: SocketAddr,
+    config: Arc<DnsFaultConfig>,
+    rng: Mutex<StdRng>,
+}
+
+impl Resolver {
+    /// Rules for the name that fire this time, plus the delay they add
+    fn roll(&self, name: &str) -> (Vec<&DnsFaultRule>, Duration) {
+        let mut rng = self.rng.lock().unwrap();
+        let mut delay_ms = 0.0;
+        let mut fired = Vec::new();
+        for rule in &self.config.rules {
+            if !rule.name.matches(name) || !rng.gen_bool(rule.probability) {
+                continue;
+            }
+            if let Some(latency) = &rule.delay {
+                delay_ms += latency.sample(&mut *rng).max(0.0);
+            }
+            fired.push(rule);
+        }
+        (fired, Duration::from_secs_f64(delay_ms / 1000.0))
+    }
+
+    /// Response to a query, or `None` to leave it unanswered
+    async fn resolve(&self, query: &[u8], transport: Transport) -> Option<Vec<u8>> {
+        // Anything that is not a plain query is passed through untouched
+        let Some(question) = Question::parse(query) else {
+            return self.forward(query, transport).await;
+        };
+        let (fired, delay) = self.roll(&question.name);
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+
+        for rule in fired {
+            if rule.drop {
+                return None;
+            }
+            if let Some(rcode) = rule.rcode {
+                return Some(question.respond(query, rcode.code(), &[], false));
+            }
+            if rule.truncate && transport == Transport::Udp {
+                return Some(question.respond(query, 0, &[], true));
+            }
+            if !rule.answer.is_empty() {
+                return Some(question.respond(query, 0, &rule.answer, false));
+            }
+        }
+        self.forward(query, transport).await
+    }
+
+    async fn forward(&self, query: &[u8], transport: Transport) -> Option<Vec<u8>> {
+        let exchange = async {
+            match transport {
+                Transport::Udp => {
+                    let local = match self.upstream {
+                        SocketAddr::V4(_) => SocketAddr::from(([0, 0, 0, 0], 0)),
+                        SocketAddr::V6(_) => SocketAddr::from(([0u16; 8], 0)),
+                    };
+                    let socket = UdpSocket::bind(local).await?;
+                    socket.connect(self.upstream).await?;
+                    socket.send(query).await?;
+                    let mut buf = vec![0u8; u16::MAX as usize];
+                    let len = socket.recv(&mut buf).await?;
+                    buf.truncate(len);
+                    Ok::<_, std::io::Error>(buf)
+                }
+                Transport::Tcp => {
+                    let mut stream = TcpStream::connect(self.upstream).await?;
+                    write_tcp_message(&mut stream, query).await?;
+                    read_tcp_message(&mut stream).await
+                }
+            }
+        };
+        match tokio::time::timeout(FORWARD_TIMEOUT, exchange).await {
+            Ok(Ok(response)) => Some(response),
+            Ok(Err(e)) => {
+                warn!("DNS fault could not reach {}: {}", self.upstream, e);
+                None
+            }
+            Err(_) => {
+                warn!("DNS fault timed out waiting for {}", self.upstream);
+                None
+            }
+        }
+    }
+}
+
+async fn serve_udp(socket: UdpSocket, resolver: Arc<Resolver>, cancel: CancellationToken) {
+    let socket = Arc::new(socket);
+    let mut buf = vec![0u8; u16::MAX as usize];
+    loop {
+        let (len, client) = tokio::select! {
+            _ = cancel.cancelled() => return,
+            received = socket.recv_from(&mut buf) => match received {
+                Ok(received) => received,
+                Err(e) => {
+                    warn!("DNS fault receive failed: {}", e);
+                    continue;
+                }
+            },
+        };
+
+        // Queries are answered concurrently so a slow one holds up no other
+        let query = buf[..len].to_vec();
+        let socket = socket.clone();
+        let resolver = resolver.clone();
+        let cancel = cancel.child_token();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = cancel.cancelled() => {}
+                response = resolver.resolve(&query, Transport::Udp) => {
+                    if let Some(response) = response {
+                        let _ = socket.send_to(&response, client).await;
+                    }
+                }
+            }
+        });
+    }
+}
+
+async fn serve_tcp(listener: TcpListener, resolver: Arc<Resolver>, cancel: CancellationToken) {
+    loop {
+        let mut stream = tokio::select! {
+            _ = cancel.cancelled() => return,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    warn!("DNS fault accept failed: {}", e);
+                    continue;
+                }
+            },
+        };
+
+        let resolver = resolver.clone();
+        let cancel = cancel.child_token();
+        tokio::spawn(async move {
+            let serve = async {
+                while let Ok(query) = read_tcp_message(&mut stream).await {
+                    let Some(response) = resolver.resolve(&query, Transport::Tcp).await else {
+                        // Leave the client waiting, as a dropped UDP answer would
+                        std::future::pending::<()>().await;
+                        return;
+                    };
+                    if write_tcp_message(&mut stream, &response).await.is_err() {
+                        return;
+                    }
+                }
+            };
+            tokio::select! {
+                _ = cancel.cancelled() => {}
+                _ = serve => {}
+            }
+        });
+    }
+}
+
+/// DNS over TCP prefixes every message with its length
+async fn read_tcp_message(stream: &mut TcpStream) -> std::io::Result<Vec<u8>> {
+    let len = stream.read_u16().await?;
+    let mut message = vec![0u8; len as usize];
+    stream.read_exact(&mut message).await?;
+    Ok(message)
+}
+
+async fn write_tcp_message(stream: &mut TcpStream, message: &[u8]) -> std::io::Result<()> {
+    let len = u16::try_from(message.len())
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidData, "message too long"))?;
+    let mut framed = Vec::with_capacity(message.len() + 2);
+    framed.extend_from_slice(&len.to_be_bytes());
+    framed.extend_from_slice(message);
+    stream.write_all(&framed).await
+}
+
+/// The parts of a query needed to match and answer it
+#[derive(Debug)]
+struct Question {
+    flags: u16,
+    /// Lowercase, without a trailing dot
+    name: String,
+    qtype: u16,
+    /// Offset just past the question section
+    end: usize,
+}
+
+impl Question {
+    fn parse(packet: &[u8]) -> Option<Self> {
+        let word = |at: usize| {
+            packet
+                .get(at..at + 2)
+                .map(|b| u16::from_be_bytes([b[0], b[1]]))
+        };
+        let flags = word(2)?;
+        // Only standard queries (QR clear, opcode 0) with a question
+        if flags & 0xF800 != 0 || word(4)? == 0 {
+            return None;
+        }
+
+        let mut labels = Vec::new();
+        let mut pos = 12;
+        loop {
+            let len = *packet.get(pos)? as usize;
+            pos += 1;
+            if len == 0 {
+                break;
+            }
+            // Queries never compress their only name
+            if len > 63 {
+                return None;
+            }
+            let label = packet.get(pos..pos + len)?;
+            labels.push(String::from_utf8_lossy(label).to_ascii_lowercase());
+            pos += len;
+        }
+        let qtype = word(pos)?;
+        word(pos + 2)?;
+
+        Some(Self {
+            flags,
+            name: labels.join("."),
+            qtype,
+            end: pos + 4,
+        })
+    }
+
+    /// Response echoing the question with the given rcode, any addresses of
+    /// the queried type as answers, and optionally the truncation bit
+    fn respond(&self, query: &[u8], rcode: u16, addresses: &[IpAddr], truncated: bool) -> Vec<u8> {
+        let records: Vec<Vec<u8>> = addresses
+            .iter()
+            .filter_map(|address| match (address, self.qtype) {
+                (IpAddr::V4(v4), TYPE_A) => Some(v4.octets().to_vec()),
+                (IpAddr::V6(v6), TYPE_AAAA) => Some(v6.octets().to_vec()),
+                _ => None,
+            })
+            .collect();
+
+        // QR and RA set; opcode and RD copied from the query
+        let mut flags = 0x8080 | (self.flags & 0x7900) | rcode;
+        if truncated {
+            flags |= 0x0200;
+        }
+        let mut response = Vec::with_capacity(self.end + records.len() * 28);
+        response.extend_from_slice(&query[..2]);
+        response.extend_from_slice(&flags.to_be_bytes());
+        for count in [1, records.len() as u16, 0, 0] {
+            response.extend_from_slice(&count.to_be_bytes());
+        }
+        response.extend_from_slice(&query[12..self.end]);
+        for rdata in records {
+            // Name as a pointer to the question at offset 12
+            response.extend_from_slice(&0xC00Cu16.to_be_bytes());
+            response.extend_from_slice(&self.qtype.to_be_bytes());
+            response.extend_from_slice(&1u16.to_be_bytes());
+            response.extend_from_slice(&FORGED_TTL.to_be_bytes());
+            response.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+            response.extend_from_slice(&rdata);
+        }
+        response
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DnsFaultParams {
+    #[serde(default)]
+    listen: Option<SocketAddr>,
+    #[serde(default)]
+    rules: Vec<DnsRuleParams>,
+    #[serde(default)]
+    seed: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DnsRuleParams {
+    name: NamePattern,
+    #[serde(default)]
+    probability: Option<f64>,
+    #[serde(default, deserialize_with = "params::opt_duration")]
+    delay: Option<Duration>,
+    #[serde(default, deserialize_with = "params::opt_duration")]
+    jitter: Option<Duration>,
+    #[serde(default)]
+    rcode: Option<DnsRcode>,
+    #[serde(default)]
+    truncate: bool,
+    #[serde(default)]
+    answer: Vec<IpAddr>,
+    #[serde(default)]
+    drop: bool,
+}
+
+impl DnsRuleParams {
+    fn into_rule(self) -> Result<DnsFaultRule> {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let delay = match (self.delay, self.jitter) {
+            (Some(delay), Some(jitter)) => Some(LatencyDistribution::Normal {
+                mean: ms(delay),
+                std_dev: ms(jitter),
+            }),
+            (Some(delay), None) => Some(LatencyDistribution::Fixed { value: ms(delay) }),
+            (None, Some(_)) => {
+                return Err(ChaosError::InvalidConfig(
+                    "dns_fault jitter requires a delay".to_string(),
+                ))
+            }
+            (None, None) => None,
+        };
+        Ok(DnsFaultRule {
+            probability: self.probability.unwrap_or(1.0),
+            delay,
+            rcode: self.rcode,
+            truncate: self.truncate,
+            answer: self.answer,
+            drop: self.drop,
+            ..DnsFaultRule::new(self.name)
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct DnsFaultBuilder {
+    config: DnsFaultConfig,
+}
+
+impl DnsFaultBuilder {
+    pub fn listen(mut self, listen: SocketAddr) -> Self {
+        self.config.listen = listen;
+        self
+    }
+
+    pub fn rule(mut self, rule: DnsFaultRule) -> Self {
+        self.config.rules.push(rule);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.config.seed = Some(seed);
+        self
+    }
+
+    pub fn build(self) -> DnsFaultInjector {
+        DnsFaultInjector::new(self.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    fn query(id: u16, name: &str, qtype: u16) -> Vec<u8> {
+        let mut packet = Vec::new();
+        packet.extend_from_slice(&id.to_be_bytes());
+        // RD set, one question
+        for word in [0x0100u16, 1, 0, 0, 0] {
+            packet.extend_from_slice(&word.to_be_bytes());
+        }
+        for label in name.split('.') {
+            packet.push(label.len() as u8);
+            packet.extend_from_slice(label.as_bytes());
+        }
+        packet.push(0);
+        packet.extend_from_slice(&qtype.to_be_bytes());
+        packet.extend_from_slice(&1u16.to_be_bytes());
+        packet
+    }
+
+    fn rcode(response: &[u8]) -> u16 {
+        u16::from_be_bytes([response[2], response[3]]) & 0x000F
+    }
+
+    /// First A record of a response whose answers point back at the question
+    fn first_address(response: &[u8]) -> Option<Ipv4Addr> {
+        if response.get(6..8)? == [0, 0] {
+            return None;
+        }
+        let mut pos = 12;
+        while *response.get(pos)? != 0 {
+            pos += 1 + response[pos] as usize;
+        }
+        // Past the root label, type and class; then the answer's pointer,
+        // type, class, TTL and length
+        let rdata = response.get(pos + 5 + 12..pos + 5 + 16)?;
+        Some(Ipv4Addr::new(rdata[0], rdata[1], rdata[2], rdata[3]))
+    }
+
+    /// Upstream resolver answering every A query with 10.0.0.1
+    async fn upstream() -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let address = socket.local_addr().unwrap();
+        let tcp = TcpListener::bind(address).await.unwrap();
+        let answer = |query: &[u8]| {
+            let question = Question::parse(query).unwrap();
+            question.respond(query, 0, &[IpAddr::from([10, 0, 0, 1])], false)
+        };
+        tokio::spawn(async move {
+            let mut buf = [0u8; 512];
+            while let Ok((len, client)) = socket.recv_from(&mut buf).await {
+                let _ = socket.send_to(&answer(&buf[..len]), client).await;
+            }
+        });
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = tcp.accept().await {
+                while let Ok(query) = read_tcp_message(&mut stream).await {
+                    let _ = write_tcp_message(&mut stream, &answer(&query)).await;
+                }
+            }
+        });
+        address
+    }
+
+    async fn ask(listen: SocketAddr, name: &str) -> Vec<u8> {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        socket
+            .send_to(&query(7, name, TYPE_A), listen)
+            .await
+            .unwrap();
+        let mut buf = [0u8; 512];
+        let len = tokio::time::timeout(Duration::from_secs(2), socket.recv(&mut buf))
+            .await
+            .unwrap()
+            .unwrap();
+        buf[..len].to_vec()
+    }
+
+    #[test]
+    fn test_question_round_trip() {
+        let packet = query(42, "API.Example.com", TYPE_A);
+        let question = Question::parse(&packet).unwrap();
+        assert_eq!(question.name, "api.example.com");
+        assert_eq!(question.qtype, TYPE_A);
+        assert_eq!(question.end, packet.len());
+
+        let addresses = [IpAddr::from([192, 0, 2, 1]), "2001:db8::1".parse().unwrap()];
+        let response = question.respond(&packet, 0, &addresses, false);
+        assert_eq!(&response[..2], &42u16.to_be_bytes());
+        assert_eq!(response[2] & 0x80, 0x80);
+        // Only the IPv4 address answers an A query
+        assert_eq!(&response[6..8], &1u16.to_be_bytes());
+        assert_eq!(first_address(&response), Some(Ipv4Addr::new(192, 0, 2, 1)));
+
+        let response = question.respond(&packet, DnsRcode::Nxdomain.code(), &[], true);
+        assert_eq!(rcode(&response), 3);
+        assert_eq!(response[2] & 0x02, 0x02);
+
+        assert!(NamePattern::from_str("*.Example.com.")
+            .unwrap()
+            .matches("a.b.example.com"));
+        assert!(!NamePattern::from_str("*.example.com")
+            .unwrap()
+            .matches("example.com"));
+        assert!(NamePattern::from_str("bad name").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_faults_selected_names() {
+        let injector = DnsFaultInjector::builder()
+            .rule(DnsFaultRule::new("*.down.test".parse().unwrap()).rcode(DnsRcode::Nxdomain))
+            .rule(DnsFaultRule::new("flaky.test".parse().unwrap()).rcode(DnsRcode::Servfail))
+            .rule(DnsFaultRule::new("moved.test".parse().unwrap()).answer([192, 0, 2, 9].into()))
+            .rule(DnsFaultRule::new("big.test".parse().unwrap()).truncate())
+            .build();
+        let target = Target::network(upstream().await);
+        let handle = injector.inject(&target).await.unwrap();
+        let listen: SocketAddr = handle.metadata["listen"].as_str().unwrap().parse().unwrap();
+
+        assert_eq!(rcode(&ask(listen, "api.down.test").await), 3);
+        assert_eq!(rcode(&ask(listen, "flaky.test").await), 2);
+        assert_eq!(
+            first_address(&ask(listen, "moved.test").await),
+            Some(Ipv4Addr::new(192, 0, 2, 9))
+        );
+        assert_eq!(
+            first_address(&ask(listen, "healthy.test").await),
+            Some(Ipv4Addr::new(10, 0, 0, 1))
+        );
+
+        // Truncated over UDP, answered in full when the client retries on TCP
+        let response = ask(listen, "big.test").await;
+        assert_eq!(response[2] & 0x02, 0x02);
+        let mut stream = TcpStream::connect(listen).await.unwrap();
+        write_tcp_message(&mut stream, &query(8, "big.test", TYPE_A))
+            .await
+            .unwrap();
+        let response = read_tcp_message(&mut stream).await.unwrap();
+        assert_eq!(first_address(&response), Some(Ipv4Addr::new(10, 0, 0, 1)));
+
+        injector.remove(handle).await.unwrap();
+    }
+
+    #[test]
+    fn test_dns_fault_from_params() {
+        let params: InjectorParams = serde_json::from_value(serde_json::json!({
+            "listen": "127.0.0.1:5353",
+            "rules": [
+                {"name": "*.payments.internal", "rcode": "nxdomain", "probability": 0.3},
+                {"name": "db.internal", "delay": "2s"},
+                {"name": "cache.internal", "answer": ["10.9.9.9"]},
+            ],
+        }))
+        .unwrap();
+        let injector = DnsFaultInjector::from_params(&params).unwrap();
+        let rules = &injector.config().rules;
+        assert_eq!(injector.config().listen.port(), 5353);
+        assert_eq!(rules[0].rcode, Some(DnsRcode::Nxdomain));
+        assert!(rules[0].name.matches("api.payments.internal."));
+        assert!(matches!(
+            rules[1].delay,
+            Some(LatencyDistribution::Fixed { value }) if value == 2000.0
+        ));
+        assert_eq!(rules[2].answer, [IpAddr::from([10, 9, 9, 9])]);
+
+        for invalid in [
+            serde_json::json!({"rules": []}),
+            serde_json::json!({"rules": [{"name": "a.test"}]}),
+            serde_json::json!({"rules": [{"name": "a.test", "rcode": "servfail", "drop": true}]}),
+            serde_json::json!({"rules": [{"name": "a.test", "rcode": "timeout"}]}),
+            serde_json::json!({"rules": [{"name": "a test", "drop": true}]}),
+        ] {
+            let params: InjectorParams = serde_json::from_value(invalid).unwrap();
+            assert!(DnsFaultInjector::from_params(&params).is_err());
+        }
+    }
+}
@@ -0,0 +1,620 @@
+use crate::{error::*, netns::command_in, netns::NetNamespace, state};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tracing::info;
+
+/// nftables applies a probability by drawing below this
+const NFT_RANDOM_RANGE: u32 = 10_000;
+/// Regular nftables chain holding the rules, jumped to from each hook
+const NFT_RULES_CHAIN: &str = "rules";
+
+/// Serializes edits so concurrent injections agree on whether the chain or
+/// table still has rules. Other chaos processes are kept out by an `flock`
+/// on [`FIREWALL_LOCK_FILE`] in the runtime directory.
+static FIREWALL_LOCK: Mutex<()> = Mutex::const_new(());
+const FIREWALL_LOCK_FILE: &str = "firewall.lock";
+
+async fn lock_firewall() -> Result<state::StateLock> {
+    state::lock(&FIREWALL_LOCK, FIREWALL_LOCK_FILE).await
+}
+
+/// Tool used to install packet filter rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FirewallBackend {
+    Iptables,
+    Nftables,
+}
+
+impl FirewallBackend {
+    /// iptables when it is installed, otherwise nftables
+    pub async fn detect(netns: Option<&NetNamespace>) -> Result<Self> {
+        for (backend, program) in [
+            (FirewallBackend::Iptables, "iptables"),
+            (FirewallBackend::Nftables, "nft"),
+        ] {
+            let installed = command_in(netns, program)
+                .arg("--version")
+                .output()
+                .await
+                .is_ok_and(|output| output.status.success());
+            if installed {
+                return Ok(backend);
+            }
+        }
+        Err(ChaosError::InjectionFailed(
+            "Neither iptables nor nft is installed".to_string(),
+        ))
+    }
+}
+
+/// Which way packets travel relative to the target
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrafficDirection {
+    /// Packets sent to the target
+    #[default]
+    Outbound,
+    /// Packets received from the target
+    Inbound,
+    Both,
+}
+
+impl TrafficDirection {
+    pub(crate) fn outbound(self) -> bool {
+        self != TrafficDirection::Inbound
+    }
+
+    pub(crate) fn inbound(self) -> bool {
+        self != TrafficDirection::Outbound
+    }
+}
+
+/// The address of an `addr` or `addr/prefix` network, if it is one
+pub(crate) fn parse_cidr(cidr: &str) -> Option<IpAddr> {
+    let (address, prefix) = match cidr.split_once('/') {
+        Some((address, prefix)) => (address, Some(prefix.parse::<u8>().ok()?)),
+        None => (cidr, None),
+    };
+    let address: IpAddr = address.parse().ok()?;
+    let max = if address.is_ipv4() { 32 } else { 128 };
+    prefix.is_none_or(|prefix| prefix <= max).then_some(address)
+}
+
+/// A dedicated iptables chain or nftables table that chaos rules of one kind
+/// live in, hooked in only while it has any
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RuleSet {
+    TcpReset,
+    Partition,
+}
+
+impl RuleSet {
+    fn chain(self) -> &'static str {
+        match self {
+            RuleSet::TcpReset => "CHAOS_TCP_RESET",
+            RuleSet::Partition => "CHAOS_PARTITION",
+        }
+    }
+
+    fn table(self) -> &'static str {
+        match self {
+            RuleSet::TcpReset => "chaos_tcp_reset",
+            RuleSet::Partition => "chaos_partition",
+        }
+    }
+
+    /// Netfilter hooks whose packets pass through the rules
+    fn hooks(self) -> &'static [&'static str] {
+        match self {
+            RuleSet::TcpReset => &["input", "output"],
+            // Also traffic routed or bridged through this host
+            RuleSet::Partition => &["input", "output", "forward"],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RuleAction {
+    /// Reject with a TCP reset
+    Reset,
+    /// Silently discard
+    Drop,
+}
+
+/// Packets one rule matches and what happens to them; unset fields match
+/// anything. Ports require `tcp`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FirewallRule {
+    pub ipv6: bool,
+    pub tcp: bool,
+    /// `addr` or `addr/prefix`
+    pub source: Option<String>,
+    pub source_port: Option<u16>,
+    pub destination: Option<String>,
+    pub destination_port: Option<u16>,
+    pub probability: f64,
+    pub action: RuleAction,
+}
+
+impl FirewallRule {
+    pub fn new(ipv6: bool, action: RuleAction) -> Self {
+        Self {
+            ipv6,
+            tcp: false,
+            source: None,
+            source_port: None,
+            destination: None,
+            destination_port: None,
+            probability: 1.0,
+            action,
+        }
+    }
+
+    fn iptables_spec(&self, tag: &str) -> Vec<String> {
+        let mut spec = Vec::new();
+        let mut add = |flag: &str, value: Option<String>| {
+            if let Some(value) = value {
+                spec.extend([flag.to_string(), value]);
+            }
+        };
+        add("-p", self.tcp.then(|| "tcp".to_string()));
+        add("-s", self.source.clone());
+        add("--sport", self.source_port.map(|port| port.to_string()));
+        add("-d", self.destination.clone());
+        add(
+            "--dport",
+            self.destination_port.map(|port| port.to_string()),
+        );
+        if self.probability < 1.0 {
+            spec.extend(["-m", "statistic", "--mode", "random", "--probability"].map(String::from));
+            spec.push(format!("{:.4}", self.probability));
+        }
+        spec.extend(["-m", "comment", "--comment", tag].map(String::from));
+        match self.action {
+            RuleAction::Reset => {
+                spec.extend(["-j", "REJECT", "--reject-with", "tcp-reset"].map(String::from))
+            }
+            RuleAction::Drop => spec.extend(["-j", "DROP"].map(String::from)),
+        }
+        spec
+    }
+
+    fn nft_rule(&self, table: &str, tag: &str) -> String {
+        let family = if self.ipv6 { "ip6" } else { "ip" };
+        let mut rule = format!("add rule inet {} {}", table, NFT_RULES_CHAIN);
+        if self.tcp {
+            rule += " meta l4proto tcp";
+        } else {
+            rule += &format!(" meta nfproto {}", if self.ipv6 { "ipv6" } else { "ipv4" });
+        }
+        if let Some(source) = &self.source {
+            rule += &format!(" {} saddr {}", family, source);
+        }
+        if let Some(port) = self.source_port {
+            rule += &format!(" tcp sport {}", port);
+        }
+        if let Some(destination) = &self.destination {
+            rule += &format!(" {} daddr {}", family, destination);
+        }
+        if let Some(port) = self.destination_port {
+            rule += &format!(" tcp dport {}", port);
+        }
+        if self.probability < 1.0 {
+            let below = (self.probability * NFT_RANDOM_RANGE as f64).round() as u32;
+            rule += &format!(" numgen random mod {} < {}", NFT_RANDOM_RANGE, below);
+        }
+        rule += &format!(" comment \"{}\"", tag);
+        match self.action {
+            RuleAction::Reset => rule + " reject with tcp reset",
+            RuleAction::Drop => rule + " drop",
+        }
+    }
+}
+
+/// Rules installed for one injection, tagged with a comment so they are
+/// deleted exactly
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct FirewallRules {
+    pub set: RuleSet,
+    pub backend: FirewallBackend,
+    pub tag: String,
+    /// iptables rule specs, deleted verbatim
+    #[serde(default)]
+    pub rules: Vec<Vec<String>>,
+    /// ip6tables rule specs
+    #[serde(default)]
+    pub rules6: Vec<Vec<String>>,
+    #[serde(default)]
+    pub netns: Option<NetNamespace>,
+}
+
+impl FirewallRules {
+    /// Read the rules back from a handle's metadata
+    pub fn from_metadata(metadata: &serde_json::Value) -> Result<Self> {
+        serde_json::from_value(metadata.clone())
+            .map_err(|_| ChaosError::CleanupFailed("Missing firewall rule metadata".to_string()))
+    }
+
+    /// Handle metadata recording these rules alongside `extra`
+    pub fn metadata(&self, extra: serde_json::Value) -> serde_json::Value {
+        let mut metadata = serde_json::to_value(self).unwrap_or_default();
+        if let (Some(metadata), serde_json::Value::Object(extra)) =
+            (metadata.as_object_mut(), extra)
+        {
+            metadata.extend(extra);
+        }
+        metadata
+    }
+
+    pub async fn install(
+        set: RuleSet,
+        backend: FirewallBackend,
+        rules: &[FirewallRule],
+        netns: Option<NetNamespace>,
+    ) -> Result<Self> {
+        let _guard = lock_firewall().await?;
+        let mut installed = Self {
+            set,
+            backend,
+            tag: format!("chaos:{}", uuid::Uuid::new_v4()),
+            rules: Vec::new(),
+            rules6: Vec::new(),
+            netns,
+        };
+        let netns = installed.netns.clone();
+        let netns = netns.as_ref();
+
+        match backend {
+            FirewallBackend::Iptables => {
+                for ipv6 in [false, true] {
+                    let family: Vec<_> = rules.iter().filter(|rule| rule.ipv6 == ipv6).collect();
+                    if family.is_empty() {
+                        continue;
+                    }
+                    let program = iptables(ipv6);
+                    // Fails harmlessly when another injection created it
+                    let _ = firewall_output(netns, program, &["-N", set.chain()]).await;
+                    for hook in set.hooks() {
+                        let hook = hook.to_uppercase();
+                        let jump = ["-C", &hook, "-j", set.chain()];
+                        if firewall_output(netns, program, &jump).await.is_err() {
+                            run_firewall(netns, program, &["-I", &hook, "1", "-j", set.chain()])
+                                .await?;
+                        }
+                    }
+                    for rule in family {
+                        let spec = rule.iptables_spec(&installed.tag);
+                        let mut args = vec!["-A", set.chain()];
+                        args.extend(spec.iter().map(String::as_str));
+                        if let Err(e) = run_firewall(netns, program, &args).await {
+                            installed.remove_locked().await?;
+                            return Err(e);
+                        }
+                        if ipv6 {
+                            installed.rules6.push(spec);
+                        } else {
+                            installed.rules.push(spec);
+                        }
+                    }
+                }
+            }
+            FirewallBackend::Nftables => {
+                let exists = firewall_output(netns, "nft", &["list", "table", "inet", set.table()])
+                    .await
+                    .is_ok();
+                let mut script = if exists {
+                    String::new()
+                } else {
+                    nft_table_script(set)
+                };
+                for rule in rules {
+                    script += &rule.nft_rule(set.table(), &installed.tag);
+                    script.push('\n');
+                }
+                // One script, so the rules are added atomically
+                nft_script(netns, &script).await?;
+            }
+        }
+
+        info!(
+            "Installed {} {:?} rule(s) tagged {}",
+            rules.len(),
+            backend,
+            installed.tag
+        );
+        Ok(installed)
+    }
+
+    pub async fn remove(&self) -> Result<()> {
+        if let Some(netns) = self.netns.as_ref().filter(|netns| !netns.exists()) {
+            info!("{} no longer exists; nothing to remove", netns);
+            return Ok(());
+        }
+        let _guard = lock_firewall().await?;
+        self.remove_locked().await
+    }
+
+    async fn remove_locked(&self) -> Result<()> {
+        let netns = self.netns.as_ref();
+        let set = self.set;
+        match self.backend {
+            FirewallBackend::Iptables => {
+                for (ipv6, specs) in [(false, &self.rules), (true, &self.rules6)] {
+                    if specs.is_empty() {
+                        continue;
+                    }
+                    let program = iptables(ipv6);
+                    for spec in specs {
+                        let mut args = vec!["-D", set.chain()];
+                        args.extend(spec.iter().map(String::as_str));
+                        firewall_cleanup(netns, program, &args).await?;
+                    }
+                    let listing = firewall_output(netns, program, &["-S", set.chain()])
+                        .await
+                        .unwrap_or_default();
+                    if !listing.lines().any(|line| line.starts_with("-A ")) {
+                        for hook in set.hooks() {
+                            let hook = hook.to_uppercase();
+                            firewall_cleanup(netns, program, &["-D", &hook, "-j", set.chain()])
+                                .await?;
+                        }
+                        firewall_cleanup(netns, program, &["-X", set.chain()]).await?;
+                    }
+                }
+            }
+            FirewallBackend::Nftables => {
+                let listing =
+                    firewall_output(netns, "nft", &["-a", "list", "table", "inet", set.table()])
+                        .await
+                        .unwrap_or_default();
+                let tagged = nft_rule_handles(&listing, &self.tag);
+                for (chain, handle) in &tagged {
+                    firewall_cleanup(
+                        netns,
+                        "nft",
+                        &[
+                            "delete",
+                            "rule",
+                            "inet",
+                            set.table(),
+                            chain,
+                            "handle",
+                            handle,
+                        ],
+                    )
+                    .await?;
+                }
+                let remaining = listing.matches("comment \"chaos:").count() - tagged.len();
+                if remaining == 0 {
+                    firewall_cleanup(netns, "nft", &["delete", "table", "inet", set.table()])
+                        .await?;
+                }
+            }
+        }
+        info!("Removed firewall rules tagged {}", self.tag);
+        Ok(())
+    }
+}
+
+fn iptables(ipv6: bool) -> &'static str {
+    if ipv6 {
+        "ip6tables"
+    } else {
+        "iptables"
+    }
+}
+
+/// Table with the rules chain and a base chain jumping to it for each hook
+fn nft_table_script(set: RuleSet) -> String {
+    let mut script = format!(
+        "table inet {} {{\n\tchain {} {{ }}\n",
+        set.table(),
+        NFT_RULES_CHAIN
+    );
+    for hook in set.hooks() {
+        script += &format!(
+            "\tchain {hook} {{ type filter hook {hook} priority 0; policy accept; jump {rules}; }}\n",
+            hook = hook,
+            rules = NFT_RULES_CHAIN
+        );
+    }
+    script + "}\n"
+}
+
+/// (chain, handle) of every rule in an `nft -a list` listing carrying `tag`
+fn nft_rule_handles(listing: &str, tag: &str) -> Vec<(String, String)> {
+    let comment = format!("comment \"{}\"", tag);
+    let mut chain = None;
+    let mut handles = Vec::new();
+    for line in listing.lines().map(str::trim) {
+        if let Some(rest) = line.strip_prefix("chain ") {
+            chain = rest.split_whitespace().next().map(str::to_string);
+        } else if line.contains(&comment) {
+            let handle = line.rsplit_once("# handle ").map(|(_, h)| h.trim());
+            if let (Some(chain), Some(handle)) = (&chain, handle) {
+                handles.push((chain.clone(), handle.to_string()));
+            }
+        }
+    }
+    handles
+}
+
+async fn run_firewall(netns: Option<&NetNamespace>, program: &str, args: &[&str]) -> Result<()> {
+    firewall_output(netns, program, args).await.map(|_| ())
+}
+
+/// Stdout of a command that must succeed
+async fn firewall_output(
+    netns: Option<&NetNamespace>,
+    program: &str,
+    args: &[&str],
+) -> Result<String> {
+    let output = command_in(netns, program)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| ChaosError::InjectionFailed(format!("Failed to run {}: {}", program, e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(ChaosError::InjectionFailed(format!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            stderr.trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Run a teardown command, tolerating rules that are already gone
+async fn firewall_cleanup(
+    netns: Option<&NetNamespace>,
+    program: &str,
+    args: &[&str],
+) -> Result<()> {
+    let output = command_in(netns, program)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| ChaosError::CleanupFailed(format!("Failed to run {}: {}", program, e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        info!(
+            "{} cleanup note (may be already removed): {}",
+            program,
+            stderr.trim()
+        );
+    }
+    Ok(())
+}
+
+async fn nft_script(netns: Option<&NetNamespace>, script: &str) -> Result<()> {
+    let failed =
+        |e: std::io::Error| ChaosError::InjectionFailed(format!("Failed to run nft: {}", e));
+    let mut child = command_in(netns, "nft")
+        .args(["-f", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(failed)?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(script.as_bytes()).await.map_err(failed)?;
+    }
+    let output = child.wait_with_output().await.map_err(failed)?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(ChaosError::InjectionFailed(format!(
+            "nft -f failed: {}",
+            stderr.trim()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_generation() {
+        let rule = FirewallRule {
+            tcp: true,
+            destination: Some("10.0.0.5".to_string()),
+            destination_port: Some(5432),
+            probability: 0.25,
+            ..FirewallRule::new(false, RuleAction::Reset)
+        };
+        assert_eq!(
+            rule.iptables_spec("chaos:t").join(" "),
+            "-p tcp -d 10.0.0.5 --dport 5432 -m statistic --mode random --probability 0.2500 \
+             -m comment --comment chaos:t -j REJECT --reject-with tcp-reset"
+        );
+        assert_eq!(
+            rule.nft_rule("chaos_tcp_reset", "chaos:t"),
+            "add rule inet chaos_tcp_reset rules meta l4proto tcp ip daddr 10.0.0.5 \
+             tcp dport 5432 numgen random mod 10000 < 2500 comment \"chaos:t\" \
+             reject with tcp reset"
+        );
+
+        let rule = FirewallRule {
+            source: Some("fd00::/64".to_string()),
+            destination: Some("fd01::7".to_string()),
+            ..FirewallRule::new(true, RuleAction::Drop)
+        };
+        assert_eq!(
+            rule.iptables_spec("chaos:t").join(" "),
+            "-s fd00::/64 -d fd01::7 -m comment --comment chaos:t -j DROP"
+        );
+        assert_eq!(
+            rule.nft_rule("chaos_partition", "chaos:t"),
+            "add rule inet chaos_partition rules meta nfproto ipv6 ip6 saddr fd00::/64 \
+             ip6 daddr fd01::7 comment \"chaos:t\" drop"
+        );
+
+        assert_eq!(
+            nft_table_script(RuleSet::TcpReset),
+            "table inet chaos_tcp_reset {\n\
+             \tchain rules { }\n\
+             \tchain input { type filter hook input priority 0; policy accept; jump rules; }\n\
+             \tchain output { type filter hook output priority 0; policy accept; jump rules; }\n\
+             }\n"
+        );
+
+        assert_eq!(parse_cidr("10.1.0.0/16"), Some(IpAddr::from([10, 1, 0, 0])));
+        assert!(parse_cidr("10.1.0.0/33").is_none());
+        assert!(parse_cidr("db.internal").is_none());
+    }
+
+    #[test]
+    fn test_nft_rule_handles() {
+        let listing = "table inet chaos_tcp_reset { # handle 7\n\
+            \tchain rules { # handle 1\n\
+            \t\tmeta l4proto tcp ip saddr 10.0.0.5 comment \"chaos:a\" reject with tcp reset # handle 4\n\
+            \t\tmeta l4proto tcp ip daddr 10.0.0.5 comment \"chaos:a\" reject with tcp reset # handle 3\n\
+            \t\tmeta l4proto tcp ip daddr 10.0.0.6 comment \"chaos:b\" reject with tcp reset # handle 5\n\
+            \t}\n\
+            \tchain output { # handle 2\n\
+            \t\ttype filter hook output priority filter; policy accept;\n\
+            \t\tjump rules # handle 6\n\
+            \t}\n\
+            }\n";
+        assert_eq!(
+            nft_rule_handles(listing, "chaos:a"),
+            [
+                ("rules".to_string(), "4".to_string()),
+                ("rules".to_string(), "3".to_string())
+            ]
+        );
+        assert!(nft_rule_handles(listing, "chaos:c").is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_firewall_lock_excludes_other_processes() {
+        let path = state::runtime_dir().join(FIREWALL_LOCK_FILE);
+        // Other tests may be editing the firewall, so wait a little once free
+        let try_lock = |wait: &str| {
+            std::process::Command::new("flock")
+                .args(["-w", wait, path.to_str().unwrap(), "true"])
+                .status()
+        };
+
+        let guard = lock_firewall().await.unwrap();
+        let Ok(status) = try_lock("0.2") else {
+            eprintln!("skipping firewall lock test: flock not found");
+            return;
+        };
+        assert!(!status.success());
+        drop(guard);
+        assert!(try_lock("10").unwrap().success());
+    }
+}
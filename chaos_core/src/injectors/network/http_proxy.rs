@@ -0,0 +1,805 @@
+use super::LatencyDistribution;
+use crate::{
+    error::*,
+    handle::InjectionHandle,
+    injectors::{params, Injector, InjectorParams},
+    target::Target,
+};
+use async_trait::async_trait;
+use bytes::Bytes;
+use http_body_util::{combinators::BoxBody, BodyExt, Full};
+use hyper::body::{Body, Frame, Incoming};
+use hyper::header::{HeaderName, HeaderValue, CONTENT_LENGTH, RETRY_AFTER};
+use hyper::{Method, Request, Response, StatusCode, Uri, Version};
+use hyper_util::client::legacy::{connect::HttpConnector, Client};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+type ProxyBody = BoxBody<Bytes, BoxError>;
+
+/// Requests a rule applies to: `POST /orders`, `/api/*` or `*`. A trailing
+/// `*` matches any path with that prefix.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct RouteMatch {
+    method: Option<Method>,
+    path: String,
+}
+
+impl RouteMatch {
+    pub fn matches(&self, method: &Method, path: &str) -> bool {
+        if self.method.as_ref().is_some_and(|m| m != method) {
+            return false;
+        }
+        match self.path.strip_suffix('*') {
+            Some(prefix) => path.starts_with(prefix),
+            None => path == self.path,
+        }
+    }
+}
+
+impl FromStr for RouteMatch {
+    type Err = ChaosError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || {
+            ChaosError::InvalidConfig(format!(
+                "Invalid http_proxy route '{}': expected e.g. 'POST /orders' or '/api/*'",
+                s
+            ))
+        };
+        let mut parts = s.split_whitespace();
+        let (method, path) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(path), None, None) => (None, path),
+            (Some(method), Some(path), None) => {
+                let method =
+                    Method::from_bytes(method.to_uppercase().as_bytes()).map_err(|_| invalid())?;
+                (Some(method), path)
+            }
+            _ => return Err(invalid()),
+        };
+        if !path.starts_with('/') && path != "*" {
+            return Err(invalid());
+        }
+        Ok(Self {
+            method,
+            path: path.to_string(),
+        })
+    }
+}
+
+impl TryFrom<String> for RouteMatch {
+    type Error = ChaosError;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<RouteMatch> for String {
+    fn from(route: RouteMatch) -> Self {
+        match route.method {
+            Some(method) => format!("{} {}", method, route.path),
+            None => route.path,
+        }
+    }
+}
+
+/// Faults applied to requests matching a route. Every action set on the rule
+/// is applied when it fires.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpFaultRule {
+    pub route: RouteMatch,
+    /// Probability per matching request that the rule fires
+    pub probability: f64,
+    /// Delay before the request is answered, in milliseconds
+    pub delay: Option<LatencyDistribution>,
+    /// Answer with this status instead of asking the upstream
+    pub status: Option<u16>,
+    /// `Retry-After` sent with an injected status
+    pub retry_after: Option<Duration>,
+    /// Body sent with an injected status
+    pub body: Option<String>,
+    /// Abort the response body after this many bytes
+    pub truncate: Option<usize>,
+    /// Response headers to strip
+    pub drop_headers: Vec<String>,
+    /// Replace the response body with random bytes of the same length
+    pub malformed: bool,
+}
+
+impl HttpFaultRule {
+    pub fn new(route: RouteMatch) -> Self {
+        Self {
+            route,
+            probability: 1.0,
+            delay: None,
+            status: None,
+            retry_after: None,
+            body: None,
+            truncate: None,
+            drop_headers: Vec::new(),
+            malformed: false,
+        }
+    }
+
+    pub fn probability(mut self, probability: f64) -> Self {
+        self.probability = probability;
+        self
+    }
+
+    pub fn delay(mut self, delay: LatencyDistribution) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    pub fn status(mut self, status: u16) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    pub fn retry_after(mut self, retry_after: Duration) -> Self {
+        self.retry_after = Some(retry_after);
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<String>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    pub fn truncate(mut self, bytes: usize) -> Self {
+        self.truncate = Some(bytes);
+        self
+    }
+
+    pub fn drop_header(mut self, header: impl Into<String>) -> Self {
+        self.drop_headers.push(header.into());
+        self
+    }
+
+    pub fn malformed(mut self) -> Self {
+        self.malformed = true;
+        self
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let route = String::from(self.route.clone());
+        let invalid = |reason: String| {
+            Err(ChaosError::InvalidConfig(format!(
+                "http_proxy rule '{}': {}",
+                route, reason
+            )))
+        };
+        if !(0.0..=1.0).contains(&self.probability) {
+            return invalid(format!(
+                "probability must be between 0.0 and 1.0, got {}",
+                self.probability
+            ));
+        }
+        if let Some(status) = self.status {
+            if !(100..=599).contains(&status) {
+                return invalid(format!("{} is not an HTTP status", status));
+            }
+        } else if self.retry_after.is_some() || self.body.is_some() {
+            return invalid("retry_after and body require a status".to_string());
+        }
+        if let Some(Err(e)) = self.delay.as_ref().map(LatencyDistribution::validate) {
+            return invalid(e.to_string());
+        }
+        for header in &self.drop_headers {
+            if HeaderName::from_bytes(header.as_bytes()).is_err() {
+                return invalid(format!("'{}' is not a header name", header));
+            }
+        }
+        if self.delay.is_none()
+            && self.status.is_none()
+            && self.truncate.is_none()
+            && self.drop_headers.is_empty()
+            && !self.malformed
+        {
+            return invalid("no fault configured".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpProxyConfig {
+    /// Address to accept HTTP/1.1 and cleartext HTTP/2 on; port 0 picks a
+    /// free one
+    pub listen: SocketAddr,
+    /// Checked in order; every matching rule that fires is applied
+    pub rules: Vec<HttpFaultRule>,
+    /// Makes every random decision reproducible
+    pub seed: Option<u64>,
+}
+
+impl Default for HttpProxyConfig {
+    fn default() -> Self {
+        Self {
+            listen: SocketAddr::from(([127, 0, 0, 1], 0)),
+            rules: Vec::new(),
+            seed: None,
+        }
+    }
+}
+
+impl HttpProxyConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.rules.is_empty() {
+            return Err(ChaosError::InvalidConfig(
+                "http_proxy requires at least one rule".to_string(),
+            ));
+        }
+        self.rules.iter().try_for_each(HttpFaultRule::validate)
+    }
+}
+
+/// Userspace HTTP proxy that forwards to a network target and injects
+/// route-scoped faults into requests and responses. Needs no privileges:
+/// clients talk to the proxy instead.
+#[derive(Default)]
+pub struct HttpProxyInjector {
+    config: Arc<HttpProxyConfig>,
+    /// Stops the accept loop and every open connection of each proxy
+    proxies: Mutex<HashMap<String, CancellationToken>>,
+}
+
+impl HttpProxyInjector {
+    pub fn new(config: HttpProxyConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            proxies: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn builder() -> HttpProxyBuilder {
+        HttpProxyBuilder::default()
+    }
+
+    pub fn config(&self) -> &HttpProxyConfig {
+        &self.config
+    }
+
+    pub fn from_params(params: &InjectorParams) -> Result<Self> {
+        let params: HttpProxyParams = params::parse_params("http_proxy", params)?;
+        let mut builder = Self::builder();
+
+        if let Some(listen) = params.listen {
+            builder = builder.listen(listen);
+        }
+        for rule in params.rules {
+            builder = builder.rule(rule.into_rule()?);
+        }
+        if let Some(seed) = params.seed {
+            builder = builder.seed(seed);
+        }
+
+        let injector = builder.build();
+        injector.config.validate()?;
+        Ok(injector)
+    }
+}
+
+#[async_trait]
+impl Injector for HttpProxyInjector {
+    async fn inject(&self, target: &Target) -> Result<InjectionHandle> {
+        let Target::Network { address: upstream } = target else {
+            return Err(ChaosError::InvalidConfig(
+                "HTTP proxy requires Network target".to_string(),
+            ));
+        };
+        self.config.validate()?;
+
+        let listener = TcpListener::bind(self.config.listen).await.map_err(|e| {
+            ChaosError::InjectionFailed(format!(
+                "Failed to listen on {}: {}",
+                self.config.listen, e
+            ))
+        })?;
+        let listen = listener.local_addr()?;
+        info!("Proxying HTTP {} -> {} with faults", listen, upstream);
+
+        let cancel = CancellationToken::new();
+        let rng = self
+            .config
+            .seed
+            .map(StdRng::seed_from_u64)
+            .unwrap_or_else(StdRng::from_entropy);
+        let proxy = Arc::new(HttpProxy {
+            upstream: *upstream,
+            config: self.config.clone(),
+            rng: Mutex::new(rng),
+            http1: Client::builder(TokioExecutor::new()).build_http(),
+            http2: Client::builder(TokioExecutor::new())
+                .http2_only(true)
+                .build_http(),
+        });
+        tokio::spawn(accept_loop(listener, proxy, cancel.clone()));
+
+        let handle = InjectionHandle::new(
+            "http_proxy",
+            target.clone(),
+            serde_json::json!({
+                "listen": listen.to_string(),
+                "upstream": upstream.to_string(),
+            }),
+        );
+        self.proxies
+            .lock()
+            .unwrap()
+            .insert(handle.id.clone(), cancel);
+        Ok(handle)
+    }
+
+    async fn remove(&self, handle: InjectionHandle) -> Result<()> {
+        match self.proxies.lock().unwrap().remove(&handle.id) {
+            Some(cancel) => {
+                info!("Stopping HTTP proxy {}", handle.metadata["listen"]);
+                cancel.cancel();
+            }
+            // The proxy only lives as long as the process that started it
+            None => info!("HTTP proxy {} is not running", handle.id),
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "http_proxy"
+    }
+
+    async fn validate(&self) -> Result<()> {
+        self.config.validate()
+    }
+}
+
+struct HttpProxy {
+    upstream: SocketAddr,
+    config: Arc<HttpProxyConfig>,
+    rng: Mutex<StdRng>,
+    http1: Client<HttpConnector, Incoming>,
+    http2: Client<HttpConnector, Incoming>,
+}
+
+impl HttpProxy {
+    /// Rules matching the request that fire this time, plus the delay they add
+    fn roll(&self, method: &Method, path: &str) -> (Vec<&HttpFaultRule>, Duration) {
+        let mut rng = self.rng.lock().unwrap();
+        let mut delay_ms = 0.0;
+        let mut fired = Vec::new();
+        for rule in &self.config.rules {
+            if !rule.route.matches(method, path) || !rng.gen_bool(rule.probability) {
+                continue;
+            }
+            if let Some(latency) = &rule.delay {
+                delay_ms += latency.sample(&mut *rng).max(0.0);
+            }
+            fired.push(rule);
+        }
+        (fired, Duration::from_secs_f64(delay_ms / 1000.0))
+    }
+
+    async fn handle(&self, mut request: Request<Incoming>) -> Response<ProxyBody> {
+        let method = request.method().clone();
+        let path = request.uri().path().to_string();
+        let (fired, delay) = self.roll(&method, &path);
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+
+        let injected = fired.iter().find_map(|rule| rule.status.map(|s| (rule, s)));
+        let mut response = match injected {
+            Some((rule, status)) => {
+                let status =
+                    StatusCode::from_u16(status).unwrap_or(StatusCode::SERVICE_UNAVAILABLE);
+                let body = rule
+                    .body
+                    .clone()
+                    .unwrap_or_else(|| status.canonical_reason().unwrap_or_default().to_string());
+                let mut response = Response::new(full(body));
+                *response.status_mut() = status;
+                if let Some(retry_after) = rule.retry_after {
+                    response
+                        .headers_mut()
+                        .insert(RETRY_AFTER, HeaderValue::from(retry_after.as_secs()));
+                }
+                response
+            }
+            None => {
+                let path_and_query = request
+                    .uri()
+                    .path_and_query()
+                    .map(|p| p.as_str())
+                    .unwrap_or("/");
+                let uri = format!("http://{}{}", self.upstream, path_and_query);
+                match Uri::try_from(uri) {
+                    Ok(uri) => {
+                        *request.uri_mut() = uri;
+                        self.forward(request).await
+                    }
+                    Err(e) => {
+                        // Answer rather than panic the connection task
+                        warn!(
+                            "HTTP proxy cannot forward {} to {}: {}",
+                            path, self.upstream, e
+                        );
+                        bad_gateway("cannot forward request")
+                    }
+                }
+            }
+        };
+
+        for rule in &fired {
+            for header in &rule.drop_headers {
+                response.headers_mut().remove(header.as_str());
+            }
+        }
+        if fired.iter().any(|rule| rule.malformed) {
+            response = self.garble(response).await;
+        }
+        if let Some(limit) = fired.iter().filter_map(|rule| rule.truncate).min() {
+            response = response.map(|body| Truncated::new(body, limit).boxed());
+        }
+        response
+    }
+
+    /// Send `request` on to the upstream, answering 502 if it cannot be reached
+    async fn forward(&self, request: Request<Incoming>) -> Response<ProxyBody> {
+        let client = if request.version() == Version::HTTP_2 {
+            &self.http2
+        } else {
+            &self.http1
+        };
+        match client.request(request).await {
+            Ok(response) => response.map(|body| body.map_err(BoxError::from).boxed()),
+            Err(e) => {
+                warn!("HTTP proxy could not reach {}: {}", self.upstream, e);
+                bad_gateway("upstream unreachable")
+            }
+        }
+    }
+
+    /// Same status and headers, but a body of random bytes no client can parse
+    async fn garble(&self, response: Response<ProxyBody>) -> Response<ProxyBody> {
+        let (mut parts, body) = response.into_parts();
+        let len = match body.collect().await {
+            Ok(collected) => collected.to_bytes().len(),
+            Err(_) => 0,
+        };
+        let mut garbage = vec![0u8; len.max(16)];
+        self.rng.lock().unwrap().fill(&mut garbage[..]);
+        parts.headers.remove(CONTENT_LENGTH);
+        Response::from_parts(parts, full(garbage))
+    }
+}
+
+fn full(body: impl Into<Bytes>) -> ProxyBody {
+    Full::new(body.into())
+        .map_err(|never| match never {})
+        .boxed()
+}
+
+fn bad_gateway(body: &'static str) -> Response<ProxyBody> {
+    let mut response = Response::new(full(body));
+    *response.status_mut() = StatusCode::BAD_GATEWAY;
+    response
+}
+
+/// Passes on the first `remaining` bytes of a body, then fails it so the
+/// connection or stream is aborted short of the promised length
+struct Truncated {
+    inner: ProxyBody,
+    remaining: usize,
+    flushed: bool,
+}
+
+impl Truncated {
+    fn new(inner: ProxyBody, limit: usize) -> Self {
+        Self {
+            inner,
+            remaining: limit,
+            flushed: false,
+        }
+    }
+}
+
+impl Body for Truncated {
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<std::result::Result<Frame<Bytes>, BoxError>>> {
+        if self.remaining == 0 {
+            if !self.flushed {
+                // Pending once so the server flushes what was sent before
+                // the abort
+                self.flushed = true;
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            return Poll::Ready(Some(Err("response truncated by http_proxy".into())));
+        }
+        let frame = match Pin::new(&mut self.inner).poll_frame(cx) {
+            Poll::Ready(Some(Ok(frame))) => frame,
+            other => return other,
+        };
+        match frame.into_data() {
+            Ok(mut data) => {
+                data.truncate(self.remaining);
+                self.remaining -= data.len();
+                Poll::Ready(Some(Ok(Frame::data(data))))
+            }
+            Err(frame) => Poll::Ready(Some(Ok(frame))),
+        }
+    }
+}
+
+async fn accept_loop(listener: TcpListener, proxy: Arc<HttpProxy>, cancel: CancellationToken) {
+    loop {
+        let stream = tokio::select! {
+            _ = cancel.cancelled() => return,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    warn!("HTTP proxy accept failed: {}", e);
+                    continue;
+                }
+            },
+        };
+
+        let proxy = proxy.clone();
+        let cancel = cancel.child_token();
+        tokio::spawn(async move {
+            let service = hyper::service::service_fn(move |request| {
+                let proxy = proxy.clone();
+                async move { Ok::<_, Infallible>(proxy.handle(request).await) }
+            });
+            let server = auto::Builder::new(TokioExecutor::new());
+            tokio::select! {
+                _ = cancel.cancelled() => {}
+                _ = server.serve_connection(TokioIo::new(stream), service) => {}
+            }
+        });
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HttpProxyParams {
+    #[serde(default)]
+    listen: Option<SocketAddr>,
+    #[serde(default)]
+    rules: Vec<HttpRuleParams>,
+    #[serde(default)]
+    seed: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HttpRuleParams {
+    route: RouteMatch,
+    #[serde(default)]
+    probability: Option<f64>,
+    #[serde(default, deserialize_with = "params::opt_duration")]
+    delay: Option<Duration>,
+    #[serde(default, deserialize_with = "params::opt_duration")]
+    jitter: Option<Duration>,
+    #[serde(default)]
+    status: Option<u16>,
+    #[serde(default, deserialize_with = "params::opt_duration")]
+    retry_after: Option<Duration>,
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default)]
+    truncate: Option<usize>,
+    #[serde(default)]
+    drop_headers: Vec<String>,
+    #[serde(default)]
+    malformed: bool,
+}
+
+impl HttpRuleParams {
+    fn into_rule(self) -> Result<HttpFaultRule> {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let delay = match (self.delay, self.jitter) {
+            (Some(delay), Some(jitter)) => Some(LatencyDistribution::Normal {
+                mean: ms(delay),
+                std_dev: ms(jitter),
+            }),
+            (Some(delay), None) => Some(LatencyDistribution::Fixed { value: ms(delay) }),
+            (None, Some(_)) => {
+                return Err(ChaosError::InvalidConfig(
+                    "http_proxy jitter requires a delay".to_string(),
+                ))
+            }
+            (None, None) => None,
+        };
+        Ok(HttpFaultRule {
+            probability: self.probability.unwrap_or(1.0),
+            delay,
+            status: self.status,
+            retry_after: self.retry_after,
+            body: self.body,
+            truncate: self.truncate,
+            drop_headers: self.drop_headers,
+            malformed: self.malformed,
+            ..HttpFaultRule::new(self.route)
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct HttpProxyBuilder {
+    config: HttpProxyConfig,
+}
+
+impl HttpProxyBuilder {
+    pub fn listen(mut self, listen: SocketAddr) -> Self {
+        self.config.listen = listen;
+        self
+    }
+
+    pub fn rule(mut self, rule: HttpFaultRule) -> Self {
+        self.config.rules.push(rule);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.config.seed = Some(seed);
+        self
+    }
+
+    pub fn build(self) -> HttpProxyInjector {
+        HttpProxyInjector::new(self.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::Instant;
+
+    /// Upstream answering every request with a fixed body and header, over
+    /// HTTP/1.1 or cleartext HTTP/2
+    async fn upstream() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let service = hyper::service::service_fn(|_| async {
+                        let response = Response::builder()
+                            .header("x-upstream", "1")
+                            .body(Full::new(Bytes::from_static(b"hello world")))
+                            .unwrap();
+                        Ok::<_, Infallible>(response)
+                    });
+                    let _ = auto::Builder::new(TokioExecutor::new())
+                        .serve_connection(TokioIo::new(stream), service)
+                        .await;
+                });
+            }
+        });
+        address
+    }
+
+    async fn start(injector: &HttpProxyInjector) -> (InjectionHandle, String) {
+        let target = Target::network(upstream().await);
+        let handle = injector.inject(&target).await.unwrap();
+        let base = format!("http://{}", handle.metadata["listen"].as_str().unwrap());
+        (handle, base)
+    }
+
+    #[tokio::test]
+    async fn test_status_only_on_matching_route() {
+        let injector = HttpProxyInjector::builder()
+            .rule(
+                HttpFaultRule::new("POST /orders".parse().unwrap())
+                    .status(429)
+                    .retry_after(Duration::from_secs(5)),
+            )
+            .build();
+        let (handle, base) = start(&injector).await;
+        let url = format!("{}/orders", base);
+
+        let http1 = reqwest::Client::new();
+        let response = http1.post(&url).send().await.unwrap();
+        assert_eq!(response.status(), 429);
+        assert_eq!(response.headers()["retry-after"], "5");
+
+        let response = http1.get(&url).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.text().await.unwrap(), "hello world");
+
+        let http2 = reqwest::Client::builder()
+            .http2_prior_knowledge()
+            .build()
+            .unwrap();
+        let response = http2.post(&url).send().await.unwrap();
+        assert_eq!(response.version(), reqwest::Version::HTTP_2);
+        assert_eq!(response.status(), 429);
+        let response = http2.get(&url).send().await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "hello world");
+
+        injector.remove(handle).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_response_faults() {
+        let injector = HttpProxyInjector::builder()
+            .rule(HttpFaultRule::new("*".parse().unwrap()).drop_header("x-upstream"))
+            .rule(
+                HttpFaultRule::new("/slow".parse().unwrap())
+                    .delay(LatencyDistribution::Fixed { value: 50.0 }),
+            )
+            .rule(HttpFaultRule::new("/cut".parse().unwrap()).truncate(5))
+            .rule(HttpFaultRule::new("/junk".parse().unwrap()).malformed())
+            .seed(7)
+            .build();
+        let (_handle, base) = start(&injector).await;
+        let client = reqwest::Client::new();
+
+        let started = Instant::now();
+        let response = client.get(format!("{}/slow", base)).send().await.unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        assert!(response.headers().get("x-upstream").is_none());
+        assert_eq!(response.text().await.unwrap(), "hello world");
+
+        let response = client.get(format!("{}/cut", base)).send().await.unwrap();
+        assert!(response.text().await.is_err());
+
+        let response = client.get(format!("{}/junk", base)).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_ne!(response.bytes().await.unwrap().as_ref(), b"hello world");
+    }
+
+    #[test]
+    fn test_http_proxy_from_params() {
+        let params: InjectorParams = serde_json::from_value(serde_json::json!({
+            "listen": "127.0.0.1:9000",
+            "rules": [
+                {"route": "post /orders", "status": 503, "probability": 0.5},
+                {"route": "/api/*", "delay": "200ms", "jitter": "50ms"},
+            ],
+        }))
+        .unwrap();
+        let injector = HttpProxyInjector::from_params(&params).unwrap();
+        let rules = &injector.config().rules;
+        assert_eq!(injector.config().listen.port(), 9000);
+        assert!(rules[0].route.matches(&Method::POST, "/orders"));
+        assert!(!rules[0].route.matches(&Method::GET, "/orders"));
+        assert!(rules[1].route.matches(&Method::GET, "/api/v1/books"));
+        assert!(matches!(
+            rules[1].delay,
+            Some(LatencyDistribution::Normal { mean, std_dev }) if mean == 200.0 && std_dev == 50.0
+        ));
+
+        for invalid in [
+            serde_json::json!({"rules": []}),
+            serde_json::json!({"rules": [{"route": "/"}]}),
+            serde_json::json!({"rules": [{"route": "orders", "status": 503}]}),
+            serde_json::json!({"rules": [{"route": "/", "status": 999}]}),
+            serde_json::json!({"rules": [{"route": "/", "retry_after": "5s"}]}),
+        ] {
+            let params: InjectorParams = serde_json::from_value(invalid).unwrap();
+            assert!(HttpProxyInjector::from_params(&params).is_err());
+        }
+    }
+}
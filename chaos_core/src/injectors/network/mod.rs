@@ -1,38 +1,128 @@
-use crate::{error::*, handle::InjectionHandle, injectors::Injector, target::Target};
+use crate::{
+    error::*,
+    handle::InjectionHandle,
+    injectors::{params, Injector, InjectorParams},
+    netns::NetNamespace,
+    target::Target,
+};
 use async_trait::async_trait;
 use rand::Rng;
 use rand_distr::{Distribution, Exp, Normal, Uniform};
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
 use std::time::Duration;
 
 #[allow(unused_imports)] // Used in platform-specific code blocks
 use tokio::process::Command;
 use tracing::info;
 
+mod dns;
+mod firewall;
+mod http_proxy;
+mod netem;
+mod partition;
+mod proxy;
+mod tc;
+mod websocket_proxy;
+
+pub use dns::*;
+pub use firewall::{FirewallBackend, TrafficDirection};
+use firewall::{FirewallRule, FirewallRules, RuleAction, RuleSet};
+pub use http_proxy::*;
+pub use netem::*;
+pub use partition::*;
+pub use proxy::*;
+#[cfg(target_os = "linux")]
+use tc::interface_for_target;
+pub use tc::IpProtocol;
+use tc::NetemAttachment;
+pub use websocket_proxy::*;
+
+/// Namespace to inject in: an explicit `netns` parameter, otherwise the one a
+/// process or container target lives in
+#[cfg(target_os = "linux")]
+async fn resolve_netns(
+    netns: Option<&NetNamespace>,
+    target: &Target,
+) -> Result<Option<NetNamespace>> {
+    match netns {
+        Some(netns) => Ok(Some(netns.clone())),
+        None => NetNamespace::for_target(target).await,
+    }
+}
+
+/// Longest latency a distribution may be configured with, in milliseconds
+const MAX_LATENCY_MS: f64 = 86_400_000.0;
+
+/// Delay distribution; every value is in milliseconds
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum LatencyDistribution {
+    #[serde(alias = "normal")]
     Normal { mean: f64, std_dev: f64 },
+    #[serde(alias = "uniform")]
     Uniform { min: f64, max: f64 },
+    #[serde(alias = "exponential")]
     Exponential { lambda: f64 },
+    #[serde(alias = "fixed")]
     Fixed { value: f64 },
 }
 
 impl LatencyDistribution {
+    /// Reject parameters that cannot be sampled from: non-finite or
+    /// out-of-range values, `min >= max` and `lambda <= 0`
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |reason: &str| {
+            Err(ChaosError::InvalidConfig(format!(
+                "Invalid latency distribution {:?}: {}",
+                self, reason
+            )))
+        };
+        let values = match *self {
+            LatencyDistribution::Normal { mean, std_dev } => vec![mean, std_dev],
+            LatencyDistribution::Uniform { min, max } => vec![min, max],
+            LatencyDistribution::Exponential { lambda } => vec![lambda],
+            LatencyDistribution::Fixed { value } => vec![value],
+        };
+        if !values.iter().all(|v| v.abs() <= MAX_LATENCY_MS) {
+            return invalid("values must be finite and at most a day");
+        }
+        match *self {
+            LatencyDistribution::Normal { std_dev, .. } if std_dev < 0.0 => {
+                invalid("std_dev must not be negative")
+            }
+            LatencyDistribution::Uniform { min, max } if min >= max => {
+                invalid("min must be below max")
+            }
+            LatencyDistribution::Exponential { lambda } if lambda <= 0.0 => {
+                invalid("lambda must be positive")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Draw a delay, never negative. Parameters `validate` rejects fall
+    /// back to a fixed delay instead of panicking.
     pub fn sample<R: Rng>(&self, rng: &mut R) -> f64 {
-        match self {
+        let value = match *self {
             LatencyDistribution::Normal { mean, std_dev } => {
-                let normal = Normal::new(*mean, *std_dev).unwrap();
-                normal.sample(rng).max(0.0)
+                Normal::new(mean, std_dev).map_or(mean, |normal| normal.sample(rng))
             }
             LatencyDistribution::Uniform { min, max } => {
-                let uniform = Uniform::new(*min, *max);
-                uniform.sample(rng)
+                if min < max && (max - min).is_finite() {
+                    Uniform::new(min, max).sample(rng)
+                } else {
+                    min
+                }
             }
             LatencyDistribution::Exponential { lambda } => {
-                let exp = Exp::new(*lambda).unwrap();
-                exp.sample(rng)
+                Exp::new(lambda).map_or(0.0, |exp| exp.sample(rng))
             }
-            LatencyDistribution::Fixed { value } => *value,
+            LatencyDistribution::Fixed { value } => value,
+        };
+        if value.is_nan() {
+            0.0
+        } else {
+            value.clamp(0.0, MAX_LATENCY_MS)
         }
     }
 }
@@ -43,6 +133,12 @@ pub struct NetworkLatencyConfig {
     pub jitter: Duration,
     pub distribution: LatencyDistribution,
     pub correlation: f64, // 0.0 - 1.0, how correlated successive delays are
+    /// Only delay this protocol for network targets
+    #[serde(default)]
+    pub protocol: Option<IpProtocol>,
+    /// Namespace to shape in instead of the target's own
+    #[serde(default)]
+    pub netns: Option<NetNamespace>,
 }
 
 impl Default for NetworkLatencyConfig {
@@ -55,23 +151,17 @@ impl Default for NetworkLatencyConfig {
                 std_dev: 20.0,
             },
             correlation: 0.0,
+            protocol: None,
+            netns: None,
         }
     }
 }
 
+#[derive(Default)]
 pub struct NetworkLatencyInjector {
-    #[allow(dead_code)]
     config: NetworkLatencyConfig,
 }
 
-impl Default for NetworkLatencyInjector {
-    fn default() -> Self {
-        Self {
-            config: NetworkLatencyConfig::default(),
-        }
-    }
-}
-
 impl NetworkLatencyInjector {
     pub fn new(config: NetworkLatencyConfig) -> Self {
         Self { config }
@@ -81,52 +171,58 @@ impl NetworkLatencyInjector {
         NetworkLatencyBuilder::default()
     }
 
+    pub fn from_params(params: &InjectorParams) -> Result<Self> {
+        let params: NetworkLatencyParams = params::parse_params("network_latency", params)?;
+        let mut builder = Self::builder();
+        if let Some(delay) = params.delay {
+            builder = builder.mean(delay);
+        }
+        if let Some(jitter) = params.jitter {
+            builder = builder.jitter(jitter);
+        }
+        if let Some(distribution) = params.distribution {
+            builder = builder.distribution(distribution);
+        }
+        if let Some(correlation) = params.correlation {
+            builder = builder.correlation(correlation);
+        }
+        if let Some(protocol) = params.protocol {
+            builder = builder.protocol(protocol);
+        }
+        if let Some(netns) = params.netns {
+            builder = builder.netns(NetNamespace::parse(&netns));
+        }
+        let injector = builder.build();
+        NetemDelay::from_latency(&injector.config)?;
+        Ok(injector)
+    }
+
     #[cfg(target_os = "linux")]
     async fn inject_linux(&self, target: &Target) -> Result<InjectionHandle> {
-        let interface = self.get_interface_for_target(target).await?;
-        let mean_ms = self.config.mean.as_millis();
-        let jitter_ms = self.config.jitter.as_millis();
-        let correlation = (self.config.correlation * 100.0) as u32;
+        let netns = resolve_netns(self.config.netns.as_ref(), target).await?;
+        let interface = interface_for_target(target, netns.as_ref()).await?;
+        let delay = NetemDelay::from_latency(&self.config)?;
+        let config = NetemConfig {
+            delay: Some(delay.clone()),
+            ..Default::default()
+        };
 
         info!(
-            "Injecting network latency on {}: mean={}ms, jitter={}ms",
-            interface, mean_ms, jitter_ms
+            "Injecting network latency on {}: {}",
+            interface,
+            config.to_args().join(" ")
         );
 
         // Use tc (traffic control) with netem
-        let output = Command::new("tc")
-            .args(&[
-                "qdisc",
-                "add",
-                "dev",
-                &interface,
-                "root",
-                "netem",
-                "delay",
-                &format!("{}ms", mean_ms),
-                &format!("{}ms", jitter_ms),
-                &format!("{}%", correlation),
-                "distribution",
-                "normal",
-            ])
-            .output()
-            .await
-            .map_err(|e| ChaosError::InjectionFailed(format!("Failed to run tc: {}", e)))?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(ChaosError::InjectionFailed(format!(
-                "tc command failed: {}",
-                stderr
-            )));
-        }
+        let attachment =
+            NetemAttachment::install(&interface, target, self.config.protocol, &config, netns)
+                .await?;
 
-        let metadata = serde_json::json!({
-            "interface": interface,
-            "mean_ms": mean_ms,
-            "jitter_ms": jitter_ms,
-            "distribution": "normal"
-        });
+        let metadata = attachment.metadata(serde_json::json!({
+            "mean_ms": delay.time.as_millis(),
+            "jitter_ms": delay.jitter.as_millis(),
+            "distribution": delay.distribution,
+        }));
 
         Ok(InjectionHandle::new(
             "network_latency",
@@ -281,40 +377,17 @@ impl NetworkLatencyInjector {
         ))
     }
 
+    #[cfg(not(target_os = "linux"))]
     #[allow(dead_code)]
-    async fn get_interface_for_target(&self, target: &Target) -> Result<String> {
-        match target {
-            Target::Network { address: _ } => {
-                // Simplified: use default interface
-                // In production, resolve actual interface for the route to address
-                Ok("eth0".to_string())
-            }
-            _ => Ok("eth0".to_string()),
-        }
+    async fn get_interface_for_target(&self, _target: &Target) -> Result<String> {
+        Ok("eth0".to_string())
     }
 
     #[cfg(target_os = "linux")]
     async fn remove_linux(&self, handle: &InjectionHandle) -> Result<()> {
-        let interface = handle
-            .metadata
-            .get("interface")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| ChaosError::CleanupFailed("Missing interface metadata".to_string()))?;
-
-        info!("Removing network latency from {}", interface);
-
-        let output = Command::new("tc")
-            .args(&["qdisc", "del", "dev", interface, "root"])
-            .output()
-            .await
-            .map_err(|e| ChaosError::CleanupFailed(format!("Failed to run tc: {}", e)))?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            info!("tc cleanup note (may be already removed): {}", stderr);
-        }
-
-        Ok(())
+        let attachment = NetemAttachment::from_metadata(&handle.metadata)?;
+        info!("Removing network latency from {}", attachment.interface);
+        attachment.remove().await
     }
 
     #[cfg(target_os = "windows")]
@@ -398,12 +471,31 @@ impl Injector for NetworkLatencyInjector {
     }
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NetworkLatencyParams {
+    #[serde(default, alias = "mean", deserialize_with = "params::opt_duration")]
+    delay: Option<Duration>,
+    #[serde(default, deserialize_with = "params::opt_duration")]
+    jitter: Option<Duration>,
+    #[serde(default)]
+    distribution: Option<LatencyDistribution>,
+    #[serde(default)]
+    correlation: Option<f64>,
+    #[serde(default)]
+    protocol: Option<IpProtocol>,
+    #[serde(default)]
+    netns: Option<String>,
+}
+
 #[derive(Default)]
 pub struct NetworkLatencyBuilder {
     mean: Option<Duration>,
     jitter: Option<Duration>,
     distribution: Option<LatencyDistribution>,
     correlation: Option<f64>,
+    protocol: Option<IpProtocol>,
+    netns: Option<NetNamespace>,
 }
 
 impl NetworkLatencyBuilder {
@@ -427,6 +519,18 @@ impl NetworkLatencyBuilder {
         self
     }
 
+    /// Only delay this transport protocol for network targets
+    pub fn protocol(mut self, protocol: IpProtocol) -> Self {
+        self.protocol = Some(protocol);
+        self
+    }
+
+    /// Shape inside this namespace instead of the target's own
+    pub fn netns(mut self, netns: NetNamespace) -> Self {
+        self.netns = Some(netns);
+        self
+    }
+
     pub fn build(self) -> NetworkLatencyInjector {
         let mean = self.mean.unwrap_or(Duration::from_millis(100));
         let jitter = self.jitter.unwrap_or(Duration::from_millis(20));
@@ -442,6 +546,8 @@ impl NetworkLatencyBuilder {
                     std_dev: jitter_ms,
                 }),
                 correlation: self.correlation.unwrap_or(0.0),
+                protocol: self.protocol,
+                netns: self.netns,
             },
         }
     }
@@ -452,6 +558,12 @@ impl NetworkLatencyBuilder {
 pub struct PacketLossConfig {
     pub rate: f64, // 0.0 - 1.0
     pub correlation: f64,
+    /// Only drop this protocol for network targets
+    #[serde(default)]
+    pub protocol: Option<IpProtocol>,
+    /// Namespace to shape in instead of the target's own
+    #[serde(default)]
+    pub netns: Option<NetNamespace>,
 }
 
 impl Default for PacketLossConfig {
@@ -459,21 +571,29 @@ impl Default for PacketLossConfig {
         Self {
             rate: 0.01, // 1% loss
             correlation: 0.0,
+            protocol: None,
+            netns: None,
         }
     }
 }
 
+#[derive(Default)]
 pub struct PacketLossInjector {
     #[allow(dead_code)]
     config: PacketLossConfig,
 }
 
-impl Default for PacketLossInjector {
-    fn default() -> Self {
-        Self {
-            config: PacketLossConfig::default(),
-        }
-    }
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PacketLossParams {
+    #[serde(default, alias = "loss_rate")]
+    rate: Option<f64>,
+    #[serde(default)]
+    correlation: Option<f64>,
+    #[serde(default)]
+    protocol: Option<IpProtocol>,
+    #[serde(default)]
+    netns: Option<String>,
 }
 
 impl PacketLossInjector {
@@ -481,50 +601,52 @@ impl PacketLossInjector {
         Self {
             config: PacketLossConfig {
                 rate,
-                correlation: 0.0,
+                ..Default::default()
             },
         }
     }
 
+    pub fn from_params(params: &InjectorParams) -> Result<Self> {
+        let params: PacketLossParams = params::parse_params("packet_loss", params)?;
+        let defaults = PacketLossConfig::default();
+        Ok(Self {
+            config: PacketLossConfig {
+                rate: params.rate.unwrap_or(defaults.rate).clamp(0.0, 1.0),
+                correlation: params
+                    .correlation
+                    .unwrap_or(defaults.correlation)
+                    .clamp(0.0, 1.0),
+                protocol: params.protocol,
+                netns: params.netns.map(|netns| NetNamespace::parse(&netns)),
+            },
+        })
+    }
+
     #[cfg(target_os = "linux")]
     async fn inject_linux(&self, target: &Target) -> Result<InjectionHandle> {
-        let interface = self.get_interface_for_target(target).await?;
-        let loss_percent = (self.config.rate * 100.0) as u32;
-        let correlation = (self.config.correlation * 100.0) as u32;
+        let netns = resolve_netns(self.config.netns.as_ref(), target).await?;
+        let interface = interface_for_target(target, netns.as_ref()).await?;
+        let config = NetemConfig {
+            loss: Some(LossModel::Random {
+                rate: self.config.rate,
+                correlation: self.config.correlation,
+            }),
+            ..Default::default()
+        };
 
         info!(
             "Injecting packet loss on {}: rate={}%",
-            interface, loss_percent
+            interface,
+            self.config.rate * 100.0
         );
 
-        let output = Command::new("tc")
-            .args(&[
-                "qdisc",
-                "add",
-                "dev",
-                &interface,
-                "root",
-                "netem",
-                "loss",
-                &format!("{}%", loss_percent),
-                &format!("{}%", correlation),
-            ])
-            .output()
-            .await
-            .map_err(|e| ChaosError::InjectionFailed(format!("Failed to run tc: {}", e)))?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(ChaosError::InjectionFailed(format!(
-                "tc command failed: {}",
-                stderr
-            )));
-        }
+        let attachment =
+            NetemAttachment::install(&interface, target, self.config.protocol, &config, netns)
+                .await?;
 
-        let metadata = serde_json::json!({
-            "interface": interface,
-            "loss_percent": loss_percent,
-        });
+        let metadata = attachment.metadata(serde_json::json!({
+            "loss_percent": self.config.rate * 100.0,
+        }));
 
         Ok(InjectionHandle::new(
             "packet_loss",
@@ -601,11 +723,6 @@ impl PacketLossInjector {
             "Packet loss injection not supported on this platform".to_string(),
         ))
     }
-
-    #[allow(dead_code)]
-    async fn get_interface_for_target(&self, _target: &Target) -> Result<String> {
-        Ok("eth0".to_string())
-    }
 }
 
 #[async_trait]
@@ -617,25 +734,9 @@ impl Injector for PacketLossInjector {
     async fn remove(&self, _handle: InjectionHandle) -> Result<()> {
         #[cfg(target_os = "linux")]
         {
-            let interface = _handle
-                .metadata
-                .get("interface")
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| {
-                    ChaosError::CleanupFailed("Missing interface metadata".to_string())
-                })?;
-
-            info!("Removing packet loss from {}", interface);
-
-            let output = Command::new("tc")
-                .args(&["qdisc", "del", "dev", interface, "root"])
-                .output()
-                .await
-                .map_err(|e| ChaosError::CleanupFailed(format!("Failed to run tc: {}", e)))?;
-
-            if !output.status.success() {
-                info!("tc cleanup note (may be already removed)");
-            }
+            let attachment = NetemAttachment::from_metadata(&_handle.metadata)?;
+            info!("Removing packet loss from {}", attachment.interface);
+            attachment.remove().await?;
         }
 
         #[cfg(target_os = "windows")]
@@ -675,19 +776,161 @@ impl Injector for PacketLossInjector {
 // TCP Reset Injector
 #[derive(Debug, Clone)]
 pub struct TcpResetInjector {
-    #[allow(dead_code)]
+    /// Probability per matching packet that it is answered with a reset
     rate: f64,
+    direction: TrafficDirection,
+    /// Only reset packets on our side of this network, `addr` or `addr/prefix`
+    local_address: Option<String>,
+    local_port: Option<u16>,
+    /// Detected from what is installed when unset
+    backend: Option<FirewallBackend>,
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    netns: Option<NetNamespace>,
 }
 
 impl Default for TcpResetInjector {
     fn default() -> Self {
-        Self { rate: 0.1 }
+        Self::new(0.1)
     }
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TcpResetParams {
+    #[serde(default)]
+    rate: Option<f64>,
+    #[serde(default)]
+    direction: Option<TrafficDirection>,
+    #[serde(default)]
+    local_address: Option<String>,
+    #[serde(default)]
+    local_port: Option<u16>,
+    #[serde(default)]
+    backend: Option<FirewallBackend>,
+    #[serde(default)]
+    netns: Option<String>,
+}
+
 impl TcpResetInjector {
     pub fn new(rate: f64) -> Self {
-        Self { rate }
+        Self {
+            rate,
+            direction: TrafficDirection::default(),
+            local_address: None,
+            local_port: None,
+            backend: None,
+            netns: None,
+        }
+    }
+
+    pub fn with_direction(mut self, direction: TrafficDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn with_local_address(mut self, cidr: impl Into<String>) -> Self {
+        self.local_address = Some(cidr.into());
+        self
+    }
+
+    pub fn with_local_port(mut self, port: u16) -> Self {
+        self.local_port = Some(port);
+        self
+    }
+
+    pub fn with_backend(mut self, backend: FirewallBackend) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Install the rule inside this namespace instead of the host's
+    pub fn with_netns(mut self, netns: NetNamespace) -> Self {
+        self.netns = Some(netns);
+        self
+    }
+
+    pub fn from_params(params: &InjectorParams) -> Result<Self> {
+        let params: TcpResetParams = params::parse_params("tcp_reset", params)?;
+        let mut injector = params.rate.map(Self::new).unwrap_or_default();
+        if let Some(direction) = params.direction {
+            injector = injector.with_direction(direction);
+        }
+        if let Some(cidr) = params.local_address {
+            injector = injector.with_local_address(cidr);
+        }
+        if let Some(port) = params.local_port {
+            injector = injector.with_local_port(port);
+        }
+        if let Some(backend) = params.backend {
+            injector = injector.with_backend(backend);
+        }
+        if let Some(netns) = params.netns {
+            injector = injector.with_netns(NetNamespace::parse(&netns));
+        }
+        injector.validate_config()?;
+        Ok(injector)
+    }
+
+    fn validate_config(&self) -> Result<()> {
+        if !(self.rate > 0.0 && self.rate <= 1.0) {
+            return Err(ChaosError::InvalidConfig(format!(
+                "tcp_reset rate must be above 0.0 and at most 1.0, got {}",
+                self.rate
+            )));
+        }
+        if let Some(cidr) = &self.local_address {
+            if firewall::parse_cidr(cidr).is_none() {
+                return Err(ChaosError::InvalidConfig(format!(
+                    "Invalid tcp_reset local_address '{}': expected e.g. '10.0.0.0/24'",
+                    cidr
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// One rule per direction for TCP packets exchanged with `peer`: to it
+    /// when outbound, from it when inbound. An unspecified peer address or
+    /// port matches any.
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    fn packet_matches(&self, peer: SocketAddr) -> Result<Vec<FirewallRule>> {
+        let local = self.local_address.as_deref().and_then(firewall::parse_cidr);
+        if local.is_some_and(|local| local.is_ipv4() != peer.is_ipv4()) {
+            return Err(ChaosError::InvalidConfig(format!(
+                "tcp_reset local_address {} and target {} are different IP versions",
+                self.local_address.as_deref().unwrap_or_default(),
+                peer
+            )));
+        }
+        let peer_address = (!peer.ip().is_unspecified()).then(|| peer.ip().to_string());
+        let peer_port = (peer.port() != 0).then_some(peer.port());
+        let rule = |outbound: bool| {
+            let ours = (self.local_address.clone(), self.local_port);
+            let theirs = (peer_address.clone(), peer_port);
+            let ((source, source_port), (destination, destination_port)) = if outbound {
+                (ours, theirs)
+            } else {
+                (theirs, ours)
+            };
+            FirewallRule {
+                tcp: true,
+                source,
+                source_port,
+                destination,
+                destination_port,
+                probability: self.rate,
+                ..FirewallRule::new(peer.is_ipv6(), RuleAction::Reset)
+            }
+        };
+        let directions = [
+            (true, self.direction.outbound()),
+            (false, self.direction.inbound()),
+        ];
+        Ok(directions
+            .into_iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(outbound, _)| rule(outbound))
+            .collect())
     }
 
     #[cfg(target_os = "linux")]
@@ -697,40 +940,26 @@ impl TcpResetInjector {
                 "TCP reset requires Network target".to_string(),
             ));
         };
+        self.validate_config()?;
+        let matches = self.packet_matches(*address)?;
 
-        info!("Injecting TCP resets for {}", address);
-
-        // Use iptables to inject RST packets
-        let port = address.port();
-        let output = Command::new("iptables")
-            .args(&[
-                "-A",
-                "OUTPUT",
-                "-p",
-                "tcp",
-                "--dport",
-                &port.to_string(),
-                "-j",
-                "REJECT",
-                "--reject-with",
-                "tcp-reset",
-            ])
-            .output()
-            .await
-            .map_err(|e| ChaosError::InjectionFailed(format!("Failed to run iptables: {}", e)))?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(ChaosError::InjectionFailed(format!(
-                "iptables command failed: {}",
-                stderr
-            )));
-        }
+        info!(
+            "Injecting TCP resets for {} ({:?}, rate {})",
+            address, self.direction, self.rate
+        );
 
-        let metadata = serde_json::json!({
-            "port": port,
+        let backend = match self.backend {
+            Some(backend) => backend,
+            None => FirewallBackend::detect(self.netns.as_ref()).await?,
+        };
+        let rules =
+            FirewallRules::install(RuleSet::TcpReset, backend, &matches, self.netns.clone())
+                .await?;
+        let metadata = rules.metadata(serde_json::json!({
+            "port": address.port(),
             "address": address.to_string(),
-        });
+            "rate": self.rate,
+        }));
 
         Ok(InjectionHandle::new("tcp_reset", target.clone(), metadata))
     }
@@ -804,34 +1033,9 @@ impl Injector for TcpResetInjector {
     async fn remove(&self, _handle: InjectionHandle) -> Result<()> {
         #[cfg(target_os = "linux")]
         {
-            let port = _handle
-                .metadata
-                .get("port")
-                .and_then(|v| v.as_u64())
-                .ok_or_else(|| ChaosError::CleanupFailed("Missing port metadata".to_string()))?;
-
-            info!("Removing TCP reset rule for port {}", port);
-
-            let output = Command::new("iptables")
-                .args(&[
-                    "-D",
-                    "OUTPUT",
-                    "-p",
-                    "tcp",
-                    "--dport",
-                    &port.to_string(),
-                    "-j",
-                    "REJECT",
-                    "--reject-with",
-                    "tcp-reset",
-                ])
-                .output()
-                .await
-                .map_err(|e| ChaosError::CleanupFailed(format!("Failed to run iptables: {}", e)))?;
-
-            if !output.status.success() {
-                info!("iptables cleanup note (may be already removed)");
-            }
+            FirewallRules::from_metadata(&_handle.metadata)?
+                .remove()
+                .await?;
         }
 
         #[cfg(target_os = "windows")]
@@ -856,6 +1060,10 @@ impl Injector for TcpResetInjector {
         "tcp_reset"
     }
 
+    async fn validate(&self) -> Result<()> {
+        self.validate_config()
+    }
+
     fn required_capabilities(&self) -> Vec<String> {
         vec!["CAP_NET_ADMIN".to_string()]
     }
@@ -878,6 +1086,25 @@ mod tests {
         };
         let sample = dist.sample(&mut rng);
         assert!(sample >= 0.0);
+
+        for invalid in [
+            LatencyDistribution::Uniform { min: 5.0, max: 1.0 },
+            LatencyDistribution::Exponential { lambda: 0.0 },
+            LatencyDistribution::Normal {
+                mean: f64::NAN,
+                std_dev: 1.0,
+            },
+            LatencyDistribution::Fixed {
+                value: f64::INFINITY,
+            },
+        ] {
+            assert!(invalid.validate().is_err(), "{:?}", invalid);
+            let sample = invalid.sample(&mut rng);
+            assert!((0.0..=MAX_LATENCY_MS).contains(&sample), "{:?}", invalid);
+        }
+        assert!(LatencyDistribution::Exponential { lambda: 0.1 }
+            .validate()
+            .is_ok());
     }
 
     #[test]
@@ -890,4 +1117,67 @@ mod tests {
         assert_eq!(injector.config.mean, Duration::from_millis(50));
         assert_eq!(injector.config.jitter, Duration::from_millis(10));
     }
+
+    #[test]
+    fn test_network_latency_from_params() {
+        let params: InjectorParams =
+            serde_json::from_value(serde_json::json!({"delay": "20ms", "jitter": "5ms"})).unwrap();
+        let injector = NetworkLatencyInjector::from_params(&params).unwrap();
+
+        assert_eq!(injector.config.mean, Duration::from_millis(20));
+        assert_eq!(injector.config.jitter, Duration::from_millis(5));
+
+        let params: InjectorParams =
+            serde_json::from_value(serde_json::json!({"delay": 20})).unwrap();
+        assert!(NetworkLatencyInjector::from_params(&params).is_err());
+    }
+
+    #[test]
+    fn test_packet_loss_from_params() {
+        let params: InjectorParams =
+            serde_json::from_value(serde_json::json!({"loss_rate": 0.2})).unwrap();
+        let injector = PacketLossInjector::from_params(&params).unwrap();
+        assert_eq!(injector.config.rate, 0.2);
+
+        let params: InjectorParams =
+            serde_json::from_value(serde_json::json!({"loss": 0.2})).unwrap();
+        let err = PacketLossInjector::from_params(&params).err().unwrap();
+        assert!(err.to_string().contains("unknown field `loss`"));
+    }
+
+    #[test]
+    fn test_tcp_reset_from_params() {
+        let params: InjectorParams = serde_json::from_value(serde_json::json!({
+            "rate": 0.3,
+            "direction": "both",
+            "local_address": "10.1.0.0/16",
+            "backend": "nftables",
+        }))
+        .unwrap();
+        let injector = TcpResetInjector::from_params(&params).unwrap();
+        assert_eq!(injector.backend, Some(FirewallBackend::Nftables));
+
+        let matches = injector
+            .packet_matches("10.0.0.5:5432".parse().unwrap())
+            .unwrap();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].source.as_deref(), Some("10.1.0.0/16"));
+        assert_eq!(matches[0].destination_port, Some(5432));
+        assert_eq!(matches[1].source.as_deref(), Some("10.0.0.5"));
+        assert_eq!(matches[1].destination.as_deref(), Some("10.1.0.0/16"));
+        assert!(matches.iter().all(|m| m.tcp && m.probability == 0.3));
+        assert!(injector
+            .packet_matches("[fd00::5]:5432".parse().unwrap())
+            .is_err());
+
+        for invalid in [
+            serde_json::json!({"rate": 0.0}),
+            serde_json::json!({"rate": 1.5}),
+            serde_json::json!({"local_address": "10.1.0.0/40"}),
+            serde_json::json!({"backend": "pf"}),
+        ] {
+            let params: InjectorParams = serde_json::from_value(invalid).unwrap();
+            assert!(TcpResetInjector::from_params(&params).is_err());
+        }
+    }
 }
@@ -0,0 +1,716 @@
+#[cfg(target_os = "linux")]
+use super::{interface_for_target, resolve_netns};
+use super::{IpProtocol, LatencyDistribution, NetemAttachment, NetworkLatencyConfig};
+use crate::{
+    error::*,
+    handle::InjectionHandle,
+    injectors::{params, Injector, InjectorParams},
+    netns::NetNamespace,
+    target::Target,
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[allow(unused_imports)] // Used in platform-specific code blocks
+use tracing::info;
+
+/// Delay distribution table shipped with iproute2
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetemDistribution {
+    /// netem's built-in jitter, used when no table is named
+    #[default]
+    Uniform,
+    Normal,
+    Pareto,
+    #[serde(alias = "paretonormal")]
+    ParetoNormal,
+}
+
+impl NetemDistribution {
+    fn table(self) -> Option<&'static str> {
+        match self {
+            NetemDistribution::Uniform => None,
+            NetemDistribution::Normal => Some("normal"),
+            NetemDistribution::Pareto => Some("pareto"),
+            NetemDistribution::ParetoNormal => Some("paretonormal"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetemDelay {
+    pub time: Duration,
+    pub jitter: Duration,
+    pub correlation: f64, // 0.0 - 1.0
+    pub distribution: NetemDistribution,
+}
+
+impl NetemDelay {
+    /// Translate a latency config into netem terms. The distribution's own
+    /// parameters (in milliseconds) take precedence over `mean`/`jitter`.
+    pub fn from_latency(config: &NetworkLatencyConfig) -> Result<Self> {
+        config.distribution.validate()?;
+        let ms = |value: f64| Duration::from_secs_f64(value.max(0.0) / 1000.0);
+        let (time, jitter, distribution) = match config.distribution {
+            LatencyDistribution::Normal { mean, std_dev } => {
+                (ms(mean), ms(std_dev), NetemDistribution::Normal)
+            }
+            LatencyDistribution::Uniform { min, max } => (
+                ms((min + max) / 2.0),
+                ms((max - min) / 2.0),
+                NetemDistribution::Uniform,
+            ),
+            LatencyDistribution::Fixed { value } => {
+                (ms(value), Duration::ZERO, NetemDistribution::Uniform)
+            }
+            LatencyDistribution::Exponential { .. } => {
+                return Err(ChaosError::InvalidConfig(
+                    "netem has no exponential delay table; use the netem injector with \
+                     distribution 'pareto' for a heavy tail"
+                        .to_string(),
+                ))
+            }
+        };
+
+        Ok(Self {
+            time,
+            jitter,
+            correlation: config.correlation,
+            distribution,
+        })
+    }
+}
+
+/// How netem decides which packets to drop
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "model", rename_all = "snake_case")]
+pub enum LossModel {
+    /// Independent drops with an optional correlation to the previous packet
+    Random {
+        rate: f64,
+        #[serde(default)]
+        correlation: f64,
+    },
+    /// Two-state Markov model producing bursts of loss
+    GilbertElliott {
+        /// Probability of moving from the good to the bad state
+        p: f64,
+        /// Probability of moving from the bad back to the good state
+        r: f64,
+        /// Loss probability in the bad state (`1-h`)
+        #[serde(default = "default_bad_loss")]
+        bad_loss: f64,
+        /// Loss probability in the good state (`1-k`)
+        #[serde(default)]
+        good_loss: f64,
+    },
+}
+
+fn default_bad_loss() -> f64 {
+    1.0
+}
+
+/// Probability of an impairment with an optional correlation, both 0.0 - 1.0
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Impairment {
+    pub rate: f64,
+    #[serde(default)]
+    pub correlation: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Reorder {
+    pub rate: f64,
+    #[serde(default)]
+    pub correlation: f64,
+    /// Reorder every Nth packet instead of at random
+    #[serde(default)]
+    pub gap: Option<u32>,
+}
+
+/// Every impairment applied by a single netem qdisc
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NetemConfig {
+    pub delay: Option<NetemDelay>,
+    pub loss: Option<LossModel>,
+    pub duplicate: Option<Impairment>,
+    pub corrupt: Option<Impairment>,
+    pub reorder: Option<Reorder>,
+    /// Bandwidth cap in tc units, e.g. `1mbit` or `500kbit`
+    pub rate: Option<String>,
+    /// Queue length in packets
+    pub limit: Option<u32>,
+}
+
+impl NetemConfig {
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |msg: String| Err(ChaosError::InvalidConfig(msg));
+        let mut ratios = Vec::new();
+
+        if let Some(delay) = &self.delay {
+            ratios.push(("delay correlation", delay.correlation));
+        }
+        match &self.loss {
+            Some(LossModel::Random { rate, correlation }) => {
+                ratios.push(("loss", *rate));
+                ratios.push(("loss correlation", *correlation));
+            }
+            Some(LossModel::GilbertElliott {
+                p,
+                r,
+                bad_loss,
+                good_loss,
+            }) => {
+                ratios.push(("gilbert_elliott p", *p));
+                ratios.push(("gilbert_elliott r", *r));
+                ratios.push(("gilbert_elliott bad_loss", *bad_loss));
+                ratios.push(("gilbert_elliott good_loss", *good_loss));
+            }
+            None => {}
+        }
+        for (name, impairment) in [("duplicate", self.duplicate), ("corrupt", self.corrupt)] {
+            if let Some(impairment) = impairment {
+                ratios.push((name, impairment.rate));
+                ratios.push((name, impairment.correlation));
+            }
+        }
+        if let Some(reorder) = &self.reorder {
+            ratios.push(("reorder", reorder.rate));
+            ratios.push(("reorder correlation", reorder.correlation));
+            if self.delay.as_ref().is_none_or(|d| d.time.is_zero()) {
+                return invalid("netem reorder only takes effect with a delay".to_string());
+            }
+        }
+
+        if let Some((name, value)) = ratios.iter().find(|(_, v)| !(0.0..=1.0).contains(v)) {
+            return invalid(format!(
+                "netem {} must be between 0.0 and 1.0, got {}",
+                name, value
+            ));
+        }
+        if let Some(rate) = &self.rate {
+            if parse_rate(rate).is_none() {
+                return invalid(format!(
+                    "Invalid netem rate '{}': expected e.g. '1mbit', '500kbit' or '10mbps'",
+                    rate
+                ));
+            }
+        }
+        if self.to_args().is_empty() {
+            return invalid("netem needs at least one impairment".to_string());
+        }
+        Ok(())
+    }
+
+    /// Arguments following `netem` on the tc command line
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+
+        if let Some(limit) = self.limit {
+            args.extend(["limit".to_string(), limit.to_string()]);
+        }
+        if let Some(delay) = &self.delay {
+            args.extend(["delay".to_string(), micros(delay.time)]);
+            if !delay.jitter.is_zero() {
+                args.push(micros(delay.jitter));
+                if delay.correlation > 0.0 {
+                    args.push(percent(delay.correlation));
+                }
+                if let Some(table) = delay.distribution.table() {
+                    args.extend(["distribution".to_string(), table.to_string()]);
+                }
+            }
+        }
+        match &self.loss {
+            Some(LossModel::Random { rate, correlation }) => {
+                args.extend(["loss".to_string(), "random".to_string(), percent(*rate)]);
+                if *correlation > 0.0 {
+                    args.push(percent(*correlation));
+                }
+            }
+            Some(LossModel::GilbertElliott {
+                p,
+                r,
+                bad_loss,
+                good_loss,
+            }) => {
+                args.extend(["loss".to_string(), "gemodel".to_string()]);
+                args.extend([*p, *r, *bad_loss, *good_loss].map(percent));
+            }
+            None => {}
+        }
+        for (name, impairment) in [("duplicate", self.duplicate), ("corrupt", self.corrupt)] {
+            if let Some(impairment) = impairment {
+                args.extend([name.to_string(), percent(impairment.rate)]);
+                if impairment.correlation > 0.0 {
+                    args.push(percent(impairment.correlation));
+                }
+            }
+        }
+        if let Some(reorder) = &self.reorder {
+            args.extend(["reorder".to_string(), percent(reorder.rate)]);
+            if reorder.correlation > 0.0 {
+                args.push(percent(reorder.correlation));
+            }
+            if let Some(gap) = reorder.gap {
+                args.extend(["gap".to_string(), gap.to_string()]);
+            }
+        }
+        if let Some(rate) = &self.rate {
+            args.extend(["rate".to_string(), rate.clone()]);
+        }
+
+        args
+    }
+}
+
+fn micros(duration: Duration) -> String {
+    format!("{}us", duration.as_micros())
+}
+
+/// Format a 0.0 - 1.0 ratio as a tc percentage without float noise
+fn percent(ratio: f64) -> String {
+    let formatted = format!("{:.4}", ratio * 100.0);
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+    format!("{}%", trimmed)
+}
+
+/// Bytes per second for a tc rate such as `1mbit` (bits) or `64kbps` (bytes)
+pub(crate) fn parse_rate(rate: &str) -> Option<u64> {
+    let split = rate
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(rate.len());
+    let (number, unit) = rate.split_at(split);
+    let number = number.parse::<f64>().ok().filter(|n| *n > 0.0)?;
+    let bits_per_unit = match unit.to_ascii_lowercase().as_str() {
+        "bit" => 1.0,
+        "kbit" => 1e3,
+        "mbit" => 1e6,
+        "gbit" => 1e9,
+        "tbit" => 1e12,
+        "bps" => 8.0,
+        "kbps" => 8e3,
+        "mbps" => 8e6,
+        "gbps" => 8e9,
+        "tbps" => 8e12,
+        _ => return None,
+    };
+    Some(((number * bits_per_unit / 8.0) as u64).max(1))
+}
+
+/// Applies delay, loss, duplication, corruption, reordering and a bandwidth
+/// cap as one netem qdisc, so they compose instead of conflicting
+pub struct NetemInjector {
+    config: NetemConfig,
+    interface: Option<String>,
+    protocol: Option<IpProtocol>,
+    netns: Option<NetNamespace>,
+}
+
+impl Default for NetemInjector {
+    fn default() -> Self {
+        Self::new(NetemConfig {
+            delay: Some(NetemDelay {
+                time: Duration::from_millis(100),
+                jitter: Duration::from_millis(20),
+                correlation: 0.0,
+                distribution: NetemDistribution::Normal,
+            }),
+            ..Default::default()
+        })
+    }
+}
+
+impl NetemInjector {
+    pub fn new(config: NetemConfig) -> Self {
+        Self {
+            config,
+            interface: None,
+            protocol: None,
+            netns: None,
+        }
+    }
+
+    pub fn builder() -> NetemBuilder {
+        NetemBuilder::default()
+    }
+
+    pub fn config(&self) -> &NetemConfig {
+        &self.config
+    }
+
+    pub fn from_params(params: &InjectorParams) -> Result<Self> {
+        if params.is_empty() {
+            return Ok(Self::default());
+        }
+
+        let params: NetemParams = params::parse_params("netem", params)?;
+        let mut builder = Self::builder();
+
+        if params.delay.is_some() || params.jitter.is_some() {
+            builder = builder.delay(NetemDelay {
+                time: params.delay.unwrap_or_default(),
+                jitter: params.jitter.unwrap_or_default(),
+                correlation: params.delay_correlation.unwrap_or(0.0),
+                distribution: params.distribution.unwrap_or_default(),
+            });
+        }
+        if let Some(loss) = params.loss {
+            builder = builder.loss(match loss {
+                LossParam::Rate(rate) => LossModel::Random {
+                    rate,
+                    correlation: params.loss_correlation.unwrap_or(0.0),
+                },
+                LossParam::Model(model) => model,
+            });
+        }
+        if let Some(rate) = params.duplicate {
+            builder = builder.duplicate(Impairment {
+                rate,
+                correlation: params.duplicate_correlation.unwrap_or(0.0),
+            });
+        }
+        if let Some(rate) = params.corrupt {
+            builder = builder.corrupt(Impairment {
+                rate,
+                correlation: params.corrupt_correlation.unwrap_or(0.0),
+            });
+        }
+        if let Some(rate) = params.reorder {
+            builder = builder.reorder(Reorder {
+                rate,
+                correlation: params.reorder_correlation.unwrap_or(0.0),
+                gap: params.reorder_gap,
+            });
+        }
+        if let Some(rate) = params.rate {
+            builder = builder.rate(rate);
+        }
+        if let Some(limit) = params.limit {
+            builder = builder.limit(limit);
+        }
+        if let Some(interface) = params.interface {
+            builder = builder.interface(interface);
+        }
+        if let Some(protocol) = params.protocol {
+            builder = builder.protocol(protocol);
+        }
+        if let Some(netns) = params.netns {
+            builder = builder.netns(NetNamespace::parse(&netns));
+        }
+
+        let injector = builder.build();
+        injector.config.validate()?;
+        Ok(injector)
+    }
+
+    /// Update the qdisc behind `handle` to this injector's config with
+    /// `tc qdisc change`, without flushing queued packets
+    pub async fn change(&self, handle: &InjectionHandle) -> Result<InjectionHandle> {
+        self.config.validate()?;
+        let attachment = NetemAttachment::from_metadata(&handle.metadata)?;
+
+        self.change_linux(&attachment).await?;
+
+        let mut updated = handle.clone();
+        updated.metadata = self.metadata(&attachment);
+        Ok(updated)
+    }
+
+    fn metadata(&self, attachment: &NetemAttachment) -> serde_json::Value {
+        attachment.metadata(serde_json::json!({
+            "netem": self.config.to_args().join(" "),
+        }))
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn inject_linux(&self, target: &Target) -> Result<InjectionHandle> {
+        self.config.validate()?;
+        let netns = resolve_netns(self.netns.as_ref(), target).await?;
+        let interface = match &self.interface {
+            Some(interface) => interface.clone(),
+            None => interface_for_target(target, netns.as_ref()).await?,
+        };
+
+        info!(
+            "Injecting netem on {}: {}",
+            interface,
+            self.config.to_args().join(" ")
+        );
+        let attachment =
+            NetemAttachment::install(&interface, target, self.protocol, &self.config, netns)
+                .await?;
+
+        Ok(InjectionHandle::new(
+            "netem",
+            target.clone(),
+            self.metadata(&attachment),
+        ))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    async fn inject_linux(&self, _target: &Target) -> Result<InjectionHandle> {
+        Err(ChaosError::SystemError(
+            "netem injection is only supported on Linux".to_string(),
+        ))
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn change_linux(&self, attachment: &NetemAttachment) -> Result<()> {
+        info!(
+            "Changing netem on {}: {}",
+            attachment.interface,
+            self.config.to_args().join(" ")
+        );
+        attachment.change(&self.config).await
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    async fn change_linux(&self, _attachment: &NetemAttachment) -> Result<()> {
+        Err(ChaosError::SystemError(
+            "netem injection is only supported on Linux".to_string(),
+        ))
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn remove_linux(&self, handle: &InjectionHandle) -> Result<()> {
+        let attachment = NetemAttachment::from_metadata(&handle.metadata)?;
+        info!("Removing netem from {}", attachment.interface);
+        attachment.remove().await
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    async fn remove_linux(&self, _handle: &InjectionHandle) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Injector for NetemInjector {
+    async fn inject(&self, target: &Target) -> Result<InjectionHandle> {
+        self.inject_linux(target).await
+    }
+
+    async fn remove(&self, handle: InjectionHandle) -> Result<()> {
+        self.remove_linux(&handle).await
+    }
+
+    fn name(&self) -> &str {
+        "netem"
+    }
+
+    async fn validate(&self) -> Result<()> {
+        self.config.validate()
+    }
+
+    fn required_capabilities(&self) -> Vec<String> {
+        vec!["CAP_NET_ADMIN".to_string()]
+    }
+}
+
+/// `loss` is either a plain ratio or a full loss model
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum LossParam {
+    Rate(f64),
+    Model(LossModel),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NetemParams {
+    #[serde(default)]
+    interface: Option<String>,
+    #[serde(default)]
+    protocol: Option<IpProtocol>,
+    #[serde(default)]
+    netns: Option<String>,
+    #[serde(default, deserialize_with = "params::opt_duration")]
+    delay: Option<Duration>,
+    #[serde(default, deserialize_with = "params::opt_duration")]
+    jitter: Option<Duration>,
+    #[serde(default)]
+    delay_correlation: Option<f64>,
+    #[serde(default)]
+    distribution: Option<NetemDistribution>,
+    #[serde(default)]
+    loss: Option<LossParam>,
+    #[serde(default)]
+    loss_correlation: Option<f64>,
+    #[serde(default)]
+    duplicate: Option<f64>,
+    #[serde(default)]
+    duplicate_correlation: Option<f64>,
+    #[serde(default)]
+    corrupt: Option<f64>,
+    #[serde(default)]
+    corrupt_correlation: Option<f64>,
+    #[serde(default)]
+    reorder: Option<f64>,
+    #[serde(default)]
+    reorder_correlation: Option<f64>,
+    #[serde(default)]
+    reorder_gap: Option<u32>,
+    #[serde(default)]
+    rate: Option<String>,
+    #[serde(default)]
+    limit: Option<u32>,
+}
+
+#[derive(Default)]
+pub struct NetemBuilder {
+    config: NetemConfig,
+    interface: Option<String>,
+    protocol: Option<IpProtocol>,
+    netns: Option<NetNamespace>,
+}
+
+impl NetemBuilder {
+    pub fn delay(mut self, delay: NetemDelay) -> Self {
+        self.config.delay = Some(delay);
+        self
+    }
+
+    pub fn loss(mut self, loss: LossModel) -> Self {
+        self.config.loss = Some(loss);
+        self
+    }
+
+    pub fn duplicate(mut self, duplicate: Impairment) -> Self {
+        self.config.duplicate = Some(duplicate);
+        self
+    }
+
+    pub fn corrupt(mut self, corrupt: Impairment) -> Self {
+        self.config.corrupt = Some(corrupt);
+        self
+    }
+
+    pub fn reorder(mut self, reorder: Reorder) -> Self {
+        self.config.reorder = Some(reorder);
+        self
+    }
+
+    pub fn rate(mut self, rate: impl Into<String>) -> Self {
+        self.config.rate = Some(rate.into());
+        self
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.config.limit = Some(limit);
+        self
+    }
+
+    /// Interface to shape instead of the one routing to the target
+    pub fn interface(mut self, interface: impl Into<String>) -> Self {
+        self.interface = Some(interface.into());
+        self
+    }
+
+    /// Only impair this transport protocol for network targets
+    pub fn protocol(mut self, protocol: IpProtocol) -> Self {
+        self.protocol = Some(protocol);
+        self
+    }
+
+    /// Shape inside this namespace instead of the target's own
+    pub fn netns(mut self, netns: NetNamespace) -> Self {
+        self.netns = Some(netns);
+        self
+    }
+
+    pub fn build(self) -> NetemInjector {
+        NetemInjector {
+            config: self.config,
+            interface: self.interface,
+            protocol: self.protocol,
+            netns: self.netns,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(json: serde_json::Value) -> InjectorParams {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_netem_args_compose() {
+        let injector = NetemInjector::from_params(&params(serde_json::json!({
+            "delay": "100ms",
+            "jitter": "20ms",
+            "delay_correlation": 0.25,
+            "distribution": "pareto",
+            "loss": 0.07,
+            "duplicate": 0.01,
+            "corrupt": 0.001,
+            "reorder": 0.25,
+            "reorder_gap": 5,
+            "rate": "1mbit",
+        })))
+        .unwrap();
+
+        assert_eq!(
+            injector.config().to_args().join(" "),
+            "delay 100000us 20000us 25% distribution pareto loss random 7% \
+             duplicate 1% corrupt 0.1% reorder 25% gap 5 rate 1mbit"
+        );
+    }
+
+    #[test]
+    fn test_gilbert_elliott_loss() {
+        let injector = NetemInjector::from_params(&params(serde_json::json!({
+            "loss": {"model": "gilbert_elliott", "p": 0.01, "r": 0.3},
+        })))
+        .unwrap();
+
+        assert_eq!(
+            injector.config().to_args().join(" "),
+            "loss gemodel 1% 30% 100% 0%"
+        );
+    }
+
+    #[test]
+    fn test_netem_validation() {
+        for invalid in [
+            serde_json::json!({"loss": 1.5}),
+            serde_json::json!({"reorder": 0.1}),
+            serde_json::json!({"rate": "fast"}),
+            serde_json::json!({"interface": "eth0"}),
+            serde_json::json!({"loss": {"model": "bursty", "p": 0.1}}),
+            serde_json::json!({"bandwidth": "1mbit"}),
+        ] {
+            assert!(
+                NetemInjector::from_params(&params(invalid.clone())).is_err(),
+                "{} should be rejected",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_delay_from_latency_distribution() {
+        let config = NetworkLatencyConfig {
+            distribution: LatencyDistribution::Uniform {
+                min: 50.0,
+                max: 150.0,
+            },
+            ..Default::default()
+        };
+        let delay = NetemDelay::from_latency(&config).unwrap();
+        assert_eq!(delay.time, Duration::from_millis(100));
+        assert_eq!(delay.jitter, Duration::from_millis(50));
+        assert_eq!(delay.distribution, NetemDistribution::Uniform);
+
+        let config = NetworkLatencyConfig {
+            distribution: LatencyDistribution::Exponential { lambda: 0.1 },
+            ..Default::default()
+        };
+        assert!(NetemDelay::from_latency(&config).is_err());
+    }
+}
@@ -0,0 +1,520 @@
+use super::firewall::{self, FirewallBackend, FirewallRule, RuleAction};
+#[cfg(target_os = "linux")]
+use super::firewall::{FirewallRules, RuleSet};
+use crate::{
+    error::*,
+    handle::InjectionHandle,
+    injectors::{params, Injector, InjectorParams},
+    netns::NetNamespace,
+    target::Target,
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::str::FromStr;
+#[cfg(target_os = "linux")]
+use tracing::info;
+
+/// One endpoint of a partition group: an `addr` or `addr/prefix` network,
+/// `pid:<pid>` or `container:<id>`. Processes and containers stand for the
+/// global addresses of their network namespace.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum PartitionMember {
+    Network(String),
+    Process(u32),
+    Container(String),
+}
+
+impl PartitionMember {
+    /// Process or container whose namespace the member's addresses live in
+    pub fn target(&self) -> Option<Target> {
+        match self {
+            PartitionMember::Network(_) => None,
+            PartitionMember::Process(pid) => Some(Target::process(*pid)),
+            PartitionMember::Container(id) => Some(Target::container(id.clone())),
+        }
+    }
+}
+
+impl FromStr for PartitionMember {
+    type Err = ChaosError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let member = if let Some(pid) = s.strip_prefix("pid:") {
+            pid.parse().ok().map(PartitionMember::Process)
+        } else if let Some(id) = s.strip_prefix("container:") {
+            (!id.is_empty()).then(|| PartitionMember::Container(id.to_string()))
+        } else {
+            firewall::parse_cidr(s).map(|_| PartitionMember::Network(s.to_string()))
+        };
+        member.ok_or_else(|| {
+            ChaosError::InvalidConfig(format!(
+                "Invalid partition member '{}': expected e.g. '10.0.1.0/24', 'pid:1234' or \
+                 'container:web'",
+                s
+            ))
+        })
+    }
+}
+
+impl TryFrom<String> for PartitionMember {
+    type Error = ChaosError;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<PartitionMember> for String {
+    fn from(member: PartitionMember) -> Self {
+        match member {
+            PartitionMember::Network(cidr) => cidr,
+            PartitionMember::Process(pid) => format!("pid:{}", pid),
+            PartitionMember::Container(id) => format!("container:{}", id),
+        }
+    }
+}
+
+/// Named set of endpoints on one side of a partition
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PartitionGroup {
+    pub name: String,
+    pub members: Vec<PartitionMember>,
+}
+
+impl PartitionGroup {
+    pub fn new(name: impl Into<String>, members: Vec<PartitionMember>) -> Self {
+        Self {
+            name: name.into(),
+            members,
+        }
+    }
+}
+
+/// Which traffic between groups is blackholed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PartitionDirection {
+    /// Every group is cut off from every other
+    #[default]
+    Both,
+    /// Only packets from a group to the groups listed after it, so later
+    /// groups still reach earlier ones but never hear back
+    OneWay,
+}
+
+/// Blackholes traffic between groups of endpoints with iptables or nftables
+/// DROP rules. Rules go into the target's namespace (the host's for a
+/// network target, or an explicit `netns`) and into the namespace of every
+/// process or container member, so the cut holds on both ends and for
+/// traffic routed through the host.
+#[derive(Debug, Clone)]
+pub struct PartitionInjector {
+    groups: Vec<PartitionGroup>,
+    direction: PartitionDirection,
+    /// Detected from what is installed when unset
+    backend: Option<FirewallBackend>,
+    netns: Option<NetNamespace>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PartitionParams {
+    groups: Vec<PartitionGroup>,
+    #[serde(default)]
+    direction: Option<PartitionDirection>,
+    #[serde(default)]
+    backend: Option<FirewallBackend>,
+    #[serde(default)]
+    netns: Option<String>,
+}
+
+impl PartitionInjector {
+    pub fn new(groups: Vec<PartitionGroup>) -> Self {
+        Self {
+            groups,
+            direction: PartitionDirection::default(),
+            backend: None,
+            netns: None,
+        }
+    }
+
+    pub fn with_direction(mut self, direction: PartitionDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn with_backend(mut self, backend: FirewallBackend) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Install the rules inside this namespace instead of the target's
+    pub fn with_netns(mut self, netns: NetNamespace) -> Self {
+        self.netns = Some(netns);
+        self
+    }
+
+    pub fn from_params(params: &InjectorParams) -> Result<Self> {
+        let params: PartitionParams = params::parse_params("partition", params)?;
+        let mut injector = Self::new(params.groups);
+        if let Some(direction) = params.direction {
+            injector = injector.with_direction(direction);
+        }
+        if let Some(backend) = params.backend {
+            injector = injector.with_backend(backend);
+        }
+        if let Some(netns) = params.netns {
+            injector = injector.with_netns(NetNamespace::parse(&netns));
+        }
+        injector.validate_config()?;
+        Ok(injector)
+    }
+
+    fn validate_config(&self) -> Result<()> {
+        if self.groups.len() < 2 {
+            return Err(ChaosError::InvalidConfig(
+                "partition needs at least two groups".to_string(),
+            ));
+        }
+        let mut names = HashSet::new();
+        for group in &self.groups {
+            if group.name.is_empty() || !names.insert(group.name.as_str()) {
+                return Err(ChaosError::InvalidConfig(format!(
+                    "partition group names must be unique and non-empty, got '{}'",
+                    group.name
+                )));
+            }
+            if group.members.is_empty() {
+                return Err(ChaosError::InvalidConfig(format!(
+                    "partition group '{}' has no members",
+                    group.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// DROP rules for every blocked pair of groups, given each group's
+    /// addresses or networks
+    fn drop_rules(&self, addresses: &[Vec<String>]) -> Vec<FirewallRule> {
+        let mut rules = Vec::new();
+        for (from, sources) in addresses.iter().enumerate() {
+            for (to, destinations) in addresses.iter().enumerate() {
+                let blocked = match self.direction {
+                    PartitionDirection::Both => from != to,
+                    PartitionDirection::OneWay => from < to,
+                };
+                if !blocked {
+                    continue;
+                }
+                for source in sources {
+                    for destination in destinations {
+                        let (Some(src), Some(dst)) = (
+                            firewall::parse_cidr(source),
+                            firewall::parse_cidr(destination),
+                        ) else {
+                            continue;
+                        };
+                        if src.is_ipv4() != dst.is_ipv4() {
+                            continue;
+                        }
+                        rules.push(FirewallRule {
+                            source: Some(source.clone()),
+                            destination: Some(destination.clone()),
+                            ..FirewallRule::new(src.is_ipv6(), RuleAction::Drop)
+                        });
+                    }
+                }
+            }
+        }
+        rules
+    }
+
+    /// Each group's networks, with process and container members replaced by
+    /// the global addresses of their namespace
+    #[cfg(target_os = "linux")]
+    async fn resolve_groups(&self) -> Result<Vec<Vec<String>>> {
+        let mut groups = Vec::new();
+        for group in &self.groups {
+            let mut addresses = Vec::new();
+            for member in &group.members {
+                let Some(target) = member.target() else {
+                    if let PartitionMember::Network(cidr) = member {
+                        addresses.push(cidr.clone());
+                    }
+                    continue;
+                };
+                let netns = NetNamespace::for_pid(target.pid().await?);
+                let output = crate::netns::command_in(Some(&netns), "ip")
+                    .args(["-o", "addr", "show", "scope", "global"])
+                    .output()
+                    .await?;
+                if !output.status.success() {
+                    return Err(ChaosError::InjectionFailed(format!(
+                        "Listing addresses of {} failed: {}",
+                        target.description(),
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    )));
+                }
+                let found = global_addresses(&String::from_utf8_lossy(&output.stdout));
+                if found.is_empty() {
+                    return Err(ChaosError::InjectionFailed(format!(
+                        "{} has no global addresses to partition",
+                        target.description()
+                    )));
+                }
+                addresses.extend(found.iter().map(IpAddr::to_string));
+            }
+            groups.push(addresses);
+        }
+        Ok(groups)
+    }
+
+    /// Namespaces to install the rules in: the vantage point first, then
+    /// every distinct process or container member's
+    #[cfg(target_os = "linux")]
+    async fn namespaces(&self, target: &Target) -> Result<Vec<Option<NetNamespace>>> {
+        let mut namespaces = vec![super::resolve_netns(self.netns.as_ref(), target).await?];
+        for member in self.groups.iter().flat_map(|group| &group.members) {
+            let Some(target) = member.target() else {
+                continue;
+            };
+            let netns = NetNamespace::for_target(&target).await?;
+            if !namespaces.contains(&netns) {
+                namespaces.push(netns);
+            }
+        }
+        Ok(namespaces)
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn inject_linux(&self, target: &Target) -> Result<InjectionHandle> {
+        self.validate_config()?;
+        let rules = self.drop_rules(&self.resolve_groups().await?);
+        if rules.is_empty() {
+            return Err(ChaosError::InvalidConfig(
+                "partition groups share no IP version, so there is nothing to block".to_string(),
+            ));
+        }
+
+        let names: Vec<_> = self
+            .groups
+            .iter()
+            .map(|group| group.name.as_str())
+            .collect();
+        info!(
+            "Partitioning {} ({:?}, {} rule(s))",
+            names.join(" | "),
+            self.direction,
+            rules.len()
+        );
+
+        let mut installed: Vec<FirewallRules> = Vec::new();
+        for netns in self.namespaces(target).await? {
+            let result = async {
+                let backend = match self.backend {
+                    Some(backend) => backend,
+                    None => FirewallBackend::detect(netns.as_ref()).await?,
+                };
+                FirewallRules::install(RuleSet::Partition, backend, &rules, netns).await
+            }
+            .await;
+            match result {
+                Ok(rules) => installed.push(rules),
+                Err(e) => {
+                    for rules in &installed {
+                        rules.remove().await?;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        let metadata = serde_json::json!({
+            "groups": names,
+            "direction": self.direction,
+            "rules": installed,
+        });
+        Ok(InjectionHandle::new("partition", target.clone(), metadata))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    async fn inject_linux(&self, _target: &Target) -> Result<InjectionHandle> {
+        Err(ChaosError::SystemError(
+            "Network partitions are only supported on Linux".to_string(),
+        ))
+    }
+}
+
+/// Addresses in `ip -o addr show` output
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn global_addresses(output: &str) -> Vec<IpAddr> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            fields.find(|field| *field == "inet" || *field == "inet6")?;
+            let cidr = fields.next()?;
+            cidr.split('/').next()?.parse().ok()
+        })
+        .collect()
+}
+
+#[async_trait]
+impl Injector for PartitionInjector {
+    async fn inject(&self, target: &Target) -> Result<InjectionHandle> {
+        self.inject_linux(target).await
+    }
+
+    async fn remove(&self, handle: InjectionHandle) -> Result<()> {
+        #[cfg(target_os = "linux")]
+        {
+            let installed = handle
+                .metadata
+                .get("rules")
+                .and_then(|rules| rules.as_array())
+                .ok_or_else(|| {
+                    ChaosError::CleanupFailed("Missing partition rule metadata".to_string())
+                })?;
+            let mut failure = None;
+            for rules in installed {
+                // Keep going so one vanished namespace does not strand the rest
+                if let Err(e) = async { FirewallRules::from_metadata(rules)?.remove().await }.await
+                {
+                    failure.get_or_insert(e);
+                }
+            }
+            info!("Healed partition");
+            failure.map_or(Ok(()), Err)
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = handle;
+            Ok(())
+        }
+    }
+
+    fn name(&self) -> &str {
+        "partition"
+    }
+
+    async fn validate(&self) -> Result<()> {
+        self.validate_config()
+    }
+
+    fn required_capabilities(&self) -> Vec<String> {
+        vec!["CAP_NET_ADMIN".to_string(), "CAP_SYS_ADMIN".to_string()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn injector(direction: PartitionDirection) -> PartitionInjector {
+        PartitionInjector::new(vec![
+            PartitionGroup::new("app", vec!["10.0.1.0/24".parse().unwrap()]),
+            PartitionGroup::new("db", vec!["10.0.2.5".parse().unwrap()]),
+            PartitionGroup::new("cache", vec!["10.0.3.0/24".parse().unwrap()]),
+        ])
+        .with_direction(direction)
+    }
+
+    #[test]
+    fn test_drop_rules() {
+        let groups = vec![
+            vec!["10.0.1.0/24".to_string(), "fd00::1".to_string()],
+            vec!["10.0.2.5".to_string()],
+            vec!["10.0.3.0/24".to_string(), "fd00::3".to_string()],
+        ];
+        let pairs = |rules: Vec<FirewallRule>| -> Vec<(String, String)> {
+            rules
+                .into_iter()
+                .inspect(|rule| assert_eq!(rule.action, RuleAction::Drop))
+                .map(|rule| (rule.source.unwrap(), rule.destination.unwrap()))
+                .collect()
+        };
+
+        let both = pairs(injector(PartitionDirection::Both).drop_rules(&groups));
+        assert_eq!(both.len(), 8);
+        assert!(both.contains(&("10.0.2.5".to_string(), "10.0.1.0/24".to_string())));
+        assert!(both.contains(&("fd00::3".to_string(), "fd00::1".to_string())));
+        assert!(!both
+            .iter()
+            .any(|(src, dst)| src.contains(':') != dst.contains(':')));
+
+        let one_way = pairs(injector(PartitionDirection::OneWay).drop_rules(&groups));
+        assert_eq!(
+            one_way,
+            [
+                ("10.0.1.0/24", "10.0.2.5"),
+                ("10.0.1.0/24", "10.0.3.0/24"),
+                ("fd00::1", "fd00::3"),
+                ("10.0.2.5", "10.0.3.0/24"),
+            ]
+            .map(|(src, dst)| (src.to_string(), dst.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_global_addresses() {
+        let output = "2: eth0    inet 172.17.0.2/16 brd 172.17.255.255 scope global eth0\\       valid_lft forever preferred_lft forever\n\
+            2: eth0    inet6 fd00::2/64 scope global \\       valid_lft forever preferred_lft forever\n";
+        assert_eq!(
+            global_addresses(output),
+            [
+                "172.17.0.2".parse::<IpAddr>().unwrap(),
+                "fd00::2".parse().unwrap()
+            ]
+        );
+        assert!(global_addresses("").is_empty());
+    }
+
+    #[test]
+    fn test_partition_from_params() {
+        let params: InjectorParams = serde_json::from_value(serde_json::json!({
+            "groups": [
+                {"name": "app", "members": ["10.0.1.0/24", "container:web"]},
+                {"name": "db", "members": ["pid:4242"]},
+            ],
+            "direction": "one_way",
+            "backend": "iptables",
+        }))
+        .unwrap();
+        let injector = PartitionInjector::from_params(&params).unwrap();
+        assert_eq!(injector.direction, PartitionDirection::OneWay);
+        assert_eq!(
+            injector.groups[0].members[1],
+            PartitionMember::Container("web".to_string())
+        );
+        assert_eq!(injector.groups[1].members, [PartitionMember::Process(4242)]);
+
+        for invalid in [
+            serde_json::json!({"groups": [{"name": "a", "members": ["10.0.0.1"]}]}),
+            serde_json::json!({"groups": [
+                {"name": "a", "members": ["10.0.0.1"]},
+                {"name": "a", "members": ["10.0.0.2"]},
+            ]}),
+            serde_json::json!({"groups": [
+                {"name": "a", "members": ["10.0.0.1"]},
+                {"name": "b", "members": []},
+            ]}),
+            serde_json::json!({"groups": [
+                {"name": "a", "members": ["10.0.0.1"]},
+                {"name": "b", "members": ["db.internal"]},
+            ]}),
+            serde_json::json!({"groups": [
+                {"name": "a", "members": ["pid:abc"]},
+                {"name": "b", "members": ["10.0.0.2"]},
+            ]}),
+        ] {
+            let params: InjectorParams = serde_json::from_value(invalid).unwrap();
+            assert!(PartitionInjector::from_params(&params).is_err());
+        }
+    }
+}
@@ -0,0 +1,554 @@
+use super::{parse_rate, LatencyDistribution};
+use crate::{
+    error::*,
+    handle::InjectionHandle,
+    injectors::{params, Injector, InjectorParams},
+    target::Target,
+};
+use async_trait::async_trait;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+const CHUNK_SIZE: usize = 16 * 1024;
+
+/// Faults applied to every connection forwarded by the proxy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TcpProxyConfig {
+    /// Address to accept connections on; port 0 picks a free one
+    pub listen: SocketAddr,
+    /// Delay before forwarding each chunk, in milliseconds
+    pub latency: Option<LatencyDistribution>,
+    /// Cap per connection and direction, in bytes per second
+    pub bandwidth: Option<u64>,
+    /// Probability per chunk that the connection is reset (RST)
+    pub reset_rate: f64,
+    /// Probability per connection that it is accepted but never forwarded
+    pub hang_rate: f64,
+    /// Delay before passing a close on to the other side
+    pub slow_close: Option<Duration>,
+    /// Probability per byte that one of its bits is flipped
+    pub corrupt_rate: f64,
+    /// Makes every random decision reproducible
+    pub seed: Option<u64>,
+}
+
+impl Default for TcpProxyConfig {
+    fn default() -> Self {
+        Self {
+            listen: SocketAddr::from(([127, 0, 0, 1], 0)),
+            latency: None,
+            bandwidth: None,
+            reset_rate: 0.0,
+            hang_rate: 0.0,
+            slow_close: None,
+            corrupt_rate: 0.0,
+            seed: None,
+        }
+    }
+}
+
+impl TcpProxyConfig {
+    pub fn validate(&self) -> Result<()> {
+        for (name, rate) in [
+            ("reset_rate", self.reset_rate),
+            ("hang_rate", self.hang_rate),
+            ("corrupt_rate", self.corrupt_rate),
+        ] {
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(ChaosError::InvalidConfig(format!(
+                    "tcp_proxy {} must be between 0.0 and 1.0, got {}",
+                    name, rate
+                )));
+            }
+        }
+        if let Some(latency) = &self.latency {
+            latency.validate()?;
+        }
+        if self.bandwidth == Some(0) {
+            return Err(ChaosError::InvalidConfig(
+                "tcp_proxy bandwidth must be positive".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Userspace TCP proxy that forwards to a network target while injecting
+/// faults. Needs no privileges: clients connect to the proxy instead.
+#[derive(Default)]
+pub struct TcpProxyInjector {
+    config: Arc<TcpProxyConfig>,
+    /// Stops the accept loop and every open connection of each proxy
+    proxies: Mutex<HashMap<String, CancellationToken>>,
+}
+
+impl TcpProxyInjector {
+    pub fn new(config: TcpProxyConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            proxies: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn builder() -> TcpProxyBuilder {
+        TcpProxyBuilder::default()
+    }
+
+    pub fn config(&self) -> &TcpProxyConfig {
+        &self.config
+    }
+
+    pub fn from_params(params: &InjectorParams) -> Result<Self> {
+        let params: TcpProxyParams = params::parse_params("tcp_proxy", params)?;
+        let mut builder = Self::builder();
+
+        if let Some(listen) = params.listen {
+            builder = builder.listen(listen);
+        }
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let latency = match (params.distribution, params.delay, params.jitter) {
+            (Some(distribution), _, _) => Some(distribution),
+            (None, Some(delay), Some(jitter)) => Some(LatencyDistribution::Normal {
+                mean: ms(delay),
+                std_dev: ms(jitter),
+            }),
+            (None, Some(delay), None) => Some(LatencyDistribution::Fixed { value: ms(delay) }),
+            (None, None, Some(_)) => {
+                return Err(ChaosError::InvalidConfig(
+                    "tcp_proxy jitter requires a delay".to_string(),
+                ))
+            }
+            (None, None, None) => None,
+        };
+        if let Some(latency) = latency {
+            builder = builder.latency(latency);
+        }
+        if let Some(bandwidth) = params.bandwidth {
+            let bytes = parse_rate(&bandwidth).ok_or_else(|| {
+                ChaosError::InvalidConfig(format!(
+                    "Invalid tcp_proxy bandwidth '{}': expected e.g. '1mbit' or '64kbps'",
+                    bandwidth
+                ))
+            })?;
+            builder = builder.bandwidth(bytes);
+        }
+        if let Some(rate) = params.reset_rate {
+            builder = builder.reset_rate(rate);
+        }
+        if let Some(rate) = params.hang_rate {
+            builder = builder.hang_rate(rate);
+        }
+        if let Some(delay) = params.slow_close {
+            builder = builder.slow_close(delay);
+        }
+        if let Some(rate) = params.corrupt_rate {
+            builder = builder.corrupt_rate(rate);
+        }
+        if let Some(seed) = params.seed {
+            builder = builder.seed(seed);
+        }
+
+        let injector = builder.build();
+        injector.config.validate()?;
+        Ok(injector)
+    }
+}
+
+#[async_trait]
+impl Injector for TcpProxyInjector {
+    async fn inject(&self, target: &Target) -> Result<InjectionHandle> {
+        let Target::Network { address: upstream } = target else {
+            return Err(ChaosError::InvalidConfig(
+                "TCP proxy requires Network target".to_string(),
+            ));
+        };
+        self.config.validate()?;
+
+        let listener = TcpListener::bind(self.config.listen).await.map_err(|e| {
+            ChaosError::InjectionFailed(format!(
+                "Failed to listen on {}: {}",
+                self.config.listen, e
+            ))
+        })?;
+        let listen = listener.local_addr()?;
+        info!("Proxying {} -> {} with faults", listen, upstream);
+
+        let cancel = CancellationToken::new();
+        let rng = self
+            .config
+            .seed
+            .map(StdRng::seed_from_u64)
+            .unwrap_or_else(StdRng::from_entropy);
+        tokio::spawn(accept_loop(
+            listener,
+            *upstream,
+            self.config.clone(),
+            rng,
+            cancel.clone(),
+        ));
+
+        let handle = InjectionHandle::new(
+            "tcp_proxy",
+            target.clone(),
+            serde_json::json!({
+                "listen": listen.to_string(),
+                "upstream": upstream.to_string(),
+            }),
+        );
+        self.proxies
+            .lock()
+            .unwrap()
+            .insert(handle.id.clone(), cancel);
+        Ok(handle)
+    }
+
+    async fn remove(&self, handle: InjectionHandle) -> Result<()> {
+        match self.proxies.lock().unwrap().remove(&handle.id) {
+            Some(cancel) => {
+                info!("Stopping TCP proxy {}", handle.metadata["listen"]);
+                cancel.cancel();
+            }
+            // The proxy only lives as long as the process that started it
+            None => info!("TCP proxy {} is not running", handle.id),
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "tcp_proxy"
+    }
+
+    async fn validate(&self) -> Result<()> {
+        self.config.validate()
+    }
+}
+
+async fn accept_loop(
+    listener: TcpListener,
+    upstream: SocketAddr,
+    config: Arc<TcpProxyConfig>,
+    mut rng: StdRng,
+    cancel: CancellationToken,
+) {
+    loop {
+        let client = tokio::select! {
+            _ = cancel.cancelled() => return,
+            accepted = listener.accept() => match accepted {
+                Ok((client, _)) => client,
+                Err(e) => {
+                    warn!("TCP proxy accept failed: {}", e);
+                    continue;
+                }
+            },
+        };
+
+        // Each connection draws from its own generator, seeded in accept
+        // order, so a seeded run replays the same faults
+        let conn_rng = StdRng::seed_from_u64(rng.gen());
+        let config = config.clone();
+        let cancel = cancel.child_token();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = cancel.cancelled() => {}
+                _ = proxy_connection(client, upstream, config, conn_rng) => {}
+            }
+        });
+    }
+}
+
+async fn proxy_connection(
+    mut client: TcpStream,
+    upstream: SocketAddr,
+    config: Arc<TcpProxyConfig>,
+    mut rng: StdRng,
+) {
+    if rng.gen_bool(config.hang_rate) {
+        // Half-open: swallow whatever the client sends and never answer
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        while matches!(client.read(&mut buf).await, Ok(n) if n > 0) {}
+        return;
+    }
+
+    let server = match TcpStream::connect(upstream).await {
+        Ok(server) => server,
+        Err(e) => {
+            warn!("TCP proxy could not reach {}: {}", upstream, e);
+            let _ = client.set_zero_linger();
+            return;
+        }
+    };
+
+    let (client_read, client_write) = client.into_split();
+    let (server_read, server_write) = server.into_split();
+    let reset = CancellationToken::new();
+    let upstream_rng = StdRng::seed_from_u64(rng.gen());
+    let downstream_rng = StdRng::seed_from_u64(rng.gen());
+
+    tokio::join!(
+        pipe(
+            client_read,
+            server_write,
+            &config,
+            upstream_rng,
+            reset.clone()
+        ),
+        pipe(
+            server_read,
+            client_write,
+            &config,
+            downstream_rng,
+            reset.clone()
+        ),
+    );
+}
+
+/// Forward one direction until EOF, an error or a reset of the connection
+async fn pipe(
+    mut from: OwnedReadHalf,
+    mut to: OwnedWriteHalf,
+    config: &TcpProxyConfig,
+    mut rng: StdRng,
+    reset: CancellationToken,
+) {
+    // Smaller chunks keep a bandwidth cap smooth
+    let chunk = config
+        .bandwidth
+        .map(|bps| (bps as usize / 10).clamp(1, CHUNK_SIZE))
+        .unwrap_or(CHUNK_SIZE);
+    let mut buf = vec![0u8; chunk];
+
+    loop {
+        let read = tokio::select! {
+            _ = reset.cancelled() => return,
+            read = from.read(&mut buf) => read,
+        };
+        let n = match read {
+            Ok(0) => {
+                if let Some(delay) = config.slow_close {
+                    tokio::time::sleep(delay).await;
+                }
+                let _ = to.shutdown().await;
+                return;
+            }
+            Ok(n) => n,
+            Err(_) => return,
+        };
+
+        if rng.gen_bool(config.reset_rate) {
+            // Dropping sockets with a zero linger sends RST instead of FIN
+            let _ = from.as_ref().set_zero_linger();
+            let _ = to.as_ref().set_zero_linger();
+            reset.cancel();
+            return;
+        }
+
+        corrupt(&mut buf[..n], config.corrupt_rate, &mut rng);
+
+        if let Some(latency) = &config.latency {
+            let delay_ms = latency.sample(&mut rng).max(0.0);
+            tokio::time::sleep(Duration::from_secs_f64(delay_ms / 1000.0)).await;
+        }
+
+        if to.write_all(&buf[..n]).await.is_err() {
+            return;
+        }
+
+        if let Some(bps) = config.bandwidth {
+            tokio::time::sleep(Duration::from_secs_f64(n as f64 / bps as f64)).await;
+        }
+    }
+}
+
+fn corrupt<R: Rng>(data: &mut [u8], rate: f64, rng: &mut R) {
+    if rate <= 0.0 {
+        return;
+    }
+    for byte in data {
+        if rng.gen_bool(rate) {
+            *byte ^= 1 << rng.gen_range(0..8);
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TcpProxyParams {
+    #[serde(default)]
+    listen: Option<SocketAddr>,
+    #[serde(default, deserialize_with = "params::opt_duration")]
+    delay: Option<Duration>,
+    #[serde(default, deserialize_with = "params::opt_duration")]
+    jitter: Option<Duration>,
+    #[serde(default)]
+    distribution: Option<LatencyDistribution>,
+    #[serde(default)]
+    bandwidth: Option<String>,
+    #[serde(default)]
+    reset_rate: Option<f64>,
+    #[serde(default)]
+    hang_rate: Option<f64>,
+    #[serde(default, deserialize_with = "params::opt_duration")]
+    slow_close: Option<Duration>,
+    #[serde(default)]
+    corrupt_rate: Option<f64>,
+    #[serde(default)]
+    seed: Option<u64>,
+}
+
+#[derive(Default)]
+pub struct TcpProxyBuilder {
+    config: TcpProxyConfig,
+}
+
+impl TcpProxyBuilder {
+    pub fn listen(mut self, listen: SocketAddr) -> Self {
+        self.config.listen = listen;
+        self
+    }
+
+    pub fn latency(mut self, latency: LatencyDistribution) -> Self {
+        self.config.latency = Some(latency);
+        self
+    }
+
+    /// Bytes per second per connection and direction
+    pub fn bandwidth(mut self, bytes_per_sec: u64) -> Self {
+        self.config.bandwidth = Some(bytes_per_sec);
+        self
+    }
+
+    pub fn reset_rate(mut self, rate: f64) -> Self {
+        self.config.reset_rate = rate;
+        self
+    }
+
+    pub fn hang_rate(mut self, rate: f64) -> Self {
+        self.config.hang_rate = rate;
+        self
+    }
+
+    pub fn slow_close(mut self, delay: Duration) -> Self {
+        self.config.slow_close = Some(delay);
+        self
+    }
+
+    pub fn corrupt_rate(mut self, rate: f64) -> Self {
+        self.config.corrupt_rate = rate;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.config.seed = Some(seed);
+        self
+    }
+
+    pub fn build(self) -> TcpProxyInjector {
+        TcpProxyInjector::new(self.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::Instant;
+
+    /// Upstream that echoes everything back
+    async fn echo_server() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let (mut read, mut write) = stream.split();
+                    let _ = tokio::io::copy(&mut read, &mut write).await;
+                });
+            }
+        });
+        address
+    }
+
+    async fn start(injector: &TcpProxyInjector) -> (InjectionHandle, TcpStream) {
+        let target = Target::network(echo_server().await);
+        let handle = injector.inject(&target).await.unwrap();
+        let listen: SocketAddr = handle.metadata["listen"].as_str().unwrap().parse().unwrap();
+        (handle, TcpStream::connect(listen).await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_proxy_forwards_with_latency() {
+        let injector = TcpProxyInjector::builder()
+            .latency(LatencyDistribution::Fixed { value: 50.0 })
+            .build();
+        let (handle, mut client) = start(&injector).await;
+
+        let started = Instant::now();
+        client.write_all(b"ping").await.unwrap();
+        let mut buf = [0u8; 4];
+        client.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+        // Delayed once on the way up and once on the way back
+        assert!(started.elapsed() >= Duration::from_millis(100));
+
+        injector.remove(handle).await.unwrap();
+        assert!(matches!(client.read(&mut buf).await, Ok(0) | Err(_)));
+    }
+
+    #[tokio::test]
+    async fn test_proxy_resets_and_corrupts() {
+        let injector = TcpProxyInjector::builder().reset_rate(1.0).build();
+        let (_handle, mut client) = start(&injector).await;
+        client.write_all(b"ping").await.unwrap();
+        let mut buf = [0u8; 4];
+        assert!(client.read_exact(&mut buf).await.is_err());
+
+        let injector = TcpProxyInjector::builder()
+            .corrupt_rate(1.0)
+            .seed(7)
+            .build();
+        let (_handle, mut client) = start(&injector).await;
+        client.write_all(b"ping").await.unwrap();
+        client.read_exact(&mut buf).await.unwrap();
+        assert_ne!(&buf, b"ping");
+    }
+
+    #[test]
+    fn test_tcp_proxy_from_params() {
+        let params: InjectorParams = serde_json::from_value(serde_json::json!({
+            "listen": "127.0.0.1:9000",
+            "delay": "20ms",
+            "jitter": "5ms",
+            "bandwidth": "1mbit",
+            "reset_rate": 0.01,
+            "slow_close": "2s",
+        }))
+        .unwrap();
+        let injector = TcpProxyInjector::from_params(&params).unwrap();
+        let config = injector.config();
+        assert_eq!(config.listen.port(), 9000);
+        assert_eq!(config.bandwidth, Some(125_000));
+        assert!(matches!(
+            config.latency,
+            Some(LatencyDistribution::Normal { mean, std_dev }) if mean == 20.0 && std_dev == 5.0
+        ));
+
+        for invalid in [
+            serde_json::json!({"reset_rate": 2.0}),
+            serde_json::json!({"bandwidth": "fast"}),
+            serde_json::json!({"jitter": "5ms"}),
+            serde_json::json!({"distribution": {"uniform": {"min": 5, "max": 1}}}),
+            serde_json::json!({"distribution": {"Exponential": {"lambda": 0.0}}}),
+        ] {
+            let params: InjectorParams = serde_json::from_value(invalid).unwrap();
+            assert!(TcpProxyInjector::from_params(&params).is_err());
+        }
+    }
+}
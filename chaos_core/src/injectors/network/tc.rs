@@ -0,0 +1,569 @@
+use super::NetemConfig;
+use crate::{error::*, netns::command_in, netns::NetNamespace, state, target::Target};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+/// Bands of the chaos prio qdisc. Bands 1-3 carry ordinary traffic through
+/// the default priomap; each filtered injection takes one of the rest.
+const PRIO_BANDS: u16 = 16;
+const FIRST_FAULT_BAND: u16 = 4;
+const PRIOMAP: [&str; 16] = [
+    "1", "2", "2", "2", "1", "2", "0", "0", "1", "1", "1", "1", "1", "1", "1", "1",
+];
+
+/// Serializes edits to the tc tree so concurrent injections pick distinct
+/// bands. Other chaos processes are kept out by an `flock` on
+/// [`TC_LOCK_FILE`] in the runtime directory.
+static TC_LOCK: Mutex<()> = Mutex::const_new(());
+const TC_LOCK_FILE: &str = "tc.lock";
+
+async fn lock_tc() -> Result<state::StateLock> {
+    state::lock(&TC_LOCK, TC_LOCK_FILE).await
+}
+
+/// Transport protocol a per-destination filter matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IpProtocol {
+    Tcp,
+    Udp,
+}
+
+impl IpProtocol {
+    fn number(self) -> u8 {
+        match self {
+            IpProtocol::Tcp => 6,
+            IpProtocol::Udp => 17,
+        }
+    }
+}
+
+/// Where a netem qdisc was installed: on a prio band reached only through
+/// filters for one destination, or on the interface root, optionally inside
+/// another network namespace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct NetemAttachment {
+    pub interface: String,
+    #[serde(default)]
+    pub band: Option<u16>,
+    #[serde(default)]
+    pub netns: Option<NetNamespace>,
+}
+
+impl NetemAttachment {
+    /// Read the attachment back from a handle's metadata
+    pub fn from_metadata(metadata: &serde_json::Value) -> Result<Self> {
+        serde_json::from_value(metadata.clone())
+            .map_err(|_| ChaosError::CleanupFailed("Missing interface metadata".to_string()))
+    }
+
+    /// Handle metadata recording this attachment alongside `extra`
+    pub fn metadata(&self, extra: serde_json::Value) -> serde_json::Value {
+        let mut metadata = serde_json::json!({ "interface": self.interface });
+        if let Some(band) = self.band {
+            metadata["band"] = band.into();
+        }
+        if let Some(netns) = &self.netns {
+            metadata["netns"] = netns.to_string().into();
+        }
+        if let (Some(metadata), serde_json::Value::Object(extra)) =
+            (metadata.as_object_mut(), extra)
+        {
+            metadata.extend(extra);
+        }
+        metadata
+    }
+
+    /// Install netem for `target`. Network targets only impair packets to or
+    /// from their address; anything else shapes the whole interface.
+    pub async fn install(
+        interface: &str,
+        target: &Target,
+        protocol: Option<IpProtocol>,
+        config: &NetemConfig,
+        netns: Option<NetNamespace>,
+    ) -> Result<Self> {
+        let _guard = lock_tc().await?;
+
+        let address = match target {
+            Target::Network { address } => *address,
+            _ => {
+                match &netns {
+                    Some(netns) => info!("Shaping all traffic on {} in {}", interface, netns),
+                    None => warn!(
+                        "{} has no address to filter on; netem will affect all traffic on {}",
+                        target.description(),
+                        interface
+                    ),
+                }
+                let attachment = Self {
+                    interface: interface.to_string(),
+                    band: None,
+                    netns,
+                };
+                attachment.netem("add", config).await?;
+                return Ok(attachment);
+            }
+        };
+
+        let qdiscs = tc_output(netns.as_ref(), &["qdisc", "show", "dev", interface]).await?;
+        let created_root = !has_chaos_root(&qdiscs);
+        if created_root {
+            let mut args = vec![
+                "qdisc".to_string(),
+                "add".to_string(),
+                "dev".to_string(),
+                interface.to_string(),
+                "root".to_string(),
+                "handle".to_string(),
+                "1:".to_string(),
+                "prio".to_string(),
+                "bands".to_string(),
+                PRIO_BANDS.to_string(),
+                "priomap".to_string(),
+            ];
+            args.extend(PRIOMAP.iter().map(|s| s.to_string()));
+            run_tc(netns.as_ref(), &args).await?;
+        }
+
+        // A band is taken by a netem child or by filters steering into it,
+        // so everything at the chosen band is ours to roll back
+        let filters = tc_output(
+            netns.as_ref(),
+            &["filter", "show", "dev", interface, "parent", "1:"],
+        )
+        .await?;
+        let mut used = used_bands(&qdiscs);
+        used.extend(filter_bands(&filters));
+        let band = (FIRST_FAULT_BAND..=PRIO_BANDS)
+            .find(|band| !used.contains(band))
+            .ok_or_else(|| {
+                ChaosError::InjectionFailed(format!(
+                    "All {} per-destination fault slots on {} are in use",
+                    PRIO_BANDS - FIRST_FAULT_BAND + 1,
+                    interface
+                ))
+            })?;
+
+        let attachment = Self {
+            interface: interface.to_string(),
+            band: Some(band),
+            netns,
+        };
+        let mut created = Created {
+            root: created_root,
+            ..Created::default()
+        };
+        if let Err(e) = attachment.netem("add", config).await {
+            attachment.roll_back(created).await;
+            return Err(e);
+        }
+        created.netem = true;
+
+        // Match both directions as seen on egress: requests to the address
+        // and responses sent from it when it is local
+        let family = if address.is_ipv4() { "ip" } else { "ipv6" };
+        for direction in [Direction::To, Direction::From] {
+            let mut args: Vec<String> = [
+                "filter", "add", "dev", interface, "parent", "1:", "protocol", family, "prio",
+            ]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+            args.extend([band.to_string(), "u32".to_string()]);
+            args.extend(u32_matches(address, protocol, direction));
+            args.extend(["flowid".to_string(), format!("1:{}", band)]);
+
+            // A failed add may still have created the filter's hash table
+            created.filters = Some(family);
+            if let Err(e) = run_tc(attachment.netns.as_ref(), &args).await {
+                attachment.roll_back(created).await;
+                return Err(e);
+            }
+        }
+
+        info!(
+            "Steering traffic for {} on {} through band 1:{}",
+            address, interface, band
+        );
+        Ok(attachment)
+    }
+
+    /// Replace the netem parameters in place with `tc qdisc change`
+    pub async fn change(&self, config: &NetemConfig) -> Result<()> {
+        let _guard = lock_tc().await?;
+        self.netem("change", config).await
+    }
+
+    /// Remove this netem and its filters, dropping the prio root once no
+    /// other injection uses it
+    pub async fn remove(&self) -> Result<()> {
+        let _guard = lock_tc().await?;
+        self.uninstall().await
+    }
+
+    async fn uninstall(&self) -> Result<()> {
+        let interface = self.interface.as_str();
+        let netns = self.netns.as_ref();
+        if let Some(netns) = netns.filter(|netns| !netns.exists()) {
+            // The qdiscs went away with the namespace
+            info!("{} no longer exists; nothing to remove", netns);
+            return Ok(());
+        }
+
+        let Some(band) = self.band else {
+            return tc_cleanup(netns, &["qdisc", "del", "dev", interface, "root"]).await;
+        };
+
+        let band = band.to_string();
+        let parent = format!("1:{}", band);
+        tc_cleanup(
+            netns,
+            &[
+                "filter", "del", "dev", interface, "parent", "1:", "prio", &band,
+            ],
+        )
+        .await?;
+        tc_cleanup(
+            netns,
+            &["qdisc", "del", "dev", interface, "parent", &parent],
+        )
+        .await?;
+
+        let qdiscs = tc_output(netns, &["qdisc", "show", "dev", interface])
+            .await
+            .unwrap_or_default();
+        let filters = tc_output(netns, &["filter", "show", "dev", interface, "parent", "1:"])
+            .await
+            .unwrap_or_default();
+        if has_chaos_root(&qdiscs)
+            && used_bands(&qdiscs).is_empty()
+            && filter_bands(&filters).is_empty()
+        {
+            tc_cleanup(netns, &["qdisc", "del", "dev", interface, "root"]).await?;
+        }
+        Ok(())
+    }
+
+    /// Undo a partial `install`, touching only what it created
+    async fn roll_back(&self, created: Created) {
+        let interface = self.interface.as_str();
+        let netns = self.netns.as_ref();
+        if let Some(band) = self.band {
+            let band = band.to_string();
+            if let Some(family) = created.filters {
+                let _ = tc_cleanup(
+                    netns,
+                    &[
+                        "filter", "del", "dev", interface, "parent", "1:", "protocol", family,
+                        "prio", &band,
+                    ],
+                )
+                .await;
+            }
+            if created.netem {
+                let parent = format!("1:{}", band);
+                let _ = tc_cleanup(
+                    netns,
+                    &["qdisc", "del", "dev", interface, "parent", &parent],
+                )
+                .await;
+            }
+        }
+        if created.root {
+            let _ = tc_cleanup(netns, &["qdisc", "del", "dev", interface, "root"]).await;
+        }
+    }
+
+    async fn netem(&self, action: &str, config: &NetemConfig) -> Result<()> {
+        let mut args: Vec<String> = ["qdisc", action, "dev", &self.interface]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        match self.band {
+            Some(band) => args.extend([
+                "parent".to_string(),
+                format!("1:{}", band),
+                "handle".to_string(),
+                format!("{:x}:", 0x100 + band),
+            ]),
+            None => args.push("root".to_string()),
+        }
+        args.push("netem".to_string());
+        args.extend(config.to_args());
+        run_tc(self.netns.as_ref(), &args).await
+    }
+}
+
+/// What an `install` has added so far
+#[derive(Debug, Clone, Copy, Default)]
+struct Created {
+    root: bool,
+    netem: bool,
+    /// Protocol family of the filters added at the band
+    filters: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    To,
+    From,
+}
+
+/// u32 selectors matching packets to or from `address`. An unspecified IP
+/// or port 0 matches any.
+fn u32_matches(
+    address: SocketAddr,
+    protocol: Option<IpProtocol>,
+    direction: Direction,
+) -> Vec<String> {
+    let (family, prefix) = match address.ip() {
+        IpAddr::V4(_) => ("ip", 32),
+        IpAddr::V6(_) => ("ip6", 128),
+    };
+    let (ip_field, port_field) = match direction {
+        Direction::To => ("dst", "dport"),
+        Direction::From => ("src", "sport"),
+    };
+
+    let mut args = Vec::new();
+    let mut selector = |fields: &[String]| {
+        args.extend(["match".to_string(), family.to_string()]);
+        args.extend_from_slice(fields);
+    };
+    if !address.ip().is_unspecified() {
+        selector(&[ip_field.to_string(), format!("{}/{}", address.ip(), prefix)]);
+    }
+    if address.port() != 0 {
+        selector(&[
+            port_field.to_string(),
+            address.port().to_string(),
+            "0xffff".to_string(),
+        ]);
+    }
+    if let Some(protocol) = protocol {
+        selector(&[
+            "protocol".to_string(),
+            protocol.number().to_string(),
+            "0xff".to_string(),
+        ]);
+    }
+
+    if args.is_empty() {
+        // Match everything of this family
+        args.extend(["match", "u32", "0", "0"].map(String::from));
+    }
+    args
+}
+
+fn has_chaos_root(qdiscs: &str) -> bool {
+    qdiscs
+        .lines()
+        .any(|line| line.starts_with("qdisc prio 1: root"))
+}
+
+/// Fault bands that currently have a netem child
+fn used_bands(qdiscs: &str) -> Vec<u16> {
+    qdiscs
+        .lines()
+        .filter(|line| line.starts_with("qdisc netem"))
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            words.find(|w| *w == "parent")?;
+            words.next()?.strip_prefix("1:")?.parse().ok()
+        })
+        .collect()
+}
+
+/// Bands that filters on the prio root steer into, from their `pref`
+fn filter_bands(filters: &str) -> Vec<u16> {
+    filters
+        .lines()
+        .filter(|line| line.starts_with("filter"))
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            words.find(|w| *w == "pref")?;
+            words.next()?.parse().ok()
+        })
+        .collect()
+}
+
+/// Device from `ip route get` output, e.g. `10.0.0.5 via 10.0.0.1 dev eth0 src ...`
+fn parse_route_device(output: &str) -> Option<String> {
+    let mut words = output.split_whitespace();
+    words.find(|w| *w == "dev")?;
+    words.next().map(str::to_string)
+}
+
+/// Device of the default route in `ip route show` output, or of the first
+/// route when there is none, as in an isolated test namespace
+fn default_route_device(routes: &str) -> Option<String> {
+    routes
+        .lines()
+        .find(|line| line.starts_with("default"))
+        .or_else(|| routes.lines().next())
+        .and_then(parse_route_device)
+}
+
+/// Interface traffic to the target leaves through: the route to a network
+/// target's address, otherwise the default route
+pub(crate) async fn interface_for_target(
+    target: &Target,
+    netns: Option<&NetNamespace>,
+) -> Result<String> {
+    let args = match target {
+        Target::Network { address } if !address.ip().is_unspecified() => {
+            vec![
+                "route".to_string(),
+                "get".to_string(),
+                address.ip().to_string(),
+            ]
+        }
+        _ => vec!["route".to_string(), "show".to_string()],
+    };
+
+    let output = command_in(netns, "ip")
+        .args(&args)
+        .output()
+        .await
+        .map_err(|e| ChaosError::InjectionFailed(format!("Failed to run ip: {}", e)))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let device = match target {
+        Target::Network { .. } => parse_route_device(&stdout),
+        _ => default_route_device(&stdout),
+    };
+    device.ok_or_else(|| {
+        ChaosError::InjectionFailed(format!(
+            "No route found for {}{} (ip {})",
+            target.description(),
+            netns.map(|n| format!(" in {}", n)).unwrap_or_default(),
+            args.join(" ")
+        ))
+    })
+}
+
+async fn run_tc(netns: Option<&NetNamespace>, args: &[String]) -> Result<()> {
+    let output = command_in(netns, "tc")
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| ChaosError::InjectionFailed(format!("Failed to run tc: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(ChaosError::InjectionFailed(format!(
+            "tc {} failed: {}",
+            args.join(" "),
+            stderr.trim()
+        )));
+    }
+    Ok(())
+}
+
+async fn tc_output(netns: Option<&NetNamespace>, args: &[&str]) -> Result<String> {
+    let output = command_in(netns, "tc")
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| ChaosError::InjectionFailed(format!("Failed to run tc: {}", e)))?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Run a teardown command, tolerating state that is already gone
+async fn tc_cleanup(netns: Option<&NetNamespace>, args: &[&str]) -> Result<()> {
+    let output = command_in(netns, "tc")
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| ChaosError::CleanupFailed(format!("Failed to run tc: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        info!(
+            "tc cleanup note (may be already removed): {}",
+            stderr.trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_u32_matches() {
+        let address: SocketAddr = "10.0.0.5:443".parse().unwrap();
+        assert_eq!(
+            u32_matches(address, Some(IpProtocol::Tcp), Direction::To).join(" "),
+            "match ip dst 10.0.0.5/32 match ip dport 443 0xffff match ip protocol 6 0xff"
+        );
+        assert_eq!(
+            u32_matches(address, None, Direction::From).join(" "),
+            "match ip src 10.0.0.5/32 match ip sport 443 0xffff"
+        );
+
+        let address: SocketAddr = "[::1]:0".parse().unwrap();
+        assert_eq!(
+            u32_matches(address, Some(IpProtocol::Udp), Direction::To).join(" "),
+            "match ip6 dst ::1/128 match ip6 protocol 17 0xff"
+        );
+    }
+
+    #[test]
+    fn test_parse_tc_state() {
+        let qdiscs =
+            "qdisc prio 1: root refcnt 2 bands 16 priomap 1 2 2 2 1 2 0 0 1 1 1 1 1 1 1 1\n\
+                      qdisc netem 104: parent 1:4 limit 1000 delay 100ms\n\
+                      qdisc netem 106: parent 1:6 limit 1000 loss 5%\n";
+        assert!(has_chaos_root(qdiscs));
+        assert_eq!(used_bands(qdiscs), vec![4, 6]);
+        let filters = "filter parent 1: protocol ip pref 5 u32 chain 0 \n\
+                       filter parent 1: protocol ip pref 5 u32 chain 0 fh 800: ht divisor 1 \n\
+                       filter parent 1: protocol ip pref 5 u32 chain 0 fh 800::800 order 2048 key ht 800 bkt 0 flowid 1:5 \n\
+                       \x20 match 0a000005/ffffffff at 16\n";
+        assert_eq!(filter_bands(filters), vec![5, 5, 5]);
+        assert!(!has_chaos_root("qdisc noqueue 0: root refcnt 2"));
+
+        assert_eq!(
+            parse_route_device("10.0.0.5 via 192.168.1.1 dev wlan0 src 192.168.1.20 uid 0"),
+            Some("wlan0".to_string())
+        );
+        assert_eq!(
+            parse_route_device("local 127.0.0.1 dev lo table local src 127.0.0.1"),
+            Some("lo".to_string())
+        );
+        assert_eq!(parse_route_device(""), None);
+
+        let routes = "10.0.0.0/24 dev veth-b proto kernel scope link src 10.0.0.2\n\
+                      default via 10.0.0.1 dev eth0\n";
+        assert_eq!(default_route_device(routes), Some("eth0".to_string()));
+        assert_eq!(
+            default_route_device(routes.lines().next().unwrap()),
+            Some("veth-b".to_string())
+        );
+    }
+
+    #[test]
+    fn test_attachment_metadata() {
+        let attachment = NetemAttachment {
+            interface: "eth0".to_string(),
+            band: Some(5),
+            netns: Some(NetNamespace::named("chaos-a")),
+        };
+        let metadata = attachment.metadata(serde_json::json!({"loss_percent": 5.0}));
+        assert_eq!(metadata["band"], 5);
+        assert_eq!(metadata["loss_percent"], 5.0);
+        assert_eq!(metadata["netns"], "/run/netns/chaos-a");
+        assert_eq!(
+            NetemAttachment::from_metadata(&metadata).unwrap(),
+            attachment
+        );
+
+        let root = NetemAttachment::from_metadata(&serde_json::json!({"interface": "eth0"}));
+        assert_eq!(root.unwrap().band, None);
+    }
+}
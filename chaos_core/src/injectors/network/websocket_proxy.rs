@@ -0,0 +1,805 @@
+use super::LatencyDistribution;
+use crate::{
+    error::*,
+    handle::InjectionHandle,
+    injectors::{params, Injector, InjectorParams},
+    target::Target,
+};
+use async_trait::async_trait;
+use futures::{Sink, SinkExt, Stream, StreamExt};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio_tungstenite::tungstenite::protocol::{frame::coding::CloseCode, CloseFrame};
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// Longest a held-back message waits for another one to overtake it
+const REORDER_WINDOW: Duration = Duration::from_millis(100);
+/// Close code that is never sent on the wire: the connection is dropped
+/// without a close frame instead
+const ABNORMAL_CLOSURE: u16 = 1006;
+
+/// Which way data messages are faulted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamDirection {
+    /// Server to client, e.g. feed updates
+    #[default]
+    Downstream,
+    /// Client to server, e.g. subscriptions
+    Upstream,
+    Both,
+}
+
+impl StreamDirection {
+    fn includes(self, direction: StreamDirection) -> bool {
+        self == StreamDirection::Both || self == direction
+    }
+}
+
+/// Faults applied to the messages and lifetime of every proxied WebSocket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebSocketProxyConfig {
+    /// Address to accept connections on; port 0 picks a free one
+    pub listen: SocketAddr,
+    pub direction: StreamDirection,
+    /// Delay before delivering each data message, in milliseconds
+    pub delay: Option<LatencyDistribution>,
+    /// Probability per data message that it is dropped
+    pub drop_rate: f64,
+    /// Probability per data message that the next one overtakes it
+    pub reorder_rate: f64,
+    /// Probability per data message that the connection goes silent without
+    /// closing, so neither data nor pongs get through and pings time out
+    pub stall_rate: f64,
+    /// Probability per data message that the connection is closed
+    pub close_rate: f64,
+    /// Code sent to the client when the proxy closes a connection; 1006
+    /// drops it without a close frame
+    pub close_code: u16,
+    /// Every connection is closed after this long, forcing reconnects
+    pub max_lifetime: Option<Duration>,
+    /// Probability per connection attempt that the handshake is refused
+    pub reject_rate: f64,
+    /// Makes every random decision reproducible
+    pub seed: Option<u64>,
+}
+
+impl Default for WebSocketProxyConfig {
+    fn default() -> Self {
+        Self {
+            listen: SocketAddr::from(([127, 0, 0, 1], 0)),
+            direction: StreamDirection::default(),
+            delay: None,
+            drop_rate: 0.0,
+            reorder_rate: 0.0,
+            stall_rate: 0.0,
+            close_rate: 0.0,
+            close_code: 1011,
+            max_lifetime: None,
+            reject_rate: 0.0,
+            seed: None,
+        }
+    }
+}
+
+impl WebSocketProxyConfig {
+    pub fn validate(&self) -> Result<()> {
+        for (name, rate) in [
+            ("drop_rate", self.drop_rate),
+            ("reorder_rate", self.reorder_rate),
+            ("stall_rate", self.stall_rate),
+            ("close_rate", self.close_rate),
+            ("reject_rate", self.reject_rate),
+        ] {
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(ChaosError::InvalidConfig(format!(
+                    "websocket_proxy {} must be between 0.0 and 1.0, got {}",
+                    name, rate
+                )));
+            }
+        }
+        // 1005 and 1015 are reserved for reporting and may not be sent
+        if !(1000..=4999).contains(&self.close_code) || [1005, 1015].contains(&self.close_code) {
+            return Err(ChaosError::InvalidConfig(format!(
+                "websocket_proxy close_code {} cannot be sent",
+                self.close_code
+            )));
+        }
+        if let Some(delay) = &self.delay {
+            delay.validate()?;
+        }
+        if self.max_lifetime.is_some_and(|lifetime| lifetime.is_zero()) {
+            return Err(ChaosError::InvalidConfig(
+                "websocket_proxy max_lifetime must be positive".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Userspace WebSocket proxy that forwards to a network target and faults
+/// individual messages and whole connections of long-lived streams. Needs no
+/// privileges: clients connect to the proxy instead.
+#[derive(Default)]
+pub struct WebSocketProxyInjector {
+    config: Arc<WebSocketProxyConfig>,
+    /// Stops the accept loop and every open connection of each proxy
+    proxies: Mutex<HashMap<String, CancellationToken>>,
+}
+
+impl WebSocketProxyInjector {
+    pub fn new(config: WebSocketProxyConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            proxies: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn builder() -> WebSocketProxyBuilder {
+        WebSocketProxyBuilder::default()
+    }
+
+    pub fn config(&self) -> &WebSocketProxyConfig {
+        &self.config
+    }
+
+    pub fn from_params(params: &InjectorParams) -> Result<Self> {
+        let params: WebSocketProxyParams = params::parse_params("websocket_proxy", params)?;
+        let mut builder = Self::builder();
+
+        if let Some(listen) = params.listen {
+            builder = builder.listen(listen);
+        }
+        if let Some(direction) = params.direction {
+            builder = builder.direction(direction);
+        }
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let delay = match (params.distribution, params.delay, params.jitter) {
+            (Some(distribution), _, _) => {
+                distribution.validate()?;
+                Some(distribution)
+            }
+            (None, Some(delay), Some(jitter)) => Some(LatencyDistribution::Normal {
+                mean: ms(delay),
+                std_dev: ms(jitter),
+            }),
+            (None, Some(delay), None) => Some(LatencyDistribution::Fixed { value: ms(delay) }),
+            (None, None, Some(_)) => {
+                return Err(ChaosError::InvalidConfig(
+                    "websocket_proxy jitter requires a delay".to_string(),
+                ))
+            }
+            (None, None, None) => None,
+        };
+        if let Some(delay) = delay {
+            builder = builder.delay(delay);
+        }
+        if let Some(rate) = params.drop_rate {
+            builder = builder.drop_rate(rate);
+        }
+        if let Some(rate) = params.reorder_rate {
+            builder = builder.reorder_rate(rate);
+        }
+        if let Some(rate) = params.stall_rate {
+            builder = builder.stall_rate(rate);
+        }
+        if let Some(rate) = params.close_rate {
+            builder = builder.close_rate(rate);
+        }
+        if let Some(code) = params.close_code {
+            builder = builder.close_code(code);
+        }
+        if let Some(lifetime) = params.max_lifetime {
+            builder = builder.max_lifetime(lifetime);
+        }
+        if let Some(rate) = params.reject_rate {
+            builder = builder.reject_rate(rate);
+        }
+        if let Some(seed) = params.seed {
+            builder = builder.seed(seed);
+        }
+
+        let injector = builder.build();
+        injector.config.validate()?;
+        Ok(injector)
+    }
+}
+
+#[async_trait]
+impl Injector for WebSocketProxyInjector {
+    async fn inject(&self, target: &Target) -> Result<InjectionHandle> {
+        let Target::Network { address: upstream } = target else {
+            return Err(ChaosError::InvalidConfig(
+                "WebSocket proxy requires Network target".to_string(),
+            ));
+        };
+        self.config.validate()?;
+
+        let listener = TcpListener::bind(self.config.listen).await.map_err(|e| {
+            ChaosError::InjectionFailed(format!(
+                "Failed to listen on {}: {}",
+                self.config.listen, e
+            ))
+        })?;
+        let listen = listener.local_addr()?;
+        info!("Proxying WebSocket {} -> {} with faults", listen, upstream);
+
+        let cancel = CancellationToken::new();
+        let rng = self
+            .config
+            .seed
+            .map(StdRng::seed_from_u64)
+            .unwrap_or_else(StdRng::from_entropy);
+        tokio::spawn(accept_loop(
+            listener,
+            *upstream,
+            self.config.clone(),
+            rng,
+            cancel.clone(),
+        ));
+
+        let handle = InjectionHandle::new(
+            "websocket_proxy",
+            target.clone(),
+            serde_json::json!({
+                "listen": listen.to_string(),
+                "upstream": upstream.to_string(),
+            }),
+        );
+        self.proxies
+            .lock()
+            .unwrap()
+            .insert(handle.id.clone(), cancel);
+        Ok(handle)
+    }
+
+    async fn remove(&self, handle: InjectionHandle) -> Result<()> {
+        match self.proxies.lock().unwrap().remove(&handle.id) {
+            Some(cancel) => {
+                info!("Stopping WebSocket proxy {}", handle.metadata["listen"]);
+                cancel.cancel();
+            }
+            // The proxy only lives as long as the process that started it
+            None => info!("WebSocket proxy {} is not running", handle.id),
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "websocket_proxy"
+    }
+
+    async fn validate(&self) -> Result<()> {
+        self.config.validate()
+    }
+}
+
+async fn accept_loop(
+    listener: TcpListener,
+    upstream: SocketAddr,
+    config: Arc<WebSocketProxyConfig>,
+    mut rng: StdRng,
+    cancel: CancellationToken,
+) {
+    loop {
+        let client = tokio::select! {
+            _ = cancel.cancelled() => return,
+            accepted = listener.accept() => match accepted {
+                Ok((client, _)) => client,
+                Err(e) => {
+                    warn!("WebSocket proxy accept failed: {}", e);
+                    continue;
+                }
+            },
+        };
+
+        // Each connection draws from its own generator, seeded in accept
+        // order, so a seeded run replays the same faults
+        let conn_rng = StdRng::seed_from_u64(rng.gen());
+        let config = config.clone();
+        let cancel = cancel.child_token();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = cancel.cancelled() => {}
+                _ = proxy_connection(client, upstream, config, conn_rng) => {}
+            }
+        });
+    }
+}
+
+/// A message waiting in a direction's delivery queue
+struct Queued {
+    deliver_at: Instant,
+    message: Message,
+}
+
+/// Why a connection stopped forwarding
+enum Verdict {
+    /// A peer closed or went away
+    Done,
+    /// The proxy closes the connection with the configured code
+    Close,
+    /// The proxy stops forwarding and answering without closing
+    Stall,
+}
+
+async fn proxy_connection(
+    client: TcpStream,
+    upstream: SocketAddr,
+    config: Arc<WebSocketProxyConfig>,
+    mut rng: StdRng,
+) {
+    let reject = rng.gen_bool(config.reject_rate);
+    let mut path = String::from("/");
+    // The rejection type is fixed by tungstenite
+    #[allow(clippy::result_large_err)]
+    let handshake = |request: &Request, response: Response| {
+        if reject {
+            let mut refused = ErrorResponse::new(Some("rejected by websocket_proxy".into()));
+            *refused.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+            return Err(refused);
+        }
+        if let Some(path_and_query) = request.uri().path_and_query() {
+            path = path_and_query.to_string();
+        }
+        Ok(response)
+    };
+    let Ok(mut client) = tokio_tungstenite::accept_hdr_async(client, handshake).await else {
+        return;
+    };
+
+    let url = format!("ws://{}{}", upstream, path);
+    let server = match TcpStream::connect(upstream).await {
+        Ok(stream) => tokio_tungstenite::client_async(url, stream).await,
+        Err(e) => Err(e.into()),
+    };
+    let server = match server {
+        Ok((server, _)) => server,
+        Err(e) => {
+            warn!("WebSocket proxy could not reach {}: {}", upstream, e);
+            let _ = client.close(Some(close_frame(1011))).await;
+            return;
+        }
+    };
+
+    let (client_tx, client_rx) = client.split();
+    let (server_tx, server_rx) = server.split();
+    let (down_tx, down_rx) = mpsc::unbounded_channel();
+    let (up_tx, up_rx) = mpsc::unbounded_channel();
+    let down_rng = StdRng::seed_from_u64(rng.gen());
+    let up_rng = StdRng::seed_from_u64(rng.gen());
+    let stalled = CancellationToken::new();
+    let aborted = CancellationToken::new();
+
+    let faults = |direction| config.direction.includes(direction).then_some(&*config);
+    let pumps = async {
+        let (down_tx, up_tx) = (down_tx, up_tx);
+        let lifetime = async {
+            match config.max_lifetime {
+                Some(lifetime) => tokio::time::sleep(lifetime).await,
+                None => std::future::pending().await,
+            }
+        };
+        let down_faults = faults(StreamDirection::Downstream);
+        let up_faults = faults(StreamDirection::Upstream);
+        let verdict = tokio::select! {
+            verdict = forward(server_rx, &down_tx, down_faults, down_rng) => verdict,
+            verdict = forward(client_rx, &up_tx, up_faults, up_rng) => verdict,
+            _ = lifetime => Verdict::Close,
+        };
+        match verdict {
+            // Closing the queues lets both sides drain and close
+            Verdict::Done => {}
+            Verdict::Close if config.close_code == ABNORMAL_CLOSURE => aborted.cancel(),
+            Verdict::Close => {
+                let close = |code: u16| Queued {
+                    deliver_at: Instant::now(),
+                    message: Message::Close(Some(close_frame(code))),
+                };
+                let _ = down_tx.send(close(config.close_code));
+                let _ = up_tx.send(close(u16::from(CloseCode::Away)));
+            }
+            Verdict::Stall => {
+                stalled.cancel();
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    tokio::select! {
+        _ = aborted.cancelled() => {}
+        _ = async {
+            tokio::join!(
+                pumps,
+                deliver(down_rx, client_tx, &stalled),
+                deliver(up_rx, server_tx, &stalled),
+            )
+        } => {}
+    }
+}
+
+/// Read messages from one peer and queue them for the other, applying this
+/// direction's faults to data messages
+async fn forward<S>(
+    mut from: S,
+    queue: &mpsc::UnboundedSender<Queued>,
+    faults: Option<&WebSocketProxyConfig>,
+    mut rng: StdRng,
+) -> Verdict
+where
+    S: Stream<Item = std::result::Result<Message, WsError>> + Unpin,
+{
+    let mut deliver_at = Instant::now();
+    let mut held: Option<Message> = None;
+    let push = |message, deliver_at| {
+        queue
+            .send(Queued {
+                deliver_at,
+                message,
+            })
+            .is_ok()
+    };
+
+    loop {
+        let next = tokio::select! {
+            next = from.next() => next,
+            _ = tokio::time::sleep(REORDER_WINDOW), if held.is_some() => {
+                // Nothing came along to overtake it
+                deliver_at = deliver_at.max(Instant::now());
+                if !push(held.take().unwrap(), deliver_at) {
+                    return Verdict::Done;
+                }
+                continue;
+            }
+        };
+        let Some(Ok(message)) = next else {
+            return Verdict::Done;
+        };
+
+        let data = matches!(message, Message::Text(_) | Message::Binary(_));
+        let Some(faults) = faults.filter(|_| data) else {
+            let closing = matches!(message, Message::Close(_));
+            let sent =
+                held.take().is_none_or(|held| push(held, deliver_at)) && push(message, deliver_at);
+            if closing || !sent {
+                return Verdict::Done;
+            }
+            continue;
+        };
+
+        if rng.gen_bool(faults.close_rate) {
+            return Verdict::Close;
+        }
+        if rng.gen_bool(faults.stall_rate) {
+            return Verdict::Stall;
+        }
+        if rng.gen_bool(faults.drop_rate) {
+            continue;
+        }
+        let delay_ms = faults
+            .delay
+            .as_ref()
+            .map_or(0.0, |delay| delay.sample(&mut rng).max(0.0));
+        // Delivery stays in order even when jitter would swap messages
+        deliver_at = deliver_at.max(Instant::now() + Duration::from_secs_f64(delay_ms / 1000.0));
+        if held.is_none() && rng.gen_bool(faults.reorder_rate) {
+            held = Some(message);
+            continue;
+        }
+        let sent =
+            push(message, deliver_at) && held.take().is_none_or(|held| push(held, deliver_at));
+        if !sent {
+            return Verdict::Done;
+        }
+    }
+}
+
+/// Send queued messages to one peer once they are due, closing it when the
+/// queue ends
+async fn deliver<S>(
+    mut queue: mpsc::UnboundedReceiver<Queued>,
+    mut to: S,
+    stalled: &CancellationToken,
+) where
+    S: Sink<Message> + Unpin,
+{
+    while let Some(queued) = queue.recv().await {
+        tokio::time::sleep_until(queued.deliver_at).await;
+        if stalled.is_cancelled() {
+            return std::future::pending().await;
+        }
+        let closing = matches!(queued.message, Message::Close(_));
+        if to.send(queued.message).await.is_err() || closing {
+            return;
+        }
+    }
+    let _ = to.close().await;
+}
+
+fn close_frame(code: u16) -> CloseFrame<'static> {
+    CloseFrame {
+        code: CloseCode::from(code),
+        reason: "websocket_proxy fault".into(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WebSocketProxyParams {
+    #[serde(default)]
+    listen: Option<SocketAddr>,
+    #[serde(default)]
+    direction: Option<StreamDirection>,
+    #[serde(default, deserialize_with = "params::opt_duration")]
+    delay: Option<Duration>,
+    #[serde(default, deserialize_with = "params::opt_duration")]
+    jitter: Option<Duration>,
+    #[serde(default)]
+    distribution: Option<LatencyDistribution>,
+    #[serde(default)]
+    drop_rate: Option<f64>,
+    #[serde(default)]
+    reorder_rate: Option<f64>,
+    #[serde(default)]
+    stall_rate: Option<f64>,
+    #[serde(default)]
+    close_rate: Option<f64>,
+    #[serde(default)]
+    close_code: Option<u16>,
+    #[serde(default, deserialize_with = "params::opt_duration")]
+    max_lifetime: Option<Duration>,
+    #[serde(default)]
+    reject_rate: Option<f64>,
+    #[serde(default)]
+    seed: Option<u64>,
+}
+
+#[derive(Default)]
+pub struct WebSocketProxyBuilder {
+    config: WebSocketProxyConfig,
+}
+
+impl WebSocketProxyBuilder {
+    pub fn listen(mut self, listen: SocketAddr) -> Self {
+        self.config.listen = listen;
+        self
+    }
+
+    pub fn direction(mut self, direction: StreamDirection) -> Self {
+        self.config.direction = direction;
+        self
+    }
+
+    pub fn delay(mut self, delay: LatencyDistribution) -> Self {
+        self.config.delay = Some(delay);
+        self
+    }
+
+    pub fn drop_rate(mut self, rate: f64) -> Self {
+        self.config.drop_rate = rate;
+        self
+    }
+
+    pub fn reorder_rate(mut self, rate: f64) -> Self {
+        self.config.reorder_rate = rate;
+        self
+    }
+
+    pub fn stall_rate(mut self, rate: f64) -> Self {
+        self.config.stall_rate = rate;
+        self
+    }
+
+    pub fn close_rate(mut self, rate: f64) -> Self {
+        self.config.close_rate = rate;
+        self
+    }
+
+    pub fn close_code(mut self, code: u16) -> Self {
+        self.config.close_code = code;
+        self
+    }
+
+    pub fn max_lifetime(mut self, lifetime: Duration) -> Self {
+        self.config.max_lifetime = Some(lifetime);
+        self
+    }
+
+    pub fn reject_rate(mut self, rate: f64) -> Self {
+        self.config.reject_rate = rate;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.config.seed = Some(seed);
+        self
+    }
+
+    pub fn build(self) -> WebSocketProxyInjector {
+        WebSocketProxyInjector::new(self.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+    /// Upstream feed that sends ten numbered messages on connect, then
+    /// echoes whatever it receives
+    async fn feed() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                    for i in 0..10 {
+                        ws.send(Message::Text(i.to_string())).await.unwrap();
+                    }
+                    while let Some(Ok(message)) = ws.next().await {
+                        if let Message::Text(text) = message {
+                            let _ = ws.send(Message::Text(text)).await;
+                        }
+                    }
+                });
+            }
+        });
+        address
+    }
+
+    async fn connect(
+        injector: &WebSocketProxyInjector,
+    ) -> (
+        InjectionHandle,
+        std::result::Result<WebSocketStream<MaybeTlsStream<TcpStream>>, WsError>,
+    ) {
+        let target = Target::network(feed().await);
+        let handle = injector.inject(&target).await.unwrap();
+        let url = format!("ws://{}/ws", handle.metadata["listen"].as_str().unwrap());
+        let client = tokio_tungstenite::connect_async(url)
+            .await
+            .map(|(ws, _)| ws);
+        (handle, client)
+    }
+
+    async fn next_text<S>(client: &mut S) -> String
+    where
+        S: Stream<Item = std::result::Result<Message, WsError>> + Unpin,
+    {
+        match client.next().await {
+            Some(Ok(Message::Text(text))) => text,
+            other => panic!("expected a text message, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delays_and_reorders_downstream() {
+        let injector = WebSocketProxyInjector::builder()
+            .delay(LatencyDistribution::Fixed { value: 30.0 })
+            .reorder_rate(1.0)
+            .build();
+        let started = Instant::now();
+        let (handle, client) = connect(&injector).await;
+        let mut client = client.unwrap();
+
+        let mut received = Vec::new();
+        for _ in 0..10 {
+            received.push(next_text(&mut client).await);
+        }
+        assert_eq!(received, ["1", "0", "3", "2", "5", "4", "7", "6", "9", "8"]);
+        assert!(started.elapsed() >= Duration::from_millis(30));
+
+        // Subscriptions go upstream untouched; the lone echo is released
+        // once nothing overtakes it
+        client
+            .send(Message::Text("subscribe".into()))
+            .await
+            .unwrap();
+        assert_eq!(next_text(&mut client).await, "subscribe");
+
+        injector.remove(handle).await.unwrap();
+        assert!(!matches!(client.next().await, Some(Ok(Message::Text(_)))));
+    }
+
+    #[tokio::test]
+    async fn test_closes_and_rejects_connections() {
+        let injector = WebSocketProxyInjector::builder()
+            .close_rate(1.0)
+            .close_code(4000)
+            .build();
+        let (_handle, client) = connect(&injector).await;
+        match client.unwrap().next().await {
+            Some(Ok(Message::Close(Some(frame)))) => assert_eq!(u16::from(frame.code), 4000),
+            other => panic!("expected a close frame, got {:?}", other),
+        }
+
+        let injector = WebSocketProxyInjector::builder()
+            .close_rate(1.0)
+            .close_code(1006)
+            .build();
+        let (_handle, client) = connect(&injector).await;
+        assert!(!matches!(
+            client.unwrap().next().await,
+            Some(Ok(Message::Close(Some(_))))
+        ));
+
+        // Every connection is cut at the same age, so clients reconnect together
+        let injector = WebSocketProxyInjector::builder()
+            .max_lifetime(Duration::from_millis(50))
+            .build();
+        let (_handle, client) = connect(&injector).await;
+        let mut client = client.unwrap();
+        for _ in 0..10 {
+            next_text(&mut client).await;
+        }
+        match client.next().await {
+            Some(Ok(Message::Close(Some(frame)))) => assert_eq!(frame.code, CloseCode::Error),
+            other => panic!("expected a close frame, got {:?}", other),
+        }
+
+        let injector = WebSocketProxyInjector::builder().reject_rate(1.0).build();
+        let (_handle, client) = connect(&injector).await;
+        assert!(client.is_err());
+    }
+
+    #[test]
+    fn test_websocket_proxy_from_params() {
+        let params: InjectorParams = serde_json::from_value(serde_json::json!({
+            "listen": "127.0.0.1:9001",
+            "direction": "both",
+            "delay": "20ms",
+            "drop_rate": 0.1,
+            "close_code": 1001,
+            "max_lifetime": "30s",
+        }))
+        .unwrap();
+        let injector = WebSocketProxyInjector::from_params(&params).unwrap();
+        let config = injector.config();
+        assert_eq!(config.listen.port(), 9001);
+        assert_eq!(config.direction, StreamDirection::Both);
+        assert_eq!(config.max_lifetime, Some(Duration::from_secs(30)));
+        assert!(matches!(
+            config.delay,
+            Some(LatencyDistribution::Fixed { value }) if value == 20.0
+        ));
+
+        for invalid in [
+            serde_json::json!({"drop_rate": 1.5}),
+            serde_json::json!({"close_code": 1005}),
+            serde_json::json!({"close_code": 200}),
+            serde_json::json!({"max_lifetime": "0s"}),
+            serde_json::json!({"direction": "sideways"}),
+        ] {
+            let params: InjectorParams = serde_json::from_value(invalid).unwrap();
+            assert!(WebSocketProxyInjector::from_params(&params).is_err());
+        }
+
+        // Rejected up front rather than panicking in every frame task
+        let params: InjectorParams = serde_json::from_value(serde_json::json!({
+            "distribution": {"uniform": {"min": 5, "max": 1}}
+        }))
+        .unwrap();
+        let error = WebSocketProxyInjector::from_params(&params)
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(error.contains("min must be below max"), "{}", error);
+        let config = WebSocketProxyConfig {
+            delay: Some(LatencyDistribution::Exponential { lambda: -1.0 }),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{error::*, injectors::DynInjector};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Raw per-injection parameters, as they appear next to `type` and `target`
+/// in a scenario file
+pub type InjectorParams = HashMap<String, serde_json::Value>;
+
+/// Builds a configured injector instance from per-injection parameters
+pub trait InjectorFactory: Send + Sync {
+    fn create(&self, params: &InjectorParams) -> Result<DynInjector>;
+}
+
+impl<F> InjectorFactory for F
+where
+    F: Fn(&InjectorParams) -> Result<DynInjector> + Send + Sync,
+{
+    fn create(&self, params: &InjectorParams) -> Result<DynInjector> {
+        self(params)
+    }
+}
+
+/// Deserialize a parameter map into a typed parameter struct, reporting
+/// unknown or mistyped keys against the injector name
+pub fn parse_params<T: DeserializeOwned>(injector: &str, params: &InjectorParams) -> Result<T> {
+    let value = serde_json::Value::Object(params.clone().into_iter().collect());
+    serde_json::from_value(value).map_err(|e| {
+        ChaosError::InvalidConfig(format!(
+            "Invalid parameters for injector '{}': {}",
+            injector, e
+        ))
+    })
+}
+
+/// Deserialize an optional human-readable duration such as `"20ms"` or `"1m"`
+pub(crate) fn opt_duration<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let opt = Option::<String>::deserialize(deserializer)?;
+    opt.map(|s| humantime::parse_duration(&s).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct TestParams {
+        #[serde(default)]
+        rate: Option<f64>,
+        #[serde(default, deserialize_with = "opt_duration")]
+        delay: Option<Duration>,
+    }
+
+    fn params(json: serde_json::Value) -> InjectorParams {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_parse_params() {
+        let parsed: TestParams = parse_params(
+            "test",
+            &params(serde_json::json!({"rate": 0.5, "delay": "20ms"})),
+        )
+        .unwrap();
+        assert_eq!(parsed.rate, Some(0.5));
+        assert_eq!(parsed.delay, Some(Duration::from_millis(20)));
+
+        let parsed: TestParams = parse_params("test", &InjectorParams::new()).unwrap();
+        assert!(parsed.rate.is_none());
+    }
+
+    #[test]
+    fn test_parse_params_errors() {
+        let unknown = params(serde_json::json!({"rte": 0.5}));
+        let err = parse_params::<TestParams>("test", &unknown)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("injector 'test'"));
+        assert!(err.contains("unknown field `rte`"));
+
+        let mistyped = params(serde_json::json!({"rate": "high"}));
+        let err = parse_params::<TestParams>("test", &mistyped)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("invalid type"));
+
+        let bad_duration = params(serde_json::json!({"delay": "soon"}));
+        assert!(parse_params::<TestParams>("test", &bad_duration).is_err());
+    }
+}
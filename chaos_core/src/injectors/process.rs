@@ -1,8 +1,21 @@
-use crate::{error::*, handle::InjectionHandle, injectors::Injector, target::Target};
+use crate::{
+    error::*,
+    handle::{InjectionHandle, InjectionReport},
+    health,
+    injectors::{params, Injector, InjectorParams},
+    target::Target,
+};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use tokio::process::Command;
+use tokio::process::{Child, Command};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
 use tracing::{info, warn};
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -39,9 +52,14 @@ impl Signal {
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum RestartMode {
-    ColdBoot, // Full restart with initialization
-    WarmBoot, // Fast restart preserving state
-    None,     // No restart
+    /// Start afresh from our own environment once the process is gone and
+    /// `restart_delay` has passed
+    ColdBoot,
+    /// Start again right away, in the working directory and environment
+    /// of the killed process
+    WarmBoot,
+    /// No restart
+    None,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -51,6 +69,14 @@ pub struct ProcessKillConfig {
     pub restart_mode: RestartMode,
     pub restart_command: Option<String>,
     pub health_check_url: Option<String>,
+    /// Kill the restarted process again at this interval while the
+    /// injection is active (a crash loop)
+    pub interval: Option<Duration>,
+    /// End the crash loop after this many kills, the first one included
+    pub max_cycles: Option<u32>,
+    /// Where the restarted process's stdout and stderr are written;
+    /// a directory under the system temp dir by default
+    pub log_dir: Option<PathBuf>,
 }
 
 impl Default for ProcessKillConfig {
@@ -61,193 +87,454 @@ impl Default for ProcessKillConfig {
             restart_mode: RestartMode::None,
             restart_command: None,
             health_check_url: None,
+            interval: None,
+            max_cycles: None,
+            log_dir: None,
         }
     }
 }
 
+impl ProcessKillConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.interval.is_none() {
+            if self.max_cycles.is_some() {
+                return Err(ChaosError::InvalidConfig(
+                    "max_cycles requires an interval".to_string(),
+                ));
+            }
+            return Ok(());
+        }
+        if self.interval == Some(Duration::ZERO) {
+            return Err(ChaosError::InvalidConfig(
+                "Crash loop interval must be > 0".to_string(),
+            ));
+        }
+        if self.max_cycles == Some(0) {
+            return Err(ChaosError::InvalidConfig(
+                "max_cycles must be at least 1".to_string(),
+            ));
+        }
+        if self.restart_mode == RestartMode::None || self.restart_command.is_none() {
+            return Err(ChaosError::InvalidConfig(
+                "A crash loop needs a restart mode and a restart command".to_string(),
+            ));
+        }
+        if matches!(self.signal, Signal::SIGSTOP) {
+            return Err(ChaosError::InvalidConfig(
+                "A crash loop cannot use SIGSTOP".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn log_dir(&self) -> PathBuf {
+        self.log_dir
+            .clone()
+            .unwrap_or_else(|| std::env::temp_dir().join("chaos-process-kill"))
+    }
+}
+
+#[derive(Default)]
 pub struct ProcessKillInjector {
     config: ProcessKillConfig,
+    /// Supervisor of each handle's restarted process
+    supervisors: Mutex<HashMap<String, Supervision>>,
 }
 
-impl Default for ProcessKillInjector {
-    fn default() -> Self {
-        Self {
-            config: ProcessKillConfig::default(),
-        }
-    }
+struct Supervision {
+    progress: Arc<Mutex<Progress>>,
+    /// Ends the crash loop before its next cycle
+    stop: CancellationToken,
+    task: JoinHandle<()>,
+}
+
+/// What a supervisor has seen of the process it restarts
+#[derive(Debug, Default)]
+struct Progress {
+    /// PID of the latest restart
+    pid: Option<u32>,
+    recoveries: Vec<Duration>,
+    logs: Vec<PathBuf>,
 }
 
 impl ProcessKillInjector {
     pub fn new(config: ProcessKillConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            supervisors: Mutex::new(HashMap::new()),
+        }
     }
 
     pub fn builder() -> ProcessKillBuilder {
         ProcessKillBuilder::default()
     }
 
-    async fn send_signal(&self, pid: u32) -> Result<()> {
-        info!("Sending {} to PID {}", self.config.signal.as_str(), pid);
+    pub fn from_params(params: &InjectorParams) -> Result<Self> {
+        let params: ProcessKillParams = params::parse_params("process_kill", params)?;
+        let mut builder = Self::builder();
+        if let Some(signal) = params.signal {
+            builder = builder.signal(signal);
+        }
+        if let Some(delay) = params.restart_delay {
+            builder = builder.restart_delay(delay);
+        }
+        if let Some(mode) = params.restart_mode {
+            builder = builder.restart_mode(mode);
+        }
+        if let Some(command) = params.restart_command {
+            builder = builder.restart_command(command);
+        }
+        if let Some(url) = params.health_check_url {
+            builder = builder.health_check_url(url);
+        }
+        if let Some(interval) = params.interval {
+            builder = builder.interval(interval);
+        }
+        if let Some(max) = params.max_cycles {
+            builder = builder.max_cycles(max);
+        }
+        if let Some(dir) = params.log_dir {
+            builder = builder.log_dir(dir);
+        }
+        let injector = builder.build();
+        injector.config.validate()?;
+        Ok(injector)
+    }
+}
 
-        #[cfg(unix)]
-        {
-            use nix::sys::signal;
-            use nix::unistd::Pid;
+/// Kills a process, restarts it and owns the replacement, so it can be
+/// reaped, logged and killed again
+struct Supervisor {
+    config: ProcessKillConfig,
+    /// Prefix of the log files
+    name: String,
+    child: Option<Child>,
+    cycles: u32,
+    progress: Arc<Mutex<Progress>>,
+}
 
-            let signal = match self.config.signal {
-                Signal::SIGTERM => signal::Signal::SIGTERM,
-                Signal::SIGKILL => signal::Signal::SIGKILL,
-                Signal::SIGSTOP => signal::Signal::SIGSTOP,
-                Signal::SIGCONT => signal::Signal::SIGCONT,
-                Signal::SIGHUP => signal::Signal::SIGHUP,
-            };
+/// Working directory and environment a warm boot restarts with
+struct WarmState {
+    cwd: PathBuf,
+    env: Vec<(String, String)>,
+}
 
-            signal::kill(Pid::from_raw(pid as i32), signal)
-                .map_err(|e| ChaosError::ProcessError(format!("Failed to send signal: {}", e)))?;
-        }
+impl WarmState {
+    fn capture(pid: u32) -> Option<Self> {
+        let cwd = std::fs::read_link(format!("/proc/{}/cwd", pid)).ok()?;
+        let environ = std::fs::read(format!("/proc/{}/environ", pid)).ok()?;
+        let env = environ
+            .split(|b| *b == 0)
+            .filter_map(|entry| {
+                let (key, value) = std::str::from_utf8(entry).ok()?.split_once('=')?;
+                Some((key.to_string(), value.to_string()))
+            })
+            .collect();
+        Some(Self { cwd, env })
+    }
+}
 
-        #[cfg(windows)]
-        {
-            // Windows doesn't have Unix signals, use TerminateProcess
-            if matches!(self.config.signal, Signal::SIGKILL) {
-                Command::new("taskkill")
-                    .args(&["/F", "/PID", &pid.to_string()])
-                    .output()
-                    .await
-                    .map_err(|e| {
-                        ChaosError::ProcessError(format!("Failed to kill process: {}", e))
-                    })?;
-            } else {
-                return Err(ChaosError::SystemError(
-                    "Only SIGKILL supported on Windows".to_string(),
-                ));
-            }
+impl Supervisor {
+    fn new(config: ProcessKillConfig, progress: Arc<Mutex<Progress>>) -> Self {
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        Self {
+            config,
+            name: format!("process_kill-{}", &id[..8]),
+            child: None,
+            cycles: 0,
+            progress,
         }
-
-        Ok(())
     }
 
-    async fn wait_for_process_death(&self, pid: u32, timeout: Duration) -> Result<()> {
-        let start = tokio::time::Instant::now();
+    /// Signal `pid`, wait for it to exit, then restart it and wait until it
+    /// is healthy. Returns the time from the signal to healthy, if restarted.
+    async fn cycle(&mut self, pid: u32) -> Result<Option<Duration>> {
+        let warm = match self.config.restart_mode {
+            RestartMode::WarmBoot => WarmState::capture(pid),
+            _ => None,
+        };
 
-        while start.elapsed() < timeout {
-            let target = Target::process(pid);
-            if !target.exists().await {
-                info!("Process {} terminated", pid);
-                return Ok(());
-            }
-            tokio::time::sleep(Duration::from_millis(100)).await;
+        let started = tokio::time::Instant::now();
+        send_signal(self.config.signal, pid).await?;
+        self.cycles += 1;
+
+        // A stopped process is resumed on removal, not replaced
+        if matches!(self.config.signal, Signal::SIGSTOP) {
+            return Ok(None);
         }
+        self.wait_for_exit(pid, Duration::from_secs(10)).await;
 
-        warn!("Process {} did not terminate within timeout", pid);
-        Ok(())
+        if self.config.restart_mode == RestartMode::None {
+            return Ok(None);
+        }
+        let new_pid = self.restart(warm).await?;
+        if let Some(url) = &self.config.health_check_url {
+            wait_for_health(url).await?;
+        }
+
+        let recovery = started.elapsed();
+        info!("Process {} recovered as {} in {:?}", pid, new_pid, recovery);
+        let mut progress = self.progress.lock().unwrap();
+        progress.pid = Some(new_pid);
+        progress.recoveries.push(recovery);
+        Ok(Some(recovery))
     }
 
-    async fn restart_process(&self) -> Result<u32> {
-        if self.config.restart_mode == RestartMode::None {
-            return Err(ChaosError::InvalidConfig(
-                "No restart mode configured".to_string(),
-            ));
+    /// Our own child is reaped; any other process is polled until gone
+    async fn wait_for_exit(&mut self, pid: u32, timeout: Duration) {
+        let Some(mut child) = self.child.take().filter(|c| c.id() == Some(pid)) else {
+            wait_for_process_death(pid, timeout).await;
+            return;
+        };
+        match tokio::time::timeout(timeout, child.wait()).await {
+            Ok(Ok(status)) => info!("Process {} terminated ({})", pid, status),
+            Ok(Err(e)) => warn!("Failed to wait for process {}: {}", pid, e),
+            Err(_) => warn!("Process {} did not terminate within timeout", pid),
         }
+    }
 
+    async fn restart(&mut self, warm: Option<WarmState>) -> Result<u32> {
         let command = self.config.restart_command.as_ref().ok_or_else(|| {
             ChaosError::InvalidConfig("No restart command configured".to_string())
         })?;
 
-        info!(
-            "Restarting process after {} seconds (mode: {:?})",
-            self.config.restart_delay.as_secs(),
-            self.config.restart_mode
-        );
-
-        tokio::time::sleep(self.config.restart_delay).await;
+        if self.config.restart_mode == RestartMode::ColdBoot {
+            info!(
+                "Restarting process after {:?} (cold boot)",
+                self.config.restart_delay
+            );
+            tokio::time::sleep(self.config.restart_delay).await;
+        } else {
+            info!("Restarting process (warm boot)");
+        }
 
-        // Execute restart command
-        let output = Command::new("sh")
+        let (stdout, stderr) = self.log_files()?;
+        let mut restart = Command::new("sh");
+        restart
             .arg("-c")
             .arg(command)
+            .stdin(Stdio::null())
+            .stdout(stdout)
+            .stderr(stderr);
+        if let Some(warm) = warm {
+            restart.env_clear().envs(warm.env).current_dir(warm.cwd);
+        }
+        let child = restart
             .spawn()
             .map_err(|e| ChaosError::ProcessError(format!("Failed to restart process: {}", e)))?;
 
-        let pid = output.id().ok_or_else(|| {
+        let pid = child.id().ok_or_else(|| {
             ChaosError::ProcessError("Failed to get PID of restarted process".to_string())
         })?;
-
         info!("Process restarted with PID {}", pid);
-
-        // Wait for health check if configured
-        if let Some(health_url) = &self.config.health_check_url {
-            self.wait_for_health(health_url).await?;
-        }
-
+        self.child = Some(child);
         Ok(pid)
     }
 
-    async fn wait_for_health(&self, url: &str) -> Result<()> {
-        info!("Waiting for health check: {}", url);
+    /// Fresh stdout and stderr files for the next restart
+    fn log_files(&self) -> Result<(File, File)> {
+        let dir = self.config.log_dir();
+        std::fs::create_dir_all(&dir)?;
+        let mut progress = self.progress.lock().unwrap();
+        let mut create = |stream: &str| -> Result<File> {
+            let path = dir.join(format!("{}-{}.{}.log", self.name, self.cycles, stream));
+            let file = File::create(&path)?;
+            progress.logs.push(path);
+            Ok(file)
+        };
+        Ok((create("stdout")?, create("stderr")?))
+    }
+
+    /// Own the restarted process until `stop` is cancelled. With an
+    /// interval, kill it again every time the interval elapses. A cycle in
+    /// progress always finishes, so the process is left running.
+    async fn supervise(mut self, stop: CancellationToken) {
+        loop {
+            let next = match self.config.max_cycles {
+                Some(max) if self.cycles >= max => None,
+                _ => self.config.interval,
+            };
+            let Some(child) = self.child.as_mut() else {
+                return;
+            };
+            let Some(pid) = child.id() else {
+                return;
+            };
 
-        for attempt in 1..=30 {
-            match reqwest::get(url).await {
-                Ok(response) if response.status().is_success() => {
-                    info!("Health check passed");
-                    return Ok(());
-                }
-                _ => {
-                    if attempt % 5 == 0 {
-                        info!("Health check attempt {}/30...", attempt);
+            tokio::select! {
+                status = child.wait() => {
+                    match status {
+                        Ok(status) => warn!("Restarted process {} exited ({})", pid, status),
+                        Err(e) => warn!("Failed to wait for restarted process {}: {}", pid, e),
                     }
-                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    return;
                 }
+                _ = stop.cancelled() => return,
+                _ = sleep_or_forever(next) => {}
+            }
+
+            info!(
+                "Crash loop: killing process {} (cycle {})",
+                pid,
+                self.cycles + 1
+            );
+            if let Err(e) = self.cycle(pid).await {
+                warn!("Crash loop on process {} stopped: {}", pid, e);
+                return;
             }
         }
+    }
+}
 
-        Err(ChaosError::ProcessError(
-            "Health check failed after 30 attempts".to_string(),
-        ))
+async fn sleep_or_forever(duration: Option<Duration>) {
+    match duration {
+        Some(duration) => tokio::time::sleep(duration).await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn send_signal(signal: Signal, pid: u32) -> Result<()> {
+    info!("Sending {} to PID {}", signal.as_str(), pid);
+
+    #[cfg(unix)]
+    {
+        use nix::sys::signal;
+        use nix::unistd::Pid;
+
+        let signal = match signal {
+            Signal::SIGTERM => signal::Signal::SIGTERM,
+            Signal::SIGKILL => signal::Signal::SIGKILL,
+            Signal::SIGSTOP => signal::Signal::SIGSTOP,
+            Signal::SIGCONT => signal::Signal::SIGCONT,
+            Signal::SIGHUP => signal::Signal::SIGHUP,
+        };
+
+        signal::kill(Pid::from_raw(pid as i32), signal)
+            .map_err(|e| ChaosError::ProcessError(format!("Failed to send signal: {}", e)))?;
+    }
+
+    #[cfg(windows)]
+    {
+        // Windows doesn't have Unix signals, use TerminateProcess
+        if matches!(signal, Signal::SIGKILL) {
+            Command::new("taskkill")
+                .args(&["/F", "/PID", &pid.to_string()])
+                .output()
+                .await
+                .map_err(|e| ChaosError::ProcessError(format!("Failed to kill process: {}", e)))?;
+        } else {
+            return Err(ChaosError::SystemError(
+                "Only SIGKILL supported on Windows".to_string(),
+            ));
+        }
     }
+
+    Ok(())
+}
+
+async fn wait_for_process_death(pid: u32, timeout: Duration) {
+    let start = tokio::time::Instant::now();
+
+    while start.elapsed() < timeout {
+        let target = Target::process(pid);
+        if !target.exists().await {
+            info!("Process {} terminated", pid);
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    warn!("Process {} did not terminate within timeout", pid);
+}
+
+async fn wait_for_health(url: &str) -> Result<()> {
+    if health::wait_for_http_health(url, 30, Duration::from_secs(1)).await {
+        return Ok(());
+    }
+
+    Err(ChaosError::ProcessError(
+        "Health check failed after 30 attempts".to_string(),
+    ))
 }
 
 #[async_trait]
 impl Injector for ProcessKillInjector {
     async fn inject(&self, target: &Target) -> Result<InjectionHandle> {
-        let Target::Process { pid } = target else {
+        // Containers are killed through their init process
+        if !matches!(target, Target::Process { .. } | Target::Container { .. }) {
             return Err(ChaosError::InvalidConfig(
-                "Process kill requires Process target".to_string(),
+                "Process kill requires Process or Container target".to_string(),
+            ));
+        }
+        self.config.validate()?;
+        if self.config.restart_mode != RestartMode::None && self.config.restart_command.is_none() {
+            return Err(ChaosError::InvalidConfig(
+                "No restart command configured".to_string(),
             ));
-        };
-
-        let original_pid = *pid;
-        self.send_signal(*pid).await?;
-
-        // Wait for process to die if not SIGSTOP
-        if !matches!(self.config.signal, Signal::SIGSTOP) {
-            self.wait_for_process_death(*pid, Duration::from_secs(10))
-                .await?;
         }
+        let pid = target.pid().await?;
 
-        // Restart if configured
-        let new_pid = if self.config.restart_mode != RestartMode::None {
-            Some(self.restart_process().await?)
-        } else {
-            None
-        };
+        let progress = Arc::new(Mutex::new(Progress::default()));
+        let mut supervisor = Supervisor::new(self.config.clone(), progress.clone());
+        let recovery = supervisor.cycle(pid).await?;
+        let new_pid = progress.lock().unwrap().pid;
+        let logs = progress.lock().unwrap().logs.clone();
 
         let metadata = serde_json::json!({
-            "original_pid": original_pid,
+            "original_pid": pid,
             "new_pid": new_pid,
             "signal": self.config.signal.as_str(),
             "restart_mode": format!("{:?}", self.config.restart_mode),
+            "recovery_ms": recovery.map(|r| r.as_millis() as u64),
+            "logs": logs,
         });
 
-        Ok(InjectionHandle::new(
-            "process_kill",
-            target.clone(),
-            metadata,
-        ))
+        let handle = InjectionHandle::new("process_kill", target.clone(), metadata);
+        if supervisor.child.is_some() {
+            let stop = CancellationToken::new();
+            let task = tokio::spawn(supervisor.supervise(stop.clone()));
+            self.supervisors.lock().unwrap().insert(
+                handle.id.clone(),
+                Supervision {
+                    progress,
+                    stop,
+                    task,
+                },
+            );
+        }
+        Ok(handle)
     }
 
-    async fn remove(&self, _handle: InjectionHandle) -> Result<()> {
-        // Process kill is a one-time action, cleanup is no-op
+    async fn remove(&self, handle: InjectionHandle) -> Result<()> {
+        // The restarted process keeps running; only the crash loop ends,
+        // after any kill it already started has been restarted
+        let supervision = self.supervisors.lock().unwrap().remove(&handle.id);
+        if let Some(supervision) = supervision {
+            supervision.stop.cancel();
+            if let Err(e) = supervision.task.await {
+                warn!("Crash loop for {} ended abnormally: {}", handle.id, e);
+            }
+        }
+
+        // Killing is a one-time action; only a stopped process is resumed
+        if handle.metadata["signal"] != Signal::SIGSTOP.as_str() {
+            return Ok(());
+        }
+        #[cfg(unix)]
+        if let Some(pid) = handle.metadata["original_pid"].as_u64() {
+            use nix::sys::signal;
+            use nix::unistd::Pid;
+
+            match signal::kill(Pid::from_raw(pid as i32), signal::Signal::SIGCONT) {
+                Ok(()) | Err(nix::errno::Errno::ESRCH) => info!("Resumed process {}", pid),
+                Err(e) => {
+                    return Err(ChaosError::CleanupFailed(format!(
+                        "Failed to resume process {}: {}",
+                        pid, e
+                    )))
+                }
+            }
+        }
         Ok(())
     }
 
@@ -258,6 +545,46 @@ impl Injector for ProcessKillInjector {
     fn required_capabilities(&self) -> Vec<String> {
         vec!["CAP_KILL".to_string()]
     }
+
+    async fn report(&self, handle: &InjectionHandle) -> InjectionReport {
+        let supervisors = self.supervisors.lock().unwrap();
+        let Some(supervision) = supervisors.get(&handle.id) else {
+            return InjectionReport::default();
+        };
+        let progress = supervision.progress.lock().unwrap();
+        // A container keeps its ID across restarts
+        let retarget = match handle.target {
+            Target::Process { .. } => progress.pid.map(Target::process),
+            _ => None,
+        };
+        InjectionReport {
+            recoveries: progress.recoveries.clone(),
+            retarget,
+            artifacts: progress.logs.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProcessKillParams {
+    #[serde(default)]
+    signal: Option<Signal>,
+    #[serde(default, deserialize_with = "params::opt_duration")]
+    restart_delay: Option<Duration>,
+    #[serde(default)]
+    restart_mode: Option<RestartMode>,
+    #[serde(default)]
+    restart_command: Option<String>,
+    #[serde(default)]
+    health_check_url: Option<String>,
+    #[serde(default, deserialize_with = "params::opt_duration")]
+    interval: Option<Duration>,
+    #[serde(default)]
+    max_cycles: Option<u32>,
+    #[serde(default)]
+    log_dir: Option<PathBuf>,
 }
 
 #[derive(Default)]
@@ -267,6 +594,9 @@ pub struct ProcessKillBuilder {
     restart_mode: Option<RestartMode>,
     restart_command: Option<String>,
     health_check_url: Option<String>,
+    interval: Option<Duration>,
+    max_cycles: Option<u32>,
+    log_dir: Option<PathBuf>,
 }
 
 impl ProcessKillBuilder {
@@ -295,16 +625,33 @@ impl ProcessKillBuilder {
         self
     }
 
+    /// Kill the restarted process again every `interval`
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    pub fn max_cycles(mut self, max: u32) -> Self {
+        self.max_cycles = Some(max);
+        self
+    }
+
+    pub fn log_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.log_dir = Some(dir.into());
+        self
+    }
+
     pub fn build(self) -> ProcessKillInjector {
-        ProcessKillInjector {
-            config: ProcessKillConfig {
-                signal: self.signal.unwrap_or(Signal::SIGTERM),
-                restart_delay: self.restart_delay.unwrap_or(Duration::from_secs(5)),
-                restart_mode: self.restart_mode.unwrap_or(RestartMode::None),
-                restart_command: self.restart_command,
-                health_check_url: self.health_check_url,
-            },
-        }
+        ProcessKillInjector::new(ProcessKillConfig {
+            signal: self.signal.unwrap_or(Signal::SIGTERM),
+            restart_delay: self.restart_delay.unwrap_or(Duration::from_secs(5)),
+            restart_mode: self.restart_mode.unwrap_or(RestartMode::None),
+            restart_command: self.restart_command,
+            health_check_url: self.health_check_url,
+            interval: self.interval,
+            max_cycles: self.max_cycles,
+            log_dir: self.log_dir,
+        })
     }
 }
 
@@ -345,13 +692,14 @@ impl ProcessSuspendInjector {
 #[async_trait]
 impl Injector for ProcessSuspendInjector {
     async fn inject(&self, target: &Target) -> Result<InjectionHandle> {
-        let Target::Process { pid } = target else {
+        if !matches!(target, Target::Process { .. } | Target::Container { .. }) {
             return Err(ChaosError::InvalidConfig(
-                "Process suspend requires Process target".to_string(),
+                "Process suspend requires Process or Container target".to_string(),
             ));
-        };
+        }
+        let pid = target.pid().await?;
 
-        self.suspend(*pid).await?;
+        self.suspend(pid).await?;
 
         let metadata = serde_json::json!({
             "pid": pid,
@@ -401,4 +749,167 @@ mod tests {
         assert!(matches!(injector.config.signal, Signal::SIGKILL));
         assert_eq!(injector.config.restart_delay, Duration::from_secs(10));
     }
+
+    #[test]
+    fn test_process_kill_from_params() {
+        let params: InjectorParams = serde_json::from_value(serde_json::json!({
+            "signal": "SIGKILL",
+            "restart_delay": "2s",
+            "restart_mode": "WarmBoot",
+        }))
+        .unwrap();
+        let injector = ProcessKillInjector::from_params(&params).unwrap();
+
+        assert!(matches!(injector.config.signal, Signal::SIGKILL));
+        assert_eq!(injector.config.restart_delay, Duration::from_secs(2));
+        assert_eq!(injector.config.restart_mode, RestartMode::WarmBoot);
+
+        let params: InjectorParams =
+            serde_json::from_value(serde_json::json!({"signal": "SIGFOO"})).unwrap();
+        assert!(ProcessKillInjector::from_params(&params).is_err());
+    }
+
+    #[test]
+    fn test_crash_loop_config() {
+        let params: InjectorParams = serde_json::from_value(serde_json::json!({
+            "signal": "SIGKILL",
+            "restart_mode": "ColdBoot",
+            "restart_command": "exec my-service",
+            "interval": "30s",
+            "max_cycles": 5,
+            "log_dir": "/tmp/chaos-logs",
+        }))
+        .unwrap();
+        let injector = ProcessKillInjector::from_params(&params).unwrap();
+        assert_eq!(injector.config.interval, Some(Duration::from_secs(30)));
+        assert_eq!(injector.config.max_cycles, Some(5));
+        assert_eq!(injector.config.log_dir(), PathBuf::from("/tmp/chaos-logs"));
+
+        // A crash loop has to restart what it kills
+        let params: InjectorParams =
+            serde_json::from_value(serde_json::json!({"interval": "30s"})).unwrap();
+        assert!(ProcessKillInjector::from_params(&params).is_err());
+        let params: InjectorParams =
+            serde_json::from_value(serde_json::json!({"max_cycles": 2})).unwrap();
+        assert!(ProcessKillInjector::from_params(&params).is_err());
+        assert!(ProcessKillInjector::builder()
+            .signal(Signal::SIGSTOP)
+            .restart_mode(RestartMode::WarmBoot)
+            .restart_command("true")
+            .interval(Duration::from_secs(1))
+            .build()
+            .config
+            .validate()
+            .is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_supervised_crash_loop() {
+        let mut service = std::process::Command::new("sleep")
+            .arg("60")
+            .spawn()
+            .unwrap();
+        let pid = service.id();
+        // Reap the original so it does not linger as a zombie
+        std::thread::spawn(move || service.wait());
+
+        let log_dir = std::env::temp_dir().join(format!("chaos-test-{}", uuid::Uuid::new_v4()));
+        let injector = ProcessKillInjector::builder()
+            .signal(Signal::SIGKILL)
+            .restart_mode(RestartMode::WarmBoot)
+            .restart_command("echo started; exec sleep 60")
+            .interval(Duration::from_millis(200))
+            .max_cycles(3)
+            .log_dir(&log_dir)
+            .build();
+
+        let handle = injector.inject(&Target::process(pid)).await.unwrap();
+        assert!(handle.metadata["new_pid"].as_u64().is_some());
+        assert!(handle.metadata["recovery_ms"].as_u64().is_some());
+
+        let mut report = injector.report(&handle).await;
+        for _ in 0..50 {
+            if report.recoveries.len() == 3 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            report = injector.report(&handle).await;
+        }
+        assert_eq!(report.recoveries.len(), 3);
+        assert_eq!(report.artifacts.len(), 6);
+        let Some(Target::Process { pid: latest }) = report.retarget else {
+            panic!("restarted process not reported");
+        };
+        assert_ne!(latest, pid);
+        assert!(Target::process(latest).exists().await);
+
+        let stdout = report
+            .artifacts
+            .iter()
+            .find(|p| p.to_string_lossy().ends_with("-1.stdout.log"));
+        let stdout = std::fs::read_to_string(stdout.unwrap()).unwrap();
+        assert_eq!(stdout, "started\n");
+
+        // The restarted process outlives the injection
+        injector.remove(handle).await.unwrap();
+        assert!(Target::process(latest).exists().await);
+        send_signal(Signal::SIGKILL, latest).await.unwrap();
+        std::fs::remove_dir_all(&log_dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_remove_mid_cycle_leaves_service_running() {
+        let mut service = std::process::Command::new("sleep")
+            .arg("60")
+            .spawn()
+            .unwrap();
+        let pid = service.id();
+        std::thread::spawn(move || service.wait());
+
+        let dir = std::env::temp_dir().join(format!("chaos-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let pid_file = dir.join("pid");
+        let injector = ProcessKillInjector::builder()
+            .signal(Signal::SIGKILL)
+            .restart_mode(RestartMode::ColdBoot)
+            .restart_delay(Duration::from_millis(500))
+            .restart_command(format!("echo $$ > {}; exec sleep 60", pid_file.display()))
+            .interval(Duration::from_millis(100))
+            .log_dir(&dir)
+            .build();
+
+        let handle = injector.inject(&Target::process(pid)).await.unwrap();
+        let first = handle.metadata["new_pid"].as_u64().unwrap() as u32;
+        // Wait for the next cycle to kill the restarted process, then remove
+        // while it sleeps before restarting
+        for _ in 0..50 {
+            if !Target::process(first).exists().await {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(!Target::process(first).exists().await);
+        injector.remove(handle).await.unwrap();
+
+        // The restarted shell records its PID shortly after being spawned
+        let mut latest = first;
+        for _ in 0..50 {
+            latest = std::fs::read_to_string(&pid_file)
+                .unwrap()
+                .trim()
+                .parse()
+                .unwrap();
+            if latest != first {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_ne!(latest, first);
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(Target::process(latest).exists().await);
+        send_signal(Signal::SIGKILL, latest).await.unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+    }
 }
@@ -0,0 +1,621 @@
+use super::network::LatencyDistribution;
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+use crate::tracer::{Registers, SyscallHook, Tracer};
+use crate::{
+    error::*,
+    handle::InjectionHandle,
+    injectors::{params, Injector, InjectorParams},
+    target::Target,
+};
+use async_trait::async_trait;
+use rand::Rng;
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::info;
+
+/// Syscalls a rule may select, by name. `accept` also covers `accept4`.
+#[cfg(target_arch = "x86_64")]
+const SYSCALLS: &[(&str, &[i64])] = &[
+    ("connect", &[libc::SYS_connect]),
+    ("accept", &[libc::SYS_accept, libc::SYS_accept4]),
+    ("read", &[libc::SYS_read]),
+    ("pread64", &[libc::SYS_pread64]),
+    ("recvfrom", &[libc::SYS_recvfrom]),
+    ("write", &[libc::SYS_write]),
+    ("pwrite64", &[libc::SYS_pwrite64]),
+    ("sendto", &[libc::SYS_sendto]),
+    ("open", &[libc::SYS_open]),
+    ("openat", &[libc::SYS_openat]),
+    ("close", &[libc::SYS_close]),
+    ("fsync", &[libc::SYS_fsync]),
+    ("fdatasync", &[libc::SYS_fdatasync]),
+];
+
+#[cfg(not(target_arch = "x86_64"))]
+const SYSCALLS: &[(&str, &[i64])] = &[];
+
+/// Error names accepted for `errno`
+const ERRNOS: &[(&str, i32)] = &[
+    ("EPERM", libc::EPERM),
+    ("ENOENT", libc::ENOENT),
+    ("EINTR", libc::EINTR),
+    ("EIO", libc::EIO),
+    ("EBADF", libc::EBADF),
+    ("EAGAIN", libc::EAGAIN),
+    ("ENOMEM", libc::ENOMEM),
+    ("EACCES", libc::EACCES),
+    ("EMFILE", libc::EMFILE),
+    ("ENOSPC", libc::ENOSPC),
+    ("EPIPE", libc::EPIPE),
+    ("EDQUOT", libc::EDQUOT),
+    ("ENETUNREACH", libc::ENETUNREACH),
+    ("ECONNABORTED", libc::ECONNABORTED),
+    ("ECONNRESET", libc::ECONNRESET),
+    ("ETIMEDOUT", libc::ETIMEDOUT),
+    ("ECONNREFUSED", libc::ECONNREFUSED),
+    ("EHOSTUNREACH", libc::EHOSTUNREACH),
+];
+
+/// Syscall numbers `name` stands for
+fn syscall_numbers(name: &str) -> Option<&'static [i64]> {
+    SYSCALLS
+        .iter()
+        .find(|(known, _)| *known == name)
+        .map(|(_, numbers)| *numbers)
+}
+
+/// An errno given by name (`ECONNREFUSED`) or number
+fn parse_errno(value: &serde_json::Value) -> Option<i32> {
+    match value {
+        serde_json::Value::Number(n) => n.as_i64().and_then(|n| i32::try_from(n).ok()),
+        serde_json::Value::String(s) => ERRNOS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(s))
+            .map(|(_, errno)| *errno),
+        _ => None,
+    }
+    .filter(|errno| *errno > 0)
+}
+
+/// Fails or delays the selected syscalls
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyscallFaultRule {
+    /// Names such as `connect` or `openat`
+    pub syscalls: Vec<String>,
+    /// Chance that the rule fires for one call
+    pub probability: f64,
+    /// Returned instead of running the syscall
+    pub errno: Option<i32>,
+    /// In milliseconds, before the syscall runs
+    pub delay: Option<LatencyDistribution>,
+}
+
+impl SyscallFaultRule {
+    pub fn new<S: Into<String>>(syscalls: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            syscalls: syscalls.into_iter().map(Into::into).collect(),
+            probability: 1.0,
+            errno: None,
+            delay: None,
+        }
+    }
+
+    pub fn probability(mut self, probability: f64) -> Self {
+        self.probability = probability;
+        self
+    }
+
+    pub fn errno(mut self, errno: i32) -> Self {
+        self.errno = Some(errno);
+        self
+    }
+
+    pub fn delay(mut self, delay: LatencyDistribution) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.syscalls.is_empty() {
+            return Err(ChaosError::InvalidConfig(
+                "syscall_fault rule selects no syscalls".to_string(),
+            ));
+        }
+        if let Some(unknown) = self
+            .syscalls
+            .iter()
+            .find(|name| syscall_numbers(name).is_none())
+        {
+            return Err(ChaosError::InvalidConfig(format!(
+                "syscall_fault cannot select '{}' (supported: {})",
+                unknown,
+                SYSCALLS
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
+        }
+        if !(0.0..=1.0).contains(&self.probability) {
+            return Err(ChaosError::InvalidConfig(format!(
+                "syscall_fault rule probability must be between 0.0 and 1.0, got {}",
+                self.probability
+            )));
+        }
+        if self.errno.is_some_and(|errno| errno <= 0) {
+            return Err(ChaosError::InvalidConfig(
+                "syscall_fault errno must be positive".to_string(),
+            ));
+        }
+        if self.errno.is_none() && self.delay.is_none() {
+            return Err(ChaosError::InvalidConfig(format!(
+                "syscall_fault rule for {} needs an errno or a delay",
+                self.syscalls.join(", ")
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyscallFaultConfig {
+    pub rules: Vec<SyscallFaultRule>,
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+/// What one syscall suffers
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct SyscallFault {
+    pub delay: Duration,
+    pub errno: Option<i32>,
+}
+
+/// Roll every rule selecting syscall `number`; delays of firing rules add up
+/// and the first errno wins
+#[cfg_attr(
+    not(all(target_os = "linux", target_arch = "x86_64")),
+    allow(dead_code)
+)]
+pub(crate) fn plan_fault<R: Rng>(
+    rules: &[SyscallFaultRule],
+    number: i64,
+    rng: &mut R,
+) -> SyscallFault {
+    let mut fault = SyscallFault::default();
+    for rule in rules {
+        let applies = rule
+            .syscalls
+            .iter()
+            .filter_map(|name| syscall_numbers(name))
+            .any(|numbers| numbers.contains(&number));
+        if !applies || !rng.gen_bool(rule.probability) {
+            continue;
+        }
+        if let Some(delay) = &rule.delay {
+            fault.delay += Duration::from_secs_f64(delay.sample(rng).max(0.0) / 1000.0);
+        }
+        if fault.errno.is_none() {
+            fault.errno = rule.errno;
+        }
+    }
+    fault
+}
+
+/// Traces a process with ptrace and makes selected syscalls fail with an
+/// errno or stall before they run, without restarting the process
+#[derive(Default)]
+pub struct SyscallFaultInjector {
+    config: SyscallFaultConfig,
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    tracers: Mutex<HashMap<String, Tracer>>,
+    #[cfg(not(all(target_os = "linux", target_arch = "x86_64")))]
+    #[allow(dead_code)]
+    tracers: Mutex<HashMap<String, ()>>,
+}
+
+impl SyscallFaultInjector {
+    pub fn new(config: SyscallFaultConfig) -> Self {
+        Self {
+            config,
+            tracers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn builder() -> SyscallFaultBuilder {
+        SyscallFaultBuilder::default()
+    }
+
+    pub fn from_params(params: &InjectorParams) -> Result<Self> {
+        let params: SyscallFaultParams = params::parse_params("syscall_fault", params)?;
+        let mut builder = Self::builder();
+        for rule in params.rules {
+            builder = builder.rule(rule.into_rule()?);
+        }
+        if let Some(seed) = params.seed {
+            builder = builder.seed(seed);
+        }
+        let injector = builder.build();
+        injector.validate_config()?;
+        Ok(injector)
+    }
+
+    fn validate_config(&self) -> Result<()> {
+        self.config
+            .rules
+            .iter()
+            .try_for_each(SyscallFaultRule::validate)
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    async fn inject_linux(&self, target: &Target) -> Result<InjectionHandle> {
+        self.validate_config()?;
+        if self.config.rules.is_empty() {
+            return Err(ChaosError::InvalidConfig(
+                "syscall_fault requires at least one rule".to_string(),
+            ));
+        }
+        let pid = target.pid().await?;
+        info!(
+            "Injecting syscall faults into {} ({} rule(s))",
+            target.description(),
+            self.config.rules.len()
+        );
+
+        let hook = FaultHook {
+            rules: self.config.rules.clone(),
+            rng: self
+                .config
+                .seed
+                .map_or_else(StdRng::from_entropy, StdRng::seed_from_u64),
+        };
+        let tracer = tokio::task::spawn_blocking(move || Tracer::attach(pid, hook))
+            .await
+            .map_err(|e| ChaosError::InjectionFailed(e.to_string()))??;
+
+        let metadata = serde_json::json!({
+            "pid": pid,
+            "rules": self.config.rules.len(),
+        });
+        let handle = InjectionHandle::new("syscall_fault", target.clone(), metadata);
+        self.tracers
+            .lock()
+            .unwrap()
+            .insert(handle.id.clone(), tracer);
+        Ok(handle)
+    }
+
+    #[cfg(not(all(target_os = "linux", target_arch = "x86_64")))]
+    async fn inject_linux(&self, _target: &Target) -> Result<InjectionHandle> {
+        Err(ChaosError::SystemError(
+            "Syscall fault injection is only supported on x86_64 Linux".to_string(),
+        ))
+    }
+}
+
+/// Rolls the rules for every syscall a traced thread enters
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+struct FaultHook {
+    rules: Vec<SyscallFaultRule>,
+    rng: StdRng,
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+impl SyscallHook for FaultHook {
+    fn enter(&mut self, regs: &Registers) -> SyscallFault {
+        plan_fault(&self.rules, regs.orig_rax as i64, &mut self.rng)
+    }
+}
+
+#[async_trait]
+impl Injector for SyscallFaultInjector {
+    async fn inject(&self, target: &Target) -> Result<InjectionHandle> {
+        self.inject_linux(target).await
+    }
+
+    async fn remove(&self, handle: InjectionHandle) -> Result<()> {
+        let tracer = self.tracers.lock().unwrap().remove(&handle.id);
+        match tracer {
+            #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+            Some(tracer) => tokio::task::spawn_blocking(move || tracer.detach())
+                .await
+                .map_err(|e| ChaosError::CleanupFailed(e.to_string()))??,
+            #[cfg(not(all(target_os = "linux", target_arch = "x86_64")))]
+            Some(()) => {}
+            // The kernel detaches a tracer's tracees when it exits, so a
+            // handle from before a restart has nothing left to undo
+            None => info!("No tracer for {}, already detached", handle.id),
+        }
+        info!("Removed syscall faults");
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "syscall_fault"
+    }
+
+    async fn validate(&self) -> Result<()> {
+        self.validate_config()
+    }
+
+    fn required_capabilities(&self) -> Vec<String> {
+        vec!["CAP_SYS_PTRACE".to_string()]
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SyscallFaultParams {
+    #[serde(default)]
+    rules: Vec<SyscallRuleParams>,
+    #[serde(default)]
+    seed: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SyscallRuleParams {
+    syscalls: Vec<String>,
+    #[serde(default)]
+    probability: Option<f64>,
+    #[serde(default)]
+    errno: Option<serde_json::Value>,
+    #[serde(default, deserialize_with = "params::opt_duration")]
+    delay: Option<Duration>,
+    #[serde(default, deserialize_with = "params::opt_duration")]
+    jitter: Option<Duration>,
+}
+
+impl SyscallRuleParams {
+    fn into_rule(self) -> Result<SyscallFaultRule> {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let delay = match (self.delay, self.jitter) {
+            (Some(delay), Some(jitter)) => Some(LatencyDistribution::Normal {
+                mean: ms(delay),
+                std_dev: ms(jitter),
+            }),
+            (Some(delay), None) => Some(LatencyDistribution::Fixed { value: ms(delay) }),
+            (None, Some(_)) => {
+                return Err(ChaosError::InvalidConfig(
+                    "syscall_fault jitter requires a delay".to_string(),
+                ))
+            }
+            (None, None) => None,
+        };
+        let errno = self
+            .errno
+            .map(|value| {
+                parse_errno(&value).ok_or_else(|| {
+                    ChaosError::InvalidConfig(format!(
+                        "Invalid syscall_fault errno {}: expected e.g. 'ECONNREFUSED' or 111",
+                        value
+                    ))
+                })
+            })
+            .transpose()?;
+        Ok(SyscallFaultRule {
+            probability: self.probability.unwrap_or(1.0),
+            errno,
+            delay,
+            ..SyscallFaultRule::new(self.syscalls)
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct SyscallFaultBuilder {
+    config: SyscallFaultConfig,
+}
+
+impl SyscallFaultBuilder {
+    pub fn rule(mut self, rule: SyscallFaultRule) -> Self {
+        self.config.rules.push(rule);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.config.seed = Some(seed);
+        self
+    }
+
+    pub fn build(self) -> SyscallFaultInjector {
+        SyscallFaultInjector::new(self.config)
+    }
+}
+
+#[cfg(all(test, target_arch = "x86_64"))]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_plan_fault() {
+        let rules = vec![
+            SyscallFaultRule::new(["connect"]).errno(libc::ECONNREFUSED),
+            SyscallFaultRule::new(["connect", "accept"])
+                .delay(LatencyDistribution::Fixed { value: 20.0 })
+                .errno(libc::ETIMEDOUT),
+            SyscallFaultRule::new(["fsync"])
+                .probability(0.0)
+                .errno(libc::EIO),
+        ];
+        let mut rng = StdRng::seed_from_u64(1);
+        assert_eq!(
+            plan_fault(&rules, libc::SYS_connect, &mut rng),
+            SyscallFault {
+                delay: Duration::from_millis(20),
+                errno: Some(libc::ECONNREFUSED),
+            }
+        );
+        assert_eq!(
+            plan_fault(&rules, libc::SYS_accept4, &mut rng).errno,
+            Some(libc::ETIMEDOUT)
+        );
+        assert_eq!(
+            plan_fault(&rules, libc::SYS_fsync, &mut rng),
+            SyscallFault::default()
+        );
+        assert_eq!(
+            plan_fault(&rules, libc::SYS_read, &mut rng),
+            SyscallFault::default()
+        );
+    }
+
+    #[test]
+    fn test_syscall_fault_params() {
+        let params: InjectorParams = serde_json::from_value(serde_json::json!({
+            "rules": [
+                {"syscalls": ["connect"], "errno": "ECONNREFUSED", "probability": 0.5},
+                {"syscalls": ["write", "fsync"], "delay": "50ms", "jitter": "10ms"},
+                {"syscalls": ["read"], "errno": 5},
+            ],
+            "seed": 7,
+        }))
+        .unwrap();
+        let injector = SyscallFaultInjector::from_params(&params).unwrap();
+        let rules = &injector.config.rules;
+        assert_eq!(rules.len(), 3);
+        assert_eq!(rules[0].errno, Some(libc::ECONNREFUSED));
+        assert_eq!(rules[0].probability, 0.5);
+        assert!(matches!(
+            rules[1].delay,
+            Some(LatencyDistribution::Normal { mean, std_dev }) if mean == 50.0 && std_dev == 10.0
+        ));
+        assert_eq!(rules[2].errno, Some(libc::EIO));
+        assert_eq!(injector.config.seed, Some(7));
+
+        for invalid in [
+            serde_json::json!({"rules": [{"syscalls": ["connect"]}]}),
+            serde_json::json!({"rules": [{"syscalls": ["mmap"], "errno": "EIO"}]}),
+            serde_json::json!({"rules": [{"syscalls": [], "errno": "EIO"}]}),
+            serde_json::json!({"rules": [{"syscalls": ["read"], "errno": "EWHATEVER"}]}),
+            serde_json::json!({"rules": [{"syscalls": ["read"], "jitter": "5ms"}]}),
+            serde_json::json!({"rules": [{"syscalls": ["read"], "errno": "EIO", "probability": 2.0}]}),
+        ] {
+            let params: InjectorParams = serde_json::from_value(invalid).unwrap();
+            assert!(SyscallFaultInjector::from_params(&params).is_err());
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_syscall_fault_live() {
+        let dir = std::env::temp_dir().join(format!("chaos_syscall_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("input");
+        let log = dir.join("log");
+        std::fs::write(&input, "hello\n").unwrap();
+        let script = format!(
+            "while :; do if read x < {}; then echo ok; else echo fail; fi; sleep 0.05; done > {} 2>/dev/null",
+            input.display(),
+            log.display()
+        );
+        let mut child = std::process::Command::new("sh")
+            .args(["-c", &script])
+            .spawn()
+            .unwrap();
+        let last_line = || async {
+            tokio::time::sleep(Duration::from_millis(400)).await;
+            let log = std::fs::read_to_string(&log).unwrap_or_default();
+            log.lines().last().unwrap_or_default().to_string()
+        };
+        assert_eq!(last_line().await, "ok");
+
+        let injector = SyscallFaultInjector::builder()
+            .rule(SyscallFaultRule::new(["openat"]).errno(libc::ENOENT))
+            .build();
+        let target = Target::Process { pid: child.id() };
+        let handle = match injector.inject(&target).await {
+            Ok(handle) => handle,
+            Err(e) => {
+                // Needs ptrace permission over the child
+                eprintln!("skipping syscall_fault test: {}", e);
+                child.kill().unwrap();
+                child.wait().unwrap();
+                std::fs::remove_dir_all(&dir).unwrap();
+                return;
+            }
+        };
+        assert_eq!(last_line().await, "fail");
+
+        injector.remove(handle).await.unwrap();
+        assert_eq!(last_line().await, "ok");
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[tokio::test]
+    async fn test_syscall_delay_live() {
+        use std::io::{BufRead, BufReader, Write};
+
+        let script = "\
+import os, sys, time
+for _ in sys.stdin:
+    start = time.monotonic()
+    try:
+        os.close(os.open('/dev/null', os.O_RDONLY))
+        result = 'ok'
+    except OSError as e:
+        result = 'errno%d' % e.errno
+    print(result, int((time.monotonic() - start) * 1000), flush=True)
+";
+        let Ok(mut child) = std::process::Command::new("python3")
+            .args(["-c", script])
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+        else {
+            eprintln!("skipping syscall delay test: python3 not found");
+            return;
+        };
+        let mut stdin = child.stdin.take().unwrap();
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut round = move || {
+            stdin.write_all(b"\n").unwrap();
+            let mut line = String::new();
+            stdout.read_line(&mut line).unwrap();
+            let (result, ms) = line.trim().split_once(' ').unwrap();
+            (result.to_string(), ms.parse::<u64>().unwrap())
+        };
+        assert_eq!(round().0, "ok");
+
+        let injector = SyscallFaultInjector::builder()
+            .rule(
+                SyscallFaultRule::new(["openat"])
+                    .errno(libc::EACCES)
+                    .delay(LatencyDistribution::Fixed { value: 200.0 }),
+            )
+            .build();
+        let target = Target::Process { pid: child.id() };
+        let handle = match injector.inject(&target).await {
+            Ok(handle) => handle,
+            Err(e) => {
+                // Needs ptrace permission over the child
+                eprintln!("skipping syscall delay test: {}", e);
+                child.kill().unwrap();
+                child.wait().unwrap();
+                return;
+            }
+        };
+        for _ in 0..3 {
+            let (result, ms) = round();
+            assert_eq!(result, format!("errno{}", libc::EACCES));
+            assert!((200..1000).contains(&ms), "delayed {}ms", ms);
+        }
+
+        injector.remove(handle).await.unwrap();
+        let (result, ms) = round();
+        assert_eq!(result, "ok");
+        assert!(ms < 200, "still delayed {}ms", ms);
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
@@ -0,0 +1,308 @@
+use crate::{
+    error::{ChaosError, Result},
+    handle::InjectionHandle,
+    injectors::InjectorParams,
+    state,
+    target::Target,
+};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// One injection as recorded on disk. The entry is written before the
+/// injector runs, completed with its handle once applied, and deleted after
+/// a successful `remove`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub id: String,
+    pub injector_name: String,
+    pub target: Target,
+    #[serde(default)]
+    pub params: InjectorParams,
+    /// Set once the injector returned; `None` means the process stopped
+    /// while the injection was being applied
+    pub handle: Option<InjectionHandle>,
+    /// Process that applied the injection
+    pub owner_pid: u32,
+    /// Start time of the owner (see [`state::process_start_time`]), so a
+    /// reused PID is not mistaken for the owner
+    #[serde(default)]
+    pub owner_start: Option<u64>,
+    /// Left in place on purpose (e.g. `chaos attach` without a duration);
+    /// only removed by an explicit cleanup
+    #[serde(default)]
+    pub detached: bool,
+    pub recorded_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl JournalEntry {
+    /// Whether the process that applied this injection is gone
+    pub async fn is_orphaned(&self) -> bool {
+        let alive =
+            self.owner_pid == std::process::id() || Target::process(self.owner_pid).exists().await;
+        if !alive {
+            return true;
+        }
+        // A live process that started at another time reuses the owner's PID
+        match (self.owner_start, state::process_start_time(self.owner_pid)) {
+            (Some(recorded), Some(current)) => recorded != current,
+            _ => false,
+        }
+    }
+}
+
+/// Directory of JSON files, one per injection that may still be applied
+#[derive(Debug, Clone)]
+pub struct Journal {
+    dir: PathBuf,
+}
+
+impl Journal {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// `$CHAOS_JOURNAL_DIR`, or `journal` under the private runtime dir
+    /// (`/run/chaos` for root). A tmpfs location is deliberate: qdiscs and
+    /// firewall rules do not survive a reboot either.
+    pub fn default_dir() -> PathBuf {
+        std::env::var_os("CHAOS_JOURNAL_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| state::runtime_dir().join("journal"))
+    }
+
+    pub fn open_default() -> Self {
+        Self::new(Self::default_dir())
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Record that an injection is about to be applied
+    pub async fn record_intent(
+        &self,
+        injector_name: &str,
+        target: &Target,
+        params: &InjectorParams,
+    ) -> Result<String> {
+        let entry = JournalEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            injector_name: injector_name.to_string(),
+            target: target.clone(),
+            params: params.clone(),
+            handle: None,
+            owner_pid: std::process::id(),
+            owner_start: state::process_start_time(std::process::id()),
+            detached: false,
+            recorded_at: chrono::Utc::now(),
+        };
+        self.write(&entry).await?;
+        Ok(entry.id)
+    }
+
+    /// Attach the handle returned by the injector to its entry
+    pub async fn record_applied(&self, entry_id: &str, handle: &InjectionHandle) -> Result<()> {
+        let mut entry = self.read(entry_id).await?;
+        entry.handle = Some(handle.clone());
+        self.write(&entry).await
+    }
+
+    /// Mark an entry as intentionally left applied
+    pub async fn mark_detached(&self, entry_id: &str) -> Result<()> {
+        let mut entry = self.read(entry_id).await?;
+        entry.detached = true;
+        self.write(&entry).await
+    }
+
+    /// Forget an entry once its injection has been removed
+    pub async fn clear(&self, entry_id: &str) -> Result<()> {
+        match tokio::fs::remove_file(self.entry_path(entry_id)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// All entries currently on disk, oldest first. Unreadable files and
+    /// files owned by another user are skipped with a warning rather than
+    /// failing the whole listing; a directory other users can write to is
+    /// refused outright.
+    pub async fn entries(&self) -> Result<Vec<JournalEntry>> {
+        match state::check_private_dir(&self.dir).await {
+            Ok(()) => {}
+            Err(ChaosError::IoError(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Vec::new())
+            }
+            Err(e) => return Err(untrusted(e)),
+        }
+        let mut dir = tokio::fs::read_dir(&self.dir).await?;
+
+        let mut entries = Vec::new();
+        while let Some(file) = dir.next_entry().await? {
+            let path = file.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            if !tokio::fs::symlink_metadata(&path)
+                .await
+                .is_ok_and(|meta| state::owned_by_us(&meta))
+            {
+                warn!(
+                    "Skipping journal entry {} not owned by this user",
+                    path.display()
+                );
+                continue;
+            }
+            match tokio::fs::read(&path)
+                .await
+                .map(|b| serde_json::from_slice(&b))
+            {
+                Ok(Ok(entry)) => entries.push(entry),
+                Ok(Err(e)) => warn!("Skipping corrupt journal entry {}: {}", path.display(), e),
+                Err(e) => warn!(
+                    "Skipping unreadable journal entry {}: {}",
+                    path.display(),
+                    e
+                ),
+            }
+        }
+
+        entries.sort_by_key(|e: &JournalEntry| e.recorded_at);
+        Ok(entries)
+    }
+
+    fn entry_path(&self, entry_id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", entry_id))
+    }
+
+    async fn read(&self, entry_id: &str) -> Result<JournalEntry> {
+        let path = self.entry_path(entry_id);
+        let unreadable = |e: &dyn std::fmt::Display| {
+            ChaosError::SystemError(format!("Journal entry '{}' unreadable: {}", entry_id, e))
+        };
+        let meta = tokio::fs::symlink_metadata(&path)
+            .await
+            .map_err(|e| unreadable(&e))?;
+        if !state::owned_by_us(&meta) {
+            return Err(unreadable(&"not owned by this user"));
+        }
+        let bytes = tokio::fs::read(&path).await.map_err(|e| unreadable(&e))?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Write via a temp file and rename so a crash never leaves a torn entry
+    async fn write(&self, entry: &JournalEntry) -> Result<()> {
+        state::ensure_private_dir(&self.dir)
+            .await
+            .map_err(untrusted)?;
+        let path = self.entry_path(&entry.id);
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec_pretty(entry)?).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+}
+
+fn untrusted(e: ChaosError) -> ChaosError {
+    ChaosError::SystemError(format!("Refusing to use the journal: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_journal() -> Journal {
+        Journal::new(
+            std::env::temp_dir().join(format!("chaos_journal_test_{}", uuid::Uuid::new_v4())),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_journal_lifecycle() {
+        let journal = temp_journal();
+        let target = Target::process(1234);
+
+        let id = journal
+            .record_intent("cpu_starvation", &target, &InjectorParams::new())
+            .await
+            .unwrap();
+        let entries = journal.entries().await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].handle.is_none());
+        assert!(!entries[0].is_orphaned().await);
+
+        let handle = InjectionHandle::new("cpu_starvation", target, serde_json::json!({}));
+        journal.record_applied(&id, &handle).await.unwrap();
+        journal.mark_detached(&id).await.unwrap();
+        let entries = journal.entries().await.unwrap();
+        assert_eq!(entries[0].handle.as_ref().unwrap().id, handle.id);
+        assert!(entries[0].detached);
+
+        journal.clear(&id).await.unwrap();
+        journal.clear(&id).await.unwrap();
+        assert!(journal.entries().await.unwrap().is_empty());
+
+        tokio::fs::remove_dir_all(journal.dir()).await.ok();
+    }
+
+    #[tokio::test]
+    async fn test_reused_owner_pid_is_orphaned() {
+        let journal = temp_journal();
+        let id = journal
+            .record_intent(
+                "cpu_starvation",
+                &Target::process(1),
+                &InjectorParams::new(),
+            )
+            .await
+            .unwrap();
+        let mut entry = journal.read(&id).await.unwrap();
+
+        // Our PID, but a process that started at another time
+        entry.owner_start = entry.owner_start.map(|start| start + 1);
+        assert!(entry.is_orphaned().await);
+        entry.owner_start = None;
+        assert!(!entry.is_orphaned().await);
+
+        tokio::fs::remove_dir_all(journal.dir()).await.ok();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_untrusted_entries_refused() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let journal = temp_journal();
+        let id = journal
+            .record_intent("disk_fill", &Target::process(1), &InjectorParams::new())
+            .await
+            .unwrap();
+
+        // A file another user planted is skipped
+        if nix::unistd::geteuid().is_root() {
+            let planted = journal.dir().join("planted.json");
+            std::fs::copy(journal.entry_path(&id), &planted).unwrap();
+            nix::unistd::chown(&planted, Some(nix::unistd::Uid::from_raw(65534)), None).unwrap();
+            assert_eq!(journal.entries().await.unwrap().len(), 1);
+        } else {
+            eprintln!("skipping planted entry check: needs root");
+        }
+
+        // A directory anyone can write to is refused
+        std::fs::set_permissions(journal.dir(), std::fs::Permissions::from_mode(0o777)).unwrap();
+        assert!(journal.entries().await.is_err());
+        assert!(journal
+            .record_intent("disk_fill", &Target::process(1), &InjectorParams::new())
+            .await
+            .is_err());
+
+        tokio::fs::remove_dir_all(journal.dir()).await.ok();
+    }
+
+    #[tokio::test]
+    async fn test_missing_journal_dir_is_empty() {
+        let journal = temp_journal();
+        assert!(journal.entries().await.unwrap().is_empty());
+    }
+}
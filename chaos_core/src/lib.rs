@@ -1,13 +1,25 @@
+pub mod cgroup;
 pub mod error;
 pub mod executor;
 pub mod handle;
+pub mod health;
 pub mod injectors;
+pub mod journal;
+pub mod netns;
+pub mod selector;
+pub mod state;
 pub mod target;
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+pub(crate) mod tracer;
 
+pub use cgroup::ChaosCgroup;
 pub use error::{ChaosError, Result};
-pub use executor::Executor;
-pub use handle::InjectionHandle;
+pub use executor::{Executor, RecoveryReport};
+pub use handle::{InjectionHandle, InjectionReport};
 pub use injectors::*;
+pub use journal::{Journal, JournalEntry};
+pub use netns::NetNamespace;
+pub use selector::{ProcessSelector, SelectionPolicy};
 pub use target::Target;
 
 // Re-export commonly used types
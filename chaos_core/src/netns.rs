@@ -0,0 +1,132 @@
+use crate::{error::*, target::Target};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+/// A network namespace that tc and iptables are run inside via
+/// `nsenter --net=<path>`, so faults hit a container's own interfaces
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct NetNamespace {
+    path: PathBuf,
+}
+
+impl NetNamespace {
+    pub fn from_path(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Namespace of a running process
+    pub fn for_pid(pid: u32) -> Self {
+        Self::from_path(format!("/proc/{}/ns/net", pid))
+    }
+
+    /// Namespace created with `ip netns add <name>`
+    pub fn named(name: &str) -> Self {
+        Self::from_path(Path::new("/run/netns").join(name))
+    }
+
+    /// An `ip netns` name, or an absolute path to a namespace file
+    pub fn parse(spec: &str) -> Self {
+        if spec.starts_with('/') {
+            Self::from_path(spec)
+        } else {
+            Self::named(spec)
+        }
+    }
+
+    /// Namespace a process or container target lives in, or `None` when it
+    /// shares ours or the target has no process behind it
+    pub async fn for_target(target: &Target) -> Result<Option<Self>> {
+        let netns = match target {
+            Target::Process { .. } | Target::Thread { .. } | Target::Container { .. } => {
+                Self::for_pid(target.pid().await?)
+            }
+            _ => return Ok(None),
+        };
+        Ok((!netns.is_current()).then_some(netns))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn exists(&self) -> bool {
+        self.path.exists()
+    }
+
+    /// Whether this is the namespace the framework itself runs in
+    pub fn is_current(&self) -> bool {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let (Ok(ours), Ok(theirs)) = (
+                std::fs::metadata("/proc/self/ns/net"),
+                std::fs::metadata(&self.path),
+            ) else {
+                return false;
+            };
+            ours.dev() == theirs.dev() && ours.ino() == theirs.ino()
+        }
+        #[cfg(not(unix))]
+        {
+            false
+        }
+    }
+
+    /// `program` wrapped to run inside this namespace
+    pub fn command(&self, program: &str) -> Command {
+        let mut command = Command::new("nsenter");
+        command
+            .arg(format!("--net={}", self.path.display()))
+            .arg("--")
+            .arg(program);
+        command
+    }
+}
+
+impl std::fmt::Display for NetNamespace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.path.display())
+    }
+}
+
+/// `program` run inside `netns`, or directly when there is none
+pub fn command_in(netns: Option<&NetNamespace>, program: &str) -> Command {
+    match netns {
+        Some(netns) => netns.command(program),
+        None => Command::new(program),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_namespace_paths() {
+        assert_eq!(
+            NetNamespace::parse("chaos-a").path(),
+            Path::new("/run/netns/chaos-a")
+        );
+        assert_eq!(
+            NetNamespace::parse("/proc/42/ns/net"),
+            NetNamespace::for_pid(42)
+        );
+
+        let command = NetNamespace::named("chaos-a").command("tc");
+        let args: Vec<_> = command.as_std().get_args().collect();
+        assert_eq!(command.as_std().get_program(), "nsenter");
+        assert_eq!(args, ["--net=/run/netns/chaos-a", "--", "tc"]);
+    }
+
+    #[tokio::test]
+    async fn test_own_namespace_is_not_entered() {
+        assert!(NetNamespace::for_pid(std::process::id()).is_current());
+        let target = Target::process(std::process::id());
+        assert_eq!(NetNamespace::for_target(&target).await.unwrap(), None);
+
+        let target = Target::network("127.0.0.1:80".parse().unwrap());
+        assert_eq!(NetNamespace::for_target(&target).await.unwrap(), None);
+    }
+}
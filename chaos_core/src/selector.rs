@@ -0,0 +1,461 @@
+use crate::error::{ChaosError, Result};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Criteria for picking processes out of /proc. Every field that is set must
+/// match; kernel threads and the framework's own process are never selected.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct ProcessSelector {
+    /// Matches `comm`, or the file name of `argv[0]` or the executable
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Regex matched against the space-joined command line
+    #[serde(default)]
+    pub cmdline_regex: Option<String>,
+    /// Exact path of the executable (`/proc/<pid>/exe`)
+    #[serde(default)]
+    pub exe_path: Option<String>,
+    /// User name or numeric UID owning the process
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub parent_pid: Option<u32>,
+}
+
+/// How many of the matching processes an injection hits
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum SelectionPolicy {
+    /// The lowest matching PID
+    First,
+    /// Every matching process
+    #[default]
+    All,
+    /// Up to `n` processes chosen at random
+    Random(usize),
+    /// A random share of the matches (0-100), at least one when non-zero
+    Percentage(u8),
+}
+
+impl ProcessSelector {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.cmdline_regex.is_none()
+            && self.exe_path.is_none()
+            && self.user.is_none()
+            && self.parent_pid.is_none()
+    }
+
+    pub fn description(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(name) = &self.name {
+            parts.push(format!("name={}", name));
+        }
+        if let Some(regex) = &self.cmdline_regex {
+            parts.push(format!("cmdline=~/{}/", regex));
+        }
+        if let Some(exe) = &self.exe_path {
+            parts.push(format!("exe={}", exe));
+        }
+        if let Some(user) = &self.user {
+            parts.push(format!("user={}", user));
+        }
+        if let Some(ppid) = self.parent_pid {
+            parts.push(format!("ppid={}", ppid));
+        }
+        parts.join(", ")
+    }
+
+    /// Check the selector is well-formed (non-empty, valid regex)
+    pub fn validate(&self) -> Result<()> {
+        if self.is_empty() {
+            return Err(ChaosError::InvalidConfig(
+                "Process selector needs at least one criterion".to_string(),
+            ));
+        }
+        if let Some(pattern) = &self.cmdline_regex {
+            regex::Regex::new(pattern).map_err(|e| {
+                ChaosError::InvalidConfig(format!("Invalid cmdline_regex '{}': {}", pattern, e))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// PIDs of all processes currently matching, in ascending order
+    pub fn matching_pids(&self) -> Result<Vec<u32>> {
+        self.validate()?;
+        let cmdline_regex = self
+            .cmdline_regex
+            .as_deref()
+            .map(regex::Regex::new)
+            .transpose()
+            .map_err(|e| ChaosError::InvalidConfig(e.to_string()))?;
+        let uid = self.user.as_deref().map(resolve_uid).transpose()?;
+
+        let own_pid = std::process::id();
+        let mut pids: Vec<u32> = proc::scan()?
+            .into_iter()
+            .filter(|p| p.pid != own_pid && !p.cmdline.is_empty())
+            .filter(|p| self.name.as_deref().is_none_or(|name| p.has_name(name)))
+            .filter(|p| {
+                cmdline_regex
+                    .as_ref()
+                    .is_none_or(|re| re.is_match(&p.cmdline.join(" ")))
+            })
+            .filter(|p| {
+                self.exe_path
+                    .as_deref()
+                    .is_none_or(|exe| p.exe.as_deref() == Some(std::path::Path::new(exe)))
+            })
+            .filter(|p| uid.is_none_or(|uid| p.uid == uid))
+            .filter(|p| self.parent_pid.is_none_or(|ppid| p.ppid == ppid))
+            .map(|p| p.pid)
+            .collect();
+
+        pids.sort_unstable();
+        Ok(pids)
+    }
+
+    /// Resolve to concrete PIDs and narrow them down with `policy`
+    pub fn resolve<R: Rng>(&self, policy: SelectionPolicy, rng: &mut R) -> Result<Vec<u32>> {
+        let pids = self.matching_pids()?;
+        if pids.is_empty() {
+            return Err(ChaosError::TargetNotFound(format!(
+                "No process matches {}",
+                self.description()
+            )));
+        }
+        Ok(policy.apply(pids, rng))
+    }
+}
+
+impl SelectionPolicy {
+    /// Pick from `pids` (sorted ascending); the result is sorted as well
+    pub fn apply<R: Rng>(&self, pids: Vec<u32>, rng: &mut R) -> Vec<u32> {
+        let count = match *self {
+            SelectionPolicy::First => return pids.into_iter().take(1).collect(),
+            SelectionPolicy::All => return pids,
+            SelectionPolicy::Random(n) => n.min(pids.len()),
+            SelectionPolicy::Percentage(pct) => {
+                let share = (pids.len() * pct.min(100) as usize).div_ceil(100);
+                share.min(pids.len())
+            }
+        };
+
+        let mut chosen: Vec<u32> = pids.choose_multiple(rng, count).copied().collect();
+        chosen.sort_unstable();
+        chosen
+    }
+}
+
+impl fmt::Display for SelectionPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SelectionPolicy::First => write!(f, "first"),
+            SelectionPolicy::All => write!(f, "all"),
+            SelectionPolicy::Random(n) => write!(f, "random({})", n),
+            SelectionPolicy::Percentage(pct) => write!(f, "{}%", pct),
+        }
+    }
+}
+
+impl FromStr for SelectionPolicy {
+    type Err = String;
+
+    /// Accepts `first`, `all`, `random(n)`, `percentage(p)` and `p%`
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        let invalid = || {
+            format!(
+                "Invalid selection policy '{}': expected first, all, random(n) or percentage(p)",
+                s
+            )
+        };
+
+        let argument = |prefix: &str| {
+            s.strip_prefix(prefix)
+                .and_then(|rest| rest.strip_prefix('('))
+                .and_then(|rest| rest.strip_suffix(')'))
+                .map(str::trim)
+        };
+
+        match s.as_str() {
+            "first" => Ok(SelectionPolicy::First),
+            "all" => Ok(SelectionPolicy::All),
+            _ => {
+                if let Some(n) = argument("random") {
+                    n.parse()
+                        .map(SelectionPolicy::Random)
+                        .map_err(|_| invalid())
+                } else if let Some(pct) = argument("percentage").or(s.strip_suffix('%')) {
+                    match pct.trim().parse::<u8>() {
+                        Ok(pct) if pct <= 100 => Ok(SelectionPolicy::Percentage(pct)),
+                        _ => Err(invalid()),
+                    }
+                } else {
+                    Err(invalid())
+                }
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for SelectionPolicy {
+    type Error = String;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<SelectionPolicy> for String {
+    fn from(policy: SelectionPolicy) -> Self {
+        policy.to_string()
+    }
+}
+
+/// `pid` followed by all of its descendants, parents before children
+pub fn process_tree(pid: u32) -> Result<Vec<u32>> {
+    let processes = proc::scan()?;
+    let mut tree = vec![pid];
+    let mut i = 0;
+    while let Some(&parent) = tree.get(i) {
+        tree.extend(
+            processes
+                .iter()
+                .filter(|p| p.ppid == parent && p.pid != parent)
+                .map(|p| p.pid),
+        );
+        i += 1;
+    }
+    Ok(tree)
+}
+
+fn resolve_uid(user: &str) -> Result<u32> {
+    if let Ok(uid) = user.parse::<u32>() {
+        return Ok(uid);
+    }
+
+    #[cfg(unix)]
+    {
+        nix::unistd::User::from_name(user)
+            .map_err(|e| ChaosError::SystemError(format!("Failed to look up user: {}", e)))?
+            .map(|u| u.uid.as_raw())
+            .ok_or_else(|| ChaosError::InvalidConfig(format!("Unknown user '{}'", user)))
+    }
+
+    #[cfg(not(unix))]
+    {
+        Err(ChaosError::InvalidConfig(format!(
+            "User names are not supported on this platform: '{}'",
+            user
+        )))
+    }
+}
+
+mod proc {
+    use crate::error::Result;
+    use std::path::{Path, PathBuf};
+
+    pub(super) struct ProcessInfo {
+        pub pid: u32,
+        pub ppid: u32,
+        pub uid: u32,
+        pub comm: String,
+        pub cmdline: Vec<String>,
+        pub exe: Option<PathBuf>,
+    }
+
+    impl ProcessInfo {
+        pub fn has_name(&self, name: &str) -> bool {
+            let file_name = |p: &Path| p.file_name().is_some_and(|f| f == name);
+            self.comm == name
+                || self
+                    .cmdline
+                    .first()
+                    .is_some_and(|arg0| file_name(Path::new(arg0)))
+                || self.exe.as_deref().is_some_and(file_name)
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(super) fn scan() -> Result<Vec<ProcessInfo>> {
+        let mut processes = Vec::new();
+
+        for entry in std::fs::read_dir("/proc")? {
+            let Ok(entry) = entry else { continue };
+            let Some(pid) = entry.file_name().to_str().and_then(|s| s.parse().ok()) else {
+                continue;
+            };
+            // Processes can exit between listing and reading; skip them
+            if let Some(info) = read(pid) {
+                processes.push(info);
+            }
+        }
+
+        Ok(processes)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(super) fn scan() -> Result<Vec<ProcessInfo>> {
+        Err(crate::error::ChaosError::SystemError(
+            "Process selectors require /proc and are only supported on Linux".to_string(),
+        ))
+    }
+
+    #[cfg(target_os = "linux")]
+    fn read(pid: u32) -> Option<ProcessInfo> {
+        let base = PathBuf::from(format!("/proc/{}", pid));
+        let status = std::fs::read_to_string(base.join("status")).ok()?;
+        let field = |key: &str| {
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix(key))
+                .and_then(|rest| rest.split_whitespace().next())
+                .and_then(|v| v.parse::<u32>().ok())
+        };
+
+        let cmdline = std::fs::read(base.join("cmdline")).ok()?;
+        let cmdline = cmdline
+            .split(|b| *b == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| String::from_utf8_lossy(arg).into_owned())
+            .collect();
+
+        Some(ProcessInfo {
+            pid,
+            ppid: field("PPid:")?,
+            uid: field("Uid:")?,
+            comm: std::fs::read_to_string(base.join("comm"))
+                .ok()?
+                .trim_end()
+                .to_string(),
+            cmdline,
+            exe: std::fs::read_link(base.join("exe")).ok(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_policy_parsing() {
+        assert_eq!("first".parse(), Ok(SelectionPolicy::First));
+        assert_eq!("ALL".parse(), Ok(SelectionPolicy::All));
+        assert_eq!("random(3)".parse(), Ok(SelectionPolicy::Random(3)));
+        assert_eq!(
+            "percentage(25)".parse(),
+            Ok(SelectionPolicy::Percentage(25))
+        );
+        assert_eq!("50%".parse(), Ok(SelectionPolicy::Percentage(50)));
+        assert!("random".parse::<SelectionPolicy>().is_err());
+        assert!("150%".parse::<SelectionPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_policy_apply() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let pids: Vec<u32> = (1..=10).collect();
+
+        assert_eq!(
+            SelectionPolicy::First.apply(pids.clone(), &mut rng),
+            vec![1]
+        );
+        assert_eq!(SelectionPolicy::All.apply(pids.clone(), &mut rng), pids);
+        assert_eq!(
+            SelectionPolicy::Random(3)
+                .apply(pids.clone(), &mut rng)
+                .len(),
+            3
+        );
+        assert_eq!(
+            SelectionPolicy::Percentage(25)
+                .apply(pids.clone(), &mut rng)
+                .len(),
+            3
+        );
+        assert_eq!(
+            SelectionPolicy::Percentage(1)
+                .apply(pids.clone(), &mut rng)
+                .len(),
+            1
+        );
+        assert!(SelectionPolicy::Percentage(0)
+            .apply(pids, &mut rng)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_selector_validation() {
+        assert!(ProcessSelector::default().validate().is_err());
+
+        let selector = ProcessSelector {
+            cmdline_regex: Some("(".to_string()),
+            ..Default::default()
+        };
+        assert!(selector.validate().is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_selector_resolves_child_process() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("31")
+            .spawn()
+            .unwrap();
+
+        let selector = ProcessSelector {
+            name: Some("sleep".to_string()),
+            cmdline_regex: Some("^sleep 31$".to_string()),
+            parent_pid: Some(std::process::id()),
+            ..Default::default()
+        };
+        // The child may still be exec'ing; give /proc a moment to catch up
+        let mut pids = selector.matching_pids();
+        for _ in 0..50 {
+            if pids.as_ref().is_ok_and(|p| !p.is_empty()) {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            pids = selector.matching_pids();
+        }
+
+        child.kill().ok();
+        child.wait().ok();
+
+        assert_eq!(pids.unwrap(), vec![child.id()]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_process_tree() {
+        let mut child = std::process::Command::new("sh")
+            .args(["-c", "sleep 32 & wait"])
+            .spawn()
+            .unwrap();
+        let mut tree = process_tree(child.id()).unwrap();
+        for _ in 0..50 {
+            if tree.len() > 1 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            tree = process_tree(child.id()).unwrap();
+        }
+        assert_eq!(tree[0], child.id());
+        assert_eq!(tree.len(), 2);
+        assert!(process_tree(std::process::id())
+            .unwrap()
+            .contains(&child.id()));
+
+        let _ = std::process::Command::new("kill")
+            .arg(tree[1].to_string())
+            .status();
+        child.wait().ok();
+    }
+}
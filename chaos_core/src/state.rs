@@ -0,0 +1,167 @@
+use crate::error::{ChaosError, Result};
+use std::path::{Path, PathBuf};
+use tokio::sync::{Mutex, MutexGuard};
+
+/// Directory for state that outlives a single chaos process, such as the
+/// journal and cross-process locks: `/run/chaos` for root,
+/// `$XDG_RUNTIME_DIR/chaos` or a per-user directory under the temp dir
+/// otherwise
+pub fn runtime_dir() -> PathBuf {
+    #[cfg(unix)]
+    {
+        let euid = nix::unistd::geteuid();
+        if euid.is_root() {
+            return PathBuf::from("/run/chaos");
+        }
+        if let Some(dir) = std::env::var_os("XDG_RUNTIME_DIR") {
+            return PathBuf::from(dir).join("chaos");
+        }
+        std::env::temp_dir().join(format!("chaos-{}", euid))
+    }
+    #[cfg(not(unix))]
+    std::env::temp_dir().join("chaos")
+}
+
+/// Create `dir` with mode 0700 if it is missing, then check that it can be
+/// trusted
+pub async fn ensure_private_dir(dir: &Path) -> Result<()> {
+    let mut builder = tokio::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    builder.mode(0o700);
+    builder.create(dir).await?;
+    check_private_dir(dir).await
+}
+
+/// Refuse a directory other users could plant files in: it must be a real
+/// directory owned by the effective user and not group- or world-writable
+pub async fn check_private_dir(dir: &Path) -> Result<()> {
+    let meta = tokio::fs::symlink_metadata(dir).await?;
+    if !meta.is_dir() {
+        return Err(ChaosError::SystemError(format!(
+            "{} is not a directory",
+            dir.display()
+        )));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        if meta.uid() != nix::unistd::geteuid().as_raw() {
+            return Err(ChaosError::SystemError(format!(
+                "{} is owned by uid {}, not by us",
+                dir.display(),
+                meta.uid()
+            )));
+        }
+        if meta.mode() & 0o022 != 0 {
+            return Err(ChaosError::SystemError(format!(
+                "{} is writable by other users (mode {:o})",
+                dir.display(),
+                meta.mode() & 0o777
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Held while editing state shared with other chaos processes; dropping it
+/// releases both the in-process mutex and the file lock
+pub struct StateLock {
+    _file: std::fs::File,
+    _guard: MutexGuard<'static, ()>,
+}
+
+/// Take `local`, then an exclusive `flock` on `name` in the runtime
+/// directory so other chaos processes wait as well
+pub async fn lock(local: &'static Mutex<()>, name: &str) -> Result<StateLock> {
+    let guard = local.lock().await;
+    let dir = runtime_dir();
+    ensure_private_dir(&dir).await?;
+    let mut options = std::fs::OpenOptions::new();
+    options.create(true).write(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let path = dir.join(name);
+    let file = options.open(&path)?;
+    // Another process may hold it for a while, so wait off the runtime
+    let file = tokio::task::spawn_blocking(move || {
+        #[cfg(unix)]
+        {
+            use std::os::unix::io::AsRawFd;
+            nix::fcntl::flock(file.as_raw_fd(), nix::fcntl::FlockArg::LockExclusive).map_err(
+                |e| ChaosError::SystemError(format!("Cannot lock {}: {}", path.display(), e)),
+            )?;
+        }
+        Ok::<_, ChaosError>(file)
+    })
+    .await
+    .map_err(|e| ChaosError::SystemError(e.to_string()))??;
+    Ok(StateLock {
+        _file: file,
+        _guard: guard,
+    })
+}
+
+/// Whether a file is a regular file owned by the effective user
+pub fn owned_by_us(meta: &std::fs::Metadata) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        meta.is_file() && meta.uid() == nix::unistd::geteuid().as_raw()
+    }
+    #[cfg(not(unix))]
+    meta.is_file()
+}
+
+/// When `pid` started, in clock ticks since boot, from `/proc/<pid>/stat`.
+/// Together with the PID it identifies a process across PID reuse.
+pub fn process_start_time(pid: u32) -> Option<u64> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    parse_start_time(&stat)
+}
+
+fn parse_start_time(stat: &str) -> Option<u64> {
+    // The command name may contain spaces and parentheses; fields after it
+    // start at `state` (field 3), so `starttime` (field 22) is the 20th
+    stat.rsplit_once(')')?
+        .1
+        .split_whitespace()
+        .nth(19)?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_start_time() {
+        let stat =
+            "4242 (a (b) c) S 1 4242 4242 0 -1 4194560 100 0 0 0 1 2 0 0 20 0 1 0 987654 1000 10";
+        assert_eq!(parse_start_time(stat), Some(987654));
+        assert_eq!(parse_start_time("4242 (sh) S 1"), None);
+
+        #[cfg(target_os = "linux")]
+        assert!(process_start_time(std::process::id()).is_some());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_private_dir() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("chaos_state_test_{}", uuid::Uuid::new_v4()));
+        let nested = dir.join("journal");
+        ensure_private_dir(&nested).await.unwrap();
+        let mode = std::fs::metadata(&nested).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+
+        std::fs::set_permissions(&nested, std::fs::Permissions::from_mode(0o777)).unwrap();
+        assert!(check_private_dir(&nested).await.is_err());
+        assert!(ensure_private_dir(&nested).await.is_err());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
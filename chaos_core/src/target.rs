@@ -1,3 +1,6 @@
+use crate::error::{ChaosError, Result};
+use crate::selector::{ProcessSelector, SelectionPolicy};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
 
@@ -17,6 +20,12 @@ pub enum Target {
 
     /// Target all processes matching a pattern
     ProcessPattern { pattern: String },
+
+    /// Processes picked from /proc at injection time
+    ProcessSelector {
+        selector: ProcessSelector,
+        policy: SelectionPolicy,
+    },
 }
 
 impl Target {
@@ -42,6 +51,10 @@ impl Target {
         }
     }
 
+    pub fn process_selector(selector: ProcessSelector, policy: SelectionPolicy) -> Self {
+        Self::ProcessSelector { selector, policy }
+    }
+
     pub fn description(&self) -> String {
         match self {
             Target::Process { pid } => format!("Process PID {}", pid),
@@ -49,6 +62,40 @@ impl Target {
             Target::Container { id } => format!("Container {}", id),
             Target::Thread { tid } => format!("Thread TID {}", tid),
             Target::ProcessPattern { pattern } => format!("Process pattern '{}'", pattern),
+            Target::ProcessSelector { selector, policy } => {
+                format!(
+                    "Processes [{}] (policy: {})",
+                    selector.description(),
+                    policy
+                )
+            }
+        }
+    }
+
+    /// Expand selector targets into concrete `Process` targets; every other
+    /// target resolves to itself
+    pub fn resolve<R: Rng>(&self, rng: &mut R) -> Result<Vec<Target>> {
+        match self {
+            Target::ProcessSelector { selector, policy } => Ok(selector
+                .resolve(*policy, rng)?
+                .into_iter()
+                .map(Target::process)
+                .collect()),
+            _ => Ok(vec![self.clone()]),
+        }
+    }
+
+    /// PID behind a process, thread or container target. Containers resolve
+    /// to their init process.
+    pub async fn pid(&self) -> Result<u32> {
+        match self {
+            Target::Process { pid } => Ok(*pid),
+            Target::Thread { tid } => Ok(*tid),
+            Target::Container { id } => container_pid(id).await,
+            _ => Err(ChaosError::InvalidConfig(format!(
+                "{} does not identify a single process",
+                self.description()
+            ))),
         }
     }
 
@@ -73,10 +120,7 @@ impl Target {
                 // Check if address is reachable
                 tokio::net::TcpStream::connect(address).await.is_ok()
             }
-            Target::Container { id } => {
-                // Check if container exists (simplified)
-                std::path::Path::new(&format!("/sys/fs/cgroup/docker/{}", id)).exists()
-            }
+            Target::Container { .. } => self.pid().await.is_ok(),
             Target::Thread { tid: _ } => {
                 #[cfg(unix)]
                 {
@@ -94,8 +138,66 @@ impl Target {
                 sys.refresh_processes();
                 sys.processes().values().any(|p| p.name().contains(pattern))
             }
+            Target::ProcessSelector { selector, .. } => selector
+                .matching_pids()
+                .map(|pids| !pids.is_empty())
+                .unwrap_or(false),
+        }
+    }
+}
+
+/// Ask docker or podman for the container's init PID, falling back to the
+/// cgroup paths in /proc for runtimes without a CLI (containerd, CRI-O)
+async fn container_pid(id: &str) -> Result<u32> {
+    for runtime in ["docker", "podman"] {
+        let Ok(output) = tokio::process::Command::new(runtime)
+            .args(["inspect", "--format", "{{.State.Pid}}", id])
+            .output()
+            .await
+        else {
+            continue;
+        };
+        let pid = String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse::<u32>();
+        if let (true, Ok(pid @ 1..)) = (output.status.success(), pid) {
+            return Ok(pid);
+        }
+    }
+
+    let mut pids = Vec::new();
+    if let Ok(entries) = std::fs::read_dir("/proc") {
+        for entry in entries.flatten() {
+            let Some(pid) = entry
+                .file_name()
+                .to_str()
+                .and_then(|s| s.parse::<u32>().ok())
+            else {
+                continue;
+            };
+            let cgroup = std::fs::read_to_string(entry.path().join("cgroup")).unwrap_or_default();
+            if cgroup_mentions(&cgroup, id) {
+                pids.push(pid);
+            }
         }
     }
+
+    pids.into_iter()
+        .min()
+        .ok_or_else(|| ChaosError::TargetNotFound(format!("Container {} is not running", id)))
+}
+
+/// Whether a /proc/<pid>/cgroup file places the process in the container.
+/// Only IDs (12+ hex digits) are matched, so names cannot hit unrelated paths.
+fn cgroup_mentions(cgroup: &str, id: &str) -> bool {
+    if id.len() < 12 || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return false;
+    }
+    cgroup.lines().any(|line| {
+        line.rsplit(':')
+            .next()
+            .is_some_and(|path| path.split('/').any(|segment| segment.contains(id)))
+    })
 }
 
 #[cfg(test)]
@@ -122,4 +224,15 @@ mod tests {
         let target = Target::process(999999);
         assert!(!target.exists().await);
     }
+
+    #[test]
+    fn test_cgroup_mentions_container() {
+        let id = "4f1c2a9be3d0";
+        let docker = "0::/system.slice/docker-4f1c2a9be3d07d5e1c.scope\n";
+        let containerd = "0::/kubepods/besteffort/pod1/cri-containerd-4f1c2a9be3d0.scope\n";
+        assert!(cgroup_mentions(docker, id));
+        assert!(cgroup_mentions(containerd, id));
+        assert!(!cgroup_mentions("0::/user.slice/session-1.scope\n", id));
+        assert!(!cgroup_mentions(docker, "web"));
+    }
 }
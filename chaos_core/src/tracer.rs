@@ -0,0 +1,668 @@
+//! ptrace-based syscall interception for injectors that change what a
+//! running process sees without restarting it
+
+use crate::error::*;
+use crate::injectors::syscall::SyscallFault;
+use nix::errno::Errno;
+use nix::sys::ptrace::{self, Event, Options};
+use nix::sys::signal::Signal;
+use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
+use nix::unistd::Pid;
+use std::collections::HashMap;
+use std::os::unix::fs::FileExt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Once};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+pub(crate) use libc::user_regs_struct as Registers;
+
+/// Length of the `syscall` instruction, to rewind a thread onto it
+const SYSCALL_INSN_LEN: u64 = 2;
+
+/// `PTRACE_GET_SYSCALL_INFO` (Linux 5.3) and the stops it reports
+const PTRACE_GET_SYSCALL_INFO: libc::c_uint = 0x420e;
+const PTRACE_SYSCALL_INFO_ENTRY: u8 = 1;
+const PTRACE_SYSCALL_INFO_EXIT: u8 = 2;
+
+/// Interrupts the tracer's blocking `waitpid` so it notices a detach
+fn kick_signal() -> libc::c_int {
+    // glibc reserves the first real-time signals for itself
+    libc::SIGRTMIN() + 2
+}
+
+/// Where a thread was stopped when the hook gets to see it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum StopKind {
+    /// About to run the syscall in `orig_rax`
+    SyscallEntry,
+    /// Returning from it with the result in `rax`
+    SyscallExit,
+    Other,
+}
+
+/// A thread held in a ptrace-stop while the tracer detaches
+pub(crate) struct StoppedThread {
+    pub tid: Pid,
+    pub stop: StopKind,
+    pub regs: Registers,
+    signal: Option<Signal>,
+}
+
+/// What a tracer does to the syscalls of its process
+pub(crate) trait SyscallHook: Send + 'static {
+    /// Called once every thread is seized and stopped
+    fn attached(&mut self, _pid: u32) -> Result<()> {
+        Ok(())
+    }
+
+    /// Fault for the syscall in `regs.orig_rax` a thread is entering
+    fn enter(&mut self, _regs: &Registers) -> SyscallFault {
+        SyscallFault::default()
+    }
+
+    /// Rewrite the result of a finished syscall; true if `regs` changed
+    fn exit(&mut self, _pid: u32, _regs: &mut Registers) -> bool {
+        false
+    }
+
+    /// Undo `attached` with every thread stopped, right before detaching.
+    /// Registers changed here are written back.
+    fn release(&mut self, _pid: u32, _threads: &mut [StoppedThread]) {}
+}
+
+/// Tracer thread attached to every thread of one process. ptrace ties
+/// tracees to the thread that attached them, so all tracing happens there.
+pub(crate) struct Tracer {
+    pid: u32,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    /// Kernel thread id of the tracer thread, to kick it
+    tid: libc::pid_t,
+}
+
+impl Tracer {
+    /// Seize every thread of `pid` and start running `hook`
+    pub fn attach(pid: u32, hook: impl SyscallHook) -> Result<Self> {
+        install_kick_handler();
+        let stop = Arc::new(AtomicBool::new(false));
+        let (attached_tx, attached_rx) = mpsc::channel();
+        let thread_stop = stop.clone();
+        let thread = std::thread::Builder::new()
+            .name(format!("chaos-ptrace-{}", pid))
+            .spawn(move || {
+                let mut session = match Session::seize(pid, Box::new(hook)) {
+                    Ok(session) => {
+                        let _ = attached_tx.send(Ok(nix::unistd::gettid().as_raw()));
+                        session
+                    }
+                    Err(e) => {
+                        let _ = attached_tx.send(Err(e));
+                        return;
+                    }
+                };
+                session.run(&thread_stop);
+                session.detach();
+            })?;
+        match attached_rx.recv() {
+            Ok(Ok(tid)) => Ok(Self {
+                pid,
+                stop,
+                thread: Some(thread),
+                tid,
+            }),
+            Ok(Err(e)) => {
+                let _ = thread.join();
+                Err(e)
+            }
+            Err(_) => Err(ChaosError::InjectionFailed(format!(
+                "Tracer for {} exited while attaching",
+                pid
+            ))),
+        }
+    }
+
+    /// Stop intercepting and let the process run untraced
+    pub fn detach(mut self) -> Result<()> {
+        let Some(thread) = self.stop_thread() else {
+            return Ok(());
+        };
+        thread
+            .join()
+            .map_err(|_| ChaosError::CleanupFailed(format!("Tracer for {} panicked", self.pid)))?;
+        info!("Detached from process {}", self.pid);
+        Ok(())
+    }
+
+    /// Ask the tracer thread to detach and wake it until it has. The kick
+    /// can land just before it blocks in `waitpid`, so it is repeated.
+    fn stop_thread(&mut self) -> Option<JoinHandle<()>> {
+        let thread = self.thread.take()?;
+        self.stop.store(true, Ordering::SeqCst);
+        while !thread.is_finished() {
+            // SAFETY: tgkill only sends a signal to our own tracer thread
+            unsafe {
+                libc::syscall(libc::SYS_tgkill, libc::getpid(), self.tid, kick_signal());
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        Some(thread)
+    }
+}
+
+impl Drop for Tracer {
+    fn drop(&mut self) {
+        self.stop_thread();
+    }
+}
+
+/// Make the kick signal interrupt `waitpid` (no `SA_RESTART`) without
+/// doing anything else
+fn install_kick_handler() {
+    static INSTALL: Once = Once::new();
+    extern "C" fn ignore(_: libc::c_int) {}
+    INSTALL.call_once(|| {
+        // SAFETY: the handler is async-signal-safe (it does nothing)
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = ignore as extern "C" fn(libc::c_int) as libc::sighandler_t;
+            libc::sigemptyset(&mut action.sa_mask);
+            libc::sigaction(kick_signal(), &action, std::ptr::null_mut());
+        }
+    });
+}
+
+/// A thread of the traced process
+#[derive(Debug, Default)]
+struct Tracee {
+    /// Between a syscall-enter-stop and its exit-stop; only consulted on
+    /// kernels without `PTRACE_GET_SYSCALL_INFO`
+    in_syscall: bool,
+    /// Errno to return once the skipped syscall exits
+    errno: Option<i32>,
+    /// Sleeping in a `poll` standing in for a delayed syscall
+    delayed: Option<Delay>,
+    /// Re-entering a syscall after its delay, failing with the errno if set
+    redo: Option<Option<i32>>,
+}
+
+/// A syscall put off by running `poll(NULL, 0, ms)` in its place, so the
+/// tracee sleeps on its own and the tracer stays free
+#[derive(Debug)]
+struct Delay {
+    /// Registers at the syscall-enter-stop, restored to re-run it
+    regs: Registers,
+    until: Instant,
+    errno: Option<i32>,
+}
+
+struct Session {
+    pid: u32,
+    tracees: HashMap<Pid, Tracee>,
+    hook: Box<dyn SyscallHook>,
+    /// Delayed syscalls run right away instead of sleeping on
+    detaching: bool,
+}
+
+impl Session {
+    fn seize(pid: u32, hook: Box<dyn SyscallHook>) -> Result<Self> {
+        let mut session = Self {
+            pid,
+            tracees: HashMap::new(),
+            hook,
+            detaching: false,
+        };
+        // Threads started before their parent was seized are missed by
+        // PTRACE_O_TRACECLONE, so list again until nothing new shows up
+        loop {
+            let mut found = false;
+            for tid in threads(pid)? {
+                if session.tracees.contains_key(&tid) {
+                    continue;
+                }
+                let options = Options::PTRACE_O_TRACESYSGOOD | Options::PTRACE_O_TRACECLONE;
+                match ptrace::seize(tid, options).and_then(|()| ptrace::interrupt(tid)) {
+                    Ok(()) => {
+                        session.tracees.insert(tid, Tracee::default());
+                        found = true;
+                    }
+                    // Exited meanwhile
+                    Err(Errno::ESRCH) if !session.tracees.is_empty() => {}
+                    Err(e) => {
+                        session.detach();
+                        return Err(ChaosError::InjectionFailed(format!(
+                            "Failed to trace thread {} of process {}: {}",
+                            tid, pid, e
+                        )));
+                    }
+                }
+            }
+            if !found {
+                break;
+            }
+        }
+
+        let pending = session.tracees.keys().copied().collect();
+        let stopped = session.collect_stops(pending);
+        if let Err(e) = session.hook.attached(pid) {
+            for thread in stopped {
+                let _ = ptrace::detach(thread.tid, thread.signal);
+            }
+            session.tracees.clear();
+            return Err(e);
+        }
+        for thread in stopped {
+            resume(thread.tid, thread.signal);
+        }
+        info!(
+            "Tracing {} thread(s) of process {}",
+            session.tracees.len(),
+            pid
+        );
+        Ok(session)
+    }
+
+    /// Handle stops as they are reported until asked to stop. Blocking in
+    /// `waitpid` keeps a stop from waiting on the tracer; `__WNOTHREAD`
+    /// limits it to this thread's tracees, leaving the agent's own children
+    /// to whoever spawned them.
+    fn run(&mut self, stop: &AtomicBool) {
+        while !stop.load(Ordering::SeqCst) && !self.tracees.is_empty() {
+            match waitpid(None, Some(WaitPidFlag::__WALL | WaitPidFlag::__WNOTHREAD)) {
+                Ok(status) => self.handle(status),
+                // Kicked by `Tracer::detach`
+                Err(Errno::EINTR) => {}
+                Err(e) => {
+                    debug!("Waiting on the tracees of {} failed: {}", self.pid, e);
+                    self.tracees.clear();
+                }
+            }
+        }
+        if self.tracees.is_empty() {
+            info!("Traced process {} exited", self.pid);
+        }
+    }
+
+    fn handle(&mut self, status: WaitStatus) {
+        match status {
+            WaitStatus::PtraceSyscall(tid) => self.syscall_stop(tid),
+            WaitStatus::PtraceEvent(tid, _, event) if event == Event::PTRACE_EVENT_CLONE as i32 => {
+                self.add_clone(tid);
+                resume(tid, None);
+            }
+            WaitStatus::PtraceEvent(tid, signal, event)
+                if event == Event::PTRACE_EVENT_STOP as i32 =>
+            {
+                if matches!(
+                    signal,
+                    Signal::SIGSTOP | Signal::SIGTSTP | Signal::SIGTTIN | Signal::SIGTTOU
+                ) {
+                    // Group-stop: stay stopped but keep reporting, so SIGCONT
+                    // still resumes the process
+                    listen(tid);
+                } else {
+                    resume(tid, None);
+                }
+            }
+            WaitStatus::PtraceEvent(tid, _, _) => resume(tid, None),
+            WaitStatus::Stopped(tid, signal) => resume(tid, Some(signal)),
+            WaitStatus::Exited(tid, _) | WaitStatus::Signaled(tid, _, _) => {
+                self.tracees.remove(&tid);
+            }
+            _ => {}
+        }
+    }
+
+    /// Track the thread `tid` just created; it starts out traced. With a
+    /// blocking `waitpid(-1)` its first stop may already have been seen.
+    fn add_clone(&mut self, tid: Pid) -> Option<Pid> {
+        let new = Pid::from_raw(ptrace::getevent(tid).ok()? as i32);
+        debug!("Thread {} started thread {}", tid, new);
+        self.tracees.entry(new).or_default();
+        Some(new)
+    }
+
+    fn syscall_stop(&mut self, tid: Pid) {
+        let Ok(mut regs) = ptrace::getregs(tid) else {
+            return;
+        };
+        if self.is_exit(tid) {
+            if self.finish_exit(tid, &mut regs) {
+                let _ = ptrace::setregs(tid, regs);
+            }
+            resume(tid, None);
+            return;
+        }
+
+        if let Some(errno) = self.tracees.get_mut(&tid).and_then(|t| t.redo.take()) {
+            self.enter(tid, errno);
+            return;
+        }
+        let fault = self.hook.enter(&regs);
+        if fault.delay.is_zero() {
+            self.enter(tid, fault.errno);
+        } else {
+            self.delay(tid, regs, fault.delay, fault.errno);
+        }
+    }
+
+    /// Whether a syscall-stop of `tid` is an exit rather than an entry. A
+    /// syscall that really returns `ENOSYS` looks like an entry by `rax`, so
+    /// ask the kernel, or keep count where it cannot say.
+    fn is_exit(&mut self, tid: Pid) -> bool {
+        let tracee = self.tracees.entry(tid).or_default();
+        let exit = match syscall_op(tid) {
+            Some(PTRACE_SYSCALL_INFO_ENTRY) => false,
+            Some(PTRACE_SYSCALL_INFO_EXIT) => true,
+            _ => tracee.in_syscall,
+        };
+        tracee.in_syscall = !exit;
+        exit
+    }
+
+    /// Apply the pending errno and the hook to a syscall-exit-stop; true if
+    /// `regs` changed
+    fn finish_exit(&mut self, tid: Pid, regs: &mut Registers) -> bool {
+        let detaching = self.detaching;
+        let Some(tracee) = self.tracees.get_mut(&tid) else {
+            return self.hook.exit(self.pid, regs);
+        };
+        if let Some(delay) = tracee.delayed.take() {
+            let remaining = delay.until.saturating_duration_since(Instant::now());
+            if !detaching && !remaining.is_zero() {
+                // Woken early by a signal: sleep on once it is handled
+                regs.rip -= SYSCALL_INSN_LEN;
+                set_poll(regs, remaining);
+                regs.rax = libc::SYS_poll as u64;
+                tracee.delayed = Some(delay);
+            } else {
+                // Run the syscall that was put off
+                *regs = delay.regs;
+                regs.rip -= SYSCALL_INSN_LEN;
+                regs.rax = delay.regs.orig_rax;
+                if !detaching {
+                    tracee.redo = Some(delay.errno);
+                }
+            }
+            return true;
+        }
+        if let Some(errno) = tracee.errno.take() {
+            regs.rax = (-(errno as i64)) as u64;
+            return true;
+        }
+        self.hook.exit(self.pid, regs)
+    }
+
+    /// Put off the syscall a thread is entering by `delay`, making it
+    /// `poll(NULL, 0, ms)` instead; it is re-run from its exit-stop
+    fn delay(&mut self, tid: Pid, regs: Registers, delay: Duration, errno: Option<i32>) {
+        let mut sleep = regs;
+        sleep.orig_rax = libc::SYS_poll as u64;
+        set_poll(&mut sleep, delay);
+        if ptrace::setregs(tid, sleep).is_ok() {
+            self.tracees.entry(tid).or_default().delayed = Some(Delay {
+                regs,
+                until: Instant::now() + delay,
+                errno,
+            });
+        }
+        resume(tid, None);
+    }
+
+    /// Let a thread held in syscall-enter-stop continue, skipping the
+    /// syscall if it is to fail with `errno`
+    fn enter(&mut self, tid: Pid, errno: Option<i32>) {
+        if let Some(errno) = errno {
+            let Ok(mut regs) = ptrace::getregs(tid) else {
+                return;
+            };
+            // Syscall -1 does nothing; its result is replaced at exit
+            regs.orig_rax = u64::MAX;
+            if ptrace::setregs(tid, regs).is_ok() {
+                self.tracees.entry(tid).or_default().errno = Some(errno);
+            }
+        }
+        resume(tid, None);
+    }
+
+    /// Wait until each of `pending` (interrupted or newly cloned) is in a
+    /// ptrace-stop. Syscalls finishing on the way are completed as usual.
+    fn collect_stops(&mut self, mut pending: Vec<Pid>) -> Vec<StoppedThread> {
+        let mut stopped = Vec::new();
+        while let Some(tid) = pending.pop() {
+            let (stop, signal) = loop {
+                match waitpid(tid, Some(WaitPidFlag::__WALL)) {
+                    Ok(WaitStatus::PtraceSyscall(_)) => {
+                        let Ok(mut regs) = ptrace::getregs(tid) else {
+                            break (StopKind::Other, None);
+                        };
+                        if !self.is_exit(tid) {
+                            break (StopKind::SyscallEntry, None);
+                        }
+                        if self.finish_exit(tid, &mut regs) {
+                            let _ = ptrace::setregs(tid, regs);
+                        }
+                        break (StopKind::SyscallExit, None);
+                    }
+                    Ok(WaitStatus::PtraceEvent(_, _, event))
+                        if event == Event::PTRACE_EVENT_CLONE as i32 =>
+                    {
+                        pending.extend(self.add_clone(tid));
+                        break (StopKind::Other, None);
+                    }
+                    Ok(WaitStatus::PtraceEvent(..)) => break (StopKind::Other, None),
+                    Ok(WaitStatus::Stopped(_, signal)) => break (StopKind::Other, Some(signal)),
+                    Ok(WaitStatus::Exited(..) | WaitStatus::Signaled(..)) | Err(_) => {
+                        self.tracees.remove(&tid);
+                        break (StopKind::Other, None);
+                    }
+                    Ok(_) => continue,
+                }
+            };
+            if !self.tracees.contains_key(&tid) {
+                continue;
+            }
+            match ptrace::getregs(tid) {
+                Ok(regs) => stopped.push(StoppedThread {
+                    tid,
+                    stop,
+                    regs,
+                    signal,
+                }),
+                Err(_) => {
+                    self.tracees.remove(&tid);
+                }
+            }
+        }
+        stopped
+    }
+
+    /// Stop every thread, let the hook undo its changes and detach.
+    /// Threads sleeping off a delay wake up and run their syscall.
+    fn detach(&mut self) {
+        self.detaching = true;
+        let pending = self
+            .tracees
+            .keys()
+            .copied()
+            .filter(|tid| ptrace::interrupt(*tid).is_ok())
+            .collect();
+        let mut stopped = self.collect_stops(pending);
+
+        self.hook.release(self.pid, &mut stopped);
+        for thread in stopped {
+            let _ = ptrace::setregs(thread.tid, thread.regs);
+            if let Some(tracee) = self.tracees.get(&thread.tid) {
+                if tracee.errno.is_some() {
+                    warn!(
+                        "Thread {} skipped a syscall that returned no error",
+                        thread.tid
+                    );
+                }
+            }
+            let _ = ptrace::detach(thread.tid, thread.signal);
+        }
+        self.tracees.clear();
+    }
+}
+
+/// Thread ids of `pid`
+fn threads(pid: u32) -> Result<Vec<Pid>> {
+    let tasks = std::fs::read_dir(format!("/proc/{}/task", pid))
+        .map_err(|_| ChaosError::TargetNotFound(format!("Process {} not found", pid)))?;
+    Ok(tasks
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+        .map(Pid::from_raw)
+        .collect())
+}
+
+/// Which syscall-stop `tid` is in, from `PTRACE_GET_SYSCALL_INFO`; `None`
+/// on kernels before 5.3
+fn syscall_op(tid: Pid) -> Option<u8> {
+    // Only `op`, the first byte of `struct ptrace_syscall_info`, is needed
+    let mut info = [0u8; 88];
+    // SAFETY: the kernel writes at most `info.len()` bytes to `info`
+    let written = unsafe {
+        libc::ptrace(
+            PTRACE_GET_SYSCALL_INFO,
+            tid.as_raw(),
+            info.len(),
+            info.as_mut_ptr(),
+        )
+    };
+    (written > 0).then_some(info[0])
+}
+
+/// Point `regs` at `poll(NULL, 0, ms)` sleeping for `delay`
+fn set_poll(regs: &mut Registers, delay: Duration) {
+    regs.rdi = 0;
+    regs.rsi = 0;
+    // Round up so a delay is never shortened
+    regs.rdx = delay.as_micros().div_ceil(1000).min(i32::MAX as u128) as u64;
+}
+
+/// Continue to the next syscall stop, delivering `signal`
+fn resume(tid: Pid, signal: Option<Signal>) {
+    if let Err(e) = ptrace::syscall(tid, signal) {
+        debug!("Could not resume thread {}: {}", tid, e);
+    }
+}
+
+/// PTRACE_LISTEN, which nix does not wrap
+fn listen(tid: Pid) {
+    // SAFETY: PTRACE_LISTEN takes no address or data
+    let result = unsafe {
+        libc::ptrace(
+            libc::PTRACE_LISTEN,
+            tid.as_raw(),
+            std::ptr::null_mut::<libc::c_void>(),
+            std::ptr::null_mut::<libc::c_void>(),
+        )
+    };
+    if result < 0 {
+        debug!(
+            "Could not listen on thread {}: {}",
+            tid,
+            std::io::Error::last_os_error()
+        );
+    }
+}
+
+/// Read `len` bytes at `address` in the memory of `pid`
+pub(crate) fn read_memory(pid: u32, address: u64, len: usize) -> std::io::Result<Vec<u8>> {
+    let mem = std::fs::File::open(format!("/proc/{}/mem", pid))?;
+    let mut buf = vec![0; len];
+    mem.read_exact_at(&mut buf, address)?;
+    Ok(buf)
+}
+
+/// Write `data` at `address` in the memory of `pid`, even to read-only
+/// mappings such as code
+pub(crate) fn write_memory(pid: u32, address: u64, data: &[u8]) -> std::io::Result<()> {
+    let mem = std::fs::OpenOptions::new()
+        .write(true)
+        .open(format!("/proc/{}/mem", pid))?;
+    mem.write_all_at(data, address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::sync::Mutex;
+
+    /// Records the syscall of every entry and exit it sees
+    struct Recorder(Arc<Mutex<Vec<(StopKind, u64, i64)>>>);
+
+    impl SyscallHook for Recorder {
+        fn enter(&mut self, regs: &Registers) -> SyscallFault {
+            let call = (StopKind::SyscallEntry, regs.orig_rax, regs.rax as i64);
+            self.0.lock().unwrap().push(call);
+            SyscallFault::default()
+        }
+
+        fn exit(&mut self, _pid: u32, regs: &mut Registers) -> bool {
+            let call = (StopKind::SyscallExit, regs.orig_rax, regs.rax as i64);
+            self.0.lock().unwrap().push(call);
+            false
+        }
+    }
+
+    #[test]
+    fn test_enosys_result_is_an_exit() {
+        const MISSING: u64 = 100_000;
+        let script = format!(
+            "import ctypes, sys\nfor _ in sys.stdin:\n    ctypes.CDLL(None).syscall({})\n    print(flush=True)\n",
+            MISSING
+        );
+        let Ok(mut child) = std::process::Command::new("python3")
+            .args(["-c", &script])
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+        else {
+            eprintln!("skipping tracer test: python3 not found");
+            return;
+        };
+        let mut stdin = child.stdin.take().unwrap();
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut round = || {
+            stdin.write_all(b"\n").unwrap();
+            stdout.read_line(&mut String::new()).unwrap();
+        };
+        round();
+
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let tracer = match Tracer::attach(child.id(), Recorder(calls.clone())) {
+            Ok(tracer) => tracer,
+            Err(e) => {
+                eprintln!("skipping tracer test: {}", e);
+                child.kill().unwrap();
+                child.wait().unwrap();
+                return;
+            }
+        };
+        for _ in 0..3 {
+            round();
+        }
+        tracer.detach().unwrap();
+
+        let missing: Vec<_> = calls
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, syscall, _)| *syscall == MISSING)
+            .map(|(stop, _, _)| *stop)
+            .collect();
+        // Each call enters once and exits once, though both stops have
+        // rax == -ENOSYS
+        assert_eq!(
+            missing,
+            [StopKind::SyscallEntry, StopKind::SyscallExit].repeat(3)
+        );
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
@@ -1,5 +1,6 @@
 use crate::collector::{Metric, MetricType};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,6 +17,9 @@ pub struct AggregatedMetrics {
     pub min_latency: Duration,
     pub max_latency: Duration,
     pub average_recovery_time: Duration,
+    /// Mean of each named custom metric
+    #[serde(default)]
+    pub custom: BTreeMap<String, f64>,
 }
 
 pub struct MetricsAggregator;
@@ -24,6 +28,7 @@ impl MetricsAggregator {
     pub fn aggregate(metrics: &[Metric]) -> AggregatedMetrics {
         let mut latencies: Vec<Duration> = Vec::new();
         let mut recovery_times: Vec<Duration> = Vec::new();
+        let mut custom: BTreeMap<String, (f64, usize)> = BTreeMap::new();
         let mut success_count = 0;
         let mut error_count = 0;
 
@@ -41,7 +46,11 @@ impl MetricsAggregator {
                 MetricType::Recovery { time } => {
                     recovery_times.push(*time);
                 }
-                MetricType::Custom { .. } => {}
+                MetricType::Custom { name, value } => {
+                    let (sum, count) = custom.entry(name.clone()).or_default();
+                    *sum += value;
+                    *count += 1;
+                }
             }
         }
 
@@ -96,6 +105,10 @@ impl MetricsAggregator {
             min_latency: min,
             max_latency: max,
             average_recovery_time: avg_recovery,
+            custom: custom
+                .into_iter()
+                .map(|(name, (sum, count))| (name, sum / count as f64))
+                .collect(),
         }
     }
 
@@ -149,6 +162,22 @@ mod tests {
                 timestamp: Utc::now(),
                 labels: Default::default(),
             },
+            Metric {
+                metric_type: MetricType::Custom {
+                    name: "cpu_utilization".to_string(),
+                    value: 0.25,
+                },
+                timestamp: Utc::now(),
+                labels: Default::default(),
+            },
+            Metric {
+                metric_type: MetricType::Custom {
+                    name: "cpu_utilization".to_string(),
+                    value: 0.75,
+                },
+                timestamp: Utc::now(),
+                labels: Default::default(),
+            },
         ];
 
         let aggregated = MetricsAggregator::aggregate(&metrics);
@@ -157,5 +186,6 @@ mod tests {
         assert_eq!(aggregated.successful_requests, 1);
         assert_eq!(aggregated.failed_requests, 1);
         assert_eq!(aggregated.error_rate, 0.5);
+        assert_eq!(aggregated.custom["cpu_utilization"], 0.5);
     }
 }
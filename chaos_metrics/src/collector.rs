@@ -73,6 +73,18 @@ impl MetricsCollector {
         .await;
     }
 
+    pub async fn record_custom(&self, name: impl Into<String>, value: f64) {
+        self.record(Metric {
+            metric_type: MetricType::Custom {
+                name: name.into(),
+                value,
+            },
+            timestamp: Utc::now(),
+            labels: std::collections::HashMap::new(),
+        })
+        .await;
+    }
+
     pub async fn get_metrics(&self) -> Vec<Metric> {
         self.metrics.read().await.clone()
     }
@@ -80,6 +92,14 @@ impl MetricsCollector {
     pub async fn clear(&self) {
         self.metrics.write().await.clear();
     }
+
+    /// Drop metrics recorded before `cutoff`
+    pub async fn clear_before(&self, cutoff: DateTime<Utc>) {
+        self.metrics
+            .write()
+            .await
+            .retain(|metric| metric.timestamp >= cutoff);
+    }
 }
 
 impl Default for MetricsCollector {
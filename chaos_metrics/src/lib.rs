@@ -1,8 +1,10 @@
 pub mod aggregator;
 pub mod collector;
 pub mod exporters;
+pub mod probe;
 pub mod slo;
 
 pub use aggregator::{AggregatedMetrics, MetricsAggregator};
 pub use collector::{Metric, MetricType, MetricsCollector};
+pub use probe::{Probe, Prober};
 pub use slo::{SloTracker, SloViolation};
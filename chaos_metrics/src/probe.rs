@@ -0,0 +1,135 @@
+use crate::collector::MetricsCollector;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+/// Request used to measure the system under test
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Probe {
+    /// `GET` the URL; any 2xx response is a success
+    Http { url: String },
+    /// Open a TCP connection
+    Tcp { address: String },
+}
+
+impl Probe {
+    pub fn description(&self) -> String {
+        match self {
+            Probe::Http { url } => format!("GET {}", url),
+            Probe::Tcp { address } => format!("tcp://{}", address),
+        }
+    }
+}
+
+/// Issues a probe periodically and records the outcome into a collector
+pub struct Prober {
+    probe: Probe,
+    timeout: Duration,
+    client: reqwest::Client,
+}
+
+impl Prober {
+    pub fn new(probe: Probe, timeout: Duration) -> Self {
+        Self {
+            probe,
+            timeout,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn probe(&self) -> &Probe {
+        &self.probe
+    }
+
+    /// Issue one probe, returning its latency or an error type
+    pub async fn probe_once(&self) -> Result<Duration, String> {
+        let start = Instant::now();
+        match &self.probe {
+            Probe::Http { url } => {
+                let response = self
+                    .client
+                    .get(url)
+                    .timeout(self.timeout)
+                    .send()
+                    .await
+                    .map_err(|e| {
+                        if e.is_timeout() {
+                            "timeout".to_string()
+                        } else {
+                            "connection".to_string()
+                        }
+                    })?;
+                if !response.status().is_success() {
+                    return Err(format!("http_{}", response.status().as_u16()));
+                }
+            }
+            Probe::Tcp { address } => {
+                tokio::time::timeout(self.timeout, tokio::net::TcpStream::connect(address))
+                    .await
+                    .map_err(|_| "timeout".to_string())?
+                    .map_err(|_| "connection".to_string())?;
+            }
+        }
+        Ok(start.elapsed())
+    }
+
+    /// Probe every `interval` until the returned task is aborted. Successes
+    /// record a latency and a success; failures record an error.
+    pub fn spawn(
+        self: Arc<Self>,
+        collector: Arc<MetricsCollector>,
+        interval: Duration,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                match self.probe_once().await {
+                    Ok(latency) => {
+                        collector.record_latency(latency).await;
+                        collector.record_success().await;
+                    }
+                    Err(error_type) => collector.record_error(error_type).await,
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MetricsAggregator;
+
+    #[tokio::test]
+    async fn test_tcp_probe_records_metrics() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move { while let Ok((_stream, _)) = listener.accept().await {} });
+
+        let collector = Arc::new(MetricsCollector::new());
+        let prober = Arc::new(Prober::new(Probe::Tcp { address }, Duration::from_secs(1)));
+        let task = prober.spawn(collector.clone(), Duration::from_millis(10));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        task.abort();
+
+        let aggregated = MetricsAggregator::aggregate(&collector.get_metrics().await);
+        assert!(aggregated.successful_requests > 0);
+        assert_eq!(aggregated.failed_requests, 0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_probe() {
+        // Bind then drop to get a port nothing listens on
+        let address = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .to_string();
+        let prober = Prober::new(Probe::Tcp { address }, Duration::from_secs(1));
+        assert_eq!(prober.probe_once().await.unwrap_err(), "connection");
+    }
+}
@@ -48,6 +48,24 @@ impl SloTracker {
         }
     }
 
+    /// Check a latency against one named SLO only; returns whether it was
+    /// violated. Unknown names never violate.
+    pub fn check_slo(&mut self, name: &str, latency: Duration) -> bool {
+        let Some(slo) = self.slos.iter().find(|s| s.name == name) else {
+            return false;
+        };
+        if latency <= slo.threshold {
+            return false;
+        }
+        self.violations.push(SloViolation {
+            slo_name: slo.name.clone(),
+            threshold: slo.threshold,
+            actual: latency,
+            timestamp: chrono::Utc::now(),
+        });
+        true
+    }
+
     pub fn violations(&self) -> &[SloViolation] {
         &self.violations
     }
@@ -69,3 +87,21 @@ impl Default for SloTracker {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_slo_by_name() {
+        let mut tracker = SloTracker::new();
+        tracker.add_slo("fast", Duration::from_millis(10));
+        tracker.add_slo("slow", Duration::from_millis(100));
+
+        assert!(tracker.check_slo("fast", Duration::from_millis(50)));
+        assert!(!tracker.check_slo("slow", Duration::from_millis(50)));
+        assert!(!tracker.check_slo("missing", Duration::from_secs(1)));
+        assert_eq!(tracker.violation_count(), 1);
+        assert_eq!(tracker.violations()[0].slo_name, "fast");
+    }
+}
@@ -0,0 +1,326 @@
+use chaos_metrics::AggregatedMetrics;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Live metrics are aggregated over this trailing window when checking
+/// abort rules
+pub const ABORT_WINDOW: Duration = Duration::from_secs(10);
+
+/// Metric an abort rule watches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbortMetric {
+    ErrorRate,
+    P50,
+    P95,
+    P99,
+    P999,
+    AverageLatency,
+    MaxLatency,
+}
+
+impl AbortMetric {
+    fn name(self) -> &'static str {
+        match self {
+            AbortMetric::ErrorRate => "error_rate",
+            AbortMetric::P50 => "p50",
+            AbortMetric::P95 => "p95",
+            AbortMetric::P99 => "p99",
+            AbortMetric::P999 => "p999",
+            AbortMetric::AverageLatency => "avg_latency",
+            AbortMetric::MaxLatency => "max_latency",
+        }
+    }
+
+    fn is_latency(self) -> bool {
+        self != AbortMetric::ErrorRate
+    }
+
+    /// Ratio for `error_rate`, seconds for latencies
+    fn value(self, metrics: &AggregatedMetrics) -> f64 {
+        match self {
+            AbortMetric::ErrorRate => metrics.error_rate,
+            AbortMetric::P50 => metrics.latency_p50.as_secs_f64(),
+            AbortMetric::P95 => metrics.latency_p95.as_secs_f64(),
+            AbortMetric::P99 => metrics.latency_p99.as_secs_f64(),
+            AbortMetric::P999 => metrics.latency_p999.as_secs_f64(),
+            AbortMetric::AverageLatency => metrics.average_latency.as_secs_f64(),
+            AbortMetric::MaxLatency => metrics.max_latency.as_secs_f64(),
+        }
+    }
+
+    fn format(self, value: f64) -> String {
+        if self.is_latency() {
+            humantime::format_duration(Duration::from_secs_f64(value)).to_string()
+        } else {
+            // Bounded precision hides float noise such as 0.2 * 100.0
+            let percent = format!("{:.2}", value * 100.0);
+            format!("{}%", percent.trim_end_matches('0').trim_end_matches('.'))
+        }
+    }
+}
+
+impl FromStr for AbortMetric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "error_rate" => Ok(AbortMetric::ErrorRate),
+            "p50" => Ok(AbortMetric::P50),
+            "p95" => Ok(AbortMetric::P95),
+            "p99" => Ok(AbortMetric::P99),
+            "p999" => Ok(AbortMetric::P999),
+            "avg_latency" => Ok(AbortMetric::AverageLatency),
+            "max_latency" => Ok(AbortMetric::MaxLatency),
+            other => Err(format!(
+                "Unknown abort metric '{}': expected error_rate, p50, p95, p99, p999, \
+                 avg_latency or max_latency",
+                other
+            )),
+        }
+    }
+}
+
+/// Kill-switch condition such as `error_rate > 20% for 30s` or `p99 > 2s`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct AbortRule {
+    pub metric: AbortMetric,
+    /// Ratio for `error_rate`, seconds for latencies
+    pub threshold: f64,
+    /// How long the threshold must be exceeded before the rule fires
+    pub sustained: Duration,
+}
+
+impl AbortRule {
+    fn is_breached(&self, metrics: &AggregatedMetrics) -> bool {
+        metrics.total_requests > 0 && self.metric.value(metrics) > self.threshold
+    }
+}
+
+impl FromStr for AbortRule {
+    type Err = String;
+
+    /// Accepts `<metric> > <threshold> [for <duration>]`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| format!("Invalid abort rule '{}': {}", s.trim(), reason);
+
+        let (metric, rest) = s
+            .split_once('>')
+            .ok_or_else(|| invalid("expected '<metric> > <threshold>'"))?;
+        let metric: AbortMetric = metric.parse().map_err(|e: String| invalid(&e))?;
+
+        let (threshold, sustained) = match rest.split_once(" for ") {
+            Some((threshold, sustained)) => {
+                let sustained = humantime::parse_duration(sustained.trim())
+                    .map_err(|e| invalid(&e.to_string()))?;
+                (threshold.trim(), sustained)
+            }
+            None => (rest.trim(), Duration::ZERO),
+        };
+
+        let threshold = if metric.is_latency() {
+            humantime::parse_duration(threshold)
+                .map_err(|e| invalid(&e.to_string()))?
+                .as_secs_f64()
+        } else {
+            let ratio = match threshold.strip_suffix('%') {
+                Some(pct) => pct.trim().parse::<f64>().map(|p| p / 100.0),
+                None => threshold.parse::<f64>(),
+            }
+            .map_err(|_| invalid("expected a ratio or percentage"))?;
+            if !(0.0..=1.0).contains(&ratio) {
+                return Err(invalid("error rate must be between 0% and 100%"));
+            }
+            ratio
+        };
+
+        Ok(AbortRule {
+            metric,
+            threshold,
+            sustained,
+        })
+    }
+}
+
+impl std::fmt::Display for AbortRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} > {}",
+            self.metric.name(),
+            self.metric.format(self.threshold)
+        )?;
+        if !self.sustained.is_zero() {
+            write!(f, " for {}", humantime::format_duration(self.sustained))?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<String> for AbortRule {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<AbortRule> for String {
+    fn from(rule: AbortRule) -> Self {
+        rule.to_string()
+    }
+}
+
+/// Tracks how long each rule has been breached
+pub struct AbortWatcher {
+    rules: Vec<AbortRule>,
+    breached_since: Vec<Option<Instant>>,
+}
+
+impl AbortWatcher {
+    pub fn new(rules: Vec<AbortRule>) -> Self {
+        let breached_since = vec![None; rules.len()];
+        Self {
+            rules,
+            breached_since,
+        }
+    }
+
+    /// Feed the latest window of metrics. Returns why the run must halt once
+    /// a rule has been breached for its full duration.
+    pub fn check(&mut self, metrics: &AggregatedMetrics, now: Instant) -> Option<String> {
+        for (rule, since) in self.rules.iter().zip(self.breached_since.iter_mut()) {
+            if !rule.is_breached(metrics) {
+                *since = None;
+                continue;
+            }
+
+            let since = *since.get_or_insert(now);
+            if now.duration_since(since) >= rule.sustained {
+                return Some(format!(
+                    "{} (observed {})",
+                    rule,
+                    rule.metric.format(rule.metric.value(metrics))
+                ));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(error_rate: f64, p99: Duration) -> AggregatedMetrics {
+        AggregatedMetrics {
+            total_requests: 10,
+            successful_requests: 10 - (error_rate * 10.0) as usize,
+            failed_requests: (error_rate * 10.0) as usize,
+            error_rate,
+            latency_p50: Duration::ZERO,
+            latency_p95: Duration::ZERO,
+            latency_p99: p99,
+            latency_p999: p99,
+            average_latency: Duration::ZERO,
+            min_latency: Duration::ZERO,
+            max_latency: p99,
+            average_recovery_time: Duration::ZERO,
+            custom: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_parse_abort_rules() {
+        let rule: AbortRule = "error_rate > 20% for 30s".parse().unwrap();
+        assert_eq!(rule.metric, AbortMetric::ErrorRate);
+        assert_eq!(rule.threshold, 0.2);
+        assert_eq!(rule.sustained, Duration::from_secs(30));
+        assert_eq!(rule.to_string(), "error_rate > 20% for 30s");
+
+        let rule: AbortRule = "p99 > 2s".parse().unwrap();
+        assert_eq!(rule.threshold, 2.0);
+        assert!(rule.sustained.is_zero());
+        assert_eq!(rule.to_string(), "p99 > 2s");
+
+        assert_eq!(
+            "error_rate > 0.5".parse::<AbortRule>().unwrap().threshold,
+            0.5
+        );
+        assert!("error_rate > 150%".parse::<AbortRule>().is_err());
+        assert!("latency > 2s".parse::<AbortRule>().is_err());
+        assert!("p99 < 2s".parse::<AbortRule>().is_err());
+        assert!("p99 > fast".parse::<AbortRule>().is_err());
+    }
+
+    #[test]
+    fn test_abort_rule_display_round_trip() {
+        for text in [
+            "error_rate > 20%",
+            "error_rate > 12.5% for 1m",
+            "error_rate > 0.1%",
+            "error_rate > 0%",
+            "error_rate > 100% for 30s",
+            "p99 > 250ms for 30s",
+            "avg_latency > 1s 100ms",
+        ] {
+            let rule: AbortRule = text.parse().unwrap();
+            assert_eq!(rule.to_string(), text);
+            assert_eq!(rule.to_string().parse::<AbortRule>().unwrap(), rule);
+        }
+        assert_eq!(
+            "error_rate > 0.2".parse::<AbortRule>().unwrap().to_string(),
+            "error_rate > 20%"
+        );
+        assert_eq!(AbortMetric::ErrorRate.format(1.0 / 3.0), "33.33%");
+    }
+
+    #[test]
+    fn test_watcher_requires_sustained_breach() {
+        let rules = vec!["error_rate > 20% for 30s".parse().unwrap()];
+        let mut watcher = AbortWatcher::new(rules);
+        let start = Instant::now();
+        let bad = metrics(0.5, Duration::ZERO);
+
+        assert!(watcher.check(&bad, start).is_none());
+        assert!(watcher
+            .check(&bad, start + Duration::from_secs(20))
+            .is_none());
+
+        // Recovering resets the clock
+        assert!(watcher
+            .check(
+                &metrics(0.0, Duration::ZERO),
+                start + Duration::from_secs(25)
+            )
+            .is_none());
+        assert!(watcher
+            .check(&bad, start + Duration::from_secs(40))
+            .is_none());
+
+        let reason = watcher
+            .check(&bad, start + Duration::from_secs(70))
+            .unwrap();
+        assert!(reason.contains("error_rate > 20% for 30s"));
+        assert!(reason.contains("observed 50%"));
+    }
+
+    #[test]
+    fn test_watcher_fires_immediately_without_duration() {
+        let mut watcher = AbortWatcher::new(vec!["p99 > 2s".parse().unwrap()]);
+        let now = Instant::now();
+        assert!(watcher
+            .check(&metrics(0.0, Duration::from_secs(1)), now)
+            .is_none());
+        assert!(watcher
+            .check(&metrics(0.0, Duration::from_secs(3)), now)
+            .is_some());
+
+        let mut empty = metrics(1.0, Duration::from_secs(3));
+        empty.total_requests = 0;
+        let mut watcher = AbortWatcher::new(vec!["error_rate > 0%".parse().unwrap()]);
+        assert!(watcher.check(&empty, now).is_none());
+    }
+}
@@ -1,3 +1,5 @@
+use crate::abort::AbortRule;
+use crate::steady_state::SteadyState;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::time::Duration;
@@ -17,6 +19,15 @@ pub struct Scenario {
     pub phases: Vec<Phase>,
     #[serde(default)]
     pub labels: HashMap<String, String>,
+    /// Probe the system under test during every phase
+    #[serde(default)]
+    pub metrics: Option<MetricsConfig>,
+    /// Hypothesis checked before, during and after the phases
+    #[serde(default)]
+    pub steady_state: Option<SteadyState>,
+    /// Halt the run and remove every injection when any rule fires
+    #[serde(default)]
+    pub abort_when: Vec<AbortRule>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,6 +60,75 @@ pub struct TargetConfig {
     pub container_id: Option<String>,
     #[serde(default)]
     pub pattern: Option<String>,
+    #[serde(default)]
+    pub process_name: Option<String>,
+    #[serde(default)]
+    pub cmdline_regex: Option<String>,
+    #[serde(default)]
+    pub exe_path: Option<String>,
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub parent_pid: Option<u32>,
+    /// How many matching processes to hit: first, all, random(n), percentage(p)
+    #[serde(default)]
+    pub policy: Option<chaos_core::SelectionPolicy>,
+}
+
+/// How the runner measures the system under test. Exactly one of `http`
+/// and `tcp` must be set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    /// URL to `GET`; any 2xx response counts as a success
+    #[serde(default)]
+    pub http: Option<String>,
+    /// `host:port` to connect to
+    #[serde(default)]
+    pub tcp: Option<String>,
+    #[serde(with = "humantime_serde", default = "default_probe_interval")]
+    pub interval: Duration,
+    #[serde(with = "humantime_serde", default = "default_probe_timeout")]
+    pub timeout: Duration,
+    /// Latency objectives checked against every probe
+    #[serde(default)]
+    pub slos: Vec<SloConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SloConfig {
+    pub name: String,
+    /// A probe slower than this is a violation
+    #[serde(with = "humantime_serde")]
+    pub latency: Duration,
+}
+
+fn default_probe_interval() -> Duration {
+    Duration::from_secs(1)
+}
+
+fn default_probe_timeout() -> Duration {
+    Duration::from_secs(2)
+}
+
+impl MetricsConfig {
+    pub fn to_probe(&self) -> Result<chaos_metrics::Probe, String> {
+        match (&self.http, &self.tcp) {
+            (Some(url), None) => Ok(chaos_metrics::Probe::Http { url: url.clone() }),
+            (None, Some(address)) => Ok(chaos_metrics::Probe::Tcp {
+                address: address.clone(),
+            }),
+            (Some(_), Some(_)) => Err("Metrics probe must set only one of http or tcp".to_string()),
+            (None, None) => Err("Metrics probe must set http or tcp".to_string()),
+        }
+    }
+
+    pub fn slo_tracker(&self) -> chaos_metrics::SloTracker {
+        let mut tracker = chaos_metrics::SloTracker::new();
+        for slo in &self.slos {
+            tracker.add_slo(slo.name.clone(), slo.latency);
+        }
+        tracker
+    }
 }
 
 impl TargetConfig {
@@ -64,10 +144,27 @@ impl TargetConfig {
             Ok(chaos_core::Target::container(id.clone()))
         } else if let Some(pattern) = &self.pattern {
             Ok(chaos_core::Target::process_pattern(pattern.clone()))
+        } else if !self.process_selector().is_empty() {
+            let selector = self.process_selector();
+            selector.validate().map_err(|e| e.to_string())?;
+            Ok(chaos_core::Target::process_selector(
+                selector,
+                self.policy.unwrap_or_default(),
+            ))
         } else {
             Err("No target specified".to_string())
         }
     }
+
+    fn process_selector(&self) -> chaos_core::ProcessSelector {
+        chaos_core::ProcessSelector {
+            name: self.process_name.clone(),
+            cmdline_regex: self.cmdline_regex.clone(),
+            exe_path: self.exe_path.clone(),
+            user: self.user.clone(),
+            parent_pid: self.parent_pid,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -112,6 +209,21 @@ impl Scenario {
             }
         }
 
+        if let Some(metrics) = &self.metrics {
+            metrics.to_probe()?;
+            if metrics.interval.is_zero() {
+                return Err("Metrics probe interval must be > 0".to_string());
+            }
+        }
+
+        if let Some(steady_state) = &self.steady_state {
+            steady_state.validate()?;
+        }
+
+        if !self.abort_when.is_empty() && self.metrics.is_none() {
+            return Err("abort_when rules need a metrics probe to watch".to_string());
+        }
+
         Ok(())
     }
 }
@@ -125,6 +237,9 @@ pub struct ScenarioBuilder {
     ramp_up: Option<Duration>,
     phases: Vec<Phase>,
     labels: HashMap<String, String>,
+    metrics: Option<MetricsConfig>,
+    steady_state: Option<SteadyState>,
+    abort_when: Vec<AbortRule>,
 }
 
 impl ScenarioBuilder {
@@ -163,6 +278,21 @@ impl ScenarioBuilder {
         self
     }
 
+    pub fn metrics(mut self, metrics: MetricsConfig) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    pub fn steady_state(mut self, steady_state: SteadyState) -> Self {
+        self.steady_state = Some(steady_state);
+        self
+    }
+
+    pub fn abort_when(mut self, rule: AbortRule) -> Self {
+        self.abort_when.push(rule);
+        self
+    }
+
     pub fn build(self) -> Scenario {
         let duration = self
             .duration
@@ -176,6 +306,9 @@ impl ScenarioBuilder {
             ramp_up: self.ramp_up,
             phases: self.phases,
             labels: self.labels,
+            metrics: self.metrics,
+            steady_state: self.steady_state,
+            abort_when: self.abort_when,
         }
     }
 }
@@ -225,7 +358,7 @@ impl PhaseBuilder {
     }
 }
 
-mod humantime_serde {
+pub(crate) mod humantime_serde {
     use serde::{Deserialize, Deserializer, Serializer};
     use std::time::Duration;
 
@@ -245,7 +378,7 @@ mod humantime_serde {
     }
 }
 
-mod humantime_serde_option {
+pub(crate) mod humantime_serde_option {
     use serde::{Deserialize, Deserializer, Serializer};
     use std::time::Duration;
 
@@ -306,5 +439,78 @@ mod tests {
 
         let invalid = Scenario::builder().build();
         assert!(invalid.validate().is_err());
+
+        let unwatched = Scenario::builder()
+            .name("unwatched")
+            .abort_when("p99 > 2s".parse().unwrap())
+            .add_phase(
+                Phase::builder()
+                    .name("phase1")
+                    .duration(Duration::from_secs(60))
+                    .build(),
+            )
+            .build();
+        assert!(unwatched.validate().is_err());
+    }
+
+    #[test]
+    fn test_process_selector_target() {
+        let config: TargetConfig = serde_yaml::from_str(
+            r#"
+process_name: "axum_http_service"
+user: "root"
+policy: "random(2)"
+"#,
+        )
+        .unwrap();
+
+        match config.to_target().unwrap() {
+            chaos_core::Target::ProcessSelector { selector, policy } => {
+                assert_eq!(selector.name.as_deref(), Some("axum_http_service"));
+                assert_eq!(selector.user.as_deref(), Some("root"));
+                assert_eq!(policy, chaos_core::SelectionPolicy::Random(2));
+            }
+            other => panic!("unexpected target: {:?}", other),
+        }
+
+        let config = TargetConfig {
+            cmdline_regex: Some("[".to_string()),
+            ..Default::default()
+        };
+        assert!(config.to_target().is_err());
+        assert!(serde_yaml::from_str::<TargetConfig>("policy: sometimes").is_err());
+    }
+
+    #[test]
+    fn test_metrics_config() {
+        let config: MetricsConfig = serde_yaml::from_str(
+            r#"
+http: "http://127.0.0.1:3000/health"
+interval: 250ms
+slos:
+  - name: "fast"
+    latency: 100ms
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.interval, Duration::from_millis(250));
+        assert_eq!(config.timeout, Duration::from_secs(2));
+        assert_eq!(
+            config.to_probe().unwrap(),
+            chaos_metrics::Probe::Http {
+                url: "http://127.0.0.1:3000/health".to_string()
+            }
+        );
+
+        let mut tracker = config.slo_tracker();
+        tracker.check_latency(Duration::from_millis(150));
+        assert_eq!(tracker.violation_count(), 1);
+
+        let both = MetricsConfig {
+            tcp: Some("127.0.0.1:9001".to_string()),
+            ..config
+        };
+        assert!(both.to_probe().is_err());
     }
 }
@@ -1,14 +1,18 @@
+pub mod abort;
 pub mod config;
 pub mod parser;
 pub mod phase;
 pub mod runner;
 pub mod scheduler;
+pub mod steady_state;
 
-pub use config::{Scenario, ScenarioConfig};
+pub use abort::AbortRule;
+pub use config::{MetricsConfig, Scenario, ScenarioConfig, SloConfig};
 pub use parser::{parse_scenario_from_file, parse_scenario_from_str};
 pub use phase::Phase;
-pub use runner::{run_scenario, ScenarioRunner};
+pub use runner::{run_scenario, RunStatus, ScenarioResult, ScenarioRunner};
 pub use scheduler::{Scheduler, SchedulingMode};
+pub use steady_state::{Hypothesis, HypothesisResult, SteadyState};
 
 /// Convenience function to parse a scenario from a YAML string
 pub fn parse_scenario(yaml: &str) -> anyhow::Result<Scenario> {
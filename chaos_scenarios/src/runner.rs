@@ -1,19 +1,71 @@
 use crate::{
+    abort::{AbortRule, AbortWatcher, ABORT_WINDOW},
     config::{InjectionConfig, Scenario},
     scheduler::{Scheduler, SchedulingMode},
+    steady_state::{Hypothesis, HypothesisResult},
 };
-use chaos_core::{Executor, InjectionHandle};
+use chaos_core::{Executor, InjectionHandle, Target};
+use chaos_metrics::{
+    AggregatedMetrics, MetricType, MetricsAggregator, MetricsCollector, Prober, SloTracker,
+    SloViolation,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
 use tracing::{info, warn};
 
+/// How long an aborted run waits for `remove_all` before giving up and
+/// leaving the rest to `chaos cleanup`
+pub const DEFAULT_ABORT_TIMEOUT: Duration = Duration::from_secs(10);
+
 pub struct ScenarioRunner {
     executor: Executor,
+    cancel: CancellationToken,
+    abort_timeout: Duration,
+    collector: Arc<MetricsCollector>,
+    artifacts_dir: Option<PathBuf>,
 }
 
 impl ScenarioRunner {
     pub fn new(executor: Executor) -> Self {
-        Self { executor }
+        Self {
+            executor,
+            cancel: CancellationToken::new(),
+            abort_timeout: DEFAULT_ABORT_TIMEOUT,
+            collector: Arc::new(MetricsCollector::new()),
+            artifacts_dir: None,
+        }
+    }
+
+    /// Copy files produced by injections (e.g. logs of restarted
+    /// processes) into `<dir>/<phase>/`
+    pub fn with_artifacts_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.artifacts_dir = Some(dir.into());
+        self
+    }
+
+    /// Bound on removing active injections after an abort
+    pub fn with_abort_timeout(mut self, timeout: Duration) -> Self {
+        self.abort_timeout = timeout;
+        self
+    }
+
+    /// Cancelling this token aborts a running scenario: no further phases
+    /// are scheduled, active injections are removed and the partial result
+    /// is returned with status `Aborted`
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+
+    /// Collector aggregated into each phase's metrics. The scenario's probe
+    /// records into it, and so can callers that measure the service
+    /// themselves (e.g. a load generator).
+    pub fn metrics_collector(&self) -> Arc<MetricsCollector> {
+        self.collector.clone()
     }
 
     pub fn with_defaults() -> Self {
@@ -23,7 +75,9 @@ impl ScenarioRunner {
     pub async fn run(&self, scenario: &Scenario) -> anyhow::Result<ScenarioResult> {
         info!("Starting scenario: {}", scenario.name);
         scenario.validate().map_err(|e| anyhow::anyhow!(e))?;
+        self.validate_injections(scenario)?;
 
+        let started_at = chrono::Utc::now();
         let start_time = Instant::now();
 
         // Create scheduler
@@ -45,11 +99,64 @@ impl ScenarioRunner {
             scheduler.apply_ramp_up(&mut phases, ramp_up);
         }
 
+        // Drives random target selection, reproducible when the scenario is seeded
+        let mut rng = scenario
+            .seed
+            .map(StdRng::seed_from_u64)
+            .unwrap_or_else(StdRng::from_entropy);
+
+        let prober = match &scenario.metrics {
+            Some(config) => {
+                let probe = config.to_probe().map_err(|e| anyhow::anyhow!(e))?;
+                info!(
+                    "Probing {} every {:?}",
+                    probe.description(),
+                    config.interval
+                );
+                Some(Arc::new(Prober::new(probe, config.timeout)))
+            }
+            None => None,
+        };
+
+        let hypothesis = scenario
+            .steady_state
+            .clone()
+            .map(|state| Arc::new(Hypothesis::new(state)));
+
+        // Nothing is injected unless the system starts out healthy
+        let steady_state_before = match &hypothesis {
+            Some(hypothesis) => Some(hypothesis.evaluate().await),
+            None => None,
+        };
+        let baseline_failed = steady_state_before.as_ref().is_some_and(|h| !h.passed);
+        if baseline_failed {
+            warn!(
+                "Steady state does not hold before scenario '{}'; skipping all phases",
+                scenario.name
+            );
+        }
+
+        // Cancelled on abort (signal) or when an abort rule fires (halt)
+        let halt = self.cancel.child_token();
+        let watch_task = match &scenario.metrics {
+            Some(config) if !scenario.abort_when.is_empty() && !baseline_failed => Some(
+                self.watch_abort_rules(scenario.abort_when.clone(), config.interval, halt.clone()),
+            ),
+            _ => None,
+        };
+
+        let abort_window = chrono::Duration::from_std(ABORT_WINDOW).unwrap_or_default();
         let mut phase_results = Vec::new();
         let mut all_handles = Vec::new();
+        // Targets replaced during the run, e.g. restarted processes
+        let mut retargets = HashMap::new();
 
         // Execute phases
         for scheduled_phase in phases {
+            if halt.is_cancelled() || baseline_failed {
+                break;
+            }
+
             // Wait until phase start time
             let elapsed = start_time.elapsed();
             if let Some(delay) = scheduled_phase.delay_until_start(elapsed) {
@@ -58,7 +165,9 @@ impl ScenarioRunner {
                     delay,
                     scheduled_phase.name()
                 );
-                tokio::time::sleep(delay).await;
+                if !sleep_unless_cancelled(delay, &halt).await {
+                    break;
+                }
             }
 
             info!(
@@ -70,12 +179,36 @@ impl ScenarioRunner {
             let phase_start = Instant::now();
             let mut handles = Vec::new();
 
+            // Each phase is measured on its own, but the abort rules keep
+            // seeing the trailing window across phase boundaries
+            let phase_started = chrono::Utc::now();
+            self.collector
+                .clear_before(phase_started - abort_window)
+                .await;
+            let probe_task =
+                prober
+                    .as_ref()
+                    .zip(scenario.metrics.as_ref())
+                    .map(|(prober, config)| {
+                        prober
+                            .clone()
+                            .spawn(self.collector.clone(), config.interval)
+                    });
+            let monitor = hypothesis.clone().map(|h| h.monitor());
+
             // Apply injections
             for injection in &scheduled_phase.phase.injections {
-                match self.apply_injection(injection).await {
-                    Ok(handle) => {
-                        info!("Applied injection: {}", injection.r#type);
-                        handles.push(handle);
+                if halt.is_cancelled() {
+                    break;
+                }
+                match self.apply_injection(injection, &mut rng, &retargets).await {
+                    Ok(applied) => {
+                        info!(
+                            "Applied injection: {} ({} target(s))",
+                            injection.r#type,
+                            applied.len()
+                        );
+                        handles.extend(applied);
                     }
                     Err(e) => {
                         warn!("Failed to apply injection '{}': {}", injection.r#type, e);
@@ -85,66 +218,333 @@ impl ScenarioRunner {
 
             // Wait for phase duration
             let phase_elapsed = phase_start.elapsed();
-            if phase_elapsed < scheduled_phase.duration() {
-                let remaining = scheduled_phase.duration() - phase_elapsed;
-                tokio::time::sleep(remaining).await;
-            }
+            let remaining = scheduled_phase.duration().saturating_sub(phase_elapsed);
+            let interrupted = !sleep_unless_cancelled(remaining, &halt).await;
+            let artifacts = self
+                .collect_reports(&handles, scheduled_phase.name(), &mut retargets)
+                .await;
 
-            // Remove injections
-            for handle in &handles {
-                if let Err(e) = self.executor.remove(handle.clone()).await {
-                    warn!("Failed to remove injection '{}': {}", handle.id, e);
+            // Remove injections; on abort or halt they are removed together below
+            if !interrupted {
+                for handle in &handles {
+                    if let Err(e) = self.executor.remove(handle.clone()).await {
+                        warn!("Failed to remove injection '{}': {}", handle.id, e);
+                    }
                 }
             }
 
             let phase_duration = phase_start.elapsed();
-            info!(
-                "Completed phase '{}' in {:?}",
-                scheduled_phase.name(),
-                phase_duration
-            );
+            if let Some(task) = probe_task {
+                task.abort();
+            }
+            let phase_hypothesis = monitor.map(|m| m.finish());
+            if phase_hypothesis.as_ref().is_some_and(|h| !h.passed) {
+                warn!(
+                    "Steady state deviated during phase '{}'",
+                    scheduled_phase.name()
+                );
+            }
+            let (metrics, slo_violations) = self
+                .phase_metrics(
+                    scenario.metrics.as_ref().map(|m| m.slo_tracker()),
+                    phase_started,
+                )
+                .await;
+            if !slo_violations.is_empty() {
+                warn!(
+                    "Phase '{}' violated SLOs {} time(s)",
+                    scheduled_phase.name(),
+                    slo_violations.len()
+                );
+            }
+
+            if interrupted {
+                warn!(
+                    "Phase '{}' interrupted after {:?}",
+                    scheduled_phase.name(),
+                    phase_duration
+                );
+            } else {
+                info!(
+                    "Completed phase '{}' in {:?}",
+                    scheduled_phase.name(),
+                    phase_duration
+                );
+            }
 
             phase_results.push(PhaseResult {
                 name: scheduled_phase.name().to_string(),
                 duration: phase_duration,
                 injection_count: handles.len(),
+                interrupted,
+                metrics,
+                slo_violations,
+                hypothesis: phase_hypothesis,
+                artifacts,
             });
 
             all_handles.extend(handles);
+
+            if interrupted {
+                break;
+            }
         }
 
+        let halt_reason = match watch_task {
+            Some(task) if halt.is_cancelled() && !self.cancel.is_cancelled() => {
+                task.await.ok().flatten()
+            }
+            Some(task) => {
+                task.abort();
+                None
+            }
+            None => None,
+        };
+
+        let status = if self.cancel.is_cancelled() {
+            warn!(
+                "Scenario '{}' aborted, removing active injections",
+                scenario.name
+            );
+            self.remove_all_bounded().await;
+            RunStatus::Aborted
+        } else if halt.is_cancelled() {
+            warn!(
+                "Scenario '{}' halted by abort rule, removing active injections",
+                scenario.name
+            );
+            self.remove_all_bounded().await;
+            RunStatus::Halted
+        } else if baseline_failed {
+            RunStatus::Skipped
+        } else {
+            RunStatus::Completed
+        };
+
+        // Did the system return to its steady state once the faults were gone?
+        let steady_state_after = match &hypothesis {
+            Some(hypothesis) if status == RunStatus::Completed => Some(hypothesis.evaluate().await),
+            _ => None,
+        };
+
         let total_duration = start_time.elapsed();
 
         info!(
-            "Scenario '{}' completed in {:?}",
-            scenario.name, total_duration
+            "Scenario '{}' {} in {:?}",
+            scenario.name, status, total_duration
         );
 
         Ok(ScenarioResult {
             scenario_name: scenario.name.clone(),
-            started_at: chrono::Utc::now(),
+            started_at,
             total_duration,
             phase_results,
             total_injections: all_handles.len(),
+            status,
+            steady_state_before,
+            steady_state_after,
+            halt_reason,
         })
     }
 
+    /// Check the abort rules against the trailing window of live metrics on
+    /// every tick. When one fires, cancel `halt` and return the reason.
+    fn watch_abort_rules(
+        &self,
+        rules: Vec<AbortRule>,
+        interval: Duration,
+        halt: CancellationToken,
+    ) -> tokio::task::JoinHandle<Option<String>> {
+        let collector = self.collector.clone();
+        tokio::spawn(async move {
+            let mut watcher = AbortWatcher::new(rules);
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            let window = chrono::Duration::from_std(ABORT_WINDOW).unwrap_or_default();
+
+            loop {
+                ticker.tick().await;
+                let since = chrono::Utc::now() - window;
+                let recent: Vec<_> = collector
+                    .get_metrics()
+                    .await
+                    .into_iter()
+                    .filter(|m| m.timestamp >= since)
+                    .collect();
+
+                if let Some(reason) =
+                    watcher.check(&MetricsAggregator::aggregate(&recent), Instant::now())
+                {
+                    warn!("Abort rule fired: {}", reason);
+                    halt.cancel();
+                    return Some(reason);
+                }
+            }
+        })
+    }
+
+    /// Take in what the phase's injections observed: recovery times and
+    /// measurements go to the collector, replaced targets into `retargets` and produced files
+    /// into the artifacts directory. Returns where the artifacts are.
+    async fn collect_reports(
+        &self,
+        handles: &[InjectionHandle],
+        phase: &str,
+        retargets: &mut HashMap<Target, Target>,
+    ) -> Vec<PathBuf> {
+        let mut artifacts = Vec::new();
+        for handle in handles {
+            let report = self.executor.report(handle).await;
+            for time in report.recoveries {
+                self.collector.record_recovery(time).await;
+            }
+            for (name, value) in report.measurements {
+                self.collector.record_custom(name, value).await;
+            }
+            if let Some(target) = report.retarget {
+                info!(
+                    "{} was replaced by {}",
+                    handle.target.description(),
+                    target.description()
+                );
+                for replaced in retargets.values_mut() {
+                    if *replaced == handle.target {
+                        *replaced = target.clone();
+                    }
+                }
+                retargets.insert(handle.target.clone(), target);
+            }
+            for file in report.artifacts {
+                artifacts.push(self.store_artifact(file, phase).await);
+            }
+        }
+        artifacts
+    }
+
+    /// Copy `file` under the artifacts directory if one is set; returns
+    /// where the artifact can be found
+    async fn store_artifact(&self, file: PathBuf, phase: &str) -> PathBuf {
+        let (Some(dir), Some(name)) = (&self.artifacts_dir, file.file_name()) else {
+            return file;
+        };
+        let dir = dir.join(phase.replace(['/', '\\'], "_"));
+        let stored = dir.join(name);
+        let copied = match tokio::fs::create_dir_all(&dir).await {
+            Ok(()) => tokio::fs::copy(&file, &stored).await.map(|_| ()),
+            Err(e) => Err(e),
+        };
+        match copied {
+            Ok(()) => stored,
+            Err(e) => {
+                warn!("Failed to store artifact {}: {}", file.display(), e);
+                file
+            }
+        }
+    }
+
+    /// Aggregate what the collector gathered since the phase started and
+    /// check each latency against the scenario's SLOs. `None` if nothing was
+    /// measured.
+    async fn phase_metrics(
+        &self,
+        slos: Option<SloTracker>,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> (Option<AggregatedMetrics>, Vec<SloViolation>) {
+        let recorded: Vec<_> = self
+            .collector
+            .get_metrics()
+            .await
+            .into_iter()
+            .filter(|metric| metric.timestamp >= since)
+            .collect();
+        if recorded.is_empty() {
+            return (None, Vec::new());
+        }
+
+        let mut tracker = slos.unwrap_or_default();
+        for metric in &recorded {
+            if let MetricType::Latency(latency) = metric.metric_type {
+                tracker.check_latency(latency);
+            }
+        }
+
+        (
+            Some(MetricsAggregator::aggregate(&recorded)),
+            tracker.violations().to_vec(),
+        )
+    }
+
+    /// Remove every active injection, giving up after the abort timeout.
+    /// Anything left behind stays journaled for `chaos cleanup`.
+    async fn remove_all_bounded(&self) {
+        match tokio::time::timeout(self.abort_timeout, self.executor.remove_all()).await {
+            Ok(Ok(())) => info!("Removed all active injections"),
+            Ok(Err(e)) => warn!("Failed to remove all injections: {}", e),
+            Err(_) => warn!(
+                "Timed out after {:?} removing injections; run 'chaos cleanup'",
+                self.abort_timeout
+            ),
+        }
+    }
+
+    /// Reject unknown injectors and bad parameters before any fault is applied
+    pub fn validate_injections(&self, scenario: &Scenario) -> anyhow::Result<()> {
+        for phase in &scenario.phases {
+            for injection in &phase.injections {
+                self.executor
+                    .validate_params(&injection.r#type, &injection.parameters)
+                    .map_err(|e| anyhow::anyhow!("Phase '{}': {}", phase.name, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolve the injection's target to concrete targets, following any
+    /// replacements, and apply the injection to each; fails only if no
+    /// target could be injected
     async fn apply_injection(
         &self,
         injection: &InjectionConfig,
-    ) -> anyhow::Result<InjectionHandle> {
+        rng: &mut StdRng,
+        retargets: &HashMap<Target, Target>,
+    ) -> anyhow::Result<Vec<InjectionHandle>> {
         let target = injection
             .target
             .to_target()
             .map_err(|e| anyhow::anyhow!("Invalid target: {}", e))?;
 
-        let handle = self
-            .executor
-            .inject(&injection.r#type, &target)
-            .await
-            .map_err(|e| anyhow::anyhow!("Injection failed: {}", e))?;
+        let targets = target
+            .resolve(rng)
+            .map_err(|e| anyhow::anyhow!("Target resolution failed: {}", e))?
+            .into_iter()
+            .map(|target| retargets.get(&target).cloned().unwrap_or(target))
+            .collect::<Vec<_>>();
+
+        let mut handles = Vec::new();
+        let mut last_error = None;
+        for target in &targets {
+            match self
+                .executor
+                .inject_with_params(&injection.r#type, target, &injection.parameters)
+                .await
+            {
+                Ok(handle) => handles.push(handle),
+                Err(e) => {
+                    warn!(
+                        "Injection '{}' failed on {}: {}",
+                        injection.r#type,
+                        target.description(),
+                        e
+                    );
+                    last_error = Some(e);
+                }
+            }
+        }
 
-        Ok(handle)
+        match last_error {
+            Some(e) if handles.is_empty() => Err(anyhow::anyhow!("Injection failed: {}", e)),
+            _ => Ok(handles),
+        }
     }
 }
 
@@ -156,14 +556,74 @@ pub struct ScenarioResult {
     pub total_duration: Duration,
     pub phase_results: Vec<PhaseResult>,
     pub total_injections: usize,
+    #[serde(default)]
+    pub status: RunStatus,
+    /// Steady-state check before the first phase
+    #[serde(default)]
+    pub steady_state_before: Option<HypothesisResult>,
+    /// Steady-state check after the last phase
+    #[serde(default)]
+    pub steady_state_after: Option<HypothesisResult>,
+    /// The abort rule that halted the run, with the value observed
+    #[serde(default)]
+    pub halt_reason: Option<String>,
 }
 
 impl ScenarioResult {
+    pub fn is_aborted(&self) -> bool {
+        self.status == RunStatus::Aborted
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.status == RunStatus::Halted
+    }
+
+    /// Share of successful requests across all measured phases. Without
+    /// measurements, the share of phases that ran to completion.
     pub fn success_rate(&self) -> f64 {
         if self.phase_results.is_empty() {
             return 0.0;
         }
-        1.0 // Simplified - in reality would track failures
+
+        let (successful, total) = self
+            .phase_results
+            .iter()
+            .filter_map(|p| p.metrics.as_ref())
+            .fold((0, 0), |(s, t), m| {
+                (s + m.successful_requests, t + m.total_requests)
+            });
+        if total > 0 {
+            return successful as f64 / total as f64;
+        }
+
+        let completed = self.phase_results.iter().filter(|p| !p.interrupted).count();
+        completed as f64 / self.phase_results.len() as f64
+    }
+
+    /// Whether the steady-state hypothesis held before, during and after
+    /// the run; `None` if the scenario declares no steady state
+    pub fn hypothesis_held(&self) -> Option<bool> {
+        let checks: Vec<&HypothesisResult> = self
+            .steady_state_before
+            .iter()
+            .chain(
+                self.phase_results
+                    .iter()
+                    .filter_map(|p| p.hypothesis.as_ref()),
+            )
+            .chain(self.steady_state_after.iter())
+            .collect();
+        if checks.is_empty() {
+            return None;
+        }
+        Some(checks.iter().all(|h| h.passed))
+    }
+
+    pub fn slo_violation_count(&self) -> usize {
+        self.phase_results
+            .iter()
+            .map(|p| p.slo_violations.len())
+            .sum()
     }
 
     pub fn average_phase_duration(&self) -> Duration {
@@ -182,6 +642,55 @@ pub struct PhaseResult {
     #[serde(with = "humantime_serde")]
     pub duration: Duration,
     pub injection_count: usize,
+    /// The run was aborted or halted while this phase was active
+    #[serde(default)]
+    pub interrupted: bool,
+    /// Service metrics gathered while the phase ran
+    #[serde(default)]
+    pub metrics: Option<AggregatedMetrics>,
+    #[serde(default)]
+    pub slo_violations: Vec<SloViolation>,
+    /// Steady-state probes run while the phase was active
+    #[serde(default)]
+    pub hypothesis: Option<HypothesisResult>,
+    /// Files the phase's injections produced, such as restart logs
+    #[serde(default)]
+    pub artifacts: Vec<PathBuf>,
+}
+
+/// How a scenario run ended
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    /// Every phase ran to completion
+    #[default]
+    Completed,
+    /// Cancelled from outside (signal or stop request) before the end
+    Aborted,
+    /// An `abort_when` rule fired; remaining phases were skipped
+    Halted,
+    /// The steady state did not hold before the first phase, so nothing
+    /// was injected
+    Skipped,
+}
+
+impl std::fmt::Display for RunStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RunStatus::Completed => write!(f, "completed"),
+            RunStatus::Aborted => write!(f, "aborted"),
+            RunStatus::Halted => write!(f, "halted"),
+            RunStatus::Skipped => write!(f, "skipped"),
+        }
+    }
+}
+
+/// Sleep for `duration`; returns false if the token was cancelled first
+async fn sleep_unless_cancelled(duration: Duration, token: &CancellationToken) -> bool {
+    tokio::select! {
+        _ = tokio::time::sleep(duration) => true,
+        _ = token.cancelled() => false,
+    }
 }
 
 pub async fn run_scenario(scenario: &Scenario) -> anyhow::Result<ScenarioResult> {
@@ -216,7 +725,30 @@ mod tests {
     #[tokio::test]
     async fn test_scenario_runner_creation() {
         let _runner = ScenarioRunner::with_defaults();
-        assert!(true); // Runner created successfully
+    }
+
+    #[test]
+    fn test_validate_injection_parameters() {
+        let runner = ScenarioRunner::with_defaults();
+        let yaml = r#"
+name: "params"
+duration: 10s
+phases:
+  - name: "cpu"
+    duration: 10s
+    injections:
+      - type: "cpu_starvation"
+        target:
+          pid: 1
+        intensity: 0.5
+        threads: [0, 1]
+"#;
+        let scenario = crate::parse_scenario(yaml).unwrap();
+        assert!(runner.validate_injections(&scenario).is_ok());
+
+        let scenario = crate::parse_scenario(&yaml.replace("intensity", "intensty")).unwrap();
+        let err = runner.validate_injections(&scenario).unwrap_err();
+        assert!(err.to_string().contains("unknown field `intensty`"));
     }
 
     #[test]
@@ -230,17 +762,330 @@ mod tests {
                     name: "phase1".to_string(),
                     duration: Duration::from_secs(50),
                     injection_count: 2,
+                    interrupted: false,
+                    metrics: None,
+                    slo_violations: Vec::new(),
+                    hypothesis: None,
+                    artifacts: Vec::new(),
                 },
                 PhaseResult {
                     name: "phase2".to_string(),
                     duration: Duration::from_secs(50),
                     injection_count: 1,
+                    interrupted: false,
+                    metrics: None,
+                    slo_violations: Vec::new(),
+                    hypothesis: None,
+                    artifacts: Vec::new(),
                 },
             ],
             total_injections: 3,
+            status: RunStatus::Completed,
+            steady_state_before: None,
+            steady_state_after: None,
+            halt_reason: None,
         };
 
         assert_eq!(result.success_rate(), 1.0);
         assert_eq!(result.average_phase_duration(), Duration::from_secs(50));
+        assert!(!result.is_aborted());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_restarted_process_is_retargeted() {
+        let mut service = std::process::Command::new("sleep")
+            .arg("60")
+            .spawn()
+            .unwrap();
+        let pid = service.id();
+        std::thread::spawn(move || service.wait());
+
+        let dir = std::env::temp_dir().join(format!("chaos-runner-{}", pid));
+        let yaml = format!(
+            r#"
+name: "restart"
+duration: 1s
+phases:
+  - name: "kill"
+    duration: 500ms
+    injections:
+      - type: "process_kill"
+        target:
+          pid: {pid}
+        signal: "SIGKILL"
+        restart_mode: "WarmBoot"
+        restart_command: "echo up; exec sleep 60"
+        log_dir: "{logs}"
+  - name: "kill again"
+    duration: 500ms
+    injections:
+      - type: "process_kill"
+        target:
+          pid: {pid}
+        signal: "SIGKILL"
+"#,
+            logs = dir.join("logs").display()
+        );
+        let scenario = crate::parse_scenario(&yaml).unwrap();
+        let runner = ScenarioRunner::with_defaults().with_artifacts_dir(dir.join("artifacts"));
+        let result = runner.run(&scenario).await.unwrap();
+        assert_eq!(result.total_injections, 2);
+
+        let kill = &result.phase_results[0];
+        let metrics = kill.metrics.as_ref().unwrap();
+        assert!(metrics.average_recovery_time > Duration::ZERO);
+        assert_eq!(kill.artifacts.len(), 2);
+        assert!(kill
+            .artifacts
+            .iter()
+            .all(|p| p.starts_with(dir.join("artifacts").join("kill"))));
+
+        // The second phase hit the restarted process, not the dead original
+        assert_eq!(result.phase_results[1].injection_count, 1);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_run_is_aborted() {
+        let runner = ScenarioRunner::with_defaults();
+        let yaml = r#"
+name: "abort"
+duration: 20s
+phases:
+  - name: "idle"
+    duration: 10s
+  - name: "never"
+    duration: 10s
+"#;
+        let scenario = crate::parse_scenario(yaml).unwrap();
+
+        let token = runner.cancellation_token();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            token.cancel();
+        });
+
+        let result = runner.run(&scenario).await.unwrap();
+        assert!(result.is_aborted());
+        assert_eq!(result.phase_results.len(), 1);
+        assert!(result.phase_results[0].interrupted);
+        assert!(result.total_duration < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_phase_metrics_from_probe() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { while let Ok((_stream, _)) = listener.accept().await {} });
+
+        let yaml = format!(
+            r#"
+name: "measured"
+duration: 300ms
+metrics:
+  tcp: "{}"
+  interval: 20ms
+  slos:
+    - name: "impossible"
+      latency: 0s
+phases:
+  - name: "baseline"
+    duration: 300ms
+"#,
+            address
+        );
+        let scenario = crate::parse_scenario(&yaml).unwrap();
+        let result = ScenarioRunner::with_defaults()
+            .run(&scenario)
+            .await
+            .unwrap();
+
+        let metrics = result.phase_results[0].metrics.as_ref().unwrap();
+        assert!(metrics.successful_requests > 0);
+        assert_eq!(metrics.failed_requests, 0);
+        assert_eq!(result.success_rate(), 1.0);
+        assert_eq!(result.slo_violation_count(), metrics.successful_requests);
+    }
+
+    #[tokio::test]
+    async fn test_steady_state_hypothesis() {
+        let yaml = r#"
+name: "hypothesis"
+duration: 200ms
+steady_state:
+  interval: 50ms
+  probes:
+    - name: "marker"
+      type: command
+      command: "test ! -e MARKER"
+phases:
+  - name: "only"
+    duration: 200ms
+"#;
+        let marker = std::env::temp_dir().join(format!("chaos_marker_{}", std::process::id()));
+        let yaml = yaml.replace("MARKER", &marker.display().to_string());
+        let scenario = crate::parse_scenario(&yaml).unwrap();
+
+        let result = ScenarioRunner::with_defaults()
+            .run(&scenario)
+            .await
+            .unwrap();
+        assert_eq!(result.status, RunStatus::Completed);
+        assert_eq!(result.hypothesis_held(), Some(true));
+        let during = result.phase_results[0].hypothesis.as_ref().unwrap();
+        assert!(during.probes[0].checks >= 2);
+        assert!(result.steady_state_after.as_ref().unwrap().passed);
+
+        // A failing baseline skips every phase
+        std::fs::write(&marker, "").unwrap();
+        let result = ScenarioRunner::with_defaults()
+            .run(&scenario)
+            .await
+            .unwrap();
+        std::fs::remove_file(&marker).unwrap();
+        assert_eq!(result.status, RunStatus::Skipped);
+        assert!(result.phase_results.is_empty());
+        assert_eq!(result.hypothesis_held(), Some(false));
+        assert!(result.steady_state_after.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_abort_rule_halts_run() {
+        // Nothing listens here, so every probe fails
+        let address = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let yaml = format!(
+            r#"
+name: "halt"
+duration: 20s
+metrics:
+  tcp: "{}"
+  interval: 20ms
+abort_when:
+  - "error_rate > 50%"
+phases:
+  - name: "first"
+    duration: 10s
+  - name: "never"
+    duration: 10s
+"#,
+            address
+        );
+        let scenario = crate::parse_scenario(&yaml).unwrap();
+
+        let result = ScenarioRunner::with_defaults()
+            .run(&scenario)
+            .await
+            .unwrap();
+        assert!(result.is_halted());
+        assert!(result
+            .halt_reason
+            .as_deref()
+            .unwrap()
+            .starts_with("error_rate > 50%"));
+        assert_eq!(result.phase_results.len(), 1);
+        assert!(result.phase_results[0].interrupted);
+        assert!(result.total_duration < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_abort_rule_sustained_across_phases() {
+        // Accepts but never answers, so each probe fails after its timeout
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut open = Vec::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                open.push(stream);
+            }
+        });
+        let phases = (0..8)
+            .map(|i| format!("  - name: \"phase{}\"\n    duration: 200ms\n", i))
+            .collect::<String>();
+        let yaml = format!(
+            r#"
+name: "sustained"
+duration: 2s
+metrics:
+  http: "http://{}/"
+  interval: 20ms
+  timeout: 150ms
+abort_when:
+  - "error_rate > 50% for 300ms"
+phases:
+{}"#,
+            address, phases
+        );
+        let scenario = crate::parse_scenario(&yaml).unwrap();
+
+        // Every phase records one failure 150ms in; the breach only lasts
+        // 300ms if the window survives the phase boundaries
+        let result = ScenarioRunner::with_defaults()
+            .run(&scenario)
+            .await
+            .unwrap();
+        assert!(result.is_halted());
+        assert!(result.phase_results.len() < 8);
+        let metrics = result.phase_results[0].metrics.as_ref().unwrap();
+        assert_eq!(metrics.failed_requests, 1);
+    }
+
+    #[test]
+    fn test_success_rate_from_metrics() {
+        let measured = |successful: usize, failed: usize| {
+            let collected: Vec<_> = (0..successful + failed)
+                .map(|i| chaos_metrics::Metric {
+                    metric_type: if i < successful {
+                        MetricType::Success
+                    } else {
+                        MetricType::Error {
+                            error_type: "timeout".to_string(),
+                        }
+                    },
+                    timestamp: chrono::Utc::now(),
+                    labels: Default::default(),
+                })
+                .collect();
+            PhaseResult {
+                name: "phase".to_string(),
+                duration: Duration::from_secs(1),
+                injection_count: 0,
+                interrupted: false,
+                metrics: Some(MetricsAggregator::aggregate(&collected)),
+                slo_violations: Vec::new(),
+                hypothesis: None,
+                artifacts: Vec::new(),
+            }
+        };
+        let result = ScenarioResult {
+            scenario_name: "test".to_string(),
+            started_at: chrono::Utc::now(),
+            total_duration: Duration::from_secs(2),
+            phase_results: vec![measured(3, 1), measured(4, 0)],
+            total_injections: 0,
+            status: RunStatus::Completed,
+            steady_state_before: None,
+            steady_state_after: None,
+            halt_reason: None,
+        };
+        assert_eq!(result.success_rate(), 7.0 / 8.0);
+    }
+
+    #[test]
+    fn test_status_defaults_for_old_results() {
+        let json = r#"{
+            "scenario_name": "old",
+            "started_at": "2024-01-01T00:00:00Z",
+            "total_duration": "10s",
+            "phase_results": [{"name": "p", "duration": "10s", "injection_count": 0}],
+            "total_injections": 0
+        }"#;
+        let result: ScenarioResult = serde_json::from_str(json).unwrap();
+        assert_eq!(result.status, RunStatus::Completed);
+        assert!(!result.phase_results[0].interrupted);
     }
 }
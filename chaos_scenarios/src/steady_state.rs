@@ -0,0 +1,627 @@
+use crate::config::{humantime_serde, humantime_serde_option, TargetConfig};
+use chaos_core::health;
+use chaos_metrics::{SloTracker, SloViolation};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+/// What "healthy" means for the system under test. Checked before the first
+/// phase, continuously during every phase and once after the last.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SteadyState {
+    /// How often the probes run while a phase is active
+    #[serde(with = "humantime_serde", default = "default_interval")]
+    pub interval: Duration,
+    pub probes: Vec<ProbeSpec>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProbeSpec {
+    pub name: String,
+    #[serde(with = "humantime_serde", default = "default_timeout")]
+    pub timeout: Duration,
+    #[serde(flatten)]
+    pub check: ProbeCheck,
+}
+
+/// A single probe and its tolerance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProbeCheck {
+    /// `GET` the URL; `status` lists accepted codes (default: any 2xx)
+    Http {
+        url: String,
+        #[serde(default)]
+        status: Vec<u16>,
+        #[serde(with = "humantime_serde_option", default)]
+        max_latency: Option<Duration>,
+    },
+    /// Open a TCP connection
+    Tcp {
+        address: String,
+        #[serde(with = "humantime_serde_option", default)]
+        max_latency: Option<Duration>,
+    },
+    /// At least one process matches the target (or none, with `alive: false`)
+    Process {
+        target: TargetConfig,
+        #[serde(default = "default_alive")]
+        alive: bool,
+    },
+    /// Run through `sh -c` and compare the exit code
+    Command {
+        command: String,
+        #[serde(default)]
+        exit_code: i32,
+    },
+    /// Scrape a Prometheus text endpoint and sum the samples matching
+    /// `query` (a metric name with optional `{label="value"}` matchers)
+    Prometheus {
+        url: String,
+        query: String,
+        #[serde(default)]
+        min: Option<f64>,
+        #[serde(default)]
+        max: Option<f64>,
+    },
+}
+
+fn default_interval() -> Duration {
+    Duration::from_secs(5)
+}
+
+fn default_timeout() -> Duration {
+    Duration::from_secs(5)
+}
+
+fn default_alive() -> bool {
+    true
+}
+
+impl SteadyState {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.probes.is_empty() {
+            return Err("Steady state must list at least one probe".to_string());
+        }
+        if self.interval.is_zero() {
+            return Err("Steady state interval must be > 0".to_string());
+        }
+
+        let mut names = HashSet::new();
+        for probe in &self.probes {
+            if probe.name.is_empty() {
+                return Err("Steady state probe name cannot be empty".to_string());
+            }
+            if !names.insert(probe.name.as_str()) {
+                return Err(format!("Duplicate steady state probe '{}'", probe.name));
+            }
+            probe
+                .validate()
+                .map_err(|e| format!("Steady state probe '{}': {}", probe.name, e))?;
+        }
+        Ok(())
+    }
+
+    /// Latency tolerances, one SLO per probe that declares `max_latency`
+    fn slo_tracker(&self) -> SloTracker {
+        let mut tracker = SloTracker::new();
+        for probe in &self.probes {
+            if let ProbeCheck::Http {
+                max_latency: Some(max),
+                ..
+            }
+            | ProbeCheck::Tcp {
+                max_latency: Some(max),
+                ..
+            } = &probe.check
+            {
+                tracker.add_slo(probe.name.clone(), *max);
+            }
+        }
+        tracker
+    }
+}
+
+impl ProbeSpec {
+    fn validate(&self) -> Result<(), String> {
+        match &self.check {
+            ProbeCheck::Http { status, .. } => {
+                if let Some(code) = status.iter().find(|c| !(100..600).contains(*c)) {
+                    return Err(format!("invalid HTTP status {}", code));
+                }
+            }
+            ProbeCheck::Tcp { .. } | ProbeCheck::Command { .. } => {}
+            ProbeCheck::Process { target, .. } => {
+                target.to_target()?;
+            }
+            ProbeCheck::Prometheus {
+                query, min, max, ..
+            } => {
+                parse_selector(query)?;
+                if min.is_none() && max.is_none() {
+                    return Err("prometheus probe needs min and/or max".to_string());
+                }
+                if let (Some(min), Some(max)) = (min, max) {
+                    if min > max {
+                        return Err(format!("min {} is greater than max {}", min, max));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Result of running one probe once
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbeOutcome {
+    pub probe: String,
+    pub latency: Option<Duration>,
+    /// Why the probe was outside its tolerance; `None` if it passed
+    pub failure: Option<String>,
+}
+
+/// Whether the hypothesis held over a window, with per-probe tallies
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HypothesisResult {
+    pub passed: bool,
+    pub probes: Vec<ProbeSummary>,
+    /// Probes that answered but slower than their `max_latency`
+    #[serde(default)]
+    pub slo_violations: Vec<SloViolation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProbeSummary {
+    pub name: String,
+    pub checks: usize,
+    pub failures: usize,
+    #[serde(default)]
+    pub last_failure: Option<String>,
+}
+
+/// Accumulates probe outcomes into a `HypothesisResult`
+#[derive(Default)]
+struct Tally {
+    probes: Vec<ProbeSummary>,
+    slos: SloTracker,
+}
+
+impl Tally {
+    fn new(state: &SteadyState) -> Self {
+        Self {
+            probes: state
+                .probes
+                .iter()
+                .map(|p| ProbeSummary {
+                    name: p.name.clone(),
+                    checks: 0,
+                    failures: 0,
+                    last_failure: None,
+                })
+                .collect(),
+            slos: state.slo_tracker(),
+        }
+    }
+
+    fn record(&mut self, mut outcome: ProbeOutcome) {
+        if let (None, Some(latency)) = (&outcome.failure, outcome.latency) {
+            if self.slos.check_slo(&outcome.probe, latency) {
+                outcome.failure = Some(format!("latency {:?} over tolerance", latency));
+            }
+        }
+
+        let Some(summary) = self.probes.iter_mut().find(|p| p.name == outcome.probe) else {
+            return;
+        };
+        summary.checks += 1;
+        if let Some(failure) = outcome.failure {
+            summary.failures += 1;
+            summary.last_failure = Some(failure);
+        }
+    }
+
+    fn finish(self) -> HypothesisResult {
+        HypothesisResult {
+            passed: self.probes.iter().all(|p| p.failures == 0),
+            probes: self.probes,
+            slo_violations: self.slos.violations().to_vec(),
+        }
+    }
+}
+
+/// Runs the steady-state probes
+pub struct Hypothesis {
+    state: SteadyState,
+    client: reqwest::Client,
+}
+
+impl Hypothesis {
+    pub fn new(state: SteadyState) -> Self {
+        Self {
+            state,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Run every probe once
+    pub async fn evaluate(&self) -> HypothesisResult {
+        let mut tally = Tally::new(&self.state);
+        for outcome in self.run_probes().await {
+            tally.record(outcome);
+        }
+        tally.finish()
+    }
+
+    /// Run the probes every interval until the monitor is finished
+    pub fn monitor(self: Arc<Self>) -> HypothesisMonitor {
+        let tally = Arc::new(Mutex::new(Tally::new(&self.state)));
+        let task_tally = tally.clone();
+        let task = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.state.interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                let outcomes = self.run_probes().await;
+                let mut tally = task_tally.lock().unwrap();
+                for outcome in outcomes {
+                    tally.record(outcome);
+                }
+            }
+        });
+        HypothesisMonitor { task, tally }
+    }
+
+    async fn run_probes(&self) -> Vec<ProbeOutcome> {
+        futures::future::join_all(self.state.probes.iter().map(|p| self.check(p))).await
+    }
+
+    /// Run one probe and compare it against its tolerance
+    pub async fn check(&self, probe: &ProbeSpec) -> ProbeOutcome {
+        let start = Instant::now();
+        let result = match &probe.check {
+            ProbeCheck::Http { url, status, .. } => {
+                match health::http_check(&self.client, url, probe.timeout).await {
+                    Ok(check) if status.is_empty() && check.is_success() => Ok(()),
+                    Ok(check) if status.contains(&check.status) => Ok(()),
+                    Ok(check) => Err(format!("unexpected status {}", check.status)),
+                    Err(e) => Err(e.to_string()),
+                }
+            }
+            ProbeCheck::Tcp { address, .. } => {
+                match tokio::time::timeout(probe.timeout, tokio::net::TcpStream::connect(address))
+                    .await
+                {
+                    Ok(Ok(_)) => Ok(()),
+                    Ok(Err(e)) => Err(format!("connect to {} failed: {}", address, e)),
+                    Err(_) => Err(format!("connect to {} timed out", address)),
+                }
+            }
+            ProbeCheck::Process { target, alive } => match target.to_target() {
+                Ok(target) if target.exists().await == *alive => Ok(()),
+                Ok(target) if *alive => Err(format!("{} is not running", target.description())),
+                Ok(target) => Err(format!("{} is still running", target.description())),
+                Err(e) => Err(e),
+            },
+            ProbeCheck::Command { command, exit_code } => {
+                run_command(command, *exit_code, probe.timeout).await
+            }
+            ProbeCheck::Prometheus {
+                url,
+                query,
+                min,
+                max,
+            } => self
+                .scrape(url, query, probe.timeout)
+                .await
+                .and_then(|value| check_range(value, *min, *max)),
+        };
+
+        ProbeOutcome {
+            probe: probe.name.clone(),
+            latency: result.is_ok().then(|| start.elapsed()),
+            failure: result.err(),
+        }
+    }
+
+    async fn scrape(&self, url: &str, query: &str, timeout: Duration) -> Result<f64, String> {
+        let body = self
+            .client
+            .get(url)
+            .timeout(timeout)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("scrape of {} failed: {}", url, e))?
+            .text()
+            .await
+            .map_err(|e| format!("scrape of {} failed: {}", url, e))?;
+        query_value(&body, query)
+    }
+}
+
+/// Handle on a running monitor; `finish` stops it and returns the tally
+pub struct HypothesisMonitor {
+    task: JoinHandle<()>,
+    tally: Arc<Mutex<Tally>>,
+}
+
+impl HypothesisMonitor {
+    pub fn finish(self) -> HypothesisResult {
+        self.task.abort();
+        std::mem::take(&mut *self.tally.lock().unwrap()).finish()
+    }
+}
+
+async fn run_command(command: &str, expected: i32, timeout: Duration) -> Result<(), String> {
+    let child = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .status();
+
+    match tokio::time::timeout(timeout, child).await {
+        Ok(Ok(status)) if status.code() == Some(expected) => Ok(()),
+        Ok(Ok(status)) => Err(format!("exited with {}", status)),
+        Ok(Err(e)) => Err(format!("failed to run: {}", e)),
+        Err(_) => Err(format!("timed out after {:?}", timeout)),
+    }
+}
+
+fn check_range(value: f64, min: Option<f64>, max: Option<f64>) -> Result<(), String> {
+    if min.is_some_and(|min| value < min) || max.is_some_and(|max| value > max) {
+        return Err(format!(
+            "value {} outside [{}, {}]",
+            value,
+            min.map_or("-inf".to_string(), |v| v.to_string()),
+            max.map_or("+inf".to_string(), |v| v.to_string())
+        ));
+    }
+    Ok(())
+}
+
+type Labels = Vec<(String, String)>;
+
+/// Split `name{a="x",b="y"}` into the metric name and its label matchers
+fn parse_selector(query: &str) -> Result<(String, Labels), String> {
+    let query = query.trim();
+    let (name, labels) = match query.split_once('{') {
+        Some((name, rest)) => {
+            let inner = rest
+                .strip_suffix('}')
+                .ok_or_else(|| format!("unterminated label set in '{}'", query))?;
+            (name.trim(), parse_labels(inner)?)
+        }
+        None => (query, Vec::new()),
+    };
+
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+    {
+        return Err(format!("invalid metric name in '{}'", query));
+    }
+    Ok((name.to_string(), labels))
+}
+
+fn parse_labels(inner: &str) -> Result<Labels, String> {
+    let mut labels = Vec::new();
+    let mut chars = inner.chars().peekable();
+
+    loop {
+        while chars.peek().is_some_and(|c| c.is_whitespace() || *c == ',') {
+            chars.next();
+        }
+        if chars.peek().is_none() {
+            return Ok(labels);
+        }
+
+        let key: String = chars.by_ref().take_while(|c| *c != '=').collect();
+        if chars.next() != Some('"') {
+            return Err(format!("expected quoted value for label '{}'", key.trim()));
+        }
+
+        let mut value = String::new();
+        loop {
+            match chars.next() {
+                Some('"') => break,
+                Some('\\') => match chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some(c) => value.push(c),
+                    None => return Err("unterminated label value".to_string()),
+                },
+                Some(c) => value.push(c),
+                None => return Err("unterminated label value".to_string()),
+            }
+        }
+        labels.push((key.trim().to_string(), value));
+    }
+}
+
+/// Sum of every sample in a Prometheus text exposition that has the query's
+/// name and carries all of its labels
+fn query_value(body: &str, query: &str) -> Result<f64, String> {
+    let (name, wanted) = parse_selector(query)?;
+    let mut total = None;
+
+    for line in body.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (sample_name, labels, rest) = match line.find('{') {
+            Some(open) => {
+                let Some(close) = line.rfind('}') else {
+                    continue;
+                };
+                let Ok(labels) = parse_labels(&line[open + 1..close]) else {
+                    continue;
+                };
+                (&line[..open], labels, &line[close + 1..])
+            }
+            None => match line.split_once(char::is_whitespace) {
+                Some((name, rest)) => (name, Vec::new(), rest),
+                None => continue,
+            },
+        };
+
+        if sample_name != name || !wanted.iter().all(|w| labels.contains(w)) {
+            continue;
+        }
+        if let Some(Ok(value)) = rest.split_whitespace().next().map(str::parse::<f64>) {
+            *total.get_or_insert(0.0) += value;
+        }
+    }
+
+    total.ok_or_else(|| format!("no samples match '{}'", query))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPOSITION: &str = r#"
+# HELP http_requests_total Requests served
+# TYPE http_requests_total counter
+http_requests_total{method="GET",status="200"} 90
+http_requests_total{method="POST",status="200"} 5
+http_requests_total{method="GET",status="500"} 3
+process_open_fds 12 1700000000000
+"#;
+
+    #[test]
+    fn test_query_value() {
+        assert_eq!(
+            query_value(EXPOSITION, "http_requests_total").unwrap(),
+            98.0
+        );
+        assert_eq!(
+            query_value(EXPOSITION, r#"http_requests_total{status="200"}"#).unwrap(),
+            95.0
+        );
+        assert_eq!(
+            query_value(
+                EXPOSITION,
+                r#"http_requests_total{method="GET", status="500"}"#
+            )
+            .unwrap(),
+            3.0
+        );
+        assert_eq!(query_value(EXPOSITION, "process_open_fds").unwrap(), 12.0);
+        assert!(query_value(EXPOSITION, "missing_metric").is_err());
+        assert!(query_value(EXPOSITION, "bad{status=200}").is_err());
+    }
+
+    #[test]
+    fn test_parse_steady_state() {
+        let state: SteadyState = serde_yaml::from_str(
+            r#"
+interval: 2s
+probes:
+  - name: "api"
+    type: http
+    url: "http://127.0.0.1:3000/health"
+    status: [200, 204]
+    max_latency: 200ms
+  - name: "service"
+    type: process
+    target:
+      process_name: "axum_http_service"
+  - name: "errors"
+    type: prometheus
+    url: "http://127.0.0.1:3000/metrics"
+    query: 'http_requests_total{status="500"}'
+    max: 10
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(state.interval, Duration::from_secs(2));
+        assert_eq!(state.probes[0].timeout, Duration::from_secs(5));
+        assert!(matches!(
+            state.probes[1].check,
+            ProbeCheck::Process { alive: true, .. }
+        ));
+        assert!(state.validate().is_ok());
+
+        let mut duplicate = state.clone();
+        duplicate.probes[1].name = "api".to_string();
+        assert!(duplicate.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_hypothesis_evaluation() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let state: SteadyState = serde_yaml::from_str(&format!(
+            r#"
+probes:
+  - name: "listening"
+    type: tcp
+    address: "{}"
+  - name: "true"
+    type: command
+    command: "true"
+  - name: "false"
+    type: command
+    command: "exit 3"
+    exit_code: 3
+  - name: "self"
+    type: process
+    target:
+      pid: {}
+"#,
+            address,
+            std::process::id()
+        ))
+        .unwrap();
+
+        let result = Hypothesis::new(state.clone()).evaluate().await;
+        assert!(result.passed, "{:?}", result);
+        assert!(result.probes.iter().all(|p| p.checks == 1));
+
+        drop(listener);
+        let result = Hypothesis::new(state).evaluate().await;
+        assert!(!result.passed);
+        assert_eq!(result.probes[0].failures, 1);
+        assert!(result.probes[0].last_failure.is_some());
+    }
+
+    #[test]
+    fn test_latency_tolerance_uses_slos() {
+        let state: SteadyState = serde_yaml::from_str(
+            r#"
+probes:
+  - name: "api"
+    type: tcp
+    address: "127.0.0.1:1"
+    max_latency: 10ms
+"#,
+        )
+        .unwrap();
+
+        let mut tally = Tally::new(&state);
+        tally.record(ProbeOutcome {
+            probe: "api".to_string(),
+            latency: Some(Duration::from_millis(5)),
+            failure: None,
+        });
+        tally.record(ProbeOutcome {
+            probe: "api".to_string(),
+            latency: Some(Duration::from_millis(50)),
+            failure: None,
+        });
+
+        let result = tally.finish();
+        assert!(!result.passed);
+        assert_eq!(result.probes[0].checks, 2);
+        assert_eq!(result.probes[0].failures, 1);
+        assert_eq!(result.slo_violations.len(), 1);
+    }
+}
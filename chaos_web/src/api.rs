@@ -62,10 +62,7 @@ pub async fn list_scenarios(
 
     while let Ok(Some(entry)) = entries.next_entry().await {
         let path = entry.path();
-        if path
-            .extension()
-            .map_or(false, |e| e == "yaml" || e == "yml")
-        {
+        if path.extension().is_some_and(|e| e == "yaml" || e == "yml") {
             if let Ok(content) = tokio::fs::read_to_string(&path).await {
                 if let Ok(scenario) = chaos_scenarios::parse_scenario(&content) {
                     let file_name = path
@@ -183,9 +180,12 @@ pub async fn run_scenario(
     let results_dir = state.config.results_dir.clone();
 
     tokio::spawn(async move {
-        let runner = chaos_scenarios::ScenarioRunner::with_defaults();
+        let executor =
+            chaos_core::Executor::with_defaults().with_journal(chaos_core::Journal::open_default());
+        let runner = chaos_scenarios::ScenarioRunner::new(executor);
+        let cancel = runner.cancellation_token();
 
-        // Simulate progress updates
+        // Simulate progress updates; a stop request aborts the run
         let phases = scenario.phases.clone();
         let state_for_progress = state_clone.clone();
         let duration = scenario.duration;
@@ -195,6 +195,7 @@ pub async fn run_scenario(
             let mut current_phase_idx = 0;
             loop {
                 if state_for_progress.should_stop() {
+                    cancel.cancel();
                     break;
                 }
 
@@ -294,7 +295,7 @@ pub async fn list_results(
     if let Ok(mut entries) = tokio::fs::read_dir(&state.config.results_dir).await {
         while let Ok(Some(entry)) = entries.next_entry().await {
             let path = entry.path();
-            if path.extension().map_or(false, |e| e == "json") {
+            if path.extension().is_some_and(|e| e == "json") {
                 if let Ok(content) = tokio::fs::read_to_string(&path).await {
                     if let Ok(result) =
                         serde_json::from_str::<chaos_scenarios::runner::ScenarioResult>(&content)
@@ -323,7 +324,7 @@ pub async fn list_results(
     }
 
     // Sort by timestamp descending
-    results.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    results.sort_by_key(|r| std::cmp::Reverse(r.timestamp));
 
     Ok(Json(results))
 }
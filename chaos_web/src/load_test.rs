@@ -83,6 +83,12 @@ pub struct LoadTestState {
     pub failed_requests: AtomicU64,
 }
 
+impl Default for LoadTestState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl LoadTestState {
     pub fn new() -> Self {
         Self {
@@ -1378,7 +1378,7 @@ pub fn result_detail_page(result: &chaos_scenarios::runner::ScenarioResult) -> S
             <div class="flex justify-between items-center">
                 <div>
                     <h1 class="page-title">{scenario_name}</h1>
-                    <p class="page-subtitle">Test completed</p>
+                    <p class="page-subtitle">Test {status}</p>
                 </div>
                 <a href="/results" class="btn btn-secondary">← Back to Results</a>
             </div>
@@ -1418,6 +1418,7 @@ pub fn result_detail_page(result: &chaos_scenarios::runner::ScenarioResult) -> S
             </div>
         </div>"#,
         scenario_name = result.scenario_name,
+        status = result.status,
         duration = result.total_duration.as_secs(),
         success_class = success_class,
         success_rate = result.success_rate() * 100.0,